@@ -0,0 +1,117 @@
+use crate::locale::Locale;
+use crate::progress::Progress;
+use crate::score::Score;
+use crate::sim::{GameSim, Side, SimEvent};
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+// Name and description are in the language files, under "achievements.<id>"
+pub struct Achievement {
+    pub id: &'static str,
+}
+
+impl Achievement {
+    pub fn name(&self, locale: &Locale) -> String {
+        locale
+            .text(&format!("achievements.{}.name", self.id))
+            .to_string()
+    }
+
+    pub fn description(&self, locale: &Locale) -> String {
+        locale
+            .text(&format!("achievements.{}.description", self.id))
+            .to_string()
+    }
+}
+
+pub static ACHIEVEMENTS: [Achievement; 6] = [
+    Achievement { id: "first_point" },
+    Achievement { id: "first_win" },
+    Achievement { id: "rally_25" },
+    Achievement { id: "rally_100" },
+    Achievement { id: "flawless" },
+    Achievement {
+        id: "small_paddle_win",
+    },
+];
+
+// Watches the simulation events of player one (the left paddle), updates the
+// lifetime stats and unlocks achievements.
+#[derive(Default)]
+pub struct AchievementTracker {
+    rally: u32,
+}
+
+impl AchievementTracker {
+    // Returns the achievements unlocked by this event.
+    pub fn on_event(
+        &mut self,
+        event: &SimEvent,
+        sim: &GameSim,
+        progress: &mut Progress,
+    ) -> Vec<&'static Achievement> {
+        let mut unlocked = Vec::new();
+        let mut unlock = |id: &str, progress: &mut Progress| {
+            if progress.achievements.contains_key(id) {
+                return;
+            }
+            if let Some(achievement) = ACHIEVEMENTS.iter().find(|a| a.id == id) {
+                progress.achievements.insert(id.to_string(), unix_time());
+                unlocked.push(achievement);
+            }
+        };
+
+        match *event {
+            SimEvent::PaddleHit { side, .. } => {
+                self.rally += 1;
+                progress.stats.longest_rally = progress.stats.longest_rally.max(self.rally);
+                if side == Side::Left {
+                    progress.stats.paddle_hits += 1;
+                }
+                if self.rally >= 25 {
+                    unlock("rally_25", progress);
+                }
+                if self.rally >= 100 {
+                    unlock("rally_100", progress);
+                }
+            }
+            SimEvent::Serve { .. } => self.rally = 0,
+            SimEvent::Goal { scorer: Side::Left } => {
+                progress.stats.points_won += 1;
+                unlock("first_point", progress);
+
+                if sim.left.score == Score::from(sim.win_score)
+                    && !sim.right.score.has_reached(sim.win_score)
+                {
+                    progress.stats.games_won += 1;
+                    unlock("first_win", progress);
+                    if sim.right.score.points() == 0 {
+                        unlock("flawless", progress);
+                    }
+                    if sim.mutators.small_paddle {
+                        unlock("small_paddle_win", progress);
+                    }
+                }
+            }
+            SimEvent::Goal {
+                scorer: Side::Right,
+            } => {
+                progress.stats.points_lost += 1;
+                if sim.right.score == Score::from(sim.win_score)
+                    && !sim.left.score.has_reached(sim.win_score)
+                {
+                    progress.stats.games_lost += 1;
+                }
+            }
+            SimEvent::WallBounce | SimEvent::Portal { .. } => (),
+        }
+
+        unlocked
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,62 @@
+use winit::keyboard::KeyCode;
+
+use super::AiController;
+use crate::config::KeyBindings;
+use crate::input::Input;
+use crate::sim::{GameSim, Side};
+
+// Decides where a paddle moves each frame, a player at the keyboard or an AI.
+// The result is turned into the same inputs either way, so replays don't need
+// to know who played.
+pub trait PaddleController {
+    // -1 up, 1 down, 0 stay, in between for a partly pushed stick
+    fn direction(&mut self, delta: f32, sim: &GameSim, side: Side, input: &Input) -> f32;
+}
+
+// The bound keys, W/S for the left paddle and the arrow keys for the right
+// one by default, or a controller.
+pub struct PlayerController {
+    up: KeyCode,
+    down: KeyCode,
+    // Controller slot, see `Input::stick`
+    gamepad: usize,
+}
+
+impl PlayerController {
+    pub fn new(side: Side, keys: &KeyBindings) -> Self {
+        match side {
+            Side::Left => Self {
+                up: keys.left_up,
+                down: keys.left_down,
+                gamepad: 0,
+            },
+            Side::Right => Self {
+                up: keys.right_up,
+                down: keys.right_down,
+                gamepad: 1,
+            },
+        }
+    }
+
+    pub fn set_gamepad(&mut self, slot: usize) {
+        self.gamepad = slot;
+    }
+}
+
+impl PaddleController for PlayerController {
+    fn direction(&mut self, _: f32, _: &GameSim, _: Side, input: &Input) -> f32 {
+        let up = input.keys.contains(&self.up) as i8 as f32;
+        let down = input.keys.contains(&self.down) as i8 as f32;
+        if up == down {
+            input.stick(self.gamepad)
+        } else {
+            down - up
+        }
+    }
+}
+
+impl PaddleController for AiController {
+    fn direction(&mut self, delta: f32, sim: &GameSim, side: Side, _: &Input) -> f32 {
+        self.update(delta, &sim.ai_view(side), &mut rand::rng())
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+// How hard an AI opponent plays, on top of its profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+pub static DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+impl Difficulty {
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+        }
+    }
+
+    // Multiplier for the profile's reaction time
+    pub fn reaction_scale(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.6,
+        }
+    }
+
+    // Share of the full paddle speed the AI moves at
+    pub fn speed_share(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.65,
+            Difficulty::Normal | Difficulty::Hard => 1.0,
+        }
+    }
+
+    // Multiplier for how far ahead the AI reads the ball
+    pub fn prediction_scale(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        }
+    }
+
+    // Without this the AI aims where the ball would be with no walls in the way
+    pub fn predicts_bounces(self) -> bool {
+        self != Difficulty::Easy
+    }
+}
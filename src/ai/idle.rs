@@ -0,0 +1,74 @@
+use log::info;
+
+use super::{AiController, AiProfile, Difficulty, PaddleController};
+use crate::input::Input;
+use crate::sim::{GameSim, Side};
+
+// Plays for a player who left their paddle alone for a while, for casual
+// matches and demos. They get it back with the next input.
+pub struct IdleTakeover {
+    profile: Option<AiProfile>,
+    difficulty: Difficulty,
+    // Seconds without input, and the AI playing instead, per side
+    idle: [f32; 2],
+    ai: [Option<AiController>; 2],
+}
+
+impl IdleTakeover {
+    pub fn new(profile: Option<AiProfile>, difficulty: Difficulty) -> Self {
+        Self {
+            profile,
+            difficulty,
+            idle: [0.0; 2],
+            ai: [None, None],
+        }
+    }
+
+    // Where the paddle on `side` moves, given the player's `human` direction.
+    // Without a `timeout` the player always plays.
+    pub fn direction(
+        &mut self,
+        side: Side,
+        human: f32,
+        delta: f32,
+        timeout: Option<f32>,
+        sim: &GameSim,
+        input: &Input,
+    ) -> f32 {
+        let index = slot(side);
+        let Some(timeout) = timeout.filter(|_| human == 0.0) else {
+            self.idle[index] = 0.0;
+            if self.ai[index].take().is_some() {
+                info!("The {:?} player is back", side);
+            }
+            return human;
+        };
+
+        self.idle[index] += delta;
+        if self.idle[index] < timeout {
+            return human;
+        }
+        if self.ai[index].is_none() {
+            info!("The AI plays for the idle {:?} player", side);
+            self.ai[index] = self
+                .profile
+                .clone()
+                .map(|profile| AiController::new(profile, self.difficulty, sim.height));
+        }
+        match self.ai[index].as_mut() {
+            Some(ai) => ai.direction(delta, sim, side, input),
+            None => human,
+        }
+    }
+
+    pub fn is_playing(&self, side: Side) -> bool {
+        self.ai[slot(side)].is_some()
+    }
+}
+
+fn slot(side: Side) -> usize {
+    match side {
+        Side::Left => 0,
+        Side::Right => 1,
+    }
+}
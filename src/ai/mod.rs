@@ -0,0 +1,196 @@
+use cgmath::Vector2;
+use rand::Rng;
+use std::collections::VecDeque;
+
+mod controller;
+mod difficulty;
+mod idle;
+mod profile;
+
+pub use controller::{PaddleController, PlayerController};
+pub use difficulty::{DIFFICULTIES, Difficulty};
+pub use idle::IdleTakeover;
+pub use profile::{AiProfile, load_profiles};
+
+// How many recent points/returns the AI looks at to judge the player
+static HISTORY_LEN: usize = 10;
+// How far a single point or return moves the AI's skill level
+static ADAPT_RATE: f32 = 0.05;
+// Share of the player's returns we want to see, keeps rallies going
+static TARGET_RETURN_RATE: f32 = 0.7;
+
+// Range the adaptive skill scales the profile's reaction time and aim error by
+static MIN_ADAPTIVE_SCALE: f32 = 0.25;
+static MAX_ADAPTIVE_SCALE: f32 = 2.0;
+// The AI stops moving once it is this close to its target
+static DEAD_ZONE: f32 = 8.0;
+
+// What the AI needs to know about the match to decide where to move.
+pub struct AiView {
+    pub ball_pos: Vector2<f32>,
+    pub ball_velocity: Vector2<f32>,
+    pub paddle_pos: Vector2<f32>,
+    pub paddle_height: f32,
+    pub field_height: f32,
+    pub portal_walls: bool,
+}
+
+// AI opponent driven by an `AiProfile` and a `Difficulty`. Adaptive profiles
+// additionally adjust their reaction time and aim error to the player's recent
+// performance, so matches stay close without picking a difficulty.
+pub struct AiController {
+    profile: AiProfile,
+    difficulty: Difficulty,
+    // 0 = slowest and least accurate, 1 = fastest and most accurate
+    skill: f32,
+    points: VecDeque<bool>,
+    returns: VecDeque<bool>,
+    reaction_timer: f32,
+    target_y: f32,
+    // Frames of movement saved up, slower difficulties move on only some frames
+    move_budget: f32,
+}
+
+impl AiController {
+    pub fn new(profile: AiProfile, difficulty: Difficulty, field_height: f32) -> Self {
+        Self {
+            profile,
+            difficulty,
+            skill: 0.5,
+            points: VecDeque::new(),
+            returns: VecDeque::new(),
+            reaction_timer: 0.0,
+            target_y: field_height / 2.0,
+            move_budget: 0.0,
+        }
+    }
+
+    pub fn profile(&self) -> &AiProfile {
+        &self.profile
+    }
+
+    pub fn skill(&self) -> Option<f32> {
+        self.profile.adaptive.then_some(self.skill)
+    }
+
+    // Multiplier for reaction time and aim error, smaller is harder
+    fn scale(&self) -> f32 {
+        if !self.profile.adaptive {
+            return 1.0;
+        }
+        MAX_ADAPTIVE_SCALE + (MIN_ADAPTIVE_SCALE - MAX_ADAPTIVE_SCALE) * self.skill
+    }
+
+    // Returns the direction the AI wants to move its paddle: -1 up, 1 down, 0 stay.
+    pub fn update(&mut self, delta: f32, view: &AiView, rng: &mut impl Rng) -> f32 {
+        self.reaction_timer -= delta;
+        if self.reaction_timer <= 0.0 {
+            self.reaction_timer =
+                self.profile.reaction_time * self.scale() * self.difficulty.reaction_scale();
+            self.target_y = self.choose_target(view, rng);
+        }
+
+        let offset = self.target_y - view.paddle_pos.y;
+        if offset.abs() < DEAD_ZONE {
+            return 0.0;
+        }
+
+        // Paddles only move at full speed, so a slower AI skips frames
+        self.move_budget += self.difficulty.speed_share();
+        if self.move_budget < 1.0 {
+            return 0.0;
+        }
+        self.move_budget -= 1.0;
+        offset.signum()
+    }
+
+    fn choose_target(&self, view: &AiView, rng: &mut impl Rng) -> f32 {
+        let center = view.field_height / 2.0;
+        let aggression = self.profile.aggression;
+
+        let approaching = (view.paddle_pos.x - view.ball_pos.x) * view.ball_velocity.x > 0.0;
+        if !approaching {
+            // Calm AIs go back to the middle, aggressive ones shadow the ball
+            return center + (view.ball_pos.y - center) * aggression;
+        }
+
+        let time = (view.paddle_pos.x - view.ball_pos.x) / view.ball_velocity.x;
+        let y = if time <= self.profile.prediction_horizon * self.difficulty.prediction_scale() {
+            predict_y(view, time, self.difficulty.predicts_bounces())
+        } else {
+            // Too far away to read, just follow the ball
+            view.ball_pos.y
+        };
+
+        // Hitting the ball with the paddle's edge gives steeper return angles.
+        // Aim the edge toward the side of the field the ball isn't heading to.
+        let edge = if y < center { -1.0 } else { 1.0 };
+        let edge_offset = edge * aggression * view.paddle_height * 0.4;
+
+        y + edge_offset + self.profile.error.sample(self.scale(), rng)
+    }
+
+    // The player's paddle returned the ball, or failed to (`returned` = false).
+    pub fn on_player_return(&mut self, returned: bool) {
+        push_limited(&mut self.returns, returned);
+        self.adapt();
+    }
+
+    // Returns a taunt if the AI feels like gloating about the point.
+    pub fn on_point(&mut self, player_won: bool, rng: &mut impl Rng) -> Option<String> {
+        push_limited(&mut self.points, player_won);
+        self.adapt();
+
+        if player_won {
+            return None;
+        }
+        self.profile.taunts.pick(rng).map(str::to_string)
+    }
+
+    fn adapt(&mut self) {
+        if !self.profile.adaptive {
+            return;
+        }
+
+        // Winning player -> stronger AI, losing player -> weaker AI
+        let win_rate = rate(&self.points).unwrap_or(0.5);
+        let return_rate = rate(&self.returns).unwrap_or(TARGET_RETURN_RATE);
+
+        self.skill += ADAPT_RATE * (win_rate - 0.5) * 2.0;
+        self.skill += ADAPT_RATE * (return_rate - TARGET_RETURN_RATE);
+        self.skill = self.skill.clamp(0.0, 1.0);
+    }
+}
+
+// Where the ball will be after `time` seconds, including wall bounces if
+// `bounces` is set.
+fn predict_y(view: &AiView, time: f32, bounces: bool) -> f32 {
+    let y = view.ball_pos.y + view.ball_velocity.y * time;
+
+    if !bounces {
+        return y.clamp(0.0, view.field_height);
+    }
+
+    if view.portal_walls {
+        return y.rem_euclid(view.field_height);
+    }
+
+    // Fold the straight-line position back into the field
+    let period = view.field_height * 2.0;
+    let y = y.rem_euclid(period);
+    if y > view.field_height { period - y } else { y }
+}
+
+fn push_limited(history: &mut VecDeque<bool>, value: bool) {
+    history.push_back(value);
+    if history.len() > HISTORY_LEN {
+        history.pop_front();
+    }
+}
+
+fn rate(history: &VecDeque<bool>) -> Option<f32> {
+    if history.is_empty() {
+        return None;
+    }
+    Some(history.iter().filter(|&&value| value).count() as f32 / history.len() as f32)
+}
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+use log::{error, info};
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::assets::assets_dir;
+
+static PROFILE_DIR: &str = "ai";
+
+// Always available, even without an assets directory
+static BUILTIN_PROFILE: &str = include_str!("../../assets/ai/adaptive.toml");
+
+// Behavior of an AI opponent, loaded from `assets/ai/*.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AiProfile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub aggression: f32,
+    pub prediction_horizon: f32,
+    pub reaction_time: f32,
+    #[serde(default)]
+    pub adaptive: bool,
+    #[serde(default)]
+    pub error: ErrorModel,
+    #[serde(default)]
+    pub taunts: Taunts,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ErrorModel {
+    #[serde(default)]
+    pub model: ErrorKind,
+    #[serde(default)]
+    pub amount: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    #[default]
+    None,
+    Uniform,
+    Gaussian,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Taunts {
+    #[serde(default)]
+    pub frequency: f32,
+    #[serde(default)]
+    pub lines: Vec<String>,
+}
+
+impl ErrorModel {
+    // Random aim offset in pixels, `scale` shrinks or grows the configured amount.
+    pub fn sample(&self, scale: f32, rng: &mut impl Rng) -> f32 {
+        let amount = self.amount * scale;
+        match self.model {
+            ErrorKind::None => 0.0,
+            ErrorKind::Uniform => rng.random_range(-1.0..=1.0) * amount,
+            ErrorKind::Gaussian => {
+                // Box-Muller transform, `amount` is the standard deviation
+                let u1 = rng.random::<f32>().max(f32::EPSILON);
+                let u2 = rng.random::<f32>();
+                (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos() * amount
+            }
+        }
+    }
+}
+
+impl Taunts {
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<&str> {
+        if self.lines.is_empty() || !rng.random_bool(self.frequency.clamp(0.0, 1.0) as f64) {
+            return None;
+        }
+        Some(&self.lines[rng.random_range(0..self.lines.len())])
+    }
+}
+
+impl AiProfile {
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut profile: Self = toml::from_str(data)?;
+        profile.aggression = profile.aggression.clamp(0.0, 1.0);
+        profile.prediction_horizon = profile.prediction_horizon.max(0.0);
+        profile.reaction_time = profile.reaction_time.max(0.0);
+        profile.error.amount = profile.error.amount.max(0.0);
+        Ok(profile)
+    }
+
+    pub fn builtin() -> Result<Self> {
+        Self::parse(BUILTIN_PROFILE)
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)?;
+        Self::parse(&data)
+    }
+}
+
+// Loads every profile from the assets directory. Broken files are logged
+// and skipped, and the built-in profile is used if none could be loaded.
+pub fn load_profiles() -> Vec<AiProfile> {
+    let dir = assets_dir().join(PROFILE_DIR);
+    let mut profiles = match read_profile_dir(&dir) {
+        Ok(profiles) => profiles,
+        Err(err) => {
+            info!("No AI profiles loaded from {}: {:#}", dir.display(), err);
+            Vec::new()
+        }
+    };
+
+    if profiles.is_empty() {
+        match AiProfile::builtin() {
+            Ok(profile) => profiles.push(profile),
+            Err(err) => error!("Invalid built-in AI profile: {:#}", err),
+        }
+    }
+    profiles
+}
+
+fn read_profile_dir(dir: &Path) -> Result<Vec<AiProfile>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("cannot read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    let mut profiles = Vec::new();
+    for path in paths {
+        match AiProfile::load(&path) {
+            Ok(profile) => {
+                info!(
+                    "Loaded AI profile {:?} from {}",
+                    profile.name,
+                    path.display()
+                );
+                profiles.push(profile);
+            }
+            Err(err) => error!("Invalid AI profile {}: {:#}", path.display(), err),
+        }
+    }
+
+    Ok(profiles)
+}
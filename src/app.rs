@@ -1,57 +1,1630 @@
-use cgmath::{Deg, Vector2};
+use anyhow::Result;
+use cgmath::Vector2;
 use log::{error, info};
 use palette::Srgba;
-use std::collections::HashSet;
+#[cfg(target_arch = "wasm32")]
+use std::cell::RefCell;
+#[cfg(target_arch = "wasm32")]
+use std::rc::Rc;
 use std::sync::Arc;
-use std::time::Instant;
 use winit::application::ApplicationHandler;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::{ElementState, WindowEvent};
-use winit::event_loop::ActiveEventLoop;
+use winit::event_loop::{ActiveEventLoop, ControlFlow};
 use winit::keyboard::{KeyCode, PhysicalKey};
-use winit::window::{Window, WindowId};
+use winit::window::{Window, WindowAttributes, WindowId};
 
-use crate::renderer::Renderer;
+use crate::achievements::AchievementTracker;
+use crate::ai::{
+    AiController, AiProfile, Difficulty, IdleTakeover, PaddleController, PlayerController,
+    load_profiles,
+};
+use crate::arena::{Arena, ArenaColors, ArenaTextures, DEFAULT_ARENA};
+use crate::audio::{Audio, AudioScene, SoundId};
+use crate::calibration::Calibration;
+use crate::clock::GameClock;
+use crate::colorpicker::contrast_ratio;
+use crate::config::Settings;
+use crate::daily::{DailyAction, DailyChallenge, DailyScreen, Date};
+use crate::director::CameraDirector;
+use crate::display_sync::DisplaySync;
+use crate::drills::{Drill, DrillAction, DrillResult, DrillRun, DrillScreen, DrillStep};
+use crate::edge_indicator;
+use crate::gallery::Gallery;
+use crate::game::{
+    GameFlow, GameOverAction, GameOverScreen, GameState, MainMenu, MainMenuAction, MatchIntro,
+};
+use crate::hudbar::HudBar;
+use crate::input::{GamepadButton, GamepadEvent, Input};
+use crate::input_script::{ScriptAction, ScriptPlayer};
+use crate::latency::LatencyOverlay;
+use crate::loading::{Assets, Loading};
+use crate::locale::Locale;
+use crate::menu::{Menu, MenuItem};
+use crate::mutators::{
+    IMPACT_TRANSFERS, MOMENTUM_ACCELERATIONS, Mutators, PortalFlash, RUBBER_BAND_STRENGTHS,
+    WALL_DAMPINGS,
+};
+use crate::name_entry::{EntryKind, NameEntry};
+use crate::net::{OnlineEvent, OnlineMatch, PeerLink, Role};
+use crate::options::{OptionsAction, OptionsScreen};
+use crate::pacing::{FramePacer, FramePacing, monitor_refresh_rate};
+use crate::paddle_colors::{MIN_CONTRAST, PaddleColorAction, PaddleColorScreen};
+use crate::paddle_trail::PaddleTrail;
+use crate::particles::{EmitterParams, Particles};
+use crate::photo::{PHOTO_SCALE, PhotoAction, PhotoMode};
+use crate::playback::Playback;
+use crate::progress::{DailyResult, Progress};
+use crate::prompts::{Action, Prompts};
+use crate::renderer::{
+    Camera2D, CameraEffects, CaptureKind, PostFilter, Rect, Renderer, letterbox, pixel_letterbox,
+};
+use crate::replay::{Replay, TickInput, analog_speed};
+use crate::resolution::ResolutionScaler;
+use crate::rewind::{REWIND_KEY, Rewind};
+use crate::ruleset::Ruleset;
+use crate::screenshot::{self, Clipboard, Destination};
+use crate::session_log::{LifecycleEvent, SessionPlayer, SessionRecorder, WindowLifecycle};
+use crate::sim::{
+    BALL_SPEED, Ball, FIELD_HEIGHT, FIELD_WIDTH, GAME_POINTS, GameSim, PADDLE_SPEED, PendingServe,
+    SERVE_AIM_LIMIT, SERVE_DELAY, Side, SimEvent, SimState, fit_field,
+};
+use crate::skins::BallLook;
+use crate::splitscreen;
+use crate::stream_overlay::{Scoreboard, StreamOverlay};
+use crate::strict_math::Math;
+use crate::telemetry::Telemetry;
+use crate::textfield::TextFieldAction;
+use crate::time::Instant;
+use crate::timeline::Timeline;
+use crate::timestep::{FixedTimestep, Positions, TICK_RATE};
+use crate::toast::Toasts;
+use crate::transition::{Screen, Transitions};
+use crate::tutorial::Tutorial;
+use crate::webhook::Webhook;
 
 static FONT_SIZE: f32 = 32.;
 static LINE_HEIGHT: f32 = 32.;
-static PADDLE_SPEED: f32 = 1000.0;
-static BALL_SPEED: f32 = 400.0;
-static BALL_RADIUS: f32 = 20.0;
+static AI_MESSAGE_DURATION: f32 = 2.0;
+static SERVE_ARC_RADIUS: f32 = 70.0;
+static SERVE_ARC_DOTS: usize = 9;
+static SERVE_BAR_WIDTH: f32 = 80.0;
+static ASSIST_BAR_WIDTH: f32 = 120.0;
+static BAR_HEIGHT: f32 = 6.0;
+// Volume change per press in the pause menu, in percent
+static VOLUME_STEP: u8 = 10;
+static HIT_PARTICLES: usize = 24;
+static BOUNCE_PARTICLES: usize = 10;
+static GOAL_PARTICLES: usize = 120;
+// The fountain on the goal line after the first burst, per second
+static GOAL_FOUNTAIN_RATE: f32 = 300.0;
+static GOAL_FOUNTAIN_DURATION: f32 = 0.6;
+static PARTICLE_GRAVITY: f32 = 600.0;
+// Camera effects for goals and for hits up to twice the serve speed
+static GOAL_SHAKE: f32 = 0.6;
+static HIT_SHAKE: f32 = 0.25;
+static HIT_PUNCH: f32 = 0.04;
+// Shows around the field where the window's aspect ratio differs
+static LETTERBOX_COLOR: Srgba = Srgba::new(0.0, 0.0, 0.0, 1.0);
+// Virtual resolution of the field in the pixel art mode
+static PIXEL_RESOLUTION: Vector2<f32> = Vector2::new(320.0, 180.0);
 
 struct State {
-    left: Paddle,
-    right: Paddle,
-    ball: Ball,
-    keys_pressed: HashSet<KeyCode>,
-    last_update: Instant,
+    sim: GameSim,
+    // Of the window, width over height, for the field of the next match
+    window_aspect: f32,
+    // Main menu, match, pause and game over
+    flow: GameFlow,
+    main_menu: Option<MainMenu>,
+    game_over: Option<GameOverScreen>,
+    drill_screen: Option<DrillScreen>,
+    // The drill being played against the practice wall
+    drill: Option<DrillRun>,
+    // The last seconds of a drill, to take back a miss
+    rewind: Rewind,
+    telemetry: Telemetry,
+    input: Input,
+    clock: GameClock,
+    timestep: FixedTimestep,
+    // Where things were before the last step, drawn in between for smooth motion
+    previous: Positions,
+    portals: Vec<PortalFlash>,
+    particles: Particles,
+    paddle_trail: PaddleTrail,
+    reset_requested: bool,
+    replay: Replay,
+    // Drives the `ai_side` paddle when set, the players drive the others
+    ai: Option<AiController>,
+    ai_side: Side,
+    players: [PlayerController; 2],
+    // Plays for the players while they're away, see `idle_timeout`
+    idle: IdleTakeover,
+    webhook: Webhook,
+    ai_profiles: Vec<AiProfile>,
+    // Taunts and profile descriptions shown next to the AI paddle
+    ai_message: Option<(String, f32)>,
+    // Brightness/gamma calibration screen, pauses the game while open
+    calibration: Option<Calibration>,
+    pacer: FramePacer,
+    // Refresh rate of the window's monitor and the rate frames reach it
+    display_sync: DisplaySync,
+    // Render scale of the scene while dynamic resolution is on
+    resolution: ResolutionScaler,
+    toasts: Toasts,
+    // Taken right after the next frame is drawn
+    capture_requested: Option<(CaptureKind, Destination, u32)>,
+    clipboard: Clipboard,
+    audio: Audio,
+    // Input-to-present latency diagnostics, toggled with F6
+    latency: Option<LatencyOverlay>,
+    // The scoreboard window for streamers is wanted, the app opens it
+    stream_overlay: bool,
+    // Pause menu, freezes the match while open
+    menu: Option<Menu>,
+    // Shown on first run and when picked from the menu
+    tutorial: Option<Tutorial>,
+    // Names, win score and countdown before a match, see `start_intro`
+    intro: Option<MatchIntro>,
+    prompts: Prompts,
+    progress: Progress,
+    // From settings.toml, changed in the options screen
+    settings: Settings,
+    options: Option<OptionsScreen>,
+    achievements: AchievementTracker,
+    // Achievement gallery opened from the menu
+    gallery: Option<Gallery>,
+    daily_screen: Option<DailyScreen>,
+    // The daily challenge being played, cleared by any restart
+    daily: Option<DailyChallenge>,
+    // The opponent and side picked before the daily challenge, brought back
+    // once it's over
+    ai_before_daily: Option<(Option<AiProfile>, Side)>,
+    // Frozen match with a free camera, toggled with F8
+    photo: Option<PhotoMode>,
+    // Event log of each point, shown as a strip after every goal
+    timeline: Timeline,
+    ball_look: BallLook,
+    arenas: Vec<Arena>,
+    // Index into `arenas`
+    arena: usize,
+    // Uploaded images per arena, decoded while loading
+    arena_textures: Vec<ArenaTextures>,
+    // Replay being watched instead of a live match, with its camera
+    playback: Option<Playback>,
+    // Match against a player on another machine, see `OnlineMatch`
+    online: Option<OnlineMatch>,
+    director: Option<CameraDirector>,
+    camera_effects: CameraEffects,
+    // One zoomed view per player side by side, toggled with F9
+    split_screen: bool,
+    // Typing the player name, opened from the menu
+    name_entry: Option<NameEntry>,
+    paddle_colors: Option<PaddleColorScreen>,
+    locale: Locale,
+    // Slide and fade of the screens above, they stay set while animating out
+    transitions: Transitions,
+    // Time left until the ball is served
+    serve_bar: HudBar,
+    // Slow motion left, under the score of each player with the save assist
+    assist_bar: HudBar,
 }
 
-struct Paddle {
-    score: u8,
-    pos: Vector2<f32>,
-    width: f32,
-    height: f32,
-}
+impl State {
+    fn new(assets: Assets, renderer: &mut Renderer) -> Self {
+        let seed = rand::random::<u64>();
+        let mut sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), seed);
+        let progress = Progress::load();
+        let settings = Settings::load();
+        sim.win_score = progress.win_score();
+        sim.paddle_speed = settings.paddle_speed;
+        sim.serve_speed = settings.ball_speed;
+        let locale = Locale::load(progress.language.as_deref());
+        let replay = Replay::new(
+            [progress.player_name().to_string(), "P2".to_string()],
+            sim.settings(),
+            seed,
+        );
+        let timeline = Timeline::new(sim.serving_toward());
+        let Assets {
+            arenas,
+            arena_pixels,
+            sounds,
+        } = assets;
+        let arena_textures = arenas
+            .iter()
+            .zip(&arena_pixels)
+            .map(|(arena, pixels)| arena.upload_images(pixels, renderer))
+            .collect();
+        let selected = progress.arena.as_deref().unwrap_or(DEFAULT_ARENA);
+        let arena = arenas
+            .iter()
+            .position(|arena| arena.name == selected)
+            .unwrap_or(0);
+        let mut transitions = Transitions::default();
+        transitions.open(Screen::MainMenu);
+        let previous = Positions::of(&sim);
+        let audio = Audio::new(sounds);
+        audio.set_volume(
+            progress.master_volume() as f32 / 100.0,
+            progress.sfx_volume() as f32 / 100.0,
+        );
+        let mut prompts = Prompts::new();
+        prompts.set_bindings(&settings.keys);
 
-struct Ball {
-    pos: Vector2<f32>,
-    velocity: Vector2<f32>,
-    radius: f32,
-}
+        Self {
+            sim,
+            window_aspect: FIELD_WIDTH / FIELD_HEIGHT,
+            flow: GameFlow::new(),
+            main_menu: Some(MainMenu::new(&progress)),
+            game_over: None,
+            drill_screen: None,
+            drill: None,
+            rewind: Rewind::new(),
+            telemetry: Telemetry::new(),
+            input: Input::new(),
+            clock: GameClock::new(),
+            timestep: FixedTimestep::new(settings.tick_rate),
+            previous,
+            portals: Vec::new(),
+            particles: Particles::default(),
+            paddle_trail: PaddleTrail::default(),
+            reset_requested: false,
+            replay,
+            ai: None,
+            ai_side: progress.ai_side(),
+            players: [
+                PlayerController::new(Side::Left, &settings.keys),
+                PlayerController::new(Side::Right, &settings.keys),
+            ],
+            idle: IdleTakeover::new(None, Difficulty::Normal),
+            webhook: Webhook::new(settings.webhook_url.as_deref()),
+            ai_profiles: load_profiles(),
+            ai_message: None,
+            calibration: None,
+            pacer: FramePacer::new(),
+            display_sync: DisplaySync::new(),
+            resolution: ResolutionScaler::new(),
+            toasts: Toasts::default(),
+            capture_requested: None,
+            clipboard: Clipboard::default(),
+            audio,
+            latency: None,
+            stream_overlay: false,
+            menu: None,
+            tutorial: (!progress.tutorial_completed).then(Tutorial::new),
+            intro: None,
+            prompts,
+            progress,
+            settings,
+            options: None,
+            achievements: AchievementTracker::default(),
+            gallery: None,
+            daily_screen: None,
+            daily: None,
+            ai_before_daily: None,
+            photo: None,
+            timeline,
+            ball_look: BallLook::default(),
+            arena_textures,
+            arenas,
+            arena,
+            playback: None,
+            online: None,
+            director: None,
+            camera_effects: CameraEffects::default(),
+            split_screen: false,
+            name_entry: None,
+            paddle_colors: None,
+            locale,
+            transitions,
+            serve_bar: HudBar::new(Srgba::new(1.0, 1.0, 0.3, 0.8), None),
+            assist_bar: HudBar::new(Srgba::new(0.3, 0.8, 1.0, 0.9), Some(5)),
+        }
+    }
+
+    // Advances the match by one frame and reacts to what happened.
+    fn tick(&mut self, delta: f32) {
+        let mut directions = [0.0; 2];
+        let idle_timeout = self.idle_timeout();
+        for (index, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+            directions[index] = match self.ai.as_mut() {
+                Some(ai) if side == self.ai_side => {
+                    ai.direction(delta, &self.sim, side, &self.input)
+                }
+                _ => {
+                    // Against the AI the first controller plays, whichever side
+                    let player = &mut self.players[index];
+                    player.set_gamepad(if self.ai.is_some() { 0 } else { index });
+                    let human = player.direction(delta, &self.sim, side, &self.input);
+                    self.idle
+                        .direction(side, human, delta, idle_timeout, &self.sim, &self.input)
+                }
+            };
+        }
+        if let Some(online) = self.online.as_ref() {
+            directions[1] = online.remote_direction();
+        }
+
+        let input = TickInput {
+            left_up: directions[0] < 0.0,
+            left_down: directions[0] > 0.0,
+            right_up: directions[1] < 0.0,
+            right_down: directions[1] > 0.0,
+            reset_ball: std::mem::take(&mut self.reset_requested),
+            left_speed: analog_speed(directions[0]),
+            right_speed: analog_speed(directions[1]),
+        };
+        if let Some(drill) = self.drill.as_ref() {
+            self.rewind.record(
+                &self.sim,
+                delta,
+                self.replay.body.ticks.len(),
+                self.replay.body.points.len(),
+                drill,
+            );
+        }
+        self.replay.record(delta, input);
+        self.step(delta, input);
+    }
+
+    // Takes the drill back while the rewind key is held, its successes and
+    // misses with it, so a rewound return is tried again for real.
+    fn rewind(&mut self, delta: f32) {
+        let Some(snapshot) = self.rewind.rewind(delta) else {
+            return;
+        };
+        self.sim = GameSim::from_state(&snapshot.state);
+        self.drill = Some(snapshot.drill);
+        self.replay.body.ticks.truncate(snapshot.replay_ticks);
+        self.replay.body.points.truncate(snapshot.replay_points);
+        self.previous = Positions::of(&self.sim);
+        if let GameState::RoundOver { .. } = self.flow.state() {
+            self.flow.play();
+        }
+    }
+
+    // Runs one sim step with the given input, live or from a replay.
+    fn step(&mut self, delta: f32, input: TickInput) {
+        let events = self.sim.step(delta, input);
+        if let Some(online) = self.online.as_mut() {
+            online.record_tick(&self.sim, delta, input);
+            online.record_events(&events);
+        }
+        self.apply_events(delta, events);
+    }
 
-impl Ball {
-    fn reset(&mut self, screen_width: f32, screen_height: f32) {
-        self.pos = Vector2::new(screen_width / 2.0, screen_height / 2.0);
+    // Reacts to what happened during `delta` seconds of the match, simulated
+    // here or by the host of an online match.
+    fn apply_events(&mut self, delta: f32, events: Vec<SimEvent>) {
+        // Drills and the tutorial aren't matches worth announcing
+        let announced = self.playback.is_none() && self.drill.is_none() && self.tutorial.is_none();
+        if announced && self.webhook.is_enabled() {
+            let names = [self.side_name(Side::Left), self.side_name(Side::Right)];
+            self.webhook.observe(&self.sim, &events, names);
+        }
+        self.telemetry.observe(&self.sim, &events);
+        self.timeline.advance(delta);
+        let mut drill_result = None;
+        for event in events {
+            if let Some(point) = self.timeline.on_event(&event)
+                && self.playback.is_none()
+            {
+                self.replay.record_point(point.clone());
+            }
+            if let Some(tutorial) = self.tutorial.as_mut() {
+                tutorial.on_event(&event);
+            }
 
-        // Random direction between -45 and 45 degrees from horizontal
-        let angle = rand::random::<f32>() * std::f32::consts::PI / 2.0 - std::f32::consts::PI / 4.0;
+            if let Some(drill) = self.drill.as_mut() {
+                match drill.on_event(&event, &self.sim) {
+                    DrillStep::Continue => (),
+                    DrillStep::Serve => self.reset_requested = true,
+                    DrillStep::Finished(result) => drill_result = Some(result),
+                }
+            }
 
-        // Randomly choose left or right direction
-        let direction = if rand::random::<bool>() { 1.0 } else { -1.0 };
+            // Watching a replay or a drill doesn't count toward the player's
+            // progress, neither does a match played on the right against the AI
+            let counts = self.playback.is_none()
+                && self.drill.is_none()
+                && (self.ai.is_none() || self.ai_side == Side::Right)
+                && !self.idle.is_playing(Side::Left);
+            if counts {
+                let unlocked = self
+                    .achievements
+                    .on_event(&event, &self.sim, &mut self.progress);
+                for achievement in &unlocked {
+                    self.toasts.push(self.locale.format(
+                        "hud.achievement_unlocked",
+                        &[("name", &achievement.name(&self.locale))],
+                    ));
+                }
+                if !unlocked.is_empty() || matches!(event, SimEvent::Goal { .. }) {
+                    self.save_progress();
+                }
+            }
 
-        self.velocity = Vector2::new(
-            direction * BALL_SPEED * angle.cos(),
-            BALL_SPEED * angle.sin(),
+            match event {
+                SimEvent::Portal {
+                    entry,
+                    exit,
+                    direction,
+                } => self.portals.push(PortalFlash::new(entry, exit, direction)),
+                SimEvent::PaddleHit { side, pos, speed } => {
+                    self.play_sound(SoundId::PaddleHit);
+                    let colors = self.colors();
+                    let color = match side {
+                        Side::Left => colors.left_paddle,
+                        Side::Right => colors.right_paddle,
+                    };
+                    self.particles.emit(
+                        pos,
+                        EmitterParams {
+                            burst: HIT_PARTICLES,
+                            speed: (speed * 0.15, speed * 0.5),
+                            direction: self.sim.ball.velocity,
+                            spread: 0.6,
+                            color: (color, fade_out(color)),
+                            ..Default::default()
+                        },
+                    );
+                    let hard = (speed / (BALL_SPEED * self.sim.field_scale().ball_speed) - 1.0)
+                        .clamp(0.0, 1.0);
+                    let shake = self.progress.shake_scale();
+                    if shake > 0.0 && hard > 0.0 {
+                        self.camera_effects.shake(HIT_SHAKE * hard * shake);
+                        self.camera_effects.punch(HIT_PUNCH * hard * shake);
+                    }
+                    if let Some(ai) = self.ai.as_mut()
+                        && side != self.ai_side
+                    {
+                        ai.on_player_return(true);
+                    }
+                }
+                SimEvent::WallBounce => {
+                    self.play_sound(SoundId::WallBounce);
+                    // Spray away from the wall that was hit
+                    let direction = Vector2::new(0.0, self.sim.ball.velocity.y.signum());
+                    let color = Srgba::new(1.0, 1.0, 1.0, 0.8);
+                    self.particles.emit(
+                        self.sim.ball.pos,
+                        EmitterParams {
+                            burst: BOUNCE_PARTICLES,
+                            speed: (90.0, 300.0),
+                            direction,
+                            spread: 1.0,
+                            color: (color, fade_out(color)),
+                            ..Default::default()
+                        },
+                    );
+                }
+                SimEvent::Serve { .. } => self.serve_bar.reset(1.0),
+                SimEvent::Goal { scorer } => {
+                    self.play_sound(SoundId::Goal);
+                    let shake = self.progress.shake_scale();
+                    if shake > 0.0 {
+                        self.camera_effects.shake(GOAL_SHAKE * shake);
+                    }
+                    let colors = self.colors();
+                    let color = match scorer {
+                        Side::Left => colors.left_paddle,
+                        Side::Right => colors.right_paddle,
+                    };
+                    // The ball is already past the edge, burst where it left the field
+                    let pos = Vector2::new(
+                        self.sim.ball.pos.x.clamp(0.0, self.sim.width),
+                        self.sim.ball.pos.y,
+                    );
+                    // Ends up white hot, then fades
+                    let end = Srgba::new(1.0, 0.95, 0.8, 0.0);
+                    self.particles.emit(
+                        pos,
+                        EmitterParams {
+                            burst: GOAL_PARTICLES,
+                            speed: (210.0, 700.0),
+                            color: (color, end),
+                            gravity: PARTICLE_GRAVITY,
+                            ..Default::default()
+                        },
+                    );
+                    // Sprays back into the field
+                    let inward = if pos.x < self.sim.width / 2.0 {
+                        1.0
+                    } else {
+                        -1.0
+                    };
+                    self.particles.emit(
+                        pos,
+                        EmitterParams {
+                            rate: GOAL_FOUNTAIN_RATE,
+                            duration: GOAL_FOUNTAIN_DURATION,
+                            lifetime: (0.5, 0.9),
+                            speed: (300.0, 650.0),
+                            direction: Vector2::new(inward, -1.5),
+                            spread: 0.35,
+                            color: (color, end),
+                            gravity: PARTICLE_GRAVITY * 1.5,
+                            drag: 1.0,
+                            ..Default::default()
+                        },
+                    );
+                    // The daily challenge shows its own result
+                    if self.playback.is_none() {
+                        let winner = self
+                            .sim
+                            .winner()
+                            .filter(|_| self.daily.is_none() && self.drill.is_none());
+                        self.flow.on_goal(scorer, winner);
+                        if let GameState::GameOver { winner } = self.flow.state() {
+                            self.game_over = Some(GameOverScreen::new(
+                                self.side_name(winner),
+                                [self.sim.left.score, self.sim.right.score],
+                            ));
+                            self.transitions.open(Screen::GameOver);
+                        }
+                    }
+                    if let Some(ai) = self.ai.as_mut() {
+                        if scorer == self.ai_side {
+                            ai.on_player_return(false);
+                        }
+                        if let Some(taunt) = ai.on_point(scorer != self.ai_side, &mut rand::rng()) {
+                            self.ai_message = Some((taunt, AI_MESSAGE_DURATION));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.ball_look.update(delta, &self.sim.ball);
+        if let Some(serve) = self.sim.pending_serve {
+            self.serve_bar.set(serve.remaining / SERVE_DELAY);
+        }
+        self.serve_bar.update(delta);
+        self.assist_bar.set(self.sim.save_assist_remaining());
+        self.assist_bar.update(delta);
+        for portal in self.portals.iter_mut() {
+            portal.update(delta);
+        }
+        self.portals.retain(|portal| !portal.is_finished());
+        self.particles.update(delta);
+        if self.progress.paddle_trails() {
+            self.paddle_trail.update(delta, &self.sim);
+        }
+
+        if self.daily.is_some() && self.sim.winner().is_some() {
+            self.finish_daily();
+        }
+        if let Some(result) = drill_result {
+            self.finish_drill(result);
+        }
+    }
+
+    fn start_drill(&mut self, drill: Drill) {
+        // Drills are timed on the standard field, like the daily challenge
+        self.start(
+            Mutators {
+                practice_wall: true,
+                ..Mutators::default()
+            },
+            rand::random::<u64>(),
+            (FIELD_WIDTH, FIELD_HEIGHT),
+        );
+        self.ai = None;
+        self.drill = Some(DrillRun::new(drill));
+        self.flow.play();
+    }
+
+    // Records the result and goes back to the drill list.
+    fn finish_drill(&mut self, result: DrillResult) {
+        let Some(run) = self.drill.take() else {
+            return;
+        };
+        let drill = run.drill();
+        self.progress
+            .drills
+            .entry(drill.id().to_string())
+            .or_default()
+            .add(result);
+        self.save_progress();
+
+        let name = drill.name(&self.locale);
+        self.toasts.push(match result {
+            DrillResult::Passed(medal) => self.locale.format(
+                "drill.passed",
+                &[
+                    ("drill", &name),
+                    ("medal", &self.locale.text(medal.label_key())),
+                ],
+            ),
+            DrillResult::Failed => self.locale.format("drill.failed", &[("drill", &name)]),
+        });
+        self.show_main_menu();
+        self.drill_screen = Some(DrillScreen::new());
+        self.transitions.open(Screen::Drills);
+    }
+
+    fn start_daily(&mut self, challenge: DailyChallenge) {
+        self.start(
+            challenge.mutators,
+            challenge.seed,
+            (FIELD_WIDTH, FIELD_HEIGHT),
+        );
+        self.ai_before_daily = Some((
+            self.ai.as_ref().map(|ai| ai.profile().clone()),
+            self.ai_side,
+        ));
+        // Every player gets the same challenge, whatever their win score and speeds
+        self.sim.win_score = GAME_POINTS;
+        self.sim.paddle_speed = PADDLE_SPEED;
+        self.sim.serve_speed = BALL_SPEED;
+        self.timestep.set_rate(TICK_RATE);
+        self.replay.header.settings = self.sim.settings();
+        self.ai = Some(AiController::new(
+            challenge.opponent.clone(),
+            Difficulty::Normal,
+            self.sim.height,
+        ));
+        self.ai_side = Side::Right;
+        self.daily = Some(challenge);
+        self.start_intro();
+    }
+
+    // Seconds without input before the AI takes over, when it may. Online
+    // matches, the daily challenge, drills and the tutorial are always played
+    // by the players.
+    fn idle_timeout(&self) -> Option<f32> {
+        let allowed = self.settings.idle_takeover
+            && self.online.is_none()
+            && self.daily.is_none()
+            && self.drill.is_none()
+            && self.tutorial.is_none();
+        allowed.then_some(self.settings.idle_timeout)
+    }
+
+    // Holds the freshly started match for the intro. Online matches start
+    // as soon as both players are there, drills and the tutorial right away.
+    fn start_intro(&mut self) {
+        self.intro = Some(MatchIntro::new(
+            [self.side_name(Side::Left), self.side_name(Side::Right)],
+            self.sim.win_score,
+        ));
+    }
+
+    // Records the result and shows the calendar.
+    fn finish_daily(&mut self) {
+        let Some(challenge) = self.daily.take() else {
+            return;
+        };
+        let result = DailyResult {
+            points_for: self.sim.left.score,
+            points_against: self.sim.right.score,
+        };
+
+        let best = self
+            .progress
+            .daily
+            .entry(challenge.date.key())
+            .or_insert(result);
+        if result.is_better_than(best) {
+            *best = result;
+        }
+        self.save_progress();
+
+        self.toasts.push(self.locale.format(
+            "hud.daily_result",
+            &[
+                ("result", &self.locale.text(result.label_key())),
+                ("points_for", &result.points_for.localized(&self.locale)),
+                (
+                    "points_against",
+                    &result.points_against.localized(&self.locale),
+                ),
+            ],
+        ));
+        self.restart(Mutators::default());
+        self.restore_ai_before_daily();
+        self.daily_screen = Some(DailyScreen::new(challenge));
+        self.transitions.open(Screen::Daily);
+    }
+
+    fn restore_ai_before_daily(&mut self) {
+        let Some((profile, side)) = self.ai_before_daily.take() else {
+            return;
+        };
+        self.ai = profile.map(|profile| {
+            AiController::new(profile, self.progress.ai_difficulty, self.sim.height)
+        });
+        self.ai_side = side;
+    }
+
+    // The arena's own sound if it has one, the built-in one otherwise
+    fn play_sound(&self, id: SoundId) {
+        match self.arenas[self.arena].sounds.tone(id) {
+            Some(tone) => self.audio.play_tone(tone),
+            None => self.audio.play(id),
+        }
+    }
+
+    // The arena's colors with the players' own paddle colors, unless they
+    // would be hard to see on this arena's background.
+    fn colors(&self) -> ArenaColors {
+        let mut colors = self.arenas[self.arena].colors.clone();
+        let [left, right] = self.progress.paddle_colors();
+        let readable = |color: &Srgba| contrast_ratio(*color, colors.background) >= MIN_CONTRAST;
+        if let Some(color) = left.filter(readable) {
+            colors.left_paddle = color;
+        }
+        if let Some(color) = right.filter(readable) {
+            colors.right_paddle = color;
+        }
+        colors
+    }
+
+    // The field, paddles and ball. Drawn through the camera in photo mode.
+    fn draw_world(&self, renderer: &mut Renderer) {
+        let arena = &self.arenas[self.arena];
+        let colors = self.colors();
+        let positions = self.previous.lerp(&self.sim, self.timestep.alpha());
+        let textures = self.arena_textures.get(self.arena);
+        arena.draw_background(renderer, textures, &colors, self.sim.width, self.sim.height);
+        if self.progress.paddle_trails() {
+            self.paddle_trail
+                .draw(renderer, [colors.left_paddle, colors.right_paddle]);
+        }
+        arena.draw_paddles(renderer, textures, &colors, &self.sim, &positions);
+
+        self.particles.draw(renderer);
+
+        // Draw Ball
+        let ball = Ball {
+            pos: positions.ball,
+            ..self.sim.ball
+        };
+        self.ball_look
+            .draw(renderer, self.progress.ball_skin, &ball);
+
+        if let Some(serve) = self.sim.pending_serve {
+            draw_serve_aim(renderer, self.sim.ball.pos, &serve);
+            let ball = self.sim.ball.pos;
+            self.serve_bar.draw(
+                renderer,
+                Rect::new(
+                    ball.x - SERVE_BAR_WIDTH / 2.,
+                    ball.y + SERVE_ARC_RADIUS,
+                    SERVE_BAR_WIDTH,
+                    BAR_HEIGHT,
+                ),
+            );
+        }
+
+        // Draw portal flashes
+        for portal in self.portals.iter() {
+            portal.draw(renderer);
+        }
+
+        if let Some(drill) = self.drill.as_ref() {
+            drill.draw_world(renderer, &self.sim);
+        }
+
+        arena.draw_center_line(renderer, self.sim.width, self.sim.height);
+    }
+
+    // Scores, messages and any screen opened on top of the match.
+    fn draw_hud(&mut self, renderer: &mut Renderer) {
+        let colors = &self.colors();
+        let text_color = Some(glyph_color(colors.text));
+        let area = renderer.safe_area();
+        let font_size = self.settings.font_size;
+        let line_height = font_size * LINE_HEIGHT / FONT_SIZE;
+
+        renderer.draw_text(
+            &self.score_label(Side::Left),
+            Vector2::new(area.x, area.y),
+            font_size,
+            line_height,
+            text_color,
+        );
+
+        let text = self.locale.text("hud.title");
+        let text_width = renderer.measure_text(text, font_size, line_height);
+        renderer.draw_text(
+            text,
+            Vector2::new(renderer.size.width as f32 / 2. - text_width / 2., area.y),
+            font_size,
+            line_height,
+            text_color,
+        );
+
+        let text = &self.score_label(Side::Right);
+        let text_width = renderer.measure_text(text, font_size, line_height);
+        renderer.draw_text(
+            text,
+            Vector2::new(area.x + area.width - text_width, area.y),
+            font_size,
+            line_height,
+            text_color,
+        );
+
+        if let Some((message, remaining)) = self.ai_message.as_mut() {
+            *remaining -= self.clock.game_delta();
+            let text_width = renderer.measure_text(message, font_size, line_height);
+            renderer.draw_text(
+                message,
+                Vector2::new(
+                    area.x + area.width - text_width - self.sim.right.width * 2.,
+                    area.y + line_height * 2.,
+                ),
+                font_size,
+                line_height,
+                None,
+            );
+        }
+        if self
+            .ai_message
+            .as_ref()
+            .is_some_and(|(_, remaining)| *remaining <= 0.)
+        {
+            self.ai_message = None;
+        }
+
+        for side in [Side::Left, Side::Right] {
+            if !self.sim.mutators.save_assist(side) {
+                continue;
+            }
+            let x = match side {
+                Side::Left => area.x,
+                Side::Right => area.x + area.width - ASSIST_BAR_WIDTH,
+            };
+            self.assist_bar.draw(
+                renderer,
+                Rect::new(x, area.y + line_height + 4., ASSIST_BAR_WIDTH, BAR_HEIGHT),
+            );
+        }
+
+        // Under the score of a player the AI plays for
+        for side in [Side::Left, Side::Right] {
+            if !self.idle.is_playing(side) {
+                continue;
+            }
+            let text = self.locale.text("hud.idle_ai");
+            let (size, height) = (font_size * 0.6, line_height * 0.6);
+            let text_width = renderer.measure_text(text, size, height);
+            let x = match side {
+                Side::Left => area.x,
+                Side::Right => area.x + area.width - text_width,
+            };
+            renderer.draw_text(
+                text,
+                Vector2::new(x, area.y + line_height + BAR_HEIGHT + 8.),
+                size,
+                height,
+                Some(glyphon::Color::rgba(255, 255, 255, 200)),
+            );
+        }
+
+        self.timeline.draw(renderer, colors, &self.locale);
+        self.toasts.draw(renderer);
+        if self.settings.telemetry && self.main_menu.is_none() {
+            self.telemetry
+                .draw(renderer, &self.locale, &self.sim, |side| {
+                    self.side_name(side)
+                });
+        }
+
+        if let Some(latency) = self.latency.as_ref() {
+            let mut sync = vec![format!(
+                "Monitor: {}",
+                self.display_sync.monitor().unwrap_or("unknown")
+            )];
+            sync.extend(self.sync_status());
+            latency.draw(
+                renderer,
+                self.locale.text(self.pacer.pacing().label_key()),
+                &sync,
+            );
+        }
+
+        if let Some(tutorial) = self.tutorial.as_ref()
+            && self.main_menu.is_none()
+        {
+            let screen = Vector2::new(renderer.size.width as f32, renderer.size.height as f32);
+            let field = self.field_view(screen);
+            tutorial.draw(renderer, &self.sim, field, &self.locale, &self.prompts);
+        }
+
+        if let Some(drill) = self.drill.as_ref() {
+            let text = drill.progress_text(&self.locale);
+            let text_width = renderer.measure_text(&text, font_size, line_height);
+            renderer.draw_text(
+                &text,
+                Vector2::new(
+                    renderer.size.width as f32 / 2. - text_width / 2.,
+                    area.y + line_height * 2.5,
+                ),
+                font_size,
+                line_height,
+                text_color,
+            );
+
+            let text = if self.input.keys.contains(&REWIND_KEY) {
+                self.locale.text("drill.rewinding").to_string()
+            } else {
+                self.locale.format(
+                    "drill.rewind",
+                    &[(
+                        "seconds",
+                        &self.locale.number(self.rewind.available().floor()),
+                    )],
+                )
+            };
+            let (small, small_height) = (font_size * 0.6, line_height * 0.6);
+            let text_width = renderer.measure_text(&text, small, small_height);
+            renderer.draw_text(
+                &text,
+                Vector2::new(
+                    renderer.size.width as f32 / 2. - text_width / 2.,
+                    area.y + line_height * 3.5,
+                ),
+                small,
+                small_height,
+                text_color,
+            );
+        }
+
+        if let GameState::RoundOver { scorer, .. } = self.flow.state() {
+            let text = self
+                .locale
+                .format("round.point", &[("name", &self.side_name(scorer))]);
+            let text_width = renderer.measure_text(&text, font_size * 1.5, line_height * 1.5);
+            renderer.draw_text(
+                &text,
+                Vector2::new(
+                    renderer.size.width as f32 / 2. - text_width / 2.,
+                    area.y + area.height / 3.,
+                ),
+                font_size * 1.5,
+                line_height * 1.5,
+                text_color,
+            );
+        }
+        if let Some(intro) = self.intro.as_ref() {
+            intro.draw(renderer, &self.locale);
+        }
+        self.draw_screens(renderer);
+
+        let mut mutators = self.sim.mutators.active_names(&self.locale);
+        if self.daily.is_some() {
+            mutators.insert(0, self.locale.text("hud.daily_challenge").to_string());
+        }
+        if !mutators.is_empty() {
+            renderer.draw_text(
+                &mutators.join(" | "),
+                Vector2::new(area.x, area.y + area.height - line_height),
+                font_size,
+                line_height,
+                None,
+            );
+        }
+    }
+
+    // Takes the name or ruleset code. An invalid code keeps the entry open
+    // and says so.
+    fn submit_entry(&mut self, renderer: &mut Renderer, window: &Window) {
+        let Some(entry) = self.name_entry.as_mut() else {
+            return;
+        };
+        match entry.kind() {
+            EntryKind::PlayerName => {
+                self.progress.player_name = entry.text().to_string();
+                self.replay.header.players[0] = self.progress.player_name().to_string();
+                self.save_progress();
+            }
+            EntryKind::RulesetCode => match Ruleset::from_code(entry.text()) {
+                Ok(ruleset) => self.apply_ruleset(ruleset, renderer, window),
+                Err(err) => {
+                    info!("Rejected ruleset code: {:#}", err);
+                    entry.set_error(self.locale.text("name_entry.invalid_code").to_string());
+                    return;
+                }
+            },
+        }
+        self.close_name_entry(window);
+    }
+
+    fn close_name_entry(&mut self, window: &Window) {
+        self.transitions.close(Screen::NameEntry);
+        self.input.keys.clear();
+        window.set_ime_allowed(false);
+    }
+
+    // Drops a screen once it has animated out.
+    fn drop_screen(&mut self, screen: Screen) {
+        match screen {
+            Screen::Menu => self.menu = None,
+            Screen::Gallery => self.gallery = None,
+            Screen::Daily => self.daily_screen = None,
+            Screen::Calibration => self.calibration = None,
+            Screen::NameEntry => self.name_entry = None,
+            Screen::PaddleColors => self.paddle_colors = None,
+            Screen::MainMenu => self.main_menu = None,
+            Screen::GameOver => self.game_over = None,
+            Screen::Drills => self.drill_screen = None,
+            Screen::Options => self.options = None,
+        }
+    }
+
+    // The screens opened on top of the match, each with its transition.
+    fn draw_screens(&mut self, renderer: &mut Renderer) {
+        let width = renderer.size.width as f32;
+        let transitions = &self.transitions;
+
+        if let Some(main_menu) = self.main_menu.as_ref()
+            && let Some(layer) = transitions.layer(Screen::MainMenu, width)
+        {
+            renderer.set_layer(Some(layer));
+            main_menu.draw(
+                renderer,
+                &self.ai_profiles,
+                &self.settings.keys,
+                &self.locale,
+                &self.prompts,
+            );
+        }
+        if let Some(screen) = self.drill_screen.as_ref()
+            && let Some(layer) = transitions.layer(Screen::Drills, width)
+        {
+            renderer.set_layer(Some(layer));
+            screen.draw(renderer, &self.progress, &self.locale);
+        }
+        if let Some(screen) = self.game_over.as_ref()
+            && let Some(layer) = transitions.layer(Screen::GameOver, width)
+        {
+            renderer.set_layer(Some(layer));
+            screen.draw(renderer, &self.locale, &self.prompts);
+        }
+        if let Some(menu) = self.menu.as_ref()
+            && let Some(layer) = transitions.layer(Screen::Menu, width)
+        {
+            renderer.set_layer(Some(layer));
+            menu.draw(
+                renderer,
+                &self.progress,
+                &self.arenas[self.arena].name,
+                &self.locale,
+            );
+        }
+        if let Some(gallery) = self.gallery.as_ref()
+            && let Some(layer) = transitions.layer(Screen::Gallery, width)
+        {
+            renderer.set_layer(Some(layer));
+            gallery.draw(renderer, &self.progress, &self.locale);
+        }
+        if let Some(screen) = self.daily_screen.as_ref()
+            && let Some(layer) = transitions.layer(Screen::Daily, width)
+        {
+            renderer.set_layer(Some(layer));
+            screen.draw(renderer, &self.progress, &self.locale);
+        }
+        if let Some(name_entry) = self.name_entry.as_mut()
+            && let Some(layer) = transitions.layer(Screen::NameEntry, width)
+        {
+            renderer.set_layer(Some(layer));
+            name_entry.draw(renderer, &self.locale);
+        }
+        if let Some(screen) = self.paddle_colors.as_ref()
+            && let Some(layer) = transitions.layer(Screen::PaddleColors, width)
+        {
+            renderer.set_layer(Some(layer));
+            screen.draw(renderer, &self.locale);
+        }
+        if let Some(screen) = self.options.as_ref()
+            && let Some(layer) = transitions.layer(Screen::Options, width)
+        {
+            renderer.set_layer(Some(layer));
+            let status = self.sync_status();
+            screen.draw(renderer, &self.locale, &self.prompts, &status);
+        }
+        if let Some(calibration) = self.calibration.as_ref()
+            && let Some(layer) = transitions.layer(Screen::Calibration, width)
+        {
+            renderer.set_layer(Some(layer));
+            calibration.draw(renderer, &self.locale);
+        }
+        renderer.set_layer(None);
+    }
+
+    // Controller buttons only act in the match itself, the screens on top
+    // of it are driven by the keyboard
+    fn gamepad_pressed(&mut self, button: GamepadButton) {
+        let screen_open = self.menu.is_some()
+            || self.main_menu.is_some()
+            || self.game_over.is_some()
+            || self.drill_screen.is_some()
+            || self.daily_screen.is_some()
+            || self.gallery.is_some()
+            || self.calibration.is_some()
+            || self.paddle_colors.is_some()
+            || self.options.is_some()
+            || self.name_entry.is_some()
+            || self.photo.is_some()
+            || self.playback.is_some();
+        if screen_open {
+            return;
+        }
+        if let Some(intro) = self.intro.as_mut()
+            && intro.holds_match()
+        {
+            intro.skip();
+            return;
+        }
+        match button {
+            GamepadButton::South => {
+                self.reset_requested = true;
+                if let Some(tutorial) = self.tutorial.as_mut() {
+                    tutorial.handle_action(Action::Serve);
+                }
+            }
+            GamepadButton::Start if self.tutorial.is_some() => self.finish_tutorial(),
+            GamepadButton::East | GamepadButton::Start => (),
+        }
+    }
+
+    fn finish_tutorial(&mut self) {
+        self.tutorial = None;
+        self.progress.tutorial_completed = true;
+        self.save_progress();
+    }
+
+    fn save_progress(&self) {
+        if let Err(err) = self.progress.save() {
+            error!("Failed to save progress: {:#}", err);
+        }
+    }
+
+    fn save_settings(&self) {
+        if let Err(err) = self.settings.save() {
+            error!("Failed to save settings: {:#}", err);
+        }
+    }
+
+    // Hands the display settings to a newly created renderer
+    fn apply_display_settings(&mut self, renderer: &mut Renderer, window: &Window) {
+        self.configure_pacing(self.settings.pacing, renderer, window);
+        renderer.set_gamma(self.settings.gamma);
+        renderer.set_brightness(self.settings.brightness);
+        renderer.set_hud_inset(self.settings.hud_inset);
+        renderer.set_font(self.settings.font);
+        self.set_window_size(renderer.size);
+    }
+
+    // Sizes of 0 while minimized keep the last aspect
+    fn set_window_size(&mut self, size: PhysicalSize<u32>) {
+        if size.width > 0 && size.height > 0 {
+            self.window_aspect = size.width as f32 / size.height as f32;
+        }
+    }
+
+    // Everything on screen is drawn from the locale each frame, so the new
+    // language shows with the next one
+    fn switch_language(&mut self, forward: bool) {
+        if forward {
+            self.locale.next();
+        } else {
+            self.locale.previous();
+        }
+        self.progress.language = Some(self.locale.language().code.clone());
+        self.save_progress();
+    }
+
+    // Where the field is drawn on `screen` and the camera that fits it there
+    fn field_view(&self, screen: Vector2<f32>) -> (Rect, Camera2D) {
+        let field = Vector2::new(self.sim.width, self.sim.height);
+        if self.settings.pixel_art {
+            let (viewport, camera, _) = pixel_letterbox(field, PIXEL_RESOLUTION, screen);
+            (viewport, camera)
+        } else {
+            letterbox(field, screen)
+        }
+    }
+
+    fn sync_status(&self) -> Vec<String> {
+        self.display_sync
+            .describe(&self.locale, self.pacer.pacing(), self.pacer.target_fps())
+    }
+
+    // The present mode and the frame budget of the dynamic resolution follow
+    // the pacing, or the menu cap while it is on
+    fn configure_pacing(
+        &mut self,
+        pacing: FramePacing,
+        renderer: &mut Renderer,
+        window: &Window,
+    ) -> wgpu::PresentMode {
+        self.pacer.configure(pacing, window);
+        self.resolution.set_budget(self.pacer.frame_budget(window));
+        renderer.set_present_mode(self.pacer.present_modes())
+    }
+
+    // Nothing moves on its own until the player presses something, e.g. a
+    // menu or the tutorial holding the ball
+    fn waits_for_input(&self) -> bool {
+        self.in_menus()
+            || self.menu.is_some()
+            || self.calibration.is_some()
+            || self.photo.is_some()
+            || self.is_online_client()
+            || self
+                .tutorial
+                .as_ref()
+                .is_some_and(|tutorial| tutorial.holds_ball())
+    }
+
+    // Nothing but menus on screen, so frames can be slow. Online the match
+    // goes on behind the pause menu.
+    fn in_menus(&self) -> bool {
+        self.online.is_none()
+            && (self.main_menu.is_some()
+                || self.menu.is_some()
+                || self.options.is_some()
+                || self.game_over.is_some()
+                || self.drill_screen.is_some()
+                || self.gallery.is_some()
+                || self.daily_screen.is_some()
+                || self.name_entry.is_some()
+                || self.paddle_colors.is_some())
+    }
+
+    // Switches between the menu cap and the pacing of the match as menus
+    // open and close
+    fn update_menu_pacing(&mut self, renderer: &mut Renderer, window: &Window) {
+        let in_menu = self.settings.menu_power_saving && self.in_menus();
+        if in_menu != self.pacer.in_menu() {
+            self.pacer.set_menu(in_menu);
+            self.configure_pacing(self.pacer.pacing(), renderer, window);
+        }
+    }
+
+    // Keys, text size and pacing change right away, the speeds with the next
+    // match so a replay never mixes them
+    fn apply_settings(&mut self, settings: Settings, renderer: &mut Renderer, window: &Window) {
+        if settings.pacing != self.settings.pacing {
+            self.configure_pacing(settings.pacing, renderer, window);
+        }
+        // Replays keep the length of every step, so the rate can change
+        // in the middle of a match
+        if self.online.is_none() && self.daily.is_none() {
+            self.timestep.set_rate(settings.tick_rate);
+        }
+        if !settings.dynamic_resolution {
+            self.resolution.reset();
+        }
+        renderer.set_font(settings.font);
+        self.settings = settings;
+        for (index, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+            self.players[index] = PlayerController::new(side, &self.settings.keys);
+        }
+        self.prompts.set_bindings(&self.settings.keys);
+        self.save_settings();
+    }
+
+    // The rules of the next local match, as shared by ruleset codes
+    fn ruleset(&self) -> Ruleset {
+        Ruleset {
+            mutators: Mutators {
+                practice_wall: false,
+                ..self.sim.mutators
+            },
+            win_score: self.progress.win_score(),
+            paddle_speed: self.settings.paddle_speed,
+            ball_speed: self.settings.ball_speed,
+            tick_rate: self.settings.tick_rate,
+        }
+    }
+
+    // Plays by the rules of someone else's code from now on. The speeds and
+    // the physics rate are saved like the options, the win score like the
+    // main menu's.
+    fn apply_ruleset(&mut self, ruleset: Ruleset, renderer: &mut Renderer, window: &Window) {
+        let settings = Settings {
+            paddle_speed: ruleset.paddle_speed,
+            ball_speed: ruleset.ball_speed,
+            tick_rate: ruleset.tick_rate,
+            ..self.settings.clone()
+        };
+        self.apply_settings(settings, renderer, window);
+        self.progress.win_score = Some(ruleset.win_score);
+        self.save_progress();
+        if let Some(main_menu) = self.main_menu.as_mut() {
+            main_menu.refresh(&self.progress);
+        }
+        self.restart(ruleset.mutators);
+        self.toasts
+            .push(self.locale.text("hud.ruleset_applied").to_string());
+    }
+
+    // Starts a fresh match and recording with a new seed. Called whenever the
+    // settings stored in the replay header change, so a replay never mixes rule sets.
+    fn restart(&mut self, mutators: Mutators) {
+        // Only a drill plays against the practice wall
+        let mutators = Mutators {
+            practice_wall: false,
+            ..mutators
+        };
+        // Both players of an online match need the same field
+        let field = if self.settings.fit_field && self.online.is_none() {
+            fit_field(self.window_aspect)
+        } else {
+            (FIELD_WIDTH, FIELD_HEIGHT)
+        };
+        self.start(mutators, rand::random::<u64>(), field);
+    }
+
+    fn start(&mut self, mutators: Mutators, seed: u64, (width, height): (f32, f32)) {
+        let left_daily = self.daily.take().is_some();
+        self.drill = None;
+        self.rewind = Rewind::new();
+        self.telemetry = Telemetry::new();
+        self.intro = None;
+        self.idle = IdleTakeover::new(
+            self.ai_profiles.first().cloned(),
+            self.progress.ai_difficulty,
+        );
+        self.timestep.set_rate(match self.online.as_ref() {
+            Some(online) => online.tick_rate(),
+            None => self.settings.tick_rate,
+        });
+        self.sim = GameSim::new(width, height, mutators, seed);
+        self.sim.win_score = self.progress.win_score();
+        // Lets the host's replays play back the same on the other player's machine,
+        // where the speeds are the defaults
+        if self.online.is_some() {
+            self.sim.math = Math::Strict;
+        } else {
+            self.sim.paddle_speed = self.settings.paddle_speed;
+            self.sim.serve_speed = self.settings.ball_speed;
+        }
+        self.sim.fit_to_field();
+        if left_daily {
+            self.restore_ai_before_daily();
+        }
+        self.replay = Replay::new(
+            [self.progress.player_name().to_string(), "P2".to_string()],
+            self.sim.settings(),
+            seed,
+        );
+        self.timeline = Timeline::new(self.sim.serving_toward());
+        self.serve_bar.reset(1.0);
+    }
+
+    // Replaces the match with a playback of `replay` on its original field.
+    fn start_playback(&mut self, replay: Replay) {
+        self.sim = GameSim::from_replay(&replay.header);
+        self.timeline = Timeline::new(self.sim.serving_toward());
+        self.ai = None;
+        self.tutorial = None;
+        self.director = Some(CameraDirector::new(&self.sim));
+        self.playback = Some(Playback::new(replay));
+        self.flow.play();
+        self.transitions.close(Screen::MainMenu);
+    }
+
+    // Starts a match from the main menu, against the AI profile at `ai` if set.
+    fn play_match(&mut self, ai: Option<usize>) {
+        self.restart(self.sim.mutators);
+        self.ai = ai
+            .and_then(|index| self.ai_profiles.get(index))
+            .map(|profile| {
+                AiController::new(
+                    profile.clone(),
+                    self.progress.ai_difficulty,
+                    self.sim.height,
+                )
+            });
+        self.ai_side = self.progress.ai_side();
+        self.flow.play();
+        self.start_intro();
+        self.transitions.close(Screen::MainMenu);
+    }
+
+    // Plays on the host's side or joins it, the connection is already set up.
+    fn start_online(&mut self, link: PeerLink, role: Role) {
+        let name = self.progress.player_name().to_string();
+        self.online = Some(OnlineMatch::new(link, role, name, self.settings.tick_rate));
+        self.ai = None;
+        self.tutorial = None;
+        // Either player uses the first controller
+        self.players[1].set_gamepad(0);
+        self.restart(self.sim.mutators);
+        self.flow.play();
+        self.main_menu = None;
+        self.transitions.close(Screen::MainMenu);
+    }
+
+    fn is_online_client(&self) -> bool {
+        self.online
+            .as_ref()
+            .is_some_and(|online| online.role() == Role::Client)
+    }
+
+    // Exchanges packets with the other player. The host sends its match, the
+    // client takes the match over from it instead of simulating.
+    fn update_online(&mut self, delta: f32) {
+        let Some(online) = self.online.as_mut() else {
+            return;
+        };
+        let opponent = online.role().side().opponent();
+        for event in online.poll(&self.sim) {
+            match event {
+                OnlineEvent::Joined {
+                    name,
+                    mutators,
+                    win_score,
+                    tick_rate,
+                } => {
+                    if self.is_online_client() {
+                        self.sim.mutators = mutators;
+                        self.sim.win_score = win_score;
+                        // Only stepped after taking over from the host
+                        self.timestep.set_rate(tick_rate);
+                    }
+                    let text = self.locale.format("hud.online_joined", &[("name", &name)]);
+                    self.toasts.push(text);
+                }
+                OnlineEvent::Restarted => {
+                    self.flow.play();
+                    self.transitions.close(Screen::GameOver);
+                }
+                OnlineEvent::Desynced => {
+                    let text = self.locale.text("hud.online_desync").to_string();
+                    self.toasts.push(text);
+                }
+                OnlineEvent::Migrated { state, events }
+                    if !matches!(self.flow.state(), GameState::GameOver { .. }) =>
+                {
+                    self.take_over_online(&state, events);
+                    return;
+                }
+                OnlineEvent::Migrated { .. } | OnlineEvent::Left => {
+                    let name = self.side_name(opponent);
+                    let text = self.locale.format("hud.online_left", &[("name", &name)]);
+                    self.toasts.push(text);
+                    self.show_main_menu();
+                    return;
+                }
+            }
+        }
+
+        let Some(online) = self.online.as_mut() else {
+            return;
+        };
+        match online.role() {
+            Role::Host => online.host_update(delta, &self.sim),
+            Role::Client => {
+                let direction =
+                    self.players[1].direction(delta, &self.sim, Side::Right, &self.input);
+                let events = online.client_update(delta, direction, &mut self.sim);
+                self.previous = Positions::of(&self.sim);
+                self.apply_events(delta, events);
+            }
+        }
+    }
+
+    // The host's game went away mid-match: ours simulates it from `state`
+    // on, with the AI playing the host's paddle.
+    fn take_over_online(&mut self, state: &SimState, events: Vec<SimEvent>) {
+        let opponent = Role::Host.side();
+        let name = self.side_name(opponent);
+        self.online = None;
+        self.sim = GameSim::from_state(state);
+        self.previous = Positions::of(&self.sim);
+        self.ai = self.ai_profiles.first().map(|profile| {
+            AiController::new(
+                profile.clone(),
+                self.progress.ai_difficulty,
+                self.sim.height,
+            )
+        });
+        self.ai_side = opponent;
+        self.apply_events(0.0, events);
+
+        let text = match self.ai.as_ref() {
+            Some(ai) => self.locale.format(
+                "hud.online_migrated",
+                &[("name", &name), ("ai", &ai.profile().name)],
+            ),
+            None => self.locale.format("hud.online_left", &[("name", &name)]),
+        };
+        self.toasts.push(text);
+    }
+
+    // Leaves the match for the main menu, the field stays frozen behind it.
+    fn show_main_menu(&mut self) {
+        self.online = None;
+        self.restart(self.sim.mutators);
+        self.flow.main_menu();
+        self.main_menu = Some(MainMenu::new(&self.progress));
+        self.transitions.open(Screen::MainMenu);
+    }
+
+    // Shown for a point or a win
+    fn side_name(&self, side: Side) -> String {
+        if let Some(online) = self.online.as_ref() {
+            return match online.peer_name() {
+                Some(name) if side != online.role().side() => name.to_string(),
+                _ if side != online.role().side() => "P2".to_string(),
+                _ => self.progress.player_name().to_string(),
+            };
+        }
+        match (side, self.ai.as_ref()) {
+            (side, Some(ai)) if side == self.ai_side => ai.profile().name.clone(),
+            // Against the AI the player may be on either side
+            (_, Some(_)) | (Side::Left, None) => self.progress.player_name().to_string(),
+            (Side::Right, None) => "P2".to_string(),
+        }
+    }
+
+    // For the scoreboard window
+    fn scoreboard(&self) -> Scoreboard {
+        let colors = self.colors();
+        Scoreboard {
+            names: [self.side_name(Side::Left), self.side_name(Side::Right)],
+            scores: [self.sim.left.score, self.sim.right.score],
+            colors: [colors.left_paddle, colors.right_paddle],
+            win_score: self.sim.win_score,
+            rally: self.timeline.rally(),
+            longest_rally: self.timeline.longest_rally(),
+            seconds: self.timeline.elapsed() as u32,
+        }
+    }
+
+    // Name and score in the HUD, with the adaptive AI's current skill
+    fn score_label(&self, side: Side) -> String {
+        let score = self.sim.paddle(side).score.localized(&self.locale);
+        match self.ai.as_ref().filter(|_| side == self.ai_side) {
+            Some(ai) => match ai.skill() {
+                Some(skill) => format!("{} ({:.0}%): {}", ai.profile().name, skill * 100.0, score),
+                None => format!("{}: {}", ai.profile().name, score),
+            },
+            None => format!("{}: {}", self.side_name(side), score),
+        }
+    }
+
+    fn draw_playback_hud(&self, renderer: &mut Renderer) {
+        let (Some(playback), Some(director)) = (self.playback.as_ref(), self.director.as_ref())
+        else {
+            return;
+        };
+        let replay = playback.replay();
+        let status = if playback.is_finished() {
+            "playback.finished"
+        } else if playback.is_paused() {
+            "playback.paused"
+        } else {
+            "playback.playing"
+        };
+        let camera = if director.is_manual() {
+            "playback.camera_manual"
+        } else {
+            "playback.camera_auto"
+        };
+        let text = self.locale.format(
+            "playback.hud",
+            &[
+                ("left", &replay.header.players[0]),
+                ("right", &replay.header.players[1]),
+                ("elapsed", &format!("{:.1}", playback.elapsed())),
+                ("duration", &format!("{:.1}", replay.duration())),
+                ("status", &self.locale.text(status)),
+                ("camera", &self.locale.text(camera)),
+            ],
+        );
+        let area = renderer.safe_area();
+        renderer.draw_text(
+            &text,
+            Vector2::new(area.x, area.y + area.height - LINE_HEIGHT * 2.5),
+            FONT_SIZE * 0.6,
+            LINE_HEIGHT * 0.75,
+            Some(glyphon::Color::rgb(255, 255, 0)),
         );
     }
 }
@@ -61,64 +1634,276 @@ pub struct App {
     window: Option<Arc<Window>>,
     renderer: Option<Renderer>,
     state: Option<State>,
+    // Played back instead of a live match once the state exists
+    watch: Option<Replay>,
+    // Started instead of the main menu once the state exists
+    online: Option<(PeerLink, Role)>,
+    // Assets read on another thread before the state exists, shown as a
+    // loading screen
+    loading: Option<Loading>,
+    lifecycle: WindowLifecycle,
+    // Writes the lifecycle events of this run, see `--record-session`
+    recorder: Option<SessionRecorder>,
+    // Feeds recorded lifecycle events to the app, see `--replay-session`
+    session: Option<SessionPlayer>,
+    // Scripted key presses, see `--input-script`
+    script: Option<ScriptPlayer>,
+    // The scoreboard window, see Shift + F6
+    overlay: Option<StreamOverlay>,
+    // Filled by the task creating the renderer in the browser
+    #[cfg(target_arch = "wasm32")]
+    pending_renderer: Rc<RefCell<Option<Result<Renderer>>>>,
+}
+
+impl App {
+    pub fn watch(replay: Replay) -> Self {
+        Self {
+            watch: Some(replay),
+            ..Self::default()
+        }
+    }
+
+    pub fn online(link: PeerLink, role: Role) -> Self {
+        Self {
+            online: Some((link, role)),
+            ..Self::default()
+        }
+    }
+
+    pub fn record_session(mut self, recorder: SessionRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn replay_session(mut self, session: SessionPlayer) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    pub fn input_script(mut self, script: ScriptPlayer) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    fn renderer_created(&mut self, result: Result<Renderer>) {
+        match result {
+            Ok(mut renderer) => {
+                // Keep the match going if the renderer had to be recreated
+                match self.state.as_mut() {
+                    Some(state) => {
+                        if let Some(window) = self.window.as_ref() {
+                            state.apply_display_settings(&mut renderer, window);
+                        }
+                        state.clock.resync();
+                    }
+                    None => {
+                        if self.loading.is_none() {
+                            self.loading = Some(Loading::start());
+                        }
+                    }
+                }
+
+                self.renderer = Some(renderer);
+            }
+            Err(err) => {
+                error!("Failed to create renderer: {}", err);
+            }
+        }
+    }
+
+    // A frame of the loading screen, creating the state once the assets are in
+    fn loading_frame(&mut self) {
+        let (Some(window), Some(renderer), Some(loading)) = (
+            self.window.as_ref(),
+            self.renderer.as_mut(),
+            self.loading.as_mut(),
+        ) else {
+            return;
+        };
+        if renderer.is_suspended() || !self.lifecycle.is_visible() {
+            return;
+        }
+        let Some(assets) = loading.poll(renderer) else {
+            return loading.draw(renderer);
+        };
+
+        self.loading = None;
+        let mut state = State::new(assets, renderer);
+        state.apply_display_settings(renderer, window);
+        if let Some(replay) = self.watch.take() {
+            state.start_playback(replay);
+        }
+        if let Some((link, role)) = self.online.take() {
+            state.start_online(link, role);
+        }
+        self.state = Some(state);
+    }
+
+    // Opens or closes the scoreboard window as the state wants it, and
+    // redraws it when the match changes
+    fn sync_stream_overlay(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+        match (state.stream_overlay, self.overlay.as_mut()) {
+            (true, Some(overlay)) => overlay.update(&state.scoreboard()),
+            // Opens once the game's renderer is there to copy the fonts of
+            (true, None) => {
+                let Some(renderer) = self.renderer.as_ref() else {
+                    return;
+                };
+                match StreamOverlay::open(event_loop, renderer.clone_font_system()) {
+                    Ok(overlay) => self.overlay = Some(overlay),
+                    Err(err) => {
+                        error!("Failed to open the scoreboard window: {:#}", err);
+                        state.stream_overlay = false;
+                    }
+                }
+            }
+            (false, Some(_)) => self.overlay = None,
+            (false, None) => (),
+        }
+    }
+
+    fn lifecycle_event(&mut self, event: LifecycleEvent) {
+        self.lifecycle.apply(event);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(event);
+        }
+    }
+
+    // Hands a recorded event to the app as if the platform had sent it
+    fn replay_lifecycle_event(&mut self, event_loop: &ActiveEventLoop, event: LifecycleEvent) {
+        info!("Replaying {:?}", event);
+        match event {
+            LifecycleEvent::Suspended => self.suspended(event_loop),
+            LifecycleEvent::Resumed => self.resumed(event_loop),
+            event => match (event.to_window_event(), self.window.clone()) {
+                (Some(window_event), Some(window)) => {
+                    if let WindowEvent::Resized(size) = window_event {
+                        let _ = window.request_inner_size(size);
+                    }
+                    self.window_event(event_loop, window.id(), window_event);
+                }
+                _ => self.lifecycle_event(event),
+            },
+        }
+    }
 }
 
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window = match event_loop.create_window(Window::default_attributes()) {
-            Ok(window) => Arc::new(window),
-            Err(err) => {
-                return error!("Failed to create window: {}", err);
+        self.lifecycle_event(LifecycleEvent::Resumed);
+        // Back from `suspended`: only the surface has to be recreated
+        if let (Some(window), Some(renderer)) = (self.window.as_ref(), self.renderer.as_mut()) {
+            match renderer.resume() {
+                Ok(()) => {
+                    if let Some(state) = self.state.as_mut() {
+                        // Don't simulate the time spent in the background
+                        state.clock.resync();
+                    }
+                    return window.request_redraw();
+                }
+                Err(err) => {
+                    error!("Failed to resume renderer, recreating it: {:#}", err);
+                    self.renderer = None;
+                }
             }
+        }
+
+        let window = match self.window.clone() {
+            Some(window) => window,
+            None => match event_loop.create_window(window_attributes()) {
+                Ok(window) => Arc::new(window),
+                Err(err) => {
+                    return error!("Failed to create window: {}", err);
+                }
+            },
         };
 
-        match pollster::block_on(Renderer::new(window.clone())) {
-            Ok(renderer) => {
-                // Initial ball velocity (moving right and slightly down)
-                let initial_velocity = Vector2::new(BALL_SPEED, BALL_SPEED / 3.0);
+        let fonts = self.state.is_some();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.renderer_created(pollster::block_on(create_renderer(window.clone(), fonts)));
+        // The browser can't block on the GPU, the renderer is handed over in
+        // `about_to_wait` once it's ready
+        #[cfg(target_arch = "wasm32")]
+        {
+            let pending = self.pending_renderer.clone();
+            let window = window.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = create_renderer(window.clone(), fonts).await;
+                *pending.borrow_mut() = Some(result);
+                window.request_redraw();
+            });
+        }
 
-                self.state = Some(State {
-                    left: Paddle {
-                        pos: Vector2 {
-                            x: 0.0,
-                            y: (renderer.size.height / 2) as f32,
-                        },
-                        score: 0,
-                        width: 20.,
-                        height: 100.,
-                    },
-                    right: Paddle {
-                        pos: Vector2 {
-                            x: renderer.size.width as f32,
-                            y: (renderer.size.height / 2) as f32,
-                        },
-                        score: 0,
-                        width: 20.,
-                        height: 100.,
-                    },
-                    ball: Ball {
-                        pos: Vector2 {
-                            x: (renderer.size.width / 2) as f32,
-                            y: (renderer.size.height / 2) as f32,
-                        },
-                        velocity: initial_velocity,
-                        radius: BALL_RADIUS,
-                    },
-                    keys_pressed: HashSet::new(),
-                    last_update: Instant::now(),
-                });
+        // Not every platform sends a resize for the new window
+        self.lifecycle.size = window.inner_size();
+        self.lifecycle.scale_factor = window.scale_factor();
+        self.window = Some(window);
+    }
 
-                self.renderer = Some(renderer);
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(recorder) = self.recorder.as_ref() {
+            match recorder.save() {
+                Ok(path) => info!("Saved session log to {}", path.display()),
+                Err(err) => error!("Failed to save session log: {:#}", err),
             }
-            Err(err) => {
-                error!("Failed to create renderer: {}", err);
+        }
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        self.lifecycle_event(LifecycleEvent::Suspended);
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.suspend();
+        }
+
+        // Key releases are not delivered while in the background
+        if let Some(state) = self.state.as_mut() {
+            state.input.keys.clear();
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        if let Some(overlay) = self.overlay.as_mut()
+            && overlay.id() == id
+        {
+            match event {
+                WindowEvent::CloseRequested => {
+                    self.overlay = None;
+                    if let Some(state) = self.state.as_mut() {
+                        state.stream_overlay = false;
+                    }
+                }
+                WindowEvent::Resized(size) => overlay.resize(size),
+                WindowEvent::RedrawRequested => {
+                    if let Some(state) = self.state.as_ref()
+                        && let Err(err) = overlay.draw(state.scoreboard(), &state.locale)
+                    {
+                        error!("Failed to draw the scoreboard window: {:#}", err);
+                    }
+                }
+                _ => (),
             }
+            return;
         }
 
-        self.window = Some(window);
-    }
-
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        if let Some(lifecycle) = LifecycleEvent::from_window_event(&event) {
+            self.lifecycle_event(lifecycle);
+        }
+        if self.state.is_none() && self.loading.is_some() {
+            match event {
+                WindowEvent::CloseRequested => event_loop.exit(),
+                WindowEvent::RedrawRequested => self.loading_frame(),
+                WindowEvent::Resized(size) => {
+                    if let Some(renderer) = self.renderer.as_mut() {
+                        renderer.resize(size);
+                    }
+                }
+                _ => (),
+            }
+            return;
+        }
         let Some(window) = self.window.as_ref() else {
             return info!("Skip window_event handling. We have no window");
         };
@@ -141,218 +1926,1104 @@ impl ApplicationHandler for App {
                 event,
                 is_synthetic: _,
             } => {
-                if let PhysicalKey::Code(key_code) = event.physical_key {
-                    match event.state {
-                        ElementState::Pressed => {
-                            state.keys_pressed.insert(key_code);
-
-                            // Reset the ball if Space is pressed
-                            if key_code == KeyCode::Space {
-                                state
-                                    .ball
-                                    .reset(renderer.size.width as f32, renderer.size.height as f32);
+                if let Some(latency) = state.latency.as_mut()
+                    && !event.repeat
+                {
+                    latency.input_received(Instant::now());
+                }
+                if event.state == ElementState::Pressed {
+                    state.input.use_keyboard();
+                }
+
+                if let Some(name_entry) = state.name_entry.as_mut()
+                    && !state.transitions.is_leaving(Screen::NameEntry)
+                {
+                    // Tracked for the modifiers, cleared again when the field closes
+                    if let PhysicalKey::Code(key_code) = event.physical_key {
+                        match event.state {
+                            ElementState::Pressed => state.input.keys.insert(key_code),
+                            ElementState::Released => state.input.keys.remove(&key_code),
+                        };
+                    }
+                    let shift = state.input.keys.contains(&KeyCode::ShiftLeft)
+                        || state.input.keys.contains(&KeyCode::ShiftRight);
+                    let ctrl = state.input.keys.contains(&KeyCode::ControlLeft)
+                        || state.input.keys.contains(&KeyCode::ControlRight);
+                    match name_entry.handle_key(&event, shift, ctrl) {
+                        Some(TextFieldAction::Submit) => state.submit_entry(renderer, window),
+                        Some(TextFieldAction::Cancel) => state.close_name_entry(window),
+                        Some(TextFieldAction::Copy) => {
+                            if let Err(err) = state.clipboard.copy_text(name_entry.selected_text())
+                            {
+                                error!("Failed to copy: {:#}", err);
                             }
                         }
-                        ElementState::Released => {
-                            state.keys_pressed.remove(&key_code);
-                        }
+                        Some(TextFieldAction::Paste) => match state.clipboard.paste_text() {
+                            Ok(text) => name_entry.paste(&text),
+                            Err(err) => error!("Failed to paste: {:#}", err),
+                        },
+                        None => (),
                     }
+                    return;
+                }
+
+                if let PhysicalKey::Code(key_code) = event.physical_key {
+                    handle_key(
+                        event_loop,
+                        window,
+                        renderer,
+                        state,
+                        key_code,
+                        event.state,
+                        event.repeat,
+                    );
+                }
+            }
+            WindowEvent::Ime(ime) => {
+                if let Some(name_entry) = state.name_entry.as_mut() {
+                    name_entry.handle_ime(&ime);
                 }
             }
             WindowEvent::RedrawRequested => {
+                // The game is paused while suspended or minimized
+                if renderer.is_suspended() || !self.lifecycle.is_visible() {
+                    return;
+                }
+
                 let now = Instant::now();
-                let delta = now.duration_since(state.last_update).as_secs_f32();
-                state.last_update = now;
+                let delta = state.clock.frame(now);
+                state.update_menu_pacing(renderer, window);
+                state.pacer.frame_started(now);
+                // The VRR cap and the frame budget depend on the monitor
+                if state.display_sync.update_monitor(window) {
+                    info!(
+                        "Window on {} at {}",
+                        state.display_sync.monitor().unwrap_or("an unknown monitor"),
+                        state.display_sync.refresh_rate().map_or(
+                            "an unknown refresh rate".to_string(),
+                            |hz| format!("{:.0} Hz", hz)
+                        )
+                    );
+                    state.configure_pacing(state.pacer.pacing(), renderer, window);
+                }
+                let render_scale = if state.settings.dynamic_resolution {
+                    state.resolution.update(delta)
+                } else {
+                    1.0
+                };
+                renderer.set_render_scale(render_scale, state.settings.upscale);
+                let pixel_scale = state.settings.pixel_art.then(|| {
+                    let field = Vector2::new(state.sim.width, state.sim.height);
+                    let screen =
+                        Vector2::new(renderer.size.width as f32, renderer.size.height as f32);
+                    pixel_letterbox(field, PIXEL_RESOLUTION, screen).2
+                });
+                renderer.set_pixel_art(pixel_scale, state.settings.scanlines);
+                state.toasts.update(delta);
+                for event in state.input.poll() {
+                    let (key, slot) = match event {
+                        GamepadEvent::Connected { slot } => ("hud.gamepad_connected", slot),
+                        GamepadEvent::Disconnected { slot } => ("hud.gamepad_disconnected", slot),
+                        GamepadEvent::Pressed { button } => {
+                            state.gamepad_pressed(button);
+                            continue;
+                        }
+                    };
+                    let text = state.locale.format(key, &[("player", &(slot + 1))]);
+                    state.toasts.push(text);
+                }
+                state.prompts.set_device(state.input.device());
+                if let Some(name_entry) = state.name_entry.as_mut() {
+                    name_entry.update(delta);
+                }
+                state.audio.set_scene(AudioScene {
+                    in_menu: state.calibration.is_some()
+                        || state.menu.is_some()
+                        || state.main_menu.is_some(),
+                    time_scale: state.sim.time_scale(),
+                });
+
+                for screen in state.transitions.update(delta) {
+                    state.drop_screen(screen);
+                }
 
-                // Input Handling:
+                // The match is frozen while the menu is open, and already runs
+                // again while it fades out. Online it goes on for the other player.
+                state.flow.set_paused(
+                    state.menu.is_some()
+                        && !state.transitions.is_leaving(Screen::Menu)
+                        && state.online.is_none(),
+                );
+                state.flow.update(delta);
+                let paused = state.flow.state() == GameState::Paused
+                    || state.calibration.is_some()
+                    || state.photo.is_some()
+                    || state.daily_screen.is_some();
+                let held = !state.flow.is_running()
+                    || state
+                        .online
+                        .as_ref()
+                        .is_some_and(|online| !online.is_connected())
+                    || state
+                        .tutorial
+                        .as_ref()
+                        .is_some_and(|tutorial| tutorial.holds_ball())
+                    || state
+                        .intro
+                        .as_ref()
+                        .is_some_and(|intro| intro.holds_match());
+                state.clock.set_match(paused, held, state.sim.time_scale());
+                let match_delta = state.clock.match_delta();
+                state.timeline.update(match_delta);
+                if let Some(intro) = state.intro.as_mut()
+                    && state.menu.is_none()
                 {
-                    // Left paddle
-                    if state.keys_pressed.contains(&KeyCode::KeyW) {
-                        state.left.pos.y -= PADDLE_SPEED * delta;
-                        if state.left.pos.y < state.left.height / 2. {
-                            state.left.pos.y = state.left.height / 2.;
-                        }
+                    intro.update(match_delta);
+                    if intro.is_finished() {
+                        state.intro = None;
                     }
-                    if state.keys_pressed.contains(&KeyCode::KeyS) {
-                        state.left.pos.y += PADDLE_SPEED * delta;
-                        if state.left.pos.y > renderer.size.height as f32 - (state.left.height / 2.)
-                        {
-                            state.left.pos.y =
-                                renderer.size.height as f32 - (state.left.height / 2.);
-                        }
+                }
+                if let Some(tutorial) = state.tutorial.as_mut()
+                    && state.menu.is_none()
+                    && state.main_menu.is_none()
+                {
+                    tutorial.update(match_delta);
+                    // The keys are handled as they are pressed, the stick here
+                    let stick = state.input.stick(0);
+                    if stick < 0.0 {
+                        tutorial.handle_action(Action::MoveUp);
+                    } else if stick > 0.0 {
+                        tutorial.handle_action(Action::MoveDown);
+                    }
+                }
+                if state
+                    .tutorial
+                    .as_ref()
+                    .is_some_and(|tutorial| tutorial.is_finished())
+                {
+                    state.finish_tutorial();
+                }
+                let rewinding = state.drill.is_some()
+                    && state.menu.is_none()
+                    && state.input.keys.contains(&REWIND_KEY)
+                    && matches!(
+                        state.flow.state(),
+                        GameState::Playing | GameState::RoundOver { .. }
+                    );
+                let mut stepped = false;
+                if rewinding {
+                    state.rewind(match_delta);
+                } else if let Some(playback) = state.playback.as_mut() {
+                    for tick in playback.advance(match_delta) {
+                        step_script(&mut self.script, event_loop, window, renderer, state);
+                        state.step(tick.dt, tick.input);
+                        stepped = true;
                     }
+                    if let Some(director) = state.director.as_mut() {
+                        let screen =
+                            Vector2::new(renderer.size.width as f32, renderer.size.height as f32);
+                        director.update(match_delta, &state.sim, screen, &state.input.keys);
+                    }
+                } else if state.clock.is_stepping() && !state.is_online_client() {
+                    for _ in 0..state.timestep.advance(state.clock.step_delta()) {
+                        step_script(&mut self.script, event_loop, window, renderer, state);
+                        state.previous = Positions::of(&state.sim);
+                        state.tick(state.timestep.dt());
+                        stepped = true;
+                    }
+                }
+                // Menus and the tutorial wait for the script's keys, the intro
+                // doesn't count
+                if !stepped
+                    && let Some(script) = self.script.as_mut()
+                    && (rewinding || state.waits_for_input())
+                {
+                    for _ in 0..script.wait(delta, state.timestep.rate()) {
+                        step_script(&mut self.script, event_loop, window, renderer, state);
+                    }
+                }
+                state.update_online(delta);
+                state.camera_effects.update(state.clock.game_delta());
+                // Replays are stepped at their recorded pace and drawn as they
+                // are, so is the host's match
+                if !state.clock.is_stepping()
+                    || rewinding
+                    || state.playback.is_some()
+                    || state.is_online_client()
+                {
+                    state.timestep.reset();
+                    state.previous = Positions::of(&state.sim);
+                }
+                if let Some(photo) = state.photo.as_mut() {
+                    photo.update(delta, &state.input.keys);
+                    renderer.set_filter(photo.filter());
+                }
+
+                state.prompts.load_texture(renderer);
+
+                // Render:
+                {
+                    renderer.begin_drawing();
+                    renderer.clear_color(state.arenas[state.arena].colors.background);
 
-                    // Right paddle
-                    if state.keys_pressed.contains(&KeyCode::ArrowUp) {
-                        state.right.pos.y -= PADDLE_SPEED * delta;
-                        if state.right.pos.y < state.right.height / 2. {
-                            state.right.pos.y = state.right.height / 2.;
+                    let screen =
+                        Vector2::new(renderer.size.width as f32, renderer.size.height as f32);
+                    let camera = match (state.photo.as_ref(), state.director.as_ref()) {
+                        (Some(photo), _) => Some(photo.camera()),
+                        (None, Some(director)) => {
+                            Some(state.camera_effects.apply(director.camera()))
+                        }
+                        (None, None) => None,
+                    };
+                    let hud_visible = state.photo.as_ref().is_none_or(|photo| photo.hud_visible());
+                    if let Some(camera) = camera {
+                        renderer.set_camera(Some(camera));
+                        state.draw_world(renderer);
+                        renderer.set_camera(None);
+                        if hud_visible {
+                            let view = Rect::new(0., 0., screen.x, screen.y);
+                            edge_indicator::draw(renderer, &state.sim.ball, &camera, view);
+                        }
+                    } else if state.split_screen {
+                        let views = splitscreen::player_views(&state.sim, screen);
+                        for (viewport, camera) in views {
+                            renderer.set_viewport(Some(viewport));
+                            renderer.set_camera(Some(state.camera_effects.apply(camera)));
+                            state.draw_world(renderer);
                         }
+                        renderer.set_viewport(None);
+                        renderer.set_camera(None);
+                        splitscreen::draw_divider(renderer);
+                        for (viewport, camera) in views {
+                            edge_indicator::draw(renderer, &state.sim.ball, &camera, viewport);
+                        }
+                    } else {
+                        // The field at its fixed size, scaled to the window
+                        let (viewport, camera) = state.field_view(screen);
+                        renderer.clear_color(LETTERBOX_COLOR);
+                        renderer.set_viewport(Some(viewport));
+                        renderer.set_camera(Some(state.camera_effects.apply(camera)));
+                        state.draw_world(renderer);
+                        renderer.set_viewport(None);
+                        renderer.set_camera(None);
                     }
-                    if state.keys_pressed.contains(&KeyCode::ArrowDown) {
-                        state.right.pos.y += PADDLE_SPEED * delta;
-                        if state.right.pos.y
-                            > renderer.size.height as f32 - (state.right.height / 2.)
-                        {
-                            state.right.pos.y =
-                                renderer.size.height as f32 - (state.right.height / 2.);
+
+                    renderer.begin_hud();
+                    if hud_visible {
+                        state.draw_hud(renderer);
+                        state.draw_playback_hud(renderer);
+                        if let Some(photo) = state.photo.as_ref() {
+                            photo.draw(renderer, &state.locale);
                         }
                     }
+
+                    if let Err(err) = renderer.end_drawing() {
+                        error!("Error: renderer.render(): {}", err);
+                    }
+                    state.display_sync.frame_presented(
+                        now,
+                        Instant::now(),
+                        renderer.acquire_wait(),
+                    );
+                    if let Some(latency) = state.latency.as_mut() {
+                        latency.frame_presented(Instant::now());
+                    }
                 }
 
-                // Ball movement
-                {
-                    state.ball.pos.x += state.ball.velocity.x * delta;
-                    state.ball.pos.y += state.ball.velocity.y * delta;
+                // Keeps the candidate window of the input method next to the caret
+                if let Some(name_entry) = state.name_entry.as_ref() {
+                    let caret = name_entry.caret();
+                    window.set_ime_cursor_area(
+                        PhysicalPosition::new(caret.x, caret.y),
+                        PhysicalSize::new(caret.width, caret.height),
+                    );
+                }
+
+                if let Some(quality) = renderer.take_quality_change() {
+                    let message = state.locale.format(
+                        "hud.quality_lowered",
+                        &[("quality", &state.locale.text(quality.label_key()))],
+                    );
+                    state.toasts.push(message);
+                }
 
-                    // Ball collision with top and bottom walls
-                    if state.ball.pos.y - state.ball.radius < 0.0 {
-                        state.ball.pos.y = state.ball.radius;
-                        state.ball.velocity.y = state.ball.velocity.y.abs(); // Bounce down
+                if let Some((kind, destination, scale)) = state.capture_requested.take() {
+                    let result =
+                        renderer
+                            .capture(kind, scale)
+                            .and_then(|image| match destination {
+                                Destination::File => screenshot::save(&image, kind).map(|path| {
+                                    state.locale.format(
+                                        "hud.screenshot_saved",
+                                        &[("path", &path.display())],
+                                    )
+                                }),
+                                Destination::Clipboard => state.clipboard.copy(&image).map(|()| {
+                                    state.locale.text("hud.screenshot_copied").to_string()
+                                }),
+                            });
+                    match result {
+                        Ok(message) => state.toasts.push(message),
+                        Err(err) => error!("Failed to take screenshot: {:#}", err),
                     }
-                    if state.ball.pos.y + state.ball.radius > renderer.size.height as f32 {
-                        state.ball.pos.y = renderer.size.height as f32 - state.ball.radius;
-                        state.ball.velocity.y = -state.ball.velocity.y.abs(); // Bounce up
+                }
+
+                if let Some(dump) = renderer.take_frame_dump() {
+                    match screenshot::save_frame_dump(&dump) {
+                        Ok(path) => state.toasts.push(
+                            state
+                                .locale
+                                .format("hud.frame_dump_saved", &[("path", &path.display())]),
+                        ),
+                        Err(err) => error!("Failed to save frame dump: {:#}", err),
                     }
+                }
 
-                    // Ball collision with left paddle
-                    if state.ball.pos.x - state.ball.radius < state.left.pos.x + state.left.width
-                        && state.ball.pos.y > state.left.pos.y - state.left.height / 2.0
-                        && state.ball.pos.y < state.left.pos.y + state.left.height / 2.0
-                    {
-                        state.ball.pos.x = state.left.pos.x + state.left.width + state.ball.radius;
+                schedule_redraw(window, state);
+            }
+            // Key releases are not delivered to unfocused windows
+            WindowEvent::Focused(false) => state.input.keys.clear(),
+            WindowEvent::Resized(size) => {
+                // Reconfigures the size of the surface. We do not re-render
+                // here as this event is always followed up by redraw request.
+                renderer.resize(size);
+                state.set_window_size(size);
+            }
+            _ => (),
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let result = self.pending_renderer.borrow_mut().take();
+            if let Some(result) = result {
+                self.renderer_created(result);
+                if let Some(window) = self.window.as_ref() {
+                    window.request_redraw();
+                }
+            }
+        }
+
+        if let Some(session) = self.session.as_mut() {
+            for event in session.due() {
+                self.replay_lifecycle_event(event_loop, event);
+            }
+            if self
+                .session
+                .as_ref()
+                .is_some_and(|session| session.is_finished())
+            {
+                info!("Session replay finished");
+                self.session = None;
+            }
+        }
+
+        self.sync_stream_overlay(event_loop);
+
+        // The loading screen shows every frame it can until the state exists
+        if self.loading.is_some()
+            && self.lifecycle.is_visible()
+            && let Some(window) = self.window.as_ref()
+        {
+            window.request_redraw();
+        }
+
+        let (Some(window), Some(state)) = (self.window.as_ref(), self.state.as_ref()) else {
+            return;
+        };
+
+        // Sleep until `resumed` while there is no surface, and until the
+        // window shows again while minimized. A session replay keeps going.
+        let hidden = !self.lifecycle.is_visible() && self.session.is_none();
+        if hidden
+            || self
+                .renderer
+                .as_ref()
+                .is_some_and(|renderer| renderer.is_suspended())
+        {
+            return event_loop.set_control_flow(ControlFlow::Wait);
+        }
+
+        // Paced frames: sleep until the next frame is due, then redraw
+        match state.pacer.next_frame() {
+            Some(deadline) if Instant::now() < deadline => {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(deadline));
+            }
+            Some(_) => {
+                event_loop.set_control_flow(ControlFlow::Poll);
+                window.request_redraw();
+            }
+            None => event_loop.set_control_flow(ControlFlow::Poll),
+        }
+    }
+}
+
+// The fonts of the system are found and loaded with the other assets at
+// startup, a renderer made again for a running game has them right away
+async fn create_renderer(window: Arc<Window>, fonts: bool) -> Result<Renderer> {
+    if fonts {
+        Renderer::new(window).await
+    } else {
+        Renderer::without_fonts(window).await
+    }
+}
+
+// Clicks for moving around in menus, confirming and going back
+fn menu_sound(key_code: KeyCode) -> SoundId {
+    match key_code {
+        KeyCode::Enter | KeyCode::Space => SoundId::MenuSelect,
+        KeyCode::Escape | KeyCode::Backspace => SoundId::MenuBack,
+        _ => SoundId::MenuMove,
+    }
+}
+
+// Feeds the actions of the input script due at its current tick to the game,
+// then counts the tick
+fn step_script(
+    script: &mut Option<ScriptPlayer>,
+    event_loop: &ActiveEventLoop,
+    window: &Arc<Window>,
+    renderer: &mut Renderer,
+    state: &mut State,
+) {
+    let Some(player) = script.as_mut() else {
+        return;
+    };
+    for action in player.step() {
+        info!("Script: {:?}", action);
+        let mut key = |key_code, element_state| {
+            handle_key(
+                event_loop,
+                window,
+                renderer,
+                state,
+                key_code,
+                element_state,
+                false,
+            )
+        };
+        match action {
+            ScriptAction::Press(key_code) => {
+                key(key_code, ElementState::Pressed);
+                key(key_code, ElementState::Released);
+            }
+            ScriptAction::Hold(key_code) => key(key_code, ElementState::Pressed),
+            ScriptAction::Release(key_code) => key(key_code, ElementState::Released),
+            ScriptAction::Quit => event_loop.exit(),
+        }
+    }
+    if player.is_finished() {
+        info!("Input script finished");
+        *script = None;
+    }
+}
+
+// A key of the keyboard, or of an input script, pressed or released
+fn handle_key(
+    event_loop: &ActiveEventLoop,
+    window: &Arc<Window>,
+    renderer: &mut Renderer,
+    state: &mut State,
+    key_code: KeyCode,
+    element_state: ElementState,
+    repeat: bool,
+) {
+    match element_state {
+        ElementState::Pressed => {
+            if let Some(calibration) = state.calibration.as_mut()
+                && !state.transitions.is_leaving(Screen::Calibration)
+            {
+                if !repeat {
+                    state.audio.play(menu_sound(key_code));
+                    if !calibration.handle_key(key_code, renderer) {
+                        state.settings.gamma = renderer.gamma();
+                        state.settings.brightness = renderer.brightness();
+                        state.settings.hud_inset = renderer.hud_inset();
+                        state.save_settings();
+                        state.transitions.close(Screen::Calibration);
+                    }
+                }
+                return;
+            }
 
-                        // Bounce right with angle based on where the ball hit the paddle
-                        let relative_intersect_y = state.left.pos.y - state.ball.pos.y;
-                        let normalized_relative_intersection_y =
-                            relative_intersect_y / (state.left.height / 2.0);
-                        let bounce_angle =
-                            normalized_relative_intersection_y * std::f32::consts::PI / 4.0;
+            if let Some(screen) = state.paddle_colors.as_mut()
+                && !state.transitions.is_leaving(Screen::PaddleColors)
+            {
+                // Held arrows keep adjusting the sliders
+                match screen.handle_key(key_code) {
+                    Some(PaddleColorAction::Save(colors)) => {
+                        state.progress.set_paddle_colors(colors);
+                        state.save_progress();
+                        state.transitions.close(Screen::PaddleColors);
+                    }
+                    Some(PaddleColorAction::Cancel) => {
+                        state.transitions.close(Screen::PaddleColors)
+                    }
+                    None => (),
+                }
+                return;
+            }
 
-                        state.ball.velocity.x = BALL_SPEED * bounce_angle.cos();
-                        state.ball.velocity.y = -BALL_SPEED * bounce_angle.sin();
+            if let Some(screen) = state.options.as_mut()
+                && !state.transitions.is_leaving(Screen::Options)
+            {
+                if repeat {
+                    return;
+                }
+                state.audio.play(menu_sound(key_code));
+                match screen.handle_key(key_code) {
+                    Some(OptionsAction::Changed(settings)) => {
+                        state.apply_settings(settings, renderer, window)
                     }
+                    Some(OptionsAction::Language { forward }) => state.switch_language(forward),
+                    Some(OptionsAction::Close) => state.transitions.close(Screen::Options),
+                    None => (),
+                }
+                return;
+            }
 
-                    // Ball collision with right paddle
-                    if state.ball.pos.x + state.ball.radius > state.right.pos.x - state.right.width
-                        && state.ball.pos.y > state.right.pos.y - state.right.height / 2.0
-                        && state.ball.pos.y < state.right.pos.y + state.right.height / 2.0
-                    {
-                        state.ball.pos.x =
-                            state.right.pos.x - state.right.width - state.ball.radius;
+            if let Some(gallery) = state.gallery.as_ref()
+                && !state.transitions.is_leaving(Screen::Gallery)
+            {
+                if !repeat && !gallery.handle_key(key_code) {
+                    state.transitions.close(Screen::Gallery);
+                }
+                return;
+            }
+
+            if let Some(photo) = state.photo.as_mut() {
+                // Held keys pan and zoom in `PhotoMode::update`
+                state.input.keys.insert(key_code);
+                if repeat {
+                    return;
+                }
+                match photo.handle_key(key_code) {
+                    Some(PhotoAction::Capture) => {
+                        state.capture_requested =
+                            Some((CaptureKind::Opaque, Destination::File, PHOTO_SCALE));
+                    }
+                    Some(PhotoAction::Exit) => {
+                        state.photo = None;
+                        renderer.set_filter(PostFilter::None);
+                    }
+                    None => (),
+                }
+                return;
+            }
 
-                        // Bounce left with angle based on where the ball hit the paddle
-                        let relative_intersect_y = state.right.pos.y - state.ball.pos.y;
-                        let normalized_relative_intersection_y =
-                            relative_intersect_y / (state.right.height / 2.0);
-                        let bounce_angle =
-                            normalized_relative_intersection_y * std::f32::consts::PI / 4.0;
+            if let Some(playback) = state.playback.as_mut() {
+                // Held keys move the camera in `CameraDirector::update`
+                state.input.keys.insert(key_code);
+                if repeat {
+                    return;
+                }
+                match key_code {
+                    KeyCode::Space => playback.toggle_pause(),
+                    KeyCode::KeyR => {
+                        if let Some(director) = state.director.as_mut() {
+                            director.reset();
+                        }
+                    }
+                    KeyCode::F12 => {
+                        state.capture_requested = Some((CaptureKind::Opaque, Destination::File, 1));
+                    }
+                    KeyCode::Escape => event_loop.exit(),
+                    _ => (),
+                }
+                return;
+            }
 
-                        state.ball.velocity.x = -BALL_SPEED * bounce_angle.cos();
-                        state.ball.velocity.y = -BALL_SPEED * bounce_angle.sin();
+            if let Some(screen) = state.drill_screen.as_mut()
+                && !state.transitions.is_leaving(Screen::Drills)
+            {
+                if repeat {
+                    return;
+                }
+                state.audio.play(menu_sound(key_code));
+                match screen.handle_key(key_code) {
+                    Some(DrillAction::Start(drill)) => {
+                        state.transitions.close(Screen::Drills);
+                        state.transitions.close(Screen::MainMenu);
+                        state.start_drill(drill);
                     }
+                    Some(DrillAction::Close) => state.transitions.close(Screen::Drills),
+                    None => (),
+                }
+                state.input.keys.clear();
+                return;
+            }
 
-                    // Scoring: ball out of bounds
-                    if state.ball.pos.x < 0.0 {
-                        state.right.score += 1;
-                        state
-                            .ball
-                            .reset(renderer.size.width as f32, renderer.size.height as f32);
+            if let Some(main_menu) = state.main_menu.as_mut()
+                && !state.transitions.is_leaving(Screen::MainMenu)
+            {
+                if repeat {
+                    return;
+                }
+                state.audio.play(menu_sound(key_code));
+                match main_menu.handle_key(key_code, state.ai_profiles.len()) {
+                    Some(MainMenuAction::Play { ai }) => state.play_match(ai),
+                    Some(MainMenuAction::Difficulty(difficulty)) => {
+                        state.progress.ai_difficulty = difficulty;
+                        state.save_progress();
+                    }
+                    Some(MainMenuAction::AiSide(side)) => {
+                        state.progress.ai_side = Some(side);
+                        state.save_progress();
+                    }
+                    Some(MainMenuAction::SwapSides) => {
+                        let mut settings = state.settings.clone();
+                        settings.keys.swap_sides();
+                        state.apply_settings(settings, renderer, window);
                     }
-                    if state.ball.pos.x > renderer.size.width as f32 {
-                        state.left.score += 1;
+                    Some(MainMenuAction::WinScore(points)) => {
+                        state.progress.win_score = Some(points);
+                        state.save_progress();
+                    }
+                    Some(MainMenuAction::RulesetCode) => {
+                        let code = state.ruleset().code();
+                        state.name_entry = Some(NameEntry::new(EntryKind::RulesetCode, &code));
+                        state.transitions.open(Screen::NameEntry);
+                    }
+                    Some(MainMenuAction::Drills) => {
+                        state.drill_screen = Some(DrillScreen::new());
+                        state.transitions.open(Screen::Drills);
+                    }
+                    Some(MainMenuAction::Quit) => event_loop.exit(),
+                    None => (),
+                }
+                state.input.keys.clear();
+                return;
+            }
+
+            if let Some(screen) = state.game_over.as_ref()
+                && !state.transitions.is_leaving(Screen::GameOver)
+            {
+                if repeat {
+                    return;
+                }
+                match screen.handle_key(key_code) {
+                    // The host starts the rematch of an online match
+                    Some(GameOverAction::Rematch) if state.is_online_client() => {
+                        let text = state.locale.text("hud.online_rematch");
+                        state.toasts.push(text.to_string());
+                    }
+                    Some(GameOverAction::Rematch) => {
+                        state.restart(state.sim.mutators);
+                        state.flow.play();
+                        state.start_intro();
+                        state.transitions.close(Screen::GameOver);
+                    }
+                    Some(GameOverAction::MainMenu) => {
+                        state.show_main_menu();
+                        state.transitions.close(Screen::GameOver);
+                    }
+                    None => (),
+                }
+                state.input.keys.clear();
+                return;
+            }
+
+            if let Some(screen) = state.daily_screen.as_ref()
+                && !state.transitions.is_leaving(Screen::Daily)
+            {
+                if repeat {
+                    return;
+                }
+                match screen.handle_key(key_code) {
+                    Some(DailyAction::Play) => {
+                        let challenge = screen.challenge().clone();
+                        state.start_daily(challenge);
+                        state.transitions.close(Screen::Daily);
+                        state.transitions.close(Screen::Menu);
+                    }
+                    Some(DailyAction::Close) => state.transitions.close(Screen::Daily),
+                    None => (),
+                }
+                state.input.keys.clear();
+                return;
+            }
+
+            if let Some(menu) = state.menu.as_mut()
+                && !state.transitions.is_leaving(Screen::Menu)
+            {
+                if repeat {
+                    return;
+                }
+                state.audio.play(menu_sound(key_code));
+                match menu.handle_key(key_code) {
+                    Some(MenuItem::Resume) => state.transitions.close(Screen::Menu),
+                    Some(MenuItem::DailyChallenge) => {
+                        state.daily_screen =
+                            DailyChallenge::for_date(Date::today(), &state.ai_profiles)
+                                .map(DailyScreen::new);
+                        if state.daily_screen.is_some() {
+                            state.transitions.open(Screen::Daily);
+                        }
+                    }
+                    Some(MenuItem::Tutorial) => {
+                        state.restart(state.sim.mutators);
+                        state.tutorial = Some(Tutorial::new());
+                        state.transitions.close(Screen::Menu);
+                    }
+                    // The menu stays open underneath
+                    Some(MenuItem::Achievements) => {
+                        state.gallery = Some(Gallery);
+                        state.transitions.open(Screen::Gallery);
+                    }
+                    Some(MenuItem::Arena) => {
+                        state.arena = (state.arena + 1) % state.arenas.len();
+                        let arena = &state.arenas[state.arena];
+                        state.progress.arena = Some(arena.name.clone());
                         state
-                            .ball
-                            .reset(renderer.size.width as f32, renderer.size.height as f32);
+                            .toasts
+                            .push(format!("{}: {}", arena.name, arena.description));
+                        state.save_progress();
+                    }
+                    // Goes up in steps and from full volume back to silent
+                    Some(item @ (MenuItem::MasterVolume | MenuItem::SfxVolume)) => {
+                        let volume = if item == MenuItem::MasterVolume {
+                            &mut state.progress.master_volume
+                        } else {
+                            &mut state.progress.sfx_volume
+                        };
+                        let current = volume.unwrap_or(100).min(100);
+                        *volume = Some(if current >= 100 {
+                            0
+                        } else {
+                            (current + VOLUME_STEP).min(100)
+                        });
+                        state.audio.set_volume(
+                            state.progress.master_volume() as f32 / 100.0,
+                            state.progress.sfx_volume() as f32 / 100.0,
+                        );
+                        state.save_progress();
+                    }
+                    Some(MenuItem::PlayerName) => {
+                        state.name_entry = Some(NameEntry::new(
+                            EntryKind::PlayerName,
+                            &state.progress.player_name,
+                        ));
+                        state.transitions.open(Screen::NameEntry);
+                        window.set_ime_allowed(true);
+                    }
+                    Some(MenuItem::PaddleColors) => {
+                        let colors = &state.arenas[state.arena].colors;
+                        state.paddle_colors = Some(PaddleColorScreen::new(
+                            state.progress.paddle_colors(),
+                            [colors.left_paddle, colors.right_paddle],
+                            colors.background,
+                        ));
+                        state.transitions.open(Screen::PaddleColors);
+                    }
+                    Some(MenuItem::BallSkin) => {
+                        state.progress.ball_skin = state.progress.ball_skin.next();
+                        state.save_progress();
+                    }
+                    Some(MenuItem::Language) => state.switch_language(true),
+                    Some(MenuItem::ScreenShake) => {
+                        let intensity = state.progress.shake_intensity().next();
+                        state.progress.set_shake_intensity(intensity);
+                        state.save_progress();
+                    }
+                    Some(MenuItem::ReducedMotion) => {
+                        state.progress.reduced_motion = Some(!state.progress.reduced_motion());
+                        state.camera_effects = CameraEffects::default();
+                        state.save_progress();
+                    }
+                    Some(MenuItem::PaddleTrails) => {
+                        state.progress.paddle_trails = Some(!state.progress.paddle_trails());
+                        state.paddle_trail.clear();
+                        state.save_progress();
+                    }
+                    Some(MenuItem::Options) => {
+                        state.options = Some(OptionsScreen::new(&state.settings));
+                        state.transitions.open(Screen::Options);
+                    }
+                    Some(MenuItem::Calibration) => {
+                        state.calibration = Some(Calibration::new());
+                        state.transitions.open(Screen::Calibration);
                     }
+                    Some(MenuItem::MainMenu) => {
+                        state.show_main_menu();
+                        state.transitions.close(Screen::Menu);
+                    }
+                    Some(MenuItem::Quit) => event_loop.exit(),
+                    None => (),
                 }
+                // Don't leak the keys into the match
+                state.input.keys.clear();
+                return;
+            }
 
-                // Render:
-                {
-                    renderer.begin_drawing();
-                    renderer.clear_color(Srgba::new(0.1, 0.1, 0.1, 1.));
+            if key_code == KeyCode::Escape && !repeat {
+                state.menu = Some(Menu::new());
+                state.transitions.open(Screen::Menu);
+                return;
+            }
 
-                    // Draw Left
-                    renderer.draw_rectangle(
-                        Vector2::new(
-                            state.left.pos.x,
-                            state.left.pos.y - (state.left.height / 2.),
-                        ),
-                        state.left.width,
-                        state.left.height,
-                        Srgba::new(1., 0., 0., 1.),
-                        Deg(0.),
-                    );
+            if let Some(intro) = state.intro.as_mut()
+                && intro.holds_match()
+            {
+                if !repeat {
+                    intro.skip();
+                }
+                return;
+            }
 
-                    // Draw Right
-                    renderer.draw_rectangle(
-                        Vector2::new(
-                            state.right.pos.x - (state.right.width),
-                            state.right.pos.y - (state.right.height / 2.),
-                        ),
-                        state.right.width,
-                        state.right.height,
-                        Srgba::new(0., 0., 1., 1.),
-                        Deg(0.),
-                    );
+            state.input.keys.insert(key_code);
 
-                    // Draw Ball
-                    renderer.draw_circle(
-                        state.ball.pos,
-                        state.ball.radius,
-                        Srgba::new(1.0, 1.0, 1.0, 1.0),
-                    );
+            if let Some(tutorial) = state.tutorial.as_mut()
+                && !repeat
+            {
+                if key_code == KeyCode::Enter {
+                    state.finish_tutorial();
+                } else if let Some(action) = state.settings.keys.action(key_code) {
+                    tutorial.handle_action(action);
+                }
+            }
 
-                    // Draw center line
-                    renderer.draw_rectangle(
-                        Vector2::new(renderer.size.width as f32 / 2.0 - 2.0, 0.0),
-                        4.0,
-                        renderer.size.height as f32,
-                        Srgba::new(0.5, 0.5, 0.5, 0.5),
-                        Deg(0.),
-                    );
+            if key_code == KeyCode::F12 && !repeat {
+                let shift = state.input.keys.contains(&KeyCode::ShiftLeft)
+                    || state.input.keys.contains(&KeyCode::ShiftRight);
+                let ctrl = state.input.keys.contains(&KeyCode::ControlLeft)
+                    || state.input.keys.contains(&KeyCode::ControlRight);
+                let kind = if shift {
+                    CaptureKind::Transparent
+                } else {
+                    CaptureKind::Opaque
+                };
+                let destination = if ctrl {
+                    Destination::Clipboard
+                } else {
+                    Destination::File
+                };
+                state.capture_requested = Some((kind, destination, 1));
+            }
 
-                    renderer.draw_text(
-                        &format!("P1: {}", state.left.score),
-                        Vector2::new(0., 0.),
-                        FONT_SIZE,
-                        LINE_HEIGHT,
-                        None,
-                    );
+            if key_code == KeyCode::F8 && !repeat {
+                let screen = Vector2::new(renderer.size.width as f32, renderer.size.height as f32);
+                let field = Vector2::new(state.sim.width, state.sim.height);
+                state.photo = Some(PhotoMode::new(Camera2D::fit(field, screen)));
+                return;
+            }
 
-                    let text = "Pong\nGame";
-                    let text_width = renderer.measure_text(text, FONT_SIZE, LINE_HEIGHT);
-                    renderer.draw_text(
-                        text,
-                        Vector2::new(renderer.size.width as f32 / 2. - text_width / 2., 0.),
-                        FONT_SIZE,
-                        LINE_HEIGHT,
-                        None,
-                    );
+            if key_code == KeyCode::F6 && !repeat {
+                let shift = state.input.keys.contains(&KeyCode::ShiftLeft)
+                    || state.input.keys.contains(&KeyCode::ShiftRight);
+                if shift {
+                    state.stream_overlay = !state.stream_overlay;
+                } else {
+                    state.latency = match state.latency {
+                        Some(_) => None,
+                        None => Some(LatencyOverlay::new()),
+                    };
+                }
+            }
 
-                    let text = &format!("P2: {}", state.right.score);
-                    let text_width = renderer.measure_text(text, FONT_SIZE, LINE_HEIGHT);
-                    renderer.draw_text(
-                        text,
-                        Vector2::new(renderer.size.width as f32 - text_width, 0.),
-                        FONT_SIZE,
-                        LINE_HEIGHT,
-                        None,
-                    );
+            if key_code == KeyCode::F4 && !repeat {
+                let pacing = state.pacer.pacing().next();
+                let mode = state.configure_pacing(pacing, renderer, window);
+                state.settings.pacing = pacing;
+                state.save_settings();
 
-                    if let Err(err) = renderer.end_drawing() {
-                        error!("Error: renderer.render(): {}", err);
-                    }
+                let locale = &state.locale;
+                let refresh = monitor_refresh_rate(window)
+                    .map(|hz| locale.format("hud.refresh_rate", &[("hz", &format!("{:.0}", hz))]))
+                    .unwrap_or_else(|| locale.text("hud.refresh_unknown").to_string());
+                let cap = state
+                    .pacer
+                    .target_fps()
+                    .map(|fps| locale.format("hud.pacing_cap", &[("fps", &format!("{:.0}", fps))]))
+                    .unwrap_or_default();
+                let message = locale.format(
+                    "hud.pacing",
+                    &[
+                        ("pacing", &locale.text(pacing.label_key())),
+                        ("mode", &format!("{:?}", mode)),
+                        ("cap", &cap),
+                        ("refresh", &refresh),
+                    ],
+                );
+                state.toasts.push(message);
+            }
+
+            if key_code == KeyCode::F3 && !repeat {
+                state.calibration = Some(Calibration::new());
+                state.transitions.open(Screen::Calibration);
+            }
+
+            // Reset the ball if the serve key is pressed
+            if key_code == state.settings.keys.serve {
+                state.reset_requested = true;
+            }
+
+            let ctrl = state.input.keys.contains(&KeyCode::ControlLeft)
+                || state.input.keys.contains(&KeyCode::ControlRight);
+            if key_code == KeyCode::F5 && ctrl && !repeat {
+                renderer.request_frame_dump();
+            } else if key_code == KeyCode::F5 && !repeat {
+                match state.replay.save() {
+                    Ok(path) => info!("Saved replay to {}", path.display()),
+                    Err(err) => error!("Failed to save replay: {:#}", err),
                 }
+            }
 
-                window.request_redraw();
+            // Cycle through the AI profiles, then back to two players
+            if key_code == KeyCode::F2 && !repeat {
+                let next = match state.ai.as_ref() {
+                    Some(ai) => state
+                        .ai_profiles
+                        .iter()
+                        .position(|profile| profile.name == ai.profile().name)
+                        .map(|index| index + 1),
+                    None => Some(0),
+                };
+                state.ai = next
+                    .and_then(|index| state.ai_profiles.get(index))
+                    .map(|profile| {
+                        AiController::new(
+                            profile.clone(),
+                            state.progress.ai_difficulty,
+                            state.sim.height,
+                        )
+                    });
+                state.ai_message = state.ai.as_ref().map(|ai| {
+                    let profile = ai.profile();
+                    (
+                        format!("{}: {}", profile.name, profile.description),
+                        AI_MESSAGE_DURATION,
+                    )
+                });
             }
-            WindowEvent::Resized(size) => {
-                // Reconfigures the size of the surface. We do not re-render
-                // here as this event is always followed up by redraw request.
-                renderer.resize(size);
+
+            // Toggle mutators
+            if key_code == KeyCode::F1 && !repeat {
+                let shift = state.input.keys.contains(&KeyCode::ShiftLeft)
+                    || state.input.keys.contains(&KeyCode::ShiftRight);
+                let mut mutators = state.sim.mutators;
+                if shift {
+                    mutators.spin = !mutators.spin;
+                } else {
+                    mutators.portal_walls = !mutators.portal_walls;
+                }
+                state.restart(mutators);
             }
-            _ => (),
+            if key_code == KeyCode::F7 && !repeat {
+                let shift = state.input.keys.contains(&KeyCode::ShiftLeft)
+                    || state.input.keys.contains(&KeyCode::ShiftRight);
+                let mut mutators = state.sim.mutators;
+                if shift {
+                    // Off, then increasingly heavy paddles
+                    let index = MOMENTUM_ACCELERATIONS
+                        .iter()
+                        .position(|acceleration| *acceleration == mutators.momentum)
+                        .map_or(0, |index| (index + 1) % MOMENTUM_ACCELERATIONS.len());
+                    mutators.momentum = MOMENTUM_ACCELERATIONS[index];
+                } else {
+                    mutators.small_paddle = !mutators.small_paddle;
+                }
+                state.restart(mutators);
+            }
+            if key_code == KeyCode::F10 && !repeat {
+                let shift = state.input.keys.contains(&KeyCode::ShiftLeft)
+                    || state.input.keys.contains(&KeyCode::ShiftRight);
+                let mut mutators = state.sim.mutators;
+                if shift {
+                    let index = WALL_DAMPINGS
+                        .iter()
+                        .position(|damping| *damping == mutators.wall_damping)
+                        .map_or(0, |index| (index + 1) % WALL_DAMPINGS.len());
+                    mutators.wall_damping = WALL_DAMPINGS[index];
+                } else {
+                    // Off, player one, player two, both
+                    let (left, right) =
+                        match (mutators.left_save_assist, mutators.right_save_assist) {
+                            (false, false) => (true, false),
+                            (true, false) => (false, true),
+                            (false, true) => (true, true),
+                            (true, true) => (false, false),
+                        };
+                    mutators.left_save_assist = left;
+                    mutators.right_save_assist = right;
+                }
+                state.restart(mutators);
+            }
+            if key_code == KeyCode::F11 && !repeat {
+                let shift = state.input.keys.contains(&KeyCode::ShiftLeft)
+                    || state.input.keys.contains(&KeyCode::ShiftRight);
+                // Off, then increasingly strong
+                let mut mutators = state.sim.mutators;
+                if shift {
+                    let index = IMPACT_TRANSFERS
+                        .iter()
+                        .position(|transfer| *transfer == mutators.impact_transfer)
+                        .map_or(0, |index| (index + 1) % IMPACT_TRANSFERS.len());
+                    mutators.impact_transfer = IMPACT_TRANSFERS[index];
+                } else {
+                    let index = RUBBER_BAND_STRENGTHS
+                        .iter()
+                        .position(|strength| *strength == mutators.rubber_band)
+                        .map_or(0, |index| (index + 1) % RUBBER_BAND_STRENGTHS.len());
+                    mutators.rubber_band = RUBBER_BAND_STRENGTHS[index];
+                }
+                state.restart(mutators);
+            }
+            if key_code == KeyCode::F9 && !repeat {
+                state.split_screen = !state.split_screen;
+            }
+        }
+        ElementState::Released => {
+            state.input.keys.remove(&key_code);
         }
     }
 }
+
+// The browser build draws into the page's canvas
+fn window_attributes() -> WindowAttributes {
+    let attributes = Window::default_attributes();
+    #[cfg(target_arch = "wasm32")]
+    let attributes = crate::web::canvas_attributes(attributes);
+    attributes
+}
+
+// Unpaced frames start right away, paced ones are requested in `about_to_wait`.
+fn schedule_redraw(window: &Window, state: &State) {
+    if state.pacer.next_frame().is_none() {
+        window.request_redraw();
+    }
+}
+
+// Arc in front of the ball showing the range the serve can be aimed in,
+// with a marker at the current aim.
+fn draw_serve_aim(renderer: &mut Renderer, ball: Vector2<f32>, serve: &PendingServe) {
+    let direction = match serve.toward {
+        Side::Left => -1.0,
+        Side::Right => 1.0,
+    };
+    let point = |angle: f32| {
+        Vector2::new(
+            ball.x + direction * SERVE_ARC_RADIUS * angle.cos(),
+            ball.y + SERVE_ARC_RADIUS * angle.sin(),
+        )
+    };
+
+    for i in 0..SERVE_ARC_DOTS {
+        let t = i as f32 / (SERVE_ARC_DOTS - 1) as f32;
+        let angle = -SERVE_AIM_LIMIT + t * 2.0 * SERVE_AIM_LIMIT;
+        renderer.draw_circle(point(angle), 3.0, Srgba::new(1.0, 1.0, 1.0, 0.35));
+    }
+
+    // Current aim
+    renderer.draw_circle(point(serve.angle), 7.0, Srgba::new(1.0, 1.0, 0.3, 1.0));
+}
+
+fn glyph_color(color: Srgba) -> glyphon::Color {
+    let color: Srgba<u8> = color.into_format();
+    glyphon::Color::rgba(color.red, color.green, color.blue, color.alpha)
+}
+
+// `color` gone fully transparent, for particles that fade as they die
+fn fade_out(color: Srgba) -> Srgba {
+    Srgba::new(color.red, color.green, color.blue, 0.0)
+}
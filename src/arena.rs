@@ -0,0 +1,414 @@
+use anyhow::{Context, Result, bail};
+use cgmath::{Deg, Vector2};
+use log::{error, info};
+use palette::Srgba;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::assets::assets_dir;
+use crate::audio::{SoundId, Tone};
+use crate::renderer::{DecodedImage, Rect, Renderer, TextureHandle, TextureParams};
+use crate::sim::GameSim;
+use crate::timestep::Positions;
+
+static ARENA_DIR: &str = "arenas";
+static ARENA_FILE: &str = "arena.toml";
+// Picked until the player chooses another one
+pub static DEFAULT_ARENA: &str = "Classic";
+
+// Always available, even without an assets directory
+static BUILTIN_ARENA: &str = include_str!("../assets/arenas/classic/arena.toml");
+
+// Look and sound of the playing field, loaded from `assets/arenas/<name>/arena.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Arena {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub colors: ArenaColors,
+    #[serde(default)]
+    pub center_line: CenterLine,
+    #[serde(default)]
+    pub sounds: ArenaSounds,
+    #[serde(default)]
+    pub images: ArenaImages,
+    // Directory the arena was loaded from, images are looked up there
+    #[serde(skip)]
+    pub dir: Option<PathBuf>,
+}
+
+// PNG files in the arena's directory, drawn instead of the flat colors.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ArenaImages {
+    // Scaled to cover the field, cropped to its aspect ratio
+    pub background: Option<String>,
+    // Tinted with the paddle colors and mirrored for the right paddle
+    pub paddle: Option<String>,
+}
+
+// An arena's images read and decoded, on the loading thread at startup.
+#[derive(Debug, Default)]
+pub struct ArenaPixels {
+    background: Option<DecodedImage>,
+    paddle: Option<DecodedImage>,
+}
+
+// An arena's images once uploaded to the GPU.
+#[derive(Debug, Default)]
+pub struct ArenaTextures {
+    pub background: Option<TextureHandle>,
+    pub paddle: Option<TextureHandle>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ArenaColors {
+    #[serde(deserialize_with = "hex_color")]
+    pub background: Srgba,
+    #[serde(deserialize_with = "hex_color")]
+    pub left_paddle: Srgba,
+    #[serde(deserialize_with = "hex_color")]
+    pub right_paddle: Srgba,
+    // Scores and titles
+    #[serde(deserialize_with = "hex_color")]
+    pub text: Srgba,
+}
+
+impl Default for ArenaColors {
+    fn default() -> Self {
+        Self {
+            background: Srgba::new(0.1, 0.1, 0.1, 1.0),
+            left_paddle: Srgba::new(1.0, 0.0, 0.0, 1.0),
+            right_paddle: Srgba::new(0.0, 0.0, 1.0, 1.0),
+            text: Srgba::new(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    None,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CenterLine {
+    pub style: LineStyle,
+    pub width: f32,
+    #[serde(deserialize_with = "hex_color")]
+    pub color: Srgba,
+}
+
+impl Default for CenterLine {
+    fn default() -> Self {
+        Self {
+            style: LineStyle::Solid,
+            width: 4.0,
+            color: Srgba::new(0.5, 0.5, 0.5, 0.5),
+        }
+    }
+}
+
+// Synthesized blips replacing the built-in sounds, which play when unset.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ArenaSounds {
+    pub paddle_hit: Option<Tone>,
+    pub wall_bounce: Option<Tone>,
+    pub goal: Option<Tone>,
+}
+
+impl ArenaSounds {
+    pub fn tone(&self, id: SoundId) -> Option<Tone> {
+        match id {
+            SoundId::PaddleHit => self.paddle_hit,
+            SoundId::WallBounce => self.wall_bounce,
+            SoundId::Goal => self.goal,
+            SoundId::MenuMove | SoundId::MenuSelect | SoundId::MenuBack => None,
+        }
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_ARENA.to_string(),
+            description: String::new(),
+            colors: ArenaColors::default(),
+            center_line: CenterLine::default(),
+            sounds: ArenaSounds::default(),
+            images: ArenaImages::default(),
+            dir: None,
+        }
+    }
+}
+
+impl Arena {
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut arena: Self = toml::from_str(data)?;
+        arena.center_line.width = arena.center_line.width.max(0.0);
+        Ok(arena)
+    }
+
+    pub fn builtin() -> Result<Self> {
+        Self::parse(BUILTIN_ARENA)
+    }
+
+    // Images that fail to load are logged and the flat colors used instead.
+    pub fn decode_images(&self) -> ArenaPixels {
+        let load = |name: &Option<String>| {
+            let path = self.dir.as_ref()?.join(name.as_ref()?);
+            DecodedImage::load(&path)
+                .inspect_err(|err| error!("Arena {:?}: {:#}", self.name, err))
+                .ok()
+        };
+        ArenaPixels {
+            background: load(&self.images.background),
+            paddle: load(&self.images.paddle),
+        }
+    }
+
+    // The images from `decode_images`, those too large for the GPU are
+    // logged and left out like the ones that failed to load
+    pub fn upload_images(&self, pixels: &ArenaPixels, renderer: &mut Renderer) -> ArenaTextures {
+        let mut upload = |image: &Option<DecodedImage>| {
+            renderer
+                .upload_image(image.as_ref()?)
+                .inspect_err(|err| error!("Arena {:?}: {:#}", self.name, err))
+                .ok()
+        };
+        ArenaTextures {
+            background: upload(&pixels.background),
+            paddle: upload(&pixels.paddle),
+        }
+    }
+
+    // The field in the background color, under the background image if the
+    // arena has one, cropped so it isn't stretched
+    pub fn draw_background(
+        &self,
+        renderer: &mut Renderer,
+        textures: Option<&ArenaTextures>,
+        colors: &ArenaColors,
+        width: f32,
+        height: f32,
+    ) {
+        // The clear color is the letterbox around it
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            colors.background,
+            Deg(0.),
+        );
+        let Some(background) = textures.and_then(|textures| textures.background) else {
+            return;
+        };
+        let (texture_width, texture_height) = renderer.texture_size(background);
+        let (texture_width, texture_height) = (texture_width as f32, texture_height as f32);
+        let field_aspect = width / height.max(1.0);
+        let source = if texture_width / texture_height > field_aspect {
+            let crop = texture_height * field_aspect;
+            Rect::new((texture_width - crop) / 2.0, 0.0, crop, texture_height)
+        } else {
+            let crop = texture_width / field_aspect;
+            Rect::new(0.0, (texture_height - crop) / 2.0, texture_width, crop)
+        };
+        renderer.draw_texture_ex(
+            background,
+            Rect::new(0.0, 0.0, width, height),
+            &TextureParams {
+                source: Some(source),
+                ..Default::default()
+            },
+        );
+    }
+
+    // Both paddles at `positions`, the paddle image mirrored on the right so
+    // the artwork faces the field
+    pub fn draw_paddles(
+        &self,
+        renderer: &mut Renderer,
+        textures: Option<&ArenaTextures>,
+        colors: &ArenaColors,
+        sim: &GameSim,
+        positions: &Positions,
+    ) {
+        let paddle_texture = textures.and_then(|textures| textures.paddle);
+
+        let pos = Vector2::new(positions.left.x, positions.left.y - sim.left.height / 2.);
+        match paddle_texture {
+            Some(texture) => renderer.draw_texture(
+                texture,
+                pos,
+                sim.left.width,
+                sim.left.height,
+                colors.left_paddle,
+            ),
+            None => renderer.draw_rectangle(
+                pos,
+                sim.left.width,
+                sim.left.height,
+                colors.left_paddle,
+                Deg(0.),
+            ),
+        }
+
+        let pos = Vector2::new(
+            positions.right.x - sim.right.width,
+            positions.right.y - sim.right.height / 2.,
+        );
+        match paddle_texture {
+            Some(texture) => renderer.draw_texture_ex(
+                texture,
+                Rect::new(pos.x, pos.y, sim.right.width, sim.right.height),
+                &TextureParams {
+                    tint: colors.right_paddle,
+                    flip_x: true,
+                    ..Default::default()
+                },
+            ),
+            None => renderer.draw_rectangle(
+                pos,
+                sim.right.width,
+                sim.right.height,
+                colors.right_paddle,
+                Deg(0.),
+            ),
+        }
+    }
+
+    pub fn draw_center_line(&self, renderer: &mut Renderer, width: f32, height: f32) {
+        let line = &self.center_line;
+        let x = width / 2.0;
+
+        match line.style {
+            LineStyle::Solid => renderer.draw_line(
+                Vector2::new(x, 0.0),
+                Vector2::new(x, height),
+                line.width,
+                line.color,
+            ),
+            LineStyle::Dashed => {
+                let dash = line.width * 4.0;
+                renderer.draw_dashed_line(
+                    Vector2::new(x, dash / 2.0),
+                    Vector2::new(x, height),
+                    line.width,
+                    dash,
+                    dash,
+                    line.color,
+                );
+            }
+            LineStyle::Dotted => {
+                // Square dots, circles would use up the shape budget quickly
+                let spacing = line.width * 3.0;
+                let mut y = spacing / 2.0;
+                while y < height {
+                    renderer.draw_rectangle(
+                        Vector2::new(x - line.width / 2.0, y - line.width / 2.0),
+                        line.width,
+                        line.width,
+                        line.color,
+                        Deg(0.),
+                    );
+                    y += spacing;
+                }
+            }
+            LineStyle::None => (),
+        }
+    }
+}
+
+// Loads every arena directory from the assets directory. Broken packs are
+// logged and skipped, and the built-in arena is used if none could be loaded,
+// so the list is never empty.
+pub fn load_arenas() -> Vec<Arena> {
+    let dir = assets_dir().join(ARENA_DIR);
+    let mut arenas = match read_arena_dir(&dir) {
+        Ok(arenas) => arenas,
+        Err(err) => {
+            info!("No arenas loaded from {}: {:#}", dir.display(), err);
+            Vec::new()
+        }
+    };
+
+    if arenas.is_empty() {
+        arenas.push(Arena::builtin().unwrap_or_else(|err| {
+            error!("Invalid built-in arena: {:#}", err);
+            Arena::default()
+        }));
+    }
+    arenas
+}
+
+fn read_arena_dir(dir: &Path) -> Result<Vec<Arena>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("cannot read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path().join(ARENA_FILE)))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    let mut arenas = Vec::new();
+    for path in paths {
+        match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Arena::parse(&data))
+        {
+            Ok(mut arena) => {
+                arena.dir = path.parent().map(Path::to_path_buf);
+                info!("Loaded arena {:?} from {}", arena.name, path.display());
+                arenas.push(arena);
+            }
+            Err(err) => error!("Invalid arena {}: {:#}", path.display(), err),
+        }
+    }
+
+    Ok(arenas)
+}
+
+// "#rrggbb" or "#rrggbbaa"
+fn hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Srgba, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    parse_hex(&text).map_err(serde::de::Error::custom)
+}
+
+pub fn parse_hex(text: &str) -> Result<Srgba> {
+    let digits = text.strip_prefix('#').unwrap_or(text);
+    if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+        bail!(
+            "invalid color {:?}, expected \"#rrggbb\" or \"#rrggbbaa\"",
+            text
+        );
+    }
+
+    let channel = |index: usize| -> Result<f32> {
+        let byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16)
+            .with_context(|| format!("invalid color {:?}", text))?;
+        Ok(f32::from(byte) / 255.0)
+    };
+    let alpha = if digits.len() == 8 { channel(3)? } else { 1.0 };
+    Ok(Srgba::new(channel(0)?, channel(1)?, channel(2)?, alpha))
+}
+
+// "#rrggbb", or "#rrggbbaa" if not opaque
+pub fn to_hex(color: Srgba) -> String {
+    let color: Srgba<u8> = color.into_format();
+    if color.alpha == 255 {
+        format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.red, color.green, color.blue, color.alpha
+        )
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+
+static ASSETS_DIR_ENV: &str = "WGPU_PONG_ASSETS";
+
+// Directory that holds user-editable game data (AI profiles, ...).
+// Can be overridden with the WGPU_PONG_ASSETS environment variable,
+// otherwise `assets/` in the working directory or next to the executable is used.
+pub fn assets_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(ASSETS_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+
+    let local = PathBuf::from("assets");
+    if local.is_dir() {
+        return local;
+    }
+
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("assets")))
+        .filter(|dir| dir.is_dir())
+        .unwrap_or(local)
+}
@@ -0,0 +1,211 @@
+use std::f32::consts::PI;
+
+// Time constant for parameter changes, so sweeps don't click
+static SMOOTHING: f32 = 0.15;
+
+pub static MAX_CUTOFF: f32 = 20_000.0;
+pub static MIN_CUTOFF: f32 = 400.0;
+
+// Schroeder reverb delay lengths at 44.1kHz. The comb delays are primes, so
+// their echoes never line up and ring.
+static COMB_DELAYS: [usize; 4] = [1553, 1613, 1493, 1427];
+static ALLPASS_DELAYS: [usize; 2] = [225, 556];
+static COMB_FEEDBACK: f32 = 0.8;
+static COMB_DAMPING: f32 = 0.25;
+static ALLPASS_FEEDBACK: f32 = 0.5;
+
+// A parameter that glides toward its target instead of jumping.
+#[derive(Clone, Copy)]
+struct Smoothed {
+    current: f32,
+    target: f32,
+}
+
+impl Smoothed {
+    fn new(value: f32) -> Self {
+        Self {
+            current: value,
+            target: value,
+        }
+    }
+
+    fn advance(&mut self, seconds: f32) -> f32 {
+        self.current += (self.target - self.current) * (1.0 - (-seconds / SMOOTHING).exp());
+        self.current
+    }
+}
+
+// Resonance-free 2-pole low-pass (RBJ cookbook biquad), one state per channel.
+struct LowPass {
+    cutoff: Smoothed,
+    coefficients: [f32; 5],
+    state: Vec<[f32; 4]>,
+}
+
+impl LowPass {
+    fn new(sample_rate: f32, channels: usize) -> Self {
+        let mut low_pass = Self {
+            cutoff: Smoothed::new(MAX_CUTOFF),
+            coefficients: [1.0, 0.0, 0.0, 0.0, 0.0],
+            state: vec![[0.0; 4]; channels],
+        };
+        low_pass.update_coefficients(sample_rate);
+        low_pass
+    }
+
+    fn update_coefficients(&mut self, sample_rate: f32) {
+        let cutoff = self.cutoff.current.min(sample_rate * 0.45);
+        let omega = 2.0 * PI * cutoff / sample_rate;
+        let alpha = omega.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        let cos = omega.cos();
+        let a0 = 1.0 + alpha;
+
+        self.coefficients = [
+            (1.0 - cos) / 2.0 / a0,
+            (1.0 - cos) / a0,
+            (1.0 - cos) / 2.0 / a0,
+            -2.0 * cos / a0,
+            (1.0 - alpha) / a0,
+        ];
+    }
+
+    fn process(&mut self, frames: &mut [f32], channels: usize) {
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+        for frame in frames.chunks_mut(channels) {
+            for (sample, state) in frame.iter_mut().zip(self.state.iter_mut()) {
+                let [x1, x2, y1, y2] = *state;
+                let x = *sample;
+                let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+                *state = [x, x1, y, y1];
+                *sample = y;
+            }
+        }
+    }
+}
+
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filtered: f32,
+}
+
+impl Comb {
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filtered = output * (1.0 - COMB_DAMPING) + self.filtered * COMB_DAMPING;
+        self.buffer[self.index] = input + self.filtered * COMB_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        let output = delayed - input;
+        self.buffer[self.index] = input + delayed * ALLPASS_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+// Mono-in, mono-out reverb tail that is mixed back into every channel.
+struct Reverb {
+    mix: Smoothed,
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl Reverb {
+    fn new(sample_rate: f32) -> Self {
+        let scale = sample_rate / 44_100.0;
+        let length = |delay: usize| ((delay as f32 * scale) as usize).max(1);
+
+        Self {
+            mix: Smoothed::new(0.0),
+            combs: COMB_DELAYS
+                .iter()
+                .map(|&delay| Comb {
+                    buffer: vec![0.0; length(delay)],
+                    index: 0,
+                    filtered: 0.0,
+                })
+                .collect(),
+            allpasses: ALLPASS_DELAYS
+                .iter()
+                .map(|&delay| Allpass {
+                    buffer: vec![0.0; length(delay)],
+                    index: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn process(&mut self, frames: &mut [f32], channels: usize) {
+        let mix = self.mix.current;
+        for frame in frames.chunks_mut(channels) {
+            let input = frame.iter().sum::<f32>() / channels as f32;
+            let mut wet = self
+                .combs
+                .iter_mut()
+                .map(|comb| comb.process(input))
+                .sum::<f32>()
+                / self.combs.len() as f32;
+            for allpass in &mut self.allpasses {
+                wet = allpass.process(wet);
+            }
+
+            for sample in frame {
+                *sample = *sample * (1.0 - mix * 0.5) + wet * mix;
+            }
+        }
+    }
+}
+
+// Effects applied to the sum of all voices: a low-pass filter followed by a reverb.
+pub struct EffectsBus {
+    sample_rate: f32,
+    channels: usize,
+    low_pass: LowPass,
+    reverb: Reverb,
+}
+
+impl EffectsBus {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            channels,
+            low_pass: LowPass::new(sample_rate as f32, channels),
+            reverb: Reverb::new(sample_rate as f32),
+        }
+    }
+
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.low_pass.cutoff.target = cutoff.clamp(MIN_CUTOFF, MAX_CUTOFF);
+    }
+
+    pub fn set_reverb_mix(&mut self, mix: f32) {
+        self.reverb.mix.target = mix.clamp(0.0, 1.0);
+    }
+
+    // Processes interleaved samples in place.
+    pub fn process(&mut self, frames: &mut [f32]) {
+        let seconds = (frames.len() / self.channels) as f32 / self.sample_rate;
+        self.low_pass.cutoff.advance(seconds);
+        self.low_pass.update_coefficients(self.sample_rate);
+        self.reverb.mix.advance(seconds);
+
+        // Skip the filters entirely while they would not be audible
+        if self.low_pass.cutoff.current < MAX_CUTOFF * 0.99 {
+            self.low_pass.process(frames, self.channels);
+        }
+        if self.reverb.mix.current > 0.001 {
+            self.reverb.process(frames, self.channels);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use serde::Deserialize;
+use std::f32::consts::TAU;
+
+use super::effects::EffectsBus;
+use super::wav::Sample;
+
+// More simultaneous sounds than this are dropped, oldest first
+static MAX_VOICES: usize = 16;
+static MASTER_GAIN: f32 = 0.5;
+
+// A short synthesized blip: a sine wave with an exponential decay.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Tone {
+    pub frequency: f32,
+    pub duration: f32,
+    pub gain: f32,
+}
+
+enum Voice {
+    Tone {
+        tone: Tone,
+        phase: f32,
+        elapsed: f32,
+    },
+    // Position in samples of the source, which may use another sample rate
+    Sample {
+        sample: Sample,
+        position: f32,
+    },
+}
+
+impl Voice {
+    fn next(&mut self, sample_rate: f32) -> f32 {
+        match self {
+            Voice::Tone {
+                tone,
+                phase,
+                elapsed,
+            } => {
+                let envelope = (-5.0 * *elapsed / tone.duration).exp();
+                let sample = (*phase * TAU).sin() * envelope * tone.gain;
+                *phase = (*phase + tone.frequency / sample_rate).fract();
+                *elapsed += 1.0 / sample_rate;
+                sample
+            }
+            Voice::Sample { sample, position } => {
+                // Linear interpolation between the two nearest source samples
+                let index = *position as usize;
+                let fraction = position.fract();
+                let current = sample.samples.get(index).copied().unwrap_or(0.0);
+                let next = sample.samples.get(index + 1).copied().unwrap_or(0.0);
+                *position += sample.sample_rate as f32 / sample_rate;
+                current + (next - current) * fraction
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        match self {
+            Voice::Tone { tone, elapsed, .. } => *elapsed >= tone.duration,
+            Voice::Sample { sample, position } => *position as usize >= sample.samples.len(),
+        }
+    }
+}
+
+// Sums all playing voices and runs the result through the effects bus.
+// Shared between the game thread and the audio callback.
+pub struct Mixer {
+    sample_rate: f32,
+    channels: usize,
+    voices: Vec<Voice>,
+    // Volume of everything and of the sound effects, from 0 to 1
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub bus: EffectsBus,
+}
+
+impl Mixer {
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            channels,
+            voices: Vec::new(),
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            bus: EffectsBus::new(sample_rate, channels),
+        }
+    }
+
+    pub fn play_tone(&mut self, tone: Tone) {
+        self.add_voice(Voice::Tone {
+            tone,
+            phase: 0.0,
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn play_sample(&mut self, sample: Sample) {
+        self.add_voice(Voice::Sample {
+            sample,
+            position: 0.0,
+        });
+    }
+
+    fn add_voice(&mut self, voice: Voice) {
+        if self.voices.len() >= MAX_VOICES {
+            self.voices.remove(0);
+        }
+        self.voices.push(voice);
+    }
+
+    // Fills interleaved `out` with the next frames.
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    pub fn render(&mut self, out: &mut [f32]) {
+        for frame in out.chunks_mut(self.channels) {
+            let sample = self
+                .voices
+                .iter_mut()
+                .map(|voice| voice.next(self.sample_rate))
+                .sum::<f32>()
+                * self.sfx_volume
+                * self.master_volume
+                * MASTER_GAIN;
+            frame.fill(sample);
+        }
+        self.voices.retain(|voice| !voice.is_finished());
+
+        self.bus.process(out);
+        for sample in out {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+}
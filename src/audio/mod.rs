@@ -0,0 +1,144 @@
+mod effects;
+mod mixer;
+mod output;
+mod wav;
+
+use log::{error, info};
+
+pub use mixer::Tone;
+use wav::Sample;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundId {
+    PaddleHit,
+    WallBounce,
+    Goal,
+    // Moving around in a menu
+    MenuMove,
+    MenuSelect,
+    MenuBack,
+}
+
+// Built into the binary, so the game needs no files to make sound
+static SOUND_FILES: [(SoundId, &[u8]); 6] = [
+    (
+        SoundId::PaddleHit,
+        include_bytes!("../../assets/sounds/paddle_hit.wav"),
+    ),
+    (
+        SoundId::WallBounce,
+        include_bytes!("../../assets/sounds/wall_bounce.wav"),
+    ),
+    (
+        SoundId::Goal,
+        include_bytes!("../../assets/sounds/goal.wav"),
+    ),
+    (
+        SoundId::MenuMove,
+        include_bytes!("../../assets/sounds/menu_move.wav"),
+    ),
+    (
+        SoundId::MenuSelect,
+        include_bytes!("../../assets/sounds/menu_select.wav"),
+    ),
+    (
+        SoundId::MenuBack,
+        include_bytes!("../../assets/sounds/menu_back.wav"),
+    ),
+];
+
+static MENU_REVERB: f32 = 0.45;
+
+// What the effects bus reacts to, set once per frame.
+#[derive(Debug, Clone, Copy)]
+pub struct AudioScene {
+    // A menu or overlay screen is open instead of the match
+    pub in_menu: bool,
+    // 1.0 at normal speed, lower during slow motion
+    pub time_scale: f32,
+}
+
+// The built-in sounds decoded, which can happen on another thread while the
+// game loads. Sounds that fail to decode stay silent.
+#[derive(Debug, Clone, Default)]
+pub struct Sounds(Vec<(SoundId, Sample)>);
+
+impl Sounds {
+    pub fn decode() -> Self {
+        Self(
+            SOUND_FILES
+                .iter()
+                .filter_map(|(id, bytes)| match wav::decode(bytes) {
+                    Ok(sample) => Some((*id, sample)),
+                    Err(err) => {
+                        error!("Cannot decode sound {:?}: {:#}", id, err);
+                        None
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+// Plays sounds through the default output device, if there is one. Without a
+// device (or without the `audio` feature) every call is a no-op.
+pub struct Audio {
+    output: Option<output::Output>,
+    sounds: Sounds,
+}
+
+impl Audio {
+    pub fn new(sounds: Sounds) -> Self {
+        let output = match output::open() {
+            Ok(output) => Some(output),
+            Err(err) => {
+                info!("Audio disabled: {:#}", err);
+                None
+            }
+        };
+        Self { output, sounds }
+    }
+
+    pub fn play(&self, id: SoundId) {
+        let Some((_, sample)) = self.sounds.0.iter().find(|(sound, _)| *sound == id) else {
+            return;
+        };
+        self.with_mixer(|mixer| mixer.play_sample(sample.clone()));
+    }
+
+    // Plays a synthesized blip, e.g. an arena's own sound
+    pub fn play_tone(&self, tone: Tone) {
+        self.with_mixer(|mixer| mixer.play_tone(tone));
+    }
+
+    // Both from 0 to 1
+    pub fn set_volume(&self, master: f32, sfx: f32) {
+        self.with_mixer(|mixer| {
+            mixer.master_volume = master.clamp(0.0, 1.0);
+            mixer.sfx_volume = sfx.clamp(0.0, 1.0);
+        });
+    }
+
+    pub fn set_scene(&self, scene: AudioScene) {
+        // Sweep the cutoff exponentially, so it sounds linear
+        let time_scale = scene.time_scale.clamp(0.0, 1.0);
+        let cutoff =
+            effects::MIN_CUTOFF * (effects::MAX_CUTOFF / effects::MIN_CUTOFF).powf(time_scale);
+        let reverb = if scene.in_menu { MENU_REVERB } else { 0.0 };
+
+        self.with_mixer(|mixer| {
+            mixer.bus.set_cutoff(cutoff);
+            mixer.bus.set_reverb_mix(reverb);
+        });
+    }
+
+    fn with_mixer(&self, f: impl FnOnce(&mut mixer::Mixer)) {
+        let Some(output) = self.output.as_ref() else {
+            return;
+        };
+        match output.mixer().lock() {
+            Ok(mut mixer) => f(&mut mixer),
+            Err(err) => error!("Audio mixer poisoned: {}", err),
+        }
+    }
+}
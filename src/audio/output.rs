@@ -0,0 +1,87 @@
+use super::mixer::Mixer;
+
+#[cfg(feature = "audio")]
+pub use enabled::{Output, open};
+
+#[cfg(not(feature = "audio"))]
+pub use disabled::{Output, open};
+
+#[cfg(feature = "audio")]
+mod enabled {
+    use anyhow::{Context, Result, bail};
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use log::error;
+    use std::sync::{Arc, Mutex};
+
+    use super::Mixer;
+
+    pub struct Output {
+        mixer: Arc<Mutex<Mixer>>,
+        // Audio stops when the stream is dropped
+        _stream: cpal::Stream,
+    }
+
+    impl Output {
+        pub fn mixer(&self) -> &Mutex<Mixer> {
+            &self.mixer
+        }
+    }
+
+    pub fn open() -> Result<Output> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .context("no audio output device")?;
+        let config = device
+            .default_output_config()
+            .context("cannot query audio output config")?;
+        if config.sample_format() != cpal::SampleFormat::F32 {
+            bail!("unsupported sample format {}", config.sample_format());
+        }
+
+        let config: cpal::StreamConfig = config.into();
+        let mixer = Arc::new(Mutex::new(Mixer::new(
+            config.sample_rate.0,
+            config.channels as usize,
+        )));
+
+        let callback_mixer = mixer.clone();
+        let stream = device
+            .build_output_stream(
+                &config,
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| match callback_mixer.lock() {
+                    Ok(mut mixer) => mixer.render(data),
+                    Err(_) => data.fill(0.0),
+                },
+                |err| error!("Audio stream error: {}", err),
+                None,
+            )
+            .context("cannot open audio stream")?;
+        stream.play().context("cannot start audio stream")?;
+
+        Ok(Output {
+            mixer,
+            _stream: stream,
+        })
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+mod disabled {
+    use anyhow::{Result, bail};
+    use std::sync::Mutex;
+
+    use super::Mixer;
+
+    // Can never be created, so the game runs silently.
+    pub enum Output {}
+
+    impl Output {
+        pub fn mixer(&self) -> &Mutex<Mixer> {
+            match *self {}
+        }
+    }
+
+    pub fn open() -> Result<Output> {
+        bail!("built without the `audio` feature")
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{Context, Result, bail};
+use std::sync::Arc;
+
+// Decoded sound, mono samples from -1 to 1.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub sample_rate: u32,
+    pub samples: Arc<[f32]>,
+}
+
+// Reads a 16 bit PCM WAV file, mixing stereo down to mono.
+pub fn decode(bytes: &[u8]) -> Result<Sample> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        bail!("not a WAV file");
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut rest = &bytes[12..];
+    while rest.len() >= 8 {
+        let id = &rest[0..4];
+        let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let body = rest.get(8..8 + size).context("truncated WAV chunk")?;
+        match id {
+            b"fmt " => format = Some(body),
+            b"data" => data = Some(body),
+            _ => (),
+        }
+        // Chunks are padded to an even size
+        rest = rest.get(8 + size + size % 2..).unwrap_or_default();
+    }
+
+    let format = format.context("WAV file has no format chunk")?;
+    let data = data.context("WAV file has no data chunk")?;
+    if format.len() < 16 {
+        bail!("WAV format chunk is too short");
+    }
+    let encoding = u16::from_le_bytes([format[0], format[1]]);
+    let channels = u16::from_le_bytes([format[2], format[3]]) as usize;
+    let sample_rate = u32::from_le_bytes([format[4], format[5], format[6], format[7]]);
+    let bits = u16::from_le_bytes([format[14], format[15]]);
+    if encoding != 1 || bits != 16 {
+        bail!("only 16 bit PCM WAV files are supported");
+    }
+    if channels == 0 || sample_rate == 0 {
+        bail!("WAV file has no channels");
+    }
+
+    let samples = data
+        .chunks_exact(2 * channels)
+        .map(|frame| {
+            frame
+                .chunks_exact(2)
+                .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0)
+                .sum::<f32>()
+                / channels as f32
+        })
+        .collect();
+    Ok(Sample {
+        sample_rate,
+        samples,
+    })
+}
@@ -0,0 +1,230 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::keyboard::KeyCode;
+
+use crate::locale::Locale;
+use crate::renderer::{MAX_INSET, Renderer};
+
+static FONT_SIZE: f32 = 32.;
+static LINE_HEIGHT: f32 = 32.;
+static SLIDER_WIDTH: f32 = 400.0;
+static SLIDER_HEIGHT: f32 = 12.0;
+
+pub static GAMMA_RANGE: (f32, f32) = (0.5, 2.5);
+static GAMMA_STEP: f32 = 0.05;
+pub static BRIGHTNESS_RANGE: (f32, f32) = (0.5, 1.5);
+static BRIGHTNESS_STEP: f32 = 0.05;
+static INSET_STEP: f32 = 0.005;
+
+// The logo is drawn just above black. With a correct setting it is barely visible.
+static LOGO_COLOR: Srgba = Srgba::new(0.06, 0.06, 0.06, 1.0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Slider {
+    Gamma,
+    Brightness,
+    // Safe area inset for the HUD
+    Inset,
+}
+
+// Screen for adjusting gamma and brightness against a barely visible logo,
+// and the HUD's safe area against an outline of it.
+pub struct Calibration {
+    selected: Slider,
+}
+
+impl Calibration {
+    pub fn new() -> Self {
+        Self {
+            selected: Slider::Gamma,
+        }
+    }
+
+    // Returns false once the screen should be closed.
+    pub fn handle_key(&mut self, key_code: KeyCode, renderer: &mut Renderer) -> bool {
+        match key_code {
+            KeyCode::ArrowUp => {
+                self.selected = match self.selected {
+                    Slider::Gamma => Slider::Inset,
+                    Slider::Brightness => Slider::Gamma,
+                    Slider::Inset => Slider::Brightness,
+                };
+            }
+            KeyCode::ArrowDown => {
+                self.selected = match self.selected {
+                    Slider::Gamma => Slider::Brightness,
+                    Slider::Brightness => Slider::Inset,
+                    Slider::Inset => Slider::Gamma,
+                };
+            }
+            KeyCode::ArrowLeft | KeyCode::ArrowRight => {
+                let sign = if key_code == KeyCode::ArrowLeft {
+                    -1.0
+                } else {
+                    1.0
+                };
+                match self.selected {
+                    Slider::Gamma => renderer.set_gamma(
+                        (renderer.gamma() + sign * GAMMA_STEP).clamp(GAMMA_RANGE.0, GAMMA_RANGE.1),
+                    ),
+                    Slider::Brightness => renderer.set_brightness(
+                        (renderer.brightness() + sign * BRIGHTNESS_STEP)
+                            .clamp(BRIGHTNESS_RANGE.0, BRIGHTNESS_RANGE.1),
+                    ),
+                    Slider::Inset => {
+                        renderer.set_hud_inset(renderer.hud_inset() + sign * INSET_STEP)
+                    }
+                }
+            }
+            KeyCode::KeyR => {
+                renderer.set_gamma(1.0);
+                renderer.set_brightness(1.0);
+                renderer.set_hud_inset(0.0);
+            }
+            KeyCode::Enter | KeyCode::Escape | KeyCode::F3 => return false,
+            _ => (),
+        }
+        true
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, locale: &Locale) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        let center = Vector2::new(width / 2.0, height / 2.5);
+
+        // Drawn over the match, so it can fade in and out
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 1.),
+            Deg(0.),
+        );
+
+        // Everything inside should be fully visible on the display
+        if self.selected == Slider::Inset {
+            let area = renderer.safe_area();
+            let color = Srgba::new(1.0, 1.0, 0.0, 0.8);
+            let border = 2.0;
+            for (x, y, w, h) in [
+                (area.x, area.y, area.width, border),
+                (area.x, area.y + area.height - border, area.width, border),
+                (area.x, area.y, border, area.height),
+                (area.x + area.width - border, area.y, border, area.height),
+            ] {
+                renderer.draw_rectangle(Vector2::new(x, y), w, h, color, Deg(0.));
+            }
+        }
+
+        // Logo: two paddles and a ball
+        let paddle_width = 30.0;
+        let paddle_height = 160.0;
+        renderer.draw_rectangle(
+            Vector2::new(center.x - 200.0, center.y - paddle_height / 2.0),
+            paddle_width,
+            paddle_height,
+            LOGO_COLOR,
+            Deg(0.),
+        );
+        renderer.draw_rectangle(
+            Vector2::new(
+                center.x + 200.0 - paddle_width,
+                center.y - paddle_height / 2.0,
+            ),
+            paddle_width,
+            paddle_height,
+            LOGO_COLOR,
+            Deg(0.),
+        );
+        renderer.draw_circle(center, 40.0, LOGO_COLOR);
+
+        let text = locale.text("calibration.hint");
+        let text_width = renderer.measure_text(text, FONT_SIZE, LINE_HEIGHT);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2.0 - text_width / 2.0, center.y + 140.0),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            None,
+        );
+
+        let gamma = renderer.gamma();
+        let brightness = renderer.brightness();
+        self.draw_slider(
+            renderer,
+            &locale.format("calibration.gamma", &[("value", &format!("{:.2}", gamma))]),
+            (gamma - GAMMA_RANGE.0) / (GAMMA_RANGE.1 - GAMMA_RANGE.0),
+            center.y + 200.0,
+            self.selected == Slider::Gamma,
+        );
+        self.draw_slider(
+            renderer,
+            &locale.format(
+                "calibration.brightness",
+                &[("value", &format!("{:.2}", brightness))],
+            ),
+            (brightness - BRIGHTNESS_RANGE.0) / (BRIGHTNESS_RANGE.1 - BRIGHTNESS_RANGE.0),
+            center.y + 270.0,
+            self.selected == Slider::Brightness,
+        );
+        let inset = renderer.hud_inset();
+        self.draw_slider(
+            renderer,
+            &locale.format(
+                "calibration.hud_inset",
+                &[("value", &format!("{:.1}", inset * 100.0))],
+            ),
+            inset / MAX_INSET,
+            center.y + 340.0,
+            self.selected == Slider::Inset,
+        );
+
+        let text = locale.text("calibration.controls");
+        let text_width = renderer.measure_text(text, FONT_SIZE * 0.6, LINE_HEIGHT * 0.6);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2.0 - text_width / 2.0, height - LINE_HEIGHT * 1.5),
+            FONT_SIZE * 0.6,
+            LINE_HEIGHT * 0.6,
+            None,
+        );
+    }
+
+    fn draw_slider(
+        &self,
+        renderer: &mut Renderer,
+        label: &str,
+        value: f32,
+        y: f32,
+        selected: bool,
+    ) {
+        let x = renderer.size.width as f32 / 2.0 - SLIDER_WIDTH / 2.0;
+        let color = if selected {
+            Srgba::new(1.0, 1.0, 1.0, 1.0)
+        } else {
+            Srgba::new(0.5, 0.5, 0.5, 1.0)
+        };
+
+        renderer.draw_text(
+            label,
+            Vector2::new(x, y - LINE_HEIGHT - 4.0),
+            FONT_SIZE * 0.75,
+            LINE_HEIGHT * 0.75,
+            None,
+        );
+        renderer.draw_rectangle(
+            Vector2::new(x, y),
+            SLIDER_WIDTH,
+            SLIDER_HEIGHT,
+            Srgba::new(0.2, 0.2, 0.2, 1.0),
+            Deg(0.),
+        );
+        renderer.draw_rectangle(
+            Vector2::new(x, y),
+            SLIDER_WIDTH * value.clamp(0.0, 1.0),
+            SLIDER_HEIGHT,
+            color,
+            Deg(0.),
+        );
+    }
+}
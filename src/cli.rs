@@ -0,0 +1,107 @@
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+
+use crate::export::EXPORT_FPS;
+use crate::net::Role;
+
+pub enum Command {
+    Play,
+    // Print the header of a replay file and check that it can be played back
+    ReplayInfo(PathBuf),
+    // Play back a replay file in a window
+    Watch(PathBuf),
+    // Play and write the window's lifecycle events to a file on exit
+    RecordSession(PathBuf),
+    // Play while feeding the app the lifecycle events of a recorded session
+    ReplaySession(PathBuf),
+    // Play while pressing the keys of an input script at their ticks
+    InputScript(PathBuf),
+    // Run a rendezvous/relay server for online matches
+    RendezvousServer(String),
+    // Connect to a peer through a rendezvous server and report the route found
+    NatTest {
+        rendezvous: String,
+        session: String,
+    },
+    // Play against another player through a rendezvous server, as the host
+    // (left paddle) or the one joining (right paddle)
+    Online {
+        role: Role,
+        rendezvous: String,
+        session: String,
+    },
+    // Run AI-vs-AI matches headlessly and print balance statistics
+    Simulate {
+        matches: u32,
+        left: Option<String>,
+        right: Option<String>,
+    },
+    // Draw a frame dump without a window and save it as a PNG
+    RenderFrame {
+        dump: PathBuf,
+        out: PathBuf,
+    },
+    // Render a replay without a window into numbered PNGs in a directory, or
+    // a YUV4MPEG2 stream on stdout for "-"
+    ExportReplay {
+        replay: PathBuf,
+        out: PathBuf,
+        fps: u32,
+    },
+}
+
+pub fn parse() -> Result<Command> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => Ok(Command::Play),
+        ["--replay-info", path] => Ok(Command::ReplayInfo(PathBuf::from(path))),
+        ["--watch", path] => Ok(Command::Watch(PathBuf::from(path))),
+        ["--record-session", path] => Ok(Command::RecordSession(PathBuf::from(path))),
+        ["--replay-session", path] => Ok(Command::ReplaySession(PathBuf::from(path))),
+        ["--input-script", path] => Ok(Command::InputScript(PathBuf::from(path))),
+        ["--rendezvous-server", bind] => Ok(Command::RendezvousServer(bind.to_string())),
+        ["--nat-test", rendezvous, session] => Ok(Command::NatTest {
+            rendezvous: rendezvous.to_string(),
+            session: session.to_string(),
+        }),
+        [flag @ ("--host" | "--join"), rendezvous, session] => Ok(Command::Online {
+            role: if *flag == "--host" {
+                Role::Host
+            } else {
+                Role::Client
+            },
+            rendezvous: rendezvous.to_string(),
+            session: session.to_string(),
+        }),
+        ["--simulate", matches, profiles @ ..] if profiles.len() <= 2 => Ok(Command::Simulate {
+            matches: matches
+                .parse()
+                .context("--simulate expects a number of matches")?,
+            left: profiles.first().map(|name| name.to_string()),
+            right: profiles.get(1).map(|name| name.to_string()),
+        }),
+        ["--render-frame", dump, out] => Ok(Command::RenderFrame {
+            dump: PathBuf::from(dump),
+            out: PathBuf::from(out),
+        }),
+        ["--export-replay", replay, out, fps @ ..] if fps.len() <= 1 => Ok(Command::ExportReplay {
+            replay: PathBuf::from(replay),
+            out: PathBuf::from(out),
+            fps: match fps.first() {
+                Some(fps) => fps
+                    .parse()
+                    .context("--export-replay expects a number of frames per second")?,
+                None => EXPORT_FPS,
+            },
+        }),
+        _ => bail!(
+            "usage: wgpu-pong [--replay-info <file> | --watch <file> | --record-session <file> | --replay-session <file> | --input-script <file> | --rendezvous-server <addr> | --nat-test <rendezvous> <session> | --host <rendezvous> <session> | --join <rendezvous> <session> | --simulate <matches> [left-ai [right-ai]] | --render-frame <dump.json> <out.png> | --export-replay <file> <dir | -> [fps]]"
+        ),
+    }
+}
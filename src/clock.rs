@@ -0,0 +1,72 @@
+use crate::time::Instant;
+
+// The times of a frame in one place, so timers that belong to the match stop
+// and slow down with it instead of each checking for the pause. Real time
+// keeps going for the menus, the toasts and the network.
+pub struct GameClock {
+    last_frame: Instant,
+    real_delta: f32,
+    // Everything of the match stands still, e.g. behind the pause menu
+    paused: bool,
+    // Only the sim waits, e.g. for the intro or after a goal
+    held: bool,
+    time_scale: f32,
+}
+
+impl GameClock {
+    pub fn new() -> Self {
+        Self {
+            last_frame: Instant::now(),
+            real_delta: 0.0,
+            paused: false,
+            held: false,
+            time_scale: 1.0,
+        }
+    }
+
+    // Starts the frame at `now`, returning the real seconds since the last
+    // one for the menus, the HUD and the network
+    pub fn frame(&mut self, now: Instant) -> f32 {
+        self.real_delta = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        self.real_delta
+    }
+
+    // Skips the time until now, e.g. the time spent in the background
+    pub fn resync(&mut self) {
+        self.last_frame = Instant::now();
+    }
+
+    // Once a frame after `frame`, whether the match is paused or only its
+    // sim is held, and how fast it runs
+    pub fn set_match(&mut self, paused: bool, held: bool, time_scale: f32) {
+        self.paused = paused;
+        self.held = held;
+        self.time_scale = time_scale;
+    }
+
+    pub fn is_stepping(&self) -> bool {
+        !self.paused && !self.held
+    }
+
+    // Seconds of this frame for the timers of the match (intro, replays,
+    // camera), 0 while it is paused
+    pub fn match_delta(&self) -> f32 {
+        if self.paused { 0.0 } else { self.real_delta }
+    }
+
+    // Seconds of this frame for the sim's steps, also 0 while it is held.
+    // Not slowed down, the sim does that itself.
+    pub fn step_delta(&self) -> f32 {
+        if self.is_stepping() {
+            self.real_delta
+        } else {
+            0.0
+        }
+    }
+
+    // Seconds of this frame for effects, slowed down with the match
+    pub fn game_delta(&self) -> f32 {
+        self.match_delta() * self.time_scale
+    }
+}
@@ -0,0 +1,188 @@
+use cgmath::{Deg, Vector2};
+use palette::{FromColor, Hsv, Srgb, Srgba};
+use winit::keyboard::KeyCode;
+
+use crate::locale::Locale;
+use crate::renderer::Renderer;
+
+static FONT_SIZE: f32 = 24.;
+static LINE_HEIGHT: f32 = 28.;
+static SLIDER_WIDTH: f32 = 360.;
+static SLIDER_HEIGHT: f32 = 12.;
+static ROW_HEIGHT: f32 = 64.;
+// Hue slider blocks, drawn in their own color
+static HUE_STEPS: usize = 24;
+
+static HUE_STEP: f32 = 5.0;
+static STEP: f32 = 0.02;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    Hue,
+    Saturation,
+    Value,
+}
+
+// Hue, saturation and brightness sliders for picking an opaque color.
+pub struct ColorPicker {
+    // Degrees
+    hue: f32,
+    saturation: f32,
+    value: f32,
+    selected: Channel,
+}
+
+impl ColorPicker {
+    pub fn new(color: Srgba) -> Self {
+        let hsv = Hsv::from_color(color.color);
+        Self {
+            hue: hsv.hue.into_positive_degrees(),
+            saturation: hsv.saturation,
+            value: hsv.value,
+            selected: Channel::Hue,
+        }
+    }
+
+    pub fn color(&self) -> Srgba {
+        hsv_color(self.hue, self.saturation, self.value)
+    }
+
+    // Returns whether the color changed.
+    pub fn handle_key(&mut self, key_code: KeyCode) -> bool {
+        match key_code {
+            KeyCode::ArrowUp => {
+                self.selected = match self.selected {
+                    Channel::Hue => Channel::Value,
+                    Channel::Saturation => Channel::Hue,
+                    Channel::Value => Channel::Saturation,
+                };
+                false
+            }
+            KeyCode::ArrowDown => {
+                self.selected = match self.selected {
+                    Channel::Hue => Channel::Saturation,
+                    Channel::Saturation => Channel::Value,
+                    Channel::Value => Channel::Hue,
+                };
+                false
+            }
+            KeyCode::ArrowLeft | KeyCode::ArrowRight => {
+                let sign = if key_code == KeyCode::ArrowLeft {
+                    -1.0
+                } else {
+                    1.0
+                };
+                match self.selected {
+                    Channel::Hue => self.hue = (self.hue + sign * HUE_STEP).rem_euclid(360.0),
+                    Channel::Saturation => {
+                        self.saturation = (self.saturation + sign * STEP).clamp(0.0, 1.0)
+                    }
+                    Channel::Value => self.value = (self.value + sign * STEP).clamp(0.0, 1.0),
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Draws the three sliders below `pos`, returns the height used.
+    pub fn draw(&self, renderer: &mut Renderer, pos: Vector2<f32>, locale: &Locale) -> f32 {
+        let label_color = |channel: Channel| {
+            if channel == self.selected {
+                glyphon::Color::rgb(255, 255, 255)
+            } else {
+                glyphon::Color::rgb(128, 128, 128)
+            }
+        };
+
+        // Hue: the full color wheel at the current saturation and brightness
+        let y = pos.y;
+        renderer.draw_text(
+            &locale.format(
+                "color_picker.hue",
+                &[("value", &format!("{:.0}", self.hue))],
+            ),
+            Vector2::new(pos.x, y),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            Some(label_color(Channel::Hue)),
+        );
+        let step_width = SLIDER_WIDTH / HUE_STEPS as f32;
+        for step in 0..HUE_STEPS {
+            let hue = step as f32 / HUE_STEPS as f32 * 360.0;
+            renderer.draw_rectangle(
+                Vector2::new(pos.x + step as f32 * step_width, y + LINE_HEIGHT),
+                step_width,
+                SLIDER_HEIGHT,
+                hsv_color(hue, self.saturation.max(0.5), self.value.max(0.5)),
+                Deg(0.),
+            );
+        }
+        draw_marker(
+            renderer,
+            pos.x + self.hue / 360.0 * SLIDER_WIDTH,
+            y + LINE_HEIGHT,
+        );
+
+        let sliders = [
+            (
+                Channel::Saturation,
+                "color_picker.saturation",
+                self.saturation,
+            ),
+            (Channel::Value, "color_picker.brightness", self.value),
+        ];
+        for (index, (channel, key, amount)) in sliders.into_iter().enumerate() {
+            let y = pos.y + (index + 1) as f32 * ROW_HEIGHT;
+            renderer.draw_text(
+                &locale.format(key, &[("value", &format!("{:.0}", amount * 100.0))]),
+                Vector2::new(pos.x, y),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(label_color(channel)),
+            );
+            renderer.draw_rectangle(
+                Vector2::new(pos.x, y + LINE_HEIGHT),
+                SLIDER_WIDTH,
+                SLIDER_HEIGHT,
+                Srgba::new(0.2, 0.2, 0.2, 1.0),
+                Deg(0.),
+            );
+            renderer.draw_rectangle(
+                Vector2::new(pos.x, y + LINE_HEIGHT),
+                SLIDER_WIDTH * amount,
+                SLIDER_HEIGHT,
+                self.color(),
+                Deg(0.),
+            );
+            draw_marker(renderer, pos.x + amount * SLIDER_WIDTH, y + LINE_HEIGHT);
+        }
+
+        ROW_HEIGHT * 3.
+    }
+}
+
+fn draw_marker(renderer: &mut Renderer, x: f32, y: f32) {
+    renderer.draw_rectangle(
+        Vector2::new(x - 2., y - 4.),
+        4.,
+        SLIDER_HEIGHT + 8.,
+        Srgba::new(1.0, 1.0, 1.0, 1.0),
+        Deg(0.),
+    );
+}
+
+fn hsv_color(hue: f32, saturation: f32, value: f32) -> Srgba {
+    let rgb = Srgb::from_color(Hsv::new(hue, saturation, value));
+    Srgba::new(rgb.red, rgb.green, rgb.blue, 1.0)
+}
+
+// WCAG contrast ratio between two colors, from 1 (same) to 21 (black on white).
+pub fn contrast_ratio(a: Srgba, b: Srgba) -> f32 {
+    let luminance = |color: Srgba| {
+        let linear = color.color.into_linear();
+        0.2126 * linear.red + 0.7152 * linear.green + 0.0722 * linear.blue
+    };
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
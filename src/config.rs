@@ -0,0 +1,509 @@
+use anyhow::{Context, Result};
+use log::error;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use winit::keyboard::KeyCode;
+
+use crate::calibration::{BRIGHTNESS_RANGE, GAMMA_RANGE};
+use crate::pacing::FramePacing;
+use crate::prompts::Action;
+use crate::renderer::{MAX_INSET, UiFont, Upscale};
+use crate::sim::{BALL_SPEED, PADDLE_SPEED, Side};
+use crate::timestep::{TICK_RATE, TICK_RATES};
+
+static APP_DIR: &str = "wgpu-pong";
+static SETTINGS_FILE: &str = "settings.toml";
+// Used where the platform has no config directory, e.g. in the browser
+static FALLBACK_DIR: &str = "save";
+
+pub static PADDLE_SPEED_RANGE: (f32, f32) = (500.0, 2000.0);
+pub static BALL_SPEED_RANGE: (f32, f32) = (200.0, 800.0);
+pub static FONT_SIZE_RANGE: (f32, f32) = (20.0, 48.0);
+pub static DEFAULT_FONT_SIZE: f32 = 32.0;
+// Seconds without input before the AI takes over a paddle, the options
+// screen steps through IDLE_TIMEOUTS
+pub static IDLE_TIMEOUT_RANGE: (f32, f32) = (3.0, 300.0);
+pub static IDLE_TIMEOUTS: [f32; 5] = [5.0, 10.0, 20.0, 30.0, 60.0];
+
+// Keys the paddles and the serve can be bound to. Enter, Escape and the
+// function keys stay reserved for the menus and the debug shortcuts.
+pub static BINDABLE_KEYS: [KeyCode; 60] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+];
+
+// Everything the player can set up in the options screen, plus the
+// calibration. Kept apart from `Progress` in a settings.toml in the
+// platform config directory, so it can be edited by hand too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Pixels per second, for matches against the AI and between two local
+    // players. Online matches and the daily challenge use the defaults.
+    pub paddle_speed: f32,
+    pub ball_speed: f32,
+    // Of the scores and messages in the match
+    pub font_size: f32,
+    // Of all text, switched right away
+    pub font: UiFont,
+    // Vsync, VRR friendly or uncapped, see F4
+    pub pacing: FramePacing,
+    // Menus only at 30 fps with vsync, whatever the pacing of the match
+    pub menu_power_saving: bool,
+    // Local matches on ultrawide and portrait windows are played on a field
+    // of the window's shape, from the next match on
+    pub fit_field: bool,
+    // Lowers the resolution of the scene while frames take too long
+    pub dynamic_resolution: bool,
+    pub upscale: Upscale,
+    // The field at a low resolution blown up by whole pixels, which takes
+    // the place of the dynamic resolution
+    pub pixel_art: bool,
+    pub scanlines: bool,
+    // Ball speed and angle, and where the last hit met the paddle
+    pub telemetry: bool,
+    // Sim steps per second, one of TICK_RATES. Online the host's counts,
+    // the daily challenge always uses TICK_RATE.
+    pub tick_rate: u32,
+    // The AI plays for a player who hasn't touched their paddle for
+    // `idle_timeout` seconds, until they do again
+    pub idle_takeover: bool,
+    pub idle_timeout: f32,
+    // Match events are posted here as JSON when built with the webhook
+    // feature, only set by hand
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    // From the calibration screen
+    pub gamma: f32,
+    pub brightness: f32,
+    pub hud_inset: f32,
+    pub keys: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            paddle_speed: PADDLE_SPEED,
+            ball_speed: BALL_SPEED,
+            font_size: DEFAULT_FONT_SIZE,
+            font: UiFont::default(),
+            pacing: FramePacing::Vsync,
+            menu_power_saving: true,
+            fit_field: true,
+            dynamic_resolution: false,
+            upscale: Upscale::Bilinear,
+            pixel_art: false,
+            scanlines: false,
+            telemetry: false,
+            tick_rate: TICK_RATE,
+            idle_takeover: false,
+            idle_timeout: 10.0,
+            webhook_url: None,
+            gamma: 1.0,
+            brightness: 1.0,
+            hud_inset: 0.0,
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // A missing file starts with the defaults, an invalid one too after
+    // logging why
+    pub fn load() -> Self {
+        let path = config_dir().join(SETTINGS_FILE);
+        let Ok(data) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        toml::from_str::<Self>(&data)
+            .map(Self::clamped)
+            .unwrap_or_else(|err| {
+                error!("Ignoring invalid {}: {}", path.display(), err);
+                Self::default()
+            })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = config_dir();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create config directory {}", dir.display()))?;
+
+        let path = dir.join(SETTINGS_FILE);
+        let data = toml::to_string_pretty(self)?;
+        fs::write(&path, data).with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    // Back to the defaults, except for the calibration which has a screen
+    // of its own
+    pub fn reset_options(&mut self) {
+        *self = Self {
+            gamma: self.gamma,
+            brightness: self.brightness,
+            hud_inset: self.hud_inset,
+            webhook_url: self.webhook_url.take(),
+            ..Self::default()
+        };
+    }
+
+    // Values edited by hand are kept in the ranges the options screen allows
+    fn clamped(self) -> Self {
+        let clamp = |value: f32, (min, max): (f32, f32)| {
+            if value.is_finite() {
+                value.clamp(min, max)
+            } else {
+                min
+            }
+        };
+        Self {
+            paddle_speed: clamp(self.paddle_speed, PADDLE_SPEED_RANGE),
+            ball_speed: clamp(self.ball_speed, BALL_SPEED_RANGE),
+            font_size: clamp(self.font_size, FONT_SIZE_RANGE),
+            idle_timeout: clamp(self.idle_timeout, IDLE_TIMEOUT_RANGE),
+            gamma: clamp(self.gamma, GAMMA_RANGE),
+            brightness: clamp(self.brightness, BRIGHTNESS_RANGE),
+            hud_inset: clamp(self.hud_inset, (0.0, MAX_INSET)),
+            tick_rate: if TICK_RATES.contains(&self.tick_rate) {
+                self.tick_rate
+            } else {
+                TICK_RATE
+            },
+            ..self
+        }
+    }
+}
+
+// The platform's config directory for the game: $XDG_CONFIG_HOME or
+// ~/.config on Linux, Application Support on macOS, %APPDATA% on Windows
+fn config_dir() -> PathBuf {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home().map(|home| home.join(".config")))
+    };
+    base.map_or_else(|| PathBuf::from(FALLBACK_DIR), |dir| dir.join(APP_DIR))
+}
+
+// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    LeftUp,
+    LeftDown,
+    RightUp,
+    RightDown,
+    Serve,
+}
+
+pub static BINDINGS: [Binding; 5] = [
+    Binding::LeftUp,
+    Binding::LeftDown,
+    Binding::RightUp,
+    Binding::RightDown,
+    Binding::Serve,
+];
+
+impl Binding {
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Binding::LeftUp => "options.left_up",
+            Binding::LeftDown => "options.left_down",
+            Binding::RightUp => "options.right_up",
+            Binding::RightDown => "options.right_down",
+            Binding::Serve => "options.serve",
+        }
+    }
+}
+
+// Preset up and down keys for a paddle, so a left-handed player or one on
+// the other side of the keyboard doesn't have to rebind both keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlLayout {
+    Wasd,
+    Arrows,
+    Ijkl,
+    Numpad,
+}
+
+pub static CONTROL_LAYOUTS: [ControlLayout; 4] = [
+    ControlLayout::Wasd,
+    ControlLayout::Arrows,
+    ControlLayout::Ijkl,
+    ControlLayout::Numpad,
+];
+
+impl ControlLayout {
+    // Up, then down
+    pub fn keys(self) -> (KeyCode, KeyCode) {
+        match self {
+            ControlLayout::Wasd => (KeyCode::KeyW, KeyCode::KeyS),
+            ControlLayout::Arrows => (KeyCode::ArrowUp, KeyCode::ArrowDown),
+            ControlLayout::Ijkl => (KeyCode::KeyI, KeyCode::KeyK),
+            ControlLayout::Numpad => (KeyCode::Numpad8, KeyCode::Numpad2),
+        }
+    }
+
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            ControlLayout::Wasd => "options.layout_wasd",
+            ControlLayout::Arrows => "options.layout_arrows",
+            ControlLayout::Ijkl => "options.layout_ijkl",
+            ControlLayout::Numpad => "options.layout_numpad",
+        }
+    }
+}
+
+// Keys stored by their names in winit, e.g. "KeyW" or "ArrowUp".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    #[serde(serialize_with = "key_name", deserialize_with = "bindable_key")]
+    pub left_up: KeyCode,
+    #[serde(serialize_with = "key_name", deserialize_with = "bindable_key")]
+    pub left_down: KeyCode,
+    #[serde(serialize_with = "key_name", deserialize_with = "bindable_key")]
+    pub right_up: KeyCode,
+    #[serde(serialize_with = "key_name", deserialize_with = "bindable_key")]
+    pub right_down: KeyCode,
+    #[serde(serialize_with = "key_name", deserialize_with = "bindable_key")]
+    pub serve: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            left_up: KeyCode::KeyW,
+            left_down: KeyCode::KeyS,
+            right_up: KeyCode::ArrowUp,
+            right_down: KeyCode::ArrowDown,
+            serve: KeyCode::Space,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn get(&self, binding: Binding) -> KeyCode {
+        match binding {
+            Binding::LeftUp => self.left_up,
+            Binding::LeftDown => self.left_down,
+            Binding::RightUp => self.right_up,
+            Binding::RightDown => self.right_down,
+            Binding::Serve => self.serve,
+        }
+    }
+
+    fn get_mut(&mut self, binding: Binding) -> &mut KeyCode {
+        match binding {
+            Binding::LeftUp => &mut self.left_up,
+            Binding::LeftDown => &mut self.left_down,
+            Binding::RightUp => &mut self.right_up,
+            Binding::RightDown => &mut self.right_down,
+            Binding::Serve => &mut self.serve,
+        }
+    }
+
+    // Binds `key`, swapping with whatever had it before so no key does two
+    // things. Returns false for keys that can't be bound.
+    pub fn set(&mut self, binding: Binding, key: KeyCode) -> bool {
+        if !BINDABLE_KEYS.contains(&key) {
+            return false;
+        }
+        let previous = self.get(binding);
+        if let Some(other) = BINDINGS.iter().find(|other| self.get(**other) == key) {
+            *self.get_mut(*other) = previous;
+        }
+        *self.get_mut(binding) = key;
+        true
+    }
+
+    fn paddle(side: Side) -> (Binding, Binding) {
+        match side {
+            Side::Left => (Binding::LeftUp, Binding::LeftDown),
+            Side::Right => (Binding::RightUp, Binding::RightDown),
+        }
+    }
+
+    // The preset the paddle's keys match, None for keys bound one by one
+    pub fn layout(&self, side: Side) -> Option<ControlLayout> {
+        let (up, down) = Self::paddle(side);
+        CONTROL_LAYOUTS
+            .iter()
+            .copied()
+            .find(|layout| layout.keys() == (self.get(up), self.get(down)))
+    }
+
+    // Like binding both keys by hand, so picking the other paddle's layout
+    // hands it this paddle's keys and mirrors the controls
+    pub fn set_layout(&mut self, side: Side, layout: ControlLayout) {
+        let (up, down) = Self::paddle(side);
+        let (up_key, down_key) = layout.keys();
+        self.set(up, up_key);
+        self.set(down, down_key);
+    }
+
+    // The left player's keys move the right paddle and the other way around
+    pub fn swap_sides(&mut self) {
+        *self = Self {
+            left_up: self.right_up,
+            left_down: self.right_down,
+            right_up: self.left_up,
+            right_down: self.left_down,
+            serve: self.serve,
+        };
+    }
+
+    // What the left player's keys do in the tutorial
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        if key == self.left_up {
+            Some(Action::MoveUp)
+        } else if key == self.left_down {
+            Some(Action::MoveDown)
+        } else if key == self.serve {
+            Some(Action::Serve)
+        } else {
+            None
+        }
+    }
+}
+
+// Short name of a key for the screen, looked up in the language files like
+// the prompt labels, so "keys.space" is translated while "W" is shown as it is
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    match key {
+        KeyCode::Space => "keys.space".to_string(),
+        KeyCode::ArrowUp => "↑".to_string(),
+        KeyCode::ArrowDown => "↓".to_string(),
+        KeyCode::ArrowLeft => "←".to_string(),
+        KeyCode::ArrowRight => "→".to_string(),
+        KeyCode::Comma => ",".to_string(),
+        KeyCode::Period => ".".to_string(),
+        KeyCode::Semicolon => ";".to_string(),
+        KeyCode::Slash => "/".to_string(),
+        _ => {
+            let name = name
+                .strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+                .unwrap_or(&name);
+            name.replace("Numpad", "Num ")
+                .replace("Left", " L")
+                .replace("Right", " R")
+                .replace("Control", "Ctrl")
+        }
+    }
+}
+
+fn key_name<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:?}", key))
+}
+
+fn bindable_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| format!("{:?}", key) == name)
+        .ok_or_else(|| D::Error::custom(format!("{:?} can't be bound", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hand_edited_values_are_clamped() {
+        let settings = Settings {
+            paddle_speed: 1e6,
+            ball_speed: f32::NAN,
+            font_size: 0.0,
+            tick_rate: 7,
+            gamma: 10.0,
+            brightness: f32::NEG_INFINITY,
+            hud_inset: -1.0,
+            ..Settings::default()
+        }
+        .clamped();
+        assert_eq!(settings.paddle_speed, PADDLE_SPEED_RANGE.1);
+        assert_eq!(settings.ball_speed, BALL_SPEED_RANGE.0);
+        assert_eq!(settings.font_size, FONT_SIZE_RANGE.0);
+        assert_eq!(settings.tick_rate, TICK_RATE);
+        assert_eq!(settings.gamma, GAMMA_RANGE.1);
+        assert_eq!(settings.brightness, BRIGHTNESS_RANGE.0);
+        assert_eq!(settings.hud_inset, 0.0);
+
+        let settings = Settings {
+            hud_inset: 1.0,
+            ..Settings::default()
+        }
+        .clamped();
+        assert_eq!(settings.hud_inset, MAX_INSET);
+    }
+}
@@ -0,0 +1,333 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use winit::keyboard::KeyCode;
+
+use crate::ai::AiProfile;
+use crate::locale::Locale;
+use crate::mutators::Mutators;
+use crate::progress::Progress;
+use crate::renderer::Renderer;
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+static FONT_SIZE: f32 = 28.;
+static LINE_HEIGHT: f32 = 32.;
+static CELL_SIZE: f32 = 56.;
+
+// A calendar date in UTC, so everyone gets the same challenge on the same day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        Self::from_days((seconds / 86_400) as i64)
+    }
+
+    // Days since 1970-01-01 to a date (Howard Hinnant's civil_from_days).
+    fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self { year, month, day }
+    }
+
+    // The inverse of `from_days`.
+    fn to_days(self) -> i64 {
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = (self.month as i64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146_097 + doe - 719_468
+    }
+
+    // 0 = Monday
+    fn weekday(self) -> u32 {
+        (self.to_days() + 3).rem_euclid(7) as u32
+    }
+
+    fn days_in_month(self) -> u32 {
+        let next = if self.month == 12 {
+            Date {
+                year: self.year + 1,
+                month: 1,
+                day: 1,
+            }
+        } else {
+            Date {
+                year: self.year,
+                month: self.month + 1,
+                day: 1,
+            }
+        };
+        (next.to_days() - Date { day: 1, ..self }.to_days()) as u32
+    }
+
+    // Used as the key for results, e.g. "2025-03-14"
+    pub fn key(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+// Today's match: the seed, mutators and opponent all follow from the date.
+#[derive(Clone)]
+pub struct DailyChallenge {
+    pub date: Date,
+    pub seed: u64,
+    pub mutators: Mutators,
+    pub opponent: AiProfile,
+}
+
+impl DailyChallenge {
+    // `profiles` must be in the same order on every install, `load_profiles` sorts them.
+    pub fn for_date(date: Date, profiles: &[AiProfile]) -> Option<Self> {
+        let seed = splitmix64(date.to_days() as u64);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mutators = Mutators {
+            portal_walls: rng.random_bool(0.4),
+            small_paddle: rng.random_bool(0.25),
+            ..Mutators::default()
+        };
+        let mut opponent = profiles
+            .get(rng.random_range(0..profiles.len().max(1)))?
+            .clone();
+        // Reuse the installed profiles, but make every day play a bit differently
+        opponent.reaction_time *= rng.random_range(0.8..1.2);
+        opponent.aggression = (opponent.aggression * rng.random_range(0.8..1.2)).clamp(0.0, 1.0);
+
+        Some(Self {
+            date,
+            seed,
+            mutators,
+            opponent,
+        })
+    }
+
+    pub fn description(&self, locale: &Locale) -> String {
+        let mutators = self.mutators.active_names(locale);
+        if mutators.is_empty() {
+            locale.format("daily.versus", &[("opponent", &self.opponent.name)])
+        } else {
+            locale.format(
+                "daily.versus_with",
+                &[
+                    ("opponent", &self.opponent.name),
+                    ("mutators", &mutators.join(", ")),
+                ],
+            )
+        }
+    }
+}
+
+// Spreads consecutive day numbers over the whole seed range.
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub enum DailyAction {
+    Play,
+    Close,
+}
+
+// Shows today's challenge and a calendar of the days played this month.
+pub struct DailyScreen {
+    challenge: DailyChallenge,
+}
+
+impl DailyScreen {
+    pub fn new(challenge: DailyChallenge) -> Self {
+        Self { challenge }
+    }
+
+    pub fn challenge(&self) -> &DailyChallenge {
+        &self.challenge
+    }
+
+    pub fn handle_key(&self, key_code: KeyCode) -> Option<DailyAction> {
+        match key_code {
+            KeyCode::Enter | KeyCode::Space => Some(DailyAction::Play),
+            KeyCode::Escape | KeyCode::Backspace => Some(DailyAction::Close),
+            _ => None,
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, progress: &Progress, locale: &Locale) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        let today = self.challenge.date;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.85),
+            Deg(0.),
+        );
+
+        let grid_width = CELL_SIZE * 7.;
+        let left = width / 2. - grid_width / 2.;
+        renderer.draw_text(
+            &locale.format("daily.title", &[("date", &today.key())]),
+            Vector2::new(left, LINE_HEIGHT),
+            FONT_SIZE * 1.3,
+            LINE_HEIGHT * 1.3,
+            None,
+        );
+        renderer.draw_text(
+            &self.challenge.description(locale),
+            Vector2::new(left, LINE_HEIGHT * 2.5),
+            FONT_SIZE * 0.8,
+            LINE_HEIGHT * 0.8,
+            None,
+        );
+        let best = match progress.daily.get(&today.key()) {
+            Some(result) => locale.format(
+                "daily.best",
+                &[
+                    ("result", &locale.text(result.label_key())),
+                    ("points_for", &result.points_for.localized(locale)),
+                    ("points_against", &result.points_against.localized(locale)),
+                ],
+            ),
+            None => locale.text("daily.not_played").to_string(),
+        };
+        renderer.draw_text(
+            &best,
+            Vector2::new(left, LINE_HEIGHT * 3.5),
+            FONT_SIZE * 0.8,
+            LINE_HEIGHT * 0.8,
+            None,
+        );
+
+        let month_name = locale.text(&format!("month.{}", today.month)).to_string();
+        renderer.draw_text(
+            &locale.format(
+                "daily.month_year",
+                &[("month", &month_name), ("year", &today.year)],
+            ),
+            Vector2::new(left, LINE_HEIGHT * 5.),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            None,
+        );
+
+        let top = LINE_HEIGHT * 6.5;
+        let first = Date { day: 1, ..today };
+        let offset = first.weekday();
+        for day in 1..=today.days_in_month() {
+            let cell = offset + day - 1;
+            let pos = Vector2::new(
+                left + (cell % 7) as f32 * CELL_SIZE,
+                top + (cell / 7) as f32 * CELL_SIZE,
+            );
+            let date = Date { day, ..today };
+
+            // Won days are green, lost ones amber, future days dimmed
+            let color = match progress.daily.get(&date.key()) {
+                Some(result) if result.won() => Srgba::new(0.2, 0.6, 0.2, 1.0),
+                Some(_) => Srgba::new(0.6, 0.45, 0.1, 1.0),
+                None if day > today.day => Srgba::new(0.08, 0.08, 0.08, 1.0),
+                None => Srgba::new(0.2, 0.2, 0.2, 1.0),
+            };
+            if day == today.day {
+                renderer.draw_rectangle(
+                    Vector2::new(pos.x, pos.y),
+                    CELL_SIZE - 4.,
+                    CELL_SIZE - 4.,
+                    Srgba::new(1.0, 1.0, 1.0, 1.0),
+                    Deg(0.),
+                );
+            }
+            renderer.draw_rectangle(
+                Vector2::new(pos.x + 3., pos.y + 3.),
+                CELL_SIZE - 10.,
+                CELL_SIZE - 10.,
+                color,
+                Deg(0.),
+            );
+            renderer.draw_text(
+                &day.to_string(),
+                Vector2::new(pos.x + 8., pos.y + 6.),
+                FONT_SIZE * 0.7,
+                LINE_HEIGHT * 0.7,
+                None,
+            );
+        }
+
+        let text = locale.text("daily.controls");
+        let text_width = renderer.measure_text(text, FONT_SIZE * 0.6, LINE_HEIGHT * 0.6);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2. - text_width / 2., height - LINE_HEIGHT * 1.5),
+            FONT_SIZE * 0.6,
+            LINE_HEIGHT * 0.6,
+            None,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i64, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    #[test]
+    fn days_round_trip_across_leap_years_and_centuries() {
+        assert_eq!(date(1970, 1, 1).to_days(), 0);
+        for day in [
+            date(1900, 2, 28),
+            date(1900, 3, 1),
+            date(2000, 2, 29),
+            date(2000, 3, 1),
+            date(2024, 2, 29),
+            date(2024, 12, 31),
+            date(2100, 2, 28),
+            date(2100, 3, 1),
+        ] {
+            assert_eq!(Date::from_days(day.to_days()), day);
+        }
+        // 2100 is no leap year, 2000 is
+        assert_eq!(date(2100, 3, 1).to_days() - date(2100, 2, 28).to_days(), 1);
+        assert_eq!(date(2000, 3, 1).to_days() - date(2000, 2, 28).to_days(), 2);
+        assert_eq!(date(2000, 2, 1).days_in_month(), 29);
+        assert_eq!(date(2100, 2, 1).days_in_month(), 28);
+        for days in -800_000..800_000 {
+            assert_eq!(Date::from_days(days).to_days(), days);
+        }
+    }
+
+    #[test]
+    fn knows_the_weekday() {
+        // Thursday
+        assert_eq!(date(1970, 1, 1).weekday(), 3);
+        // Tuesday
+        assert_eq!(date(2000, 2, 29).weekday(), 1);
+        // Monday
+        assert_eq!(date(2100, 3, 1).weekday(), 0);
+    }
+}
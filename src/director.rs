@@ -0,0 +1,89 @@
+use cgmath::Vector2;
+use std::collections::HashSet;
+use winit::keyboard::KeyCode;
+
+use crate::photo;
+use crate::renderer::Camera2D;
+use crate::sim::GameSim;
+
+// How far toward the ball the camera leans, 0 = field center, 1 = on the ball
+static FOLLOW: f32 = 0.5;
+// Zoom on top of fitting the whole field into the window
+static AUTO_ZOOM: f32 = 1.2;
+// How far ahead of the ball to look, in seconds of ball travel
+static LEAD_TIME: f32 = 0.25;
+// Higher is snappier
+static DAMPING: f32 = 3.0;
+// The director takes over again after this long without camera input
+static MANUAL_TIMEOUT: f32 = 3.0;
+
+// Automatic camera for watching a match: follows and slightly zooms toward
+// the ball. Panning or zooming by hand takes over until the keys are left alone.
+pub struct CameraDirector {
+    camera: Camera2D,
+    // Seconds left before the director takes back control
+    manual: f32,
+}
+
+impl CameraDirector {
+    pub fn new(sim: &GameSim) -> Self {
+        Self {
+            camera: Camera2D::new(Vector2::new(sim.width / 2.0, sim.height / 2.0)),
+            manual: 0.0,
+        }
+    }
+
+    pub fn camera(&self) -> Camera2D {
+        self.camera
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.manual > 0.0
+    }
+
+    pub fn reset(&mut self) {
+        self.manual = 0.0;
+    }
+
+    pub fn update(
+        &mut self,
+        delta: f32,
+        sim: &GameSim,
+        screen: Vector2<f32>,
+        keys_pressed: &HashSet<KeyCode>,
+    ) {
+        if photo::free_camera_input(&mut self.camera, delta, keys_pressed) {
+            self.manual = MANUAL_TIMEOUT;
+            return;
+        }
+        if self.is_manual() {
+            self.manual -= delta;
+            return;
+        }
+
+        let field = Vector2::new(sim.width, sim.height);
+        let fit = (screen.x / field.x).min(screen.y / field.y);
+        let zoom = fit * AUTO_ZOOM;
+
+        let field_center = field / 2.0;
+        let ahead = sim.ball.pos + sim.ball.velocity * LEAD_TIME;
+        let mut center = field_center + (ahead - field_center) * FOLLOW;
+
+        // Don't show more outside of the field than needed
+        let half_view = screen / (2.0 * zoom);
+        center.x = clamp_axis(center.x, half_view.x, field.x);
+        center.y = clamp_axis(center.y, half_view.y, field.y);
+
+        let t = 1.0 - (-DAMPING * delta).exp();
+        self.camera.center += (center - self.camera.center) * t;
+        self.camera.zoom += (zoom - self.camera.zoom) * t;
+    }
+}
+
+fn clamp_axis(center: f32, half_view: f32, size: f32) -> f32 {
+    if half_view * 2.0 >= size {
+        size / 2.0
+    } else {
+        center.clamp(half_view, size - half_view)
+    }
+}
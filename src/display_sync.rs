@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use winit::window::Window;
+
+use crate::locale::Locale;
+use crate::pacing::{FramePacing, monitor_refresh_rate};
+use crate::time::Instant;
+
+// The present rate covers this much of the recent past
+static RATE_WINDOW: Duration = Duration::from_secs(1);
+// Fewer presents than this share of the expected rate count as a mismatch,
+// more than this multiple of the refresh rate as tearing
+static SLOW_SHARE: f32 = 0.9;
+static FAST_SHARE: f32 = 1.1;
+// Frames busy for more than this share of their interval are too slow
+// themselves, below it something else holds presentation back
+static BUSY_SHARE: f32 = 0.8;
+// Seconds between looking up which monitor the window is on
+static MONITOR_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Why the game presents at another rate than the display and the pacing
+// would suggest.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncIssue {
+    // Frames take longer than the display's refresh interval
+    TooSlow { rate: f32, expected: f32 },
+    // Frames are done in time but presented less often, e.g. a 60 fps cap of
+    // the driver or compositor on a 144 Hz panel
+    HeldBack { rate: f32, expected: f32 },
+    // Uncapped above the refresh rate, which tears
+    AboveRefresh { rate: f32, refresh: f32 },
+}
+
+impl SyncIssue {
+    // What's wrong and the suggested fix
+    pub fn suggestion(self, locale: &Locale) -> String {
+        let (key, fps, hz) = match self {
+            SyncIssue::TooSlow { rate, expected } => ("sync.too_slow", rate, expected),
+            SyncIssue::HeldBack { rate, expected } => ("sync.held_back", rate, expected),
+            SyncIssue::AboveRefresh { rate, refresh } => ("sync.above_refresh", rate, refresh),
+        };
+        locale.format(
+            key,
+            &[
+                ("fps", &format!("{:.0}", fps)),
+                ("hz", &format!("{:.0}", hz)),
+            ],
+        )
+    }
+}
+
+// The refresh rate of the monitor the window is on, and the rate frames
+// actually reach it, for the latency overlay and the options screen.
+pub struct DisplaySync {
+    monitor: Option<String>,
+    refresh_rate: Option<f32>,
+    last_check: Option<Instant>,
+    // When recent frames were presented, and how long each was busy
+    // without waiting for the display
+    presents: VecDeque<(Instant, Duration)>,
+}
+
+impl DisplaySync {
+    pub fn new() -> Self {
+        Self {
+            monitor: None,
+            refresh_rate: None,
+            last_check: None,
+            presents: VecDeque::new(),
+        }
+    }
+
+    // Notices the window moving to another monitor, or the monitor changing
+    // its refresh rate. Returns true when the pacing should follow.
+    pub fn update_monitor(&mut self, window: &Window) -> bool {
+        if self
+            .last_check
+            .is_some_and(|checked| checked.elapsed() < MONITOR_CHECK_INTERVAL)
+        {
+            return false;
+        }
+        self.last_check = Some(Instant::now());
+
+        let monitor = window.current_monitor().and_then(|monitor| monitor.name());
+        let refresh_rate = monitor_refresh_rate(window);
+        if monitor == self.monitor && refresh_rate == self.refresh_rate {
+            return false;
+        }
+        self.monitor = monitor;
+        self.refresh_rate = refresh_rate;
+        self.presents.clear();
+        true
+    }
+
+    // A frame that started at `started` reached the display at `presented`,
+    // after waiting `waited` for the display to take it
+    pub fn frame_presented(&mut self, started: Instant, presented: Instant, waited: Duration) {
+        let busy = presented.duration_since(started).saturating_sub(waited);
+        self.presents.push_back((presented, busy));
+        while self
+            .presents
+            .front()
+            .is_some_and(|(at, _)| presented.duration_since(*at) > RATE_WINDOW)
+        {
+            self.presents.pop_front();
+        }
+    }
+
+    pub fn refresh_rate(&self) -> Option<f32> {
+        self.refresh_rate
+    }
+
+    pub fn monitor(&self) -> Option<&str> {
+        self.monitor.as_deref()
+    }
+
+    // Frames presented per second, once a few arrived
+    pub fn present_rate(&self) -> Option<f32> {
+        let (first, _) = self.presents.front()?;
+        let (last, _) = self.presents.back()?;
+        let span = last.duration_since(*first).as_secs_f32();
+        (self.presents.len() >= 10 && span > 0.0).then(|| (self.presents.len() - 1) as f32 / span)
+    }
+
+    // Compares the present rate with the display's and the `cap` of the
+    // pacer, if it paces the frames itself
+    pub fn issue(&self, pacing: FramePacing, cap: Option<f32>) -> Option<SyncIssue> {
+        let refresh = self.refresh_rate?;
+        let rate = self.present_rate()?;
+        if pacing == FramePacing::Uncapped {
+            return (rate > refresh * FAST_SHARE)
+                .then_some(SyncIssue::AboveRefresh { rate, refresh });
+        }
+
+        let expected = cap.map_or(refresh, |cap| cap.min(refresh));
+        if rate >= expected * SLOW_SHARE {
+            return None;
+        }
+        let busy: Duration = self.presents.iter().map(|(_, busy)| *busy).sum();
+        let busy = busy.as_secs_f32() / self.presents.len() as f32;
+        Some(if busy * rate > BUSY_SHARE {
+            SyncIssue::TooSlow { rate, expected }
+        } else {
+            SyncIssue::HeldBack { rate, expected }
+        })
+    }
+
+    // "144 Hz display, presenting at 60 fps" and the suggested fix, if any
+    pub fn describe(&self, locale: &Locale, pacing: FramePacing, cap: Option<f32>) -> Vec<String> {
+        let refresh = self
+            .refresh_rate
+            .map(|hz| locale.format("hud.refresh_rate", &[("hz", &format!("{:.0}", hz))]))
+            .unwrap_or_else(|| locale.text("hud.refresh_unknown").to_string());
+        let mut lines = vec![match self.present_rate() {
+            Some(rate) => locale.format(
+                "sync.presenting",
+                &[("refresh", &refresh), ("fps", &format!("{:.0}", rate))],
+            ),
+            None => refresh,
+        }];
+        if let Some(issue) = self.issue(pacing, cap) {
+            lines.push(issue.suggestion(locale));
+        }
+        lines
+    }
+}
@@ -0,0 +1,392 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+use winit::keyboard::KeyCode;
+
+use crate::locale::Locale;
+use crate::progress::Progress;
+use crate::renderer::Renderer;
+use crate::sim::{GameSim, Side, SimEvent};
+
+static FONT_SIZE: f32 = 28.;
+static LINE_HEIGHT: f32 = 32.;
+static ROW_HEIGHT: f32 = 96.;
+static ROW_WIDTH: f32 = 640.;
+// Share of the field height at the top and bottom that counts as a corner
+static CORNER_SHARE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drill {
+    // Return serves without missing one
+    ReturnServes,
+    // Hit the wall's top or bottom corner
+    CornerShots,
+}
+
+pub static DRILLS: [Drill; 2] = [Drill::ReturnServes, Drill::CornerShots];
+
+impl Drill {
+    // Key of the drill's record in the progress file and of its texts
+    pub fn id(self) -> &'static str {
+        match self {
+            Drill::ReturnServes => "return_serves",
+            Drill::CornerShots => "corner_shots",
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        locale
+            .text(&format!("drill.{}.name", self.id()))
+            .to_string()
+    }
+
+    pub fn description(self, locale: &Locale) -> String {
+        locale.format(
+            &format!("drill.{}.description", self.id()),
+            &[("target", &self.target())],
+        )
+    }
+
+    fn target(self) -> u32 {
+        match self {
+            Drill::ReturnServes => 10,
+            Drill::CornerShots => 5,
+        }
+    }
+
+    // Whether a miss starts the count over
+    fn in_a_row(self) -> bool {
+        match self {
+            Drill::ReturnServes => true,
+            Drill::CornerShots => false,
+        }
+    }
+
+    // Most misses allowed for gold, silver and bronze, more fail the drill
+    fn medal_misses(self) -> [u32; 3] {
+        match self {
+            Drill::ReturnServes => [0, 2, 5],
+            Drill::CornerShots => [0, 3, 7],
+        }
+    }
+
+    fn max_misses(self) -> u32 {
+        self.medal_misses()[2]
+    }
+
+    fn medal(self, misses: u32) -> Option<Medal> {
+        let [gold, silver, bronze] = self.medal_misses();
+        if misses <= gold {
+            Some(Medal::Gold)
+        } else if misses <= silver {
+            Some(Medal::Silver)
+        } else if misses <= bronze {
+            Some(Medal::Bronze)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl Medal {
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Medal::Bronze => "medal.bronze",
+            Medal::Silver => "medal.silver",
+            Medal::Gold => "medal.gold",
+        }
+    }
+}
+
+// Passes and fails of one drill, stored with the player's progress.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrillRecord {
+    pub passed: u32,
+    pub failed: u32,
+    pub best: Option<Medal>,
+}
+
+impl DrillRecord {
+    pub fn add(&mut self, result: DrillResult) {
+        match result {
+            DrillResult::Passed(medal) => {
+                self.passed += 1;
+                self.best = self.best.max(Some(medal));
+            }
+            DrillResult::Failed => self.failed += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DrillResult {
+    Passed(Medal),
+    Failed,
+}
+
+pub enum DrillStep {
+    Continue,
+    // The attempt is done, put a new ball into play
+    Serve,
+    Finished(DrillResult),
+}
+
+// A drill being played: the player on the left against a wall on the right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrillRun {
+    drill: Drill,
+    successes: u32,
+    misses: u32,
+    // Whether the current serve or shot still counts
+    attempt: bool,
+}
+
+impl DrillRun {
+    pub fn new(drill: Drill) -> Self {
+        Self {
+            drill,
+            successes: 0,
+            misses: 0,
+            attempt: false,
+        }
+    }
+
+    pub fn drill(&self) -> Drill {
+        self.drill
+    }
+
+    pub fn on_event(&mut self, event: &SimEvent, sim: &GameSim) -> DrillStep {
+        let step = match (self.drill, event) {
+            (Drill::ReturnServes, SimEvent::Serve { .. }) => {
+                self.attempt = true;
+                return DrillStep::Continue;
+            }
+            (
+                Drill::ReturnServes,
+                SimEvent::PaddleHit {
+                    side: Side::Left, ..
+                },
+            ) if self.attempt => {
+                self.success();
+                DrillStep::Serve
+            }
+            (
+                Drill::CornerShots,
+                SimEvent::PaddleHit {
+                    side: Side::Left, ..
+                },
+            ) => {
+                self.attempt = true;
+                return DrillStep::Continue;
+            }
+            (
+                Drill::CornerShots,
+                SimEvent::PaddleHit {
+                    side: Side::Right,
+                    pos,
+                    ..
+                },
+            ) if self.attempt => {
+                if in_corner(pos.y, sim.height) {
+                    self.success();
+                } else {
+                    self.miss();
+                }
+                DrillStep::Continue
+            }
+            (
+                _,
+                SimEvent::Goal {
+                    scorer: Side::Right,
+                },
+            ) if self.attempt => {
+                self.miss();
+                DrillStep::Continue
+            }
+            _ => return DrillStep::Continue,
+        };
+
+        if self.successes >= self.drill.target() {
+            let medal = self.drill.medal(self.misses);
+            DrillStep::Finished(medal.map_or(DrillResult::Failed, DrillResult::Passed))
+        } else if self.misses > self.drill.max_misses() {
+            DrillStep::Finished(DrillResult::Failed)
+        } else {
+            step
+        }
+    }
+
+    fn success(&mut self) {
+        self.successes += 1;
+        self.attempt = false;
+    }
+
+    fn miss(&mut self) {
+        self.misses += 1;
+        self.attempt = false;
+        if self.drill.in_a_row() {
+            self.successes = 0;
+        }
+    }
+
+    // Marks the corners to aim for, drawn with the field
+    pub fn draw_world(&self, renderer: &mut Renderer, sim: &GameSim) {
+        if self.drill != Drill::CornerShots {
+            return;
+        }
+        let width = sim.right.width * 3.;
+        let height = sim.height * CORNER_SHARE;
+        for y in [0., sim.height - height] {
+            renderer.draw_rectangle(
+                Vector2::new(sim.width - sim.right.width - width, y),
+                width,
+                height,
+                Srgba::new(1.0, 0.8, 0.2, 0.25),
+                Deg(0.),
+            );
+        }
+    }
+
+    pub fn progress_text(&self, locale: &Locale) -> String {
+        locale.format(
+            "drill.progress",
+            &[
+                ("drill", &self.drill.name(locale)),
+                ("successes", &self.successes),
+                ("target", &self.drill.target()),
+                ("misses", &self.misses),
+                ("max_misses", &self.drill.max_misses()),
+            ],
+        )
+    }
+}
+
+fn in_corner(y: f32, field_height: f32) -> bool {
+    y < field_height * CORNER_SHARE || y > field_height * (1.0 - CORNER_SHARE)
+}
+
+pub enum DrillAction {
+    Start(Drill),
+    Close,
+}
+
+// List of the drills with the player's medals, opened from the main menu.
+pub struct DrillScreen {
+    selected: usize,
+}
+
+impl DrillScreen {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    pub fn handle_key(&mut self, key_code: KeyCode) -> Option<DrillAction> {
+        match key_code {
+            KeyCode::ArrowUp | KeyCode::KeyW => {
+                self.selected = (self.selected + DRILLS.len() - 1) % DRILLS.len();
+            }
+            KeyCode::ArrowDown | KeyCode::KeyS => {
+                self.selected = (self.selected + 1) % DRILLS.len();
+            }
+            KeyCode::Enter | KeyCode::Space => {
+                return Some(DrillAction::Start(DRILLS[self.selected]));
+            }
+            KeyCode::Escape | KeyCode::Backspace => return Some(DrillAction::Close),
+            _ => (),
+        }
+        None
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, progress: &Progress, locale: &Locale) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        let left = width / 2. - ROW_WIDTH / 2.;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.85),
+            Deg(0.),
+        );
+
+        renderer.draw_text(
+            locale.text("drill.title"),
+            Vector2::new(left, LINE_HEIGHT),
+            FONT_SIZE * 1.3,
+            LINE_HEIGHT * 1.3,
+            None,
+        );
+
+        let top = LINE_HEIGHT * 3.;
+        for (index, drill) in DRILLS.iter().enumerate() {
+            let y = top + index as f32 * ROW_HEIGHT;
+            let record = progress.drills.get(drill.id()).copied().unwrap_or_default();
+            let background = if index == self.selected {
+                Srgba::new(0.25, 0.25, 0.35, 1.0)
+            } else {
+                Srgba::new(0.15, 0.15, 0.15, 1.0)
+            };
+            renderer.draw_rectangle(
+                Vector2::new(left, y),
+                ROW_WIDTH,
+                ROW_HEIGHT - 8.,
+                background,
+                Deg(0.),
+            );
+
+            let best = match record.best {
+                Some(medal) => {
+                    locale.format("drill.best", &[("medal", &locale.text(medal.label_key()))])
+                }
+                None => locale.text("drill.no_medal").to_string(),
+            };
+            let lines = [
+                drill.name(locale),
+                drill.description(locale),
+                format!(
+                    "{}   {}",
+                    best,
+                    locale.format(
+                        "drill.record",
+                        &[("passed", &record.passed), ("failed", &record.failed)],
+                    )
+                ),
+            ];
+            for (line, text) in lines.iter().enumerate() {
+                let (font_size, color) = if line == 0 {
+                    (FONT_SIZE, glyphon::Color::rgb(255, 255, 255))
+                } else {
+                    (FONT_SIZE * 0.7, glyphon::Color::rgb(180, 180, 180))
+                };
+                renderer.draw_text(
+                    text,
+                    Vector2::new(left + 12., y + 2. + line as f32 * LINE_HEIGHT * 0.8),
+                    font_size,
+                    LINE_HEIGHT * 0.8,
+                    Some(color),
+                );
+            }
+        }
+
+        let text = locale.text("drill.controls");
+        let text_width = renderer.measure_text(text, FONT_SIZE * 0.7, LINE_HEIGHT * 0.7);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2. - text_width / 2., height - LINE_HEIGHT * 1.5),
+            FONT_SIZE * 0.7,
+            LINE_HEIGHT * 0.7,
+            None,
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use cgmath::{Deg, InnerSpace, Vector2};
+use palette::Srgba;
+
+use crate::renderer::{Camera2D, Rect, Renderer};
+use crate::sim::Ball;
+
+// Distance of the arrow from the edge of the view
+static MARGIN: f32 = 28.0;
+static ARROW_SIZE: f32 = 16.0;
+// The arrow shrinks to this share of its size as the ball gets further away
+static MIN_ARROW_SCALE: f32 = 0.5;
+// At this many pixels off the view the arrow is smallest
+static FAR_DISTANCE: f32 = 1200.0;
+
+// Draws an arrow at the edge of `view` pointing at the ball while the ball is
+// outside of it, for zoomed cameras that don't show the whole field. Must be
+// called in screen space, after the camera and viewport are reset.
+pub fn draw(renderer: &mut Renderer, ball: &Ball, camera: &Camera2D, view: Rect) {
+    let origin = Vector2::new(view.x, view.y);
+    let size = Vector2::new(view.width, view.height);
+    let pos = origin + camera.world_to_screen(ball.pos, size);
+    let radius = ball.radius * camera.zoom;
+
+    let visible = pos.x + radius > view.x
+        && pos.x - radius < view.x + view.width
+        && pos.y + radius > view.y
+        && pos.y - radius < view.y + view.height;
+    if visible || view.width <= MARGIN * 2.0 || view.height <= MARGIN * 2.0 {
+        return;
+    }
+
+    let anchor = Vector2::new(
+        pos.x.clamp(view.x + MARGIN, view.x + view.width - MARGIN),
+        pos.y.clamp(view.y + MARGIN, view.y + view.height - MARGIN),
+    );
+    let offset = pos - anchor;
+    let distance = offset.magnitude();
+    if distance <= f32::EPSILON {
+        return;
+    }
+    let direction = offset / distance;
+    let scale = 1.0 - (1.0 - MIN_ARROW_SCALE) * (distance / FAR_DISTANCE).min(1.0);
+    let length = ARROW_SIZE * scale;
+
+    let side = Vector2::new(-direction.y, direction.x) * length * 0.6;
+    let tip = anchor + direction * length;
+    let back = anchor - direction * length * 0.5;
+    let (mut a, mut b) = (back + side, back - side);
+    // Keep the winding the pipeline expects, or the triangle is culled
+    let cross = (b - a).x * (tip - a).y - (b - a).y * (tip - a).x;
+    if cross > 0.0 {
+        std::mem::swap(&mut a, &mut b);
+    }
+    renderer.draw_triangle(a, b, tip, Srgba::new(1.0, 1.0, 1.0, 0.9), Deg(0.));
+}
@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use cgmath::Vector2;
+use palette::Srgba;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::arena::Arena;
+use crate::playback::Playback;
+use crate::renderer::{CapturedImage, Renderer, letterbox};
+use crate::replay::Replay;
+use crate::sim::GameSim;
+use crate::skins::{BallLook, BallSkin};
+use crate::timestep::Positions;
+
+pub static EXPORT_FPS: u32 = 60;
+static EXPORT_WIDTH: u32 = 1280;
+static EXPORT_HEIGHT: u32 = 720;
+static FONT_SIZE: f32 = 32.;
+static LINE_HEIGHT: f32 = 32.;
+
+// Where the frames go: numbered PNGs in a directory, or one YUV4MPEG2
+// stream on stdout for "-", e.g. `| ffmpeg -i - highlight.mp4`
+enum Output {
+    Images(PathBuf),
+    Y4m(BufWriter<std::io::Stdout>),
+}
+
+// Plays `replay` back without a window at `fps` frames per second of replay
+// time, no matter how fast the machine renders them, for highlight videos
+// without screen capture. Uses the built-in arena and ball.
+pub fn run(replay: &Path, out: &Path, fps: u32) -> Result<()> {
+    let replay = Replay::load(replay)?;
+    let arena = Arena::builtin()?;
+    let fps = fps.max(1);
+    let mut output = if out == Path::new("-") {
+        let mut stdout = BufWriter::new(std::io::stdout());
+        writeln!(
+            stdout,
+            "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444 XCOLORRANGE=FULL",
+            EXPORT_WIDTH, EXPORT_HEIGHT, fps
+        )?;
+        Output::Y4m(stdout)
+    } else {
+        fs::create_dir_all(out).with_context(|| format!("cannot create {}", out.display()))?;
+        Output::Images(out.to_path_buf())
+    };
+
+    let mut renderer = pollster::block_on(Renderer::headless(EXPORT_WIDTH, EXPORT_HEIGHT))?;
+    let mut sim = GameSim::from_replay(&replay.header);
+    let mut ball_look = BallLook::default();
+    let duration = replay.duration();
+    let mut playback = Playback::new(replay);
+    let delta = 1.0 / fps as f32;
+
+    let mut frames = 0;
+    loop {
+        for tick in playback.advance(delta) {
+            sim.step(tick.dt, tick.input);
+        }
+        ball_look.update(delta, &sim.ball);
+        let image = draw_frame(&mut renderer, &arena, &sim, &ball_look)?;
+        match &mut output {
+            Output::Images(dir) => image.save_png(&dir.join(format!("frame-{:06}.png", frames)))?,
+            Output::Y4m(stdout) => write_y4m_frame(stdout, &image)?,
+        }
+        frames += 1;
+        if frames % (fps * 10) == 0 {
+            eprintln!("{:.0}s of {:.0}s", playback.elapsed(), duration);
+        }
+        if playback.is_finished() {
+            break;
+        }
+    }
+
+    if let Output::Y4m(stdout) = &mut output {
+        stdout.flush()?;
+    }
+    // stdout may be the video, so the summary goes to stderr
+    eprintln!(
+        "exported {} frames ({:.1}s at {} fps) to {}",
+        frames,
+        frames as f32 / fps as f32,
+        fps,
+        out.display()
+    );
+    Ok(())
+}
+
+// The field letterboxed into the frame, with the scores above it
+fn draw_frame(
+    renderer: &mut Renderer,
+    arena: &Arena,
+    sim: &GameSim,
+    ball_look: &BallLook,
+) -> Result<CapturedImage> {
+    let colors = &arena.colors;
+    let screen = Vector2::new(renderer.size.width as f32, renderer.size.height as f32);
+
+    renderer.begin_drawing();
+    renderer.clear_color(Srgba::new(0., 0., 0., 1.));
+    let (viewport, camera) = letterbox(Vector2::new(sim.width, sim.height), screen);
+    renderer.set_viewport(Some(viewport));
+    renderer.set_camera(Some(camera));
+    // The game's own drawing, without interpolation as every frame lands
+    // after whole ticks
+    arena.draw_background(renderer, None, colors, sim.width, sim.height);
+    arena.draw_paddles(renderer, None, colors, sim, &Positions::of(sim));
+    ball_look.draw(renderer, BallSkin::default(), &sim.ball);
+    arena.draw_center_line(renderer, sim.width, sim.height);
+    renderer.set_viewport(None);
+    renderer.set_camera(None);
+
+    renderer.begin_hud();
+    let color = {
+        let color: Srgba<u8> = colors.text.into_format();
+        Some(glyphon::Color::rgba(
+            color.red,
+            color.green,
+            color.blue,
+            color.alpha,
+        ))
+    };
+    let area = renderer.safe_area();
+    let left = sim.left.score.to_string();
+    renderer.draw_text(
+        &left,
+        Vector2::new(area.x + area.width / 4., area.y),
+        FONT_SIZE,
+        LINE_HEIGHT,
+        color,
+    );
+    let right = sim.right.score.to_string();
+    let right_width = renderer.measure_text(&right, FONT_SIZE, LINE_HEIGHT);
+    renderer.draw_text(
+        &right,
+        Vector2::new(area.x + area.width * 0.75 - right_width, area.y),
+        FONT_SIZE,
+        LINE_HEIGHT,
+        color,
+    );
+    renderer.render_offscreen()
+}
+
+// Full range BT.601, without chroma subsampling so odd sizes work too
+fn write_y4m_frame(out: &mut impl Write, image: &CapturedImage) -> Result<()> {
+    let pixels = || {
+        image
+            .rgba
+            .chunks_exact(4)
+            .map(|p| (p[0] as f32, p[1] as f32, p[2] as f32))
+    };
+    let to_byte = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    let y: Vec<u8> = pixels()
+        .map(|(r, g, b)| to_byte(0.299 * r + 0.587 * g + 0.114 * b))
+        .collect();
+    let u: Vec<u8> = pixels()
+        .map(|(r, g, b)| to_byte(128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b))
+        .collect();
+    let v: Vec<u8> = pixels()
+        .map(|(r, g, b)| to_byte(128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b))
+        .collect();
+    out.write_all(b"FRAME\n")?;
+    out.write_all(&y)?;
+    out.write_all(&u)?;
+    out.write_all(&v)?;
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::keyboard::KeyCode;
+
+use crate::achievements::ACHIEVEMENTS;
+use crate::locale::Locale;
+use crate::progress::Progress;
+use crate::renderer::Renderer;
+
+static FONT_SIZE: f32 = 28.;
+static LINE_HEIGHT: f32 = 32.;
+static ROW_HEIGHT: f32 = 72.;
+static ROW_WIDTH: f32 = 640.;
+
+// Screen listing all achievements and the lifetime stats.
+pub struct Gallery;
+
+impl Gallery {
+    // Returns false once the screen should be closed.
+    pub fn handle_key(&self, key_code: KeyCode) -> bool {
+        !matches!(
+            key_code,
+            KeyCode::Escape | KeyCode::Enter | KeyCode::Backspace
+        )
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, progress: &Progress, locale: &Locale) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        let left = width / 2. - ROW_WIDTH / 2.;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.85),
+            Deg(0.),
+        );
+
+        let unlocked = ACHIEVEMENTS
+            .iter()
+            .filter(|achievement| progress.achievements.contains_key(achievement.id))
+            .count();
+        renderer.draw_text(
+            &locale.format(
+                "achievements.title",
+                &[("unlocked", &unlocked), ("total", &ACHIEVEMENTS.len())],
+            ),
+            Vector2::new(left, LINE_HEIGHT),
+            FONT_SIZE * 1.3,
+            LINE_HEIGHT * 1.3,
+            None,
+        );
+
+        let top = LINE_HEIGHT * 3.;
+        for (index, achievement) in ACHIEVEMENTS.iter().enumerate() {
+            let y = top + index as f32 * ROW_HEIGHT;
+            let is_unlocked = progress.achievements.contains_key(achievement.id);
+            let (background, color) = if is_unlocked {
+                (
+                    Srgba::new(0.2, 0.35, 0.2, 1.0),
+                    glyphon::Color::rgb(255, 255, 255),
+                )
+            } else {
+                (
+                    Srgba::new(0.15, 0.15, 0.15, 1.0),
+                    glyphon::Color::rgb(120, 120, 120),
+                )
+            };
+
+            renderer.draw_rectangle(
+                Vector2::new(left, y),
+                ROW_WIDTH,
+                ROW_HEIGHT - 8.,
+                background,
+                Deg(0.),
+            );
+            renderer.draw_text(
+                &achievement.name(locale),
+                Vector2::new(left + 12., y + 2.),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(color),
+            );
+            renderer.draw_text(
+                &achievement.description(locale),
+                Vector2::new(left + 12., y + LINE_HEIGHT),
+                FONT_SIZE * 0.7,
+                LINE_HEIGHT * 0.7,
+                Some(color),
+            );
+        }
+
+        let stats = &progress.stats;
+        let text = locale.format(
+            "achievements.stats",
+            &[
+                ("points_won", &stats.points_won),
+                ("points_lost", &stats.points_lost),
+                ("games_won", &stats.games_won),
+                ("games_lost", &stats.games_lost),
+                ("hits", &stats.paddle_hits),
+                ("rally", &stats.longest_rally),
+            ],
+        );
+        renderer.draw_text(
+            &text,
+            Vector2::new(left, top + ACHIEVEMENTS.len() as f32 * ROW_HEIGHT + 8.),
+            FONT_SIZE * 0.7,
+            LINE_HEIGHT * 0.7,
+            None,
+        );
+
+        let text = locale.text("achievements.back");
+        let text_width = renderer.measure_text(text, FONT_SIZE * 0.6, LINE_HEIGHT * 0.6);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2. - text_width / 2., height - LINE_HEIGHT * 1.5),
+            FONT_SIZE * 0.6,
+            LINE_HEIGHT * 0.6,
+            None,
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::keyboard::KeyCode;
+
+use crate::locale::Locale;
+use crate::prompts::{Action, Prompts};
+use crate::renderer::Renderer;
+use crate::score::Score;
+
+static FONT_SIZE: f32 = 40.;
+static LINE_HEIGHT: f32 = 56.;
+
+pub enum GameOverAction {
+    Rematch,
+    MainMenu,
+}
+
+// Result of a finished match.
+pub struct GameOverScreen {
+    winner: String,
+    score: [Score; 2],
+}
+
+impl GameOverScreen {
+    pub fn new(winner: String, score: [Score; 2]) -> Self {
+        Self { winner, score }
+    }
+
+    pub fn handle_key(&self, key_code: KeyCode) -> Option<GameOverAction> {
+        match key_code {
+            KeyCode::Enter | KeyCode::Space => Some(GameOverAction::Rematch),
+            KeyCode::Escape => Some(GameOverAction::MainMenu),
+            _ => None,
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, locale: &Locale, prompts: &Prompts) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.7),
+            Deg(0.),
+        );
+
+        let lines = [
+            (
+                locale.format("game_over.title", &[("name", &self.winner)]),
+                FONT_SIZE * 1.5,
+                LINE_HEIGHT * 1.5,
+            ),
+            (
+                format!(
+                    "{} - {}",
+                    self.score[0].localized(locale),
+                    self.score[1].localized(locale)
+                ),
+                FONT_SIZE,
+                LINE_HEIGHT,
+            ),
+        ];
+        let mut y = height / 3.;
+        for (text, font_size, line_height) in lines {
+            let text_width = renderer.measure_text(&text, font_size, line_height);
+            renderer.draw_text(
+                &text,
+                Vector2::new(width / 2. - text_width / 2., y),
+                font_size,
+                line_height,
+                None,
+            );
+            y += line_height * 1.5;
+        }
+
+        prompts.draw_row(
+            renderer,
+            locale,
+            &[
+                (Action::Confirm, locale.text("game_over.rematch")),
+                (Action::Back, locale.text("game_over.to_main_menu")),
+            ],
+            Vector2::new(width / 2., y + LINE_HEIGHT * 0.3),
+            LINE_HEIGHT * 0.6,
+        );
+    }
+}
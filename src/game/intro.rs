@@ -0,0 +1,157 @@
+use cgmath::Vector2;
+
+use crate::locale::Locale;
+use crate::renderer::Renderer;
+use crate::tween::{Easing, Tween};
+
+static FONT_SIZE: f32 = 40.;
+static LINE_HEIGHT: f32 = 56.;
+static COUNT_FONT_SIZE: f32 = 96.;
+static COUNT_LINE_HEIGHT: f32 = 120.;
+
+// Seconds into the intro: the names slide in, the win score fades in below
+// them, then 3, 2, 1 and the match starts on "Go!" while it all fades out
+static SLIDE_DURATION: f32 = 0.6;
+static RULE_AT: f32 = 0.5;
+static RULE_DURATION: f32 = 0.4;
+static COUNTDOWN_AT: f32 = 1.2;
+static COUNT_DURATION: f32 = 0.7;
+static COUNTS: usize = 3;
+static FADE_DURATION: f32 = 0.6;
+// Each count starts this much larger and shrinks to its size
+static COUNT_PULSE: f32 = 1.6;
+
+// Played before a match against the AI, between two local players and of
+// the daily challenge. Any key skips to the start.
+pub struct MatchIntro {
+    names: [String; 2],
+    win_score: u8,
+    elapsed: f32,
+    slide: Tween,
+    rule: Tween,
+    // Of the count on screen, which one is `step`
+    pulse: Tween,
+    step: Option<usize>,
+    fade: Tween,
+}
+
+impl MatchIntro {
+    pub fn new(names: [String; 2], win_score: u8) -> Self {
+        Self {
+            names,
+            win_score,
+            elapsed: 0.0,
+            slide: Tween::new(0.0, 1.0, SLIDE_DURATION, Easing::EaseOutCubic),
+            rule: Tween::new(0.0, 1.0, RULE_DURATION, Easing::EaseOutCubic),
+            pulse: Tween::new(COUNT_PULSE, 1.0, COUNT_DURATION, Easing::EaseOutCubic),
+            step: None,
+            fade: Tween::new(1.0, 0.0, FADE_DURATION, Easing::EaseInCubic),
+        }
+    }
+
+    fn go_at() -> f32 {
+        COUNTDOWN_AT + COUNT_DURATION * COUNTS as f32
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        let previous = self.elapsed;
+        self.elapsed += delta;
+        self.slide.update(delta);
+        // Only the part of the frame after each starts
+        if self.elapsed > RULE_AT {
+            self.rule.update(self.elapsed - previous.max(RULE_AT));
+        }
+        if self.elapsed > Self::go_at() {
+            self.fade.update(self.elapsed - previous.max(Self::go_at()));
+        }
+
+        let step = (self.elapsed >= COUNTDOWN_AT)
+            .then(|| (((self.elapsed - COUNTDOWN_AT) / COUNT_DURATION) as usize).min(COUNTS));
+        if step != self.step {
+            self.step = step;
+            self.pulse = Tween::new(COUNT_PULSE, 1.0, COUNT_DURATION, Easing::EaseOutCubic);
+        }
+        self.pulse.update(delta);
+    }
+
+    // Straight to "Go!"
+    pub fn skip(&mut self) {
+        if self.holds_match() {
+            self.slide.update(SLIDE_DURATION);
+            self.rule.update(RULE_DURATION);
+            self.elapsed = Self::go_at();
+            self.step = Some(COUNTS);
+            self.pulse = Tween::new(COUNT_PULSE, 1.0, COUNT_DURATION, Easing::EaseOutCubic);
+        }
+    }
+
+    // The match waits until the countdown is over
+    pub fn holds_match(&self) -> bool {
+        self.elapsed < Self::go_at()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.fade.is_finished()
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, locale: &Locale) {
+        let area = renderer.safe_area();
+        let width = renderer.size.width as f32;
+        let fade = self.fade.value();
+        let color = |alpha: f32| glyphon::Color::rgba(255, 255, 255, (alpha * 255.0) as u8);
+
+        // From beyond the edges to the middle of each half
+        let y = area.y + area.height * 0.3;
+        let slide = self.slide.value();
+        for (index, name) in self.names.iter().enumerate() {
+            let text_width = renderer.measure_text(name, FONT_SIZE, LINE_HEIGHT);
+            let (from, to) = if index == 0 {
+                (-text_width, width * 0.25 - text_width / 2.)
+            } else {
+                (width, width * 0.75 - text_width / 2.)
+            };
+            renderer.draw_text(
+                name,
+                Vector2::new(from + (to - from) * slide, y),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(color(fade)),
+            );
+        }
+
+        let text = locale.format(
+            "intro.first_to",
+            &[("points", &locale.number(self.win_score))],
+        );
+        let text_width = renderer.measure_text(&text, FONT_SIZE * 0.75, LINE_HEIGHT * 0.75);
+        renderer.draw_text(
+            &text,
+            Vector2::new(width / 2. - text_width / 2., y + LINE_HEIGHT * 1.2),
+            FONT_SIZE * 0.75,
+            LINE_HEIGHT * 0.75,
+            Some(color(self.rule.value() * fade)),
+        );
+
+        let Some(step) = self.step else {
+            return;
+        };
+        let text = if step < COUNTS {
+            locale.number(COUNTS - step)
+        } else {
+            locale.text("intro.go").to_string()
+        };
+        let scale = self.pulse.value();
+        let (font_size, line_height) = (COUNT_FONT_SIZE * scale, COUNT_LINE_HEIGHT * scale);
+        let text_width = renderer.measure_text(&text, font_size, line_height);
+        renderer.draw_text(
+            &text,
+            Vector2::new(
+                width / 2. - text_width / 2.,
+                area.y + area.height / 2. - line_height / 2.,
+            ),
+            font_size,
+            line_height,
+            Some(color(fade)),
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::keyboard::KeyCode;
+
+use super::WIN_SCORES;
+use crate::ai::{AiProfile, DIFFICULTIES, Difficulty};
+use crate::config::{KeyBindings, key_label};
+use crate::locale::Locale;
+use crate::progress::Progress;
+use crate::prompts::{Action, Prompts};
+use crate::renderer::Renderer;
+use crate::sim::Side;
+
+static FONT_SIZE: f32 = 40.;
+static LINE_HEIGHT: f32 = 56.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Play,
+    PlayAi,
+    Difficulty,
+    AiSide,
+    SwapSides,
+    Drills,
+    WinScore,
+    RulesetCode,
+    Quit,
+}
+
+static ITEMS: [Item; 9] = [
+    Item::Play,
+    Item::PlayAi,
+    Item::Difficulty,
+    Item::AiSide,
+    Item::SwapSides,
+    Item::Drills,
+    Item::WinScore,
+    Item::RulesetCode,
+    Item::Quit,
+];
+
+pub enum MainMenuAction {
+    // Start a match, against the AI profile at this index if set
+    Play { ai: Option<usize> },
+    Drills,
+    Difficulty(Difficulty),
+    AiSide(Side),
+    // The keys of the left and the right paddle trade places
+    SwapSides,
+    WinScore(u8),
+    // Show the code of the current rules, or enter someone else's
+    RulesetCode,
+    Quit,
+}
+
+// Title screen shown on start and after leaving a match.
+pub struct MainMenu {
+    selected: usize,
+    // Index into the AI profiles
+    ai: usize,
+    difficulty: Difficulty,
+    ai_side: Side,
+    win_score: u8,
+}
+
+impl MainMenu {
+    pub fn new(progress: &Progress) -> Self {
+        Self {
+            selected: 0,
+            ai: 0,
+            difficulty: progress.ai_difficulty,
+            ai_side: progress.ai_side(),
+            win_score: progress.win_score(),
+        }
+    }
+
+    // After the settings it shows changed elsewhere, e.g. by a ruleset code
+    pub fn refresh(&mut self, progress: &Progress) {
+        self.difficulty = progress.ai_difficulty;
+        self.ai_side = progress.ai_side();
+        self.win_score = progress.win_score();
+    }
+
+    // Left/Right pick the AI opponent, its difficulty and side, which keys play
+    // which paddle and the win score
+    pub fn handle_key(&mut self, key_code: KeyCode, ai_count: usize) -> Option<MainMenuAction> {
+        let step = match key_code {
+            KeyCode::ArrowUp | KeyCode::KeyW => {
+                self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len();
+                return None;
+            }
+            KeyCode::ArrowDown | KeyCode::KeyS => {
+                self.selected = (self.selected + 1) % ITEMS.len();
+                return None;
+            }
+            KeyCode::ArrowLeft | KeyCode::KeyA => -1,
+            KeyCode::ArrowRight | KeyCode::KeyD => 1,
+            KeyCode::Enter | KeyCode::Space => match ITEMS[self.selected] {
+                Item::Play => return Some(MainMenuAction::Play { ai: None }),
+                Item::PlayAi => {
+                    return (ai_count > 0).then_some(MainMenuAction::Play { ai: Some(self.ai) });
+                }
+                Item::Drills => return Some(MainMenuAction::Drills),
+                Item::SwapSides => return Some(MainMenuAction::SwapSides),
+                Item::RulesetCode => return Some(MainMenuAction::RulesetCode),
+                Item::Difficulty | Item::AiSide | Item::WinScore => 1,
+                Item::Quit => return Some(MainMenuAction::Quit),
+            },
+            _ => return None,
+        };
+
+        match ITEMS[self.selected] {
+            Item::PlayAi if ai_count > 0 => {
+                self.ai = (self.ai as isize + step).rem_euclid(ai_count as isize) as usize;
+                None
+            }
+            Item::Difficulty => {
+                let index = DIFFICULTIES
+                    .iter()
+                    .position(|difficulty| *difficulty == self.difficulty)
+                    .unwrap_or(0) as isize;
+                let index = (index + step).rem_euclid(DIFFICULTIES.len() as isize) as usize;
+                self.difficulty = DIFFICULTIES[index];
+                Some(MainMenuAction::Difficulty(self.difficulty))
+            }
+            Item::AiSide => {
+                self.ai_side = self.ai_side.opponent();
+                Some(MainMenuAction::AiSide(self.ai_side))
+            }
+            Item::SwapSides => Some(MainMenuAction::SwapSides),
+            Item::WinScore => {
+                let index = WIN_SCORES
+                    .iter()
+                    .position(|score| *score == self.win_score)
+                    .unwrap_or(0) as isize;
+                let index = (index + step).rem_euclid(WIN_SCORES.len() as isize) as usize;
+                self.win_score = WIN_SCORES[index];
+                Some(MainMenuAction::WinScore(self.win_score))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        profiles: &[AiProfile],
+        keys: &KeyBindings,
+        locale: &Locale,
+        prompts: &Prompts,
+    ) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.8),
+            Deg(0.),
+        );
+
+        let text = locale.text("main_menu.title");
+        let text_width = renderer.measure_text(text, FONT_SIZE * 2., LINE_HEIGHT * 2.);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2. - text_width / 2., height / 5.),
+            FONT_SIZE * 2.,
+            LINE_HEIGHT * 2.,
+            None,
+        );
+
+        // Below the title, and above the prompts on short windows
+        let top = (height / 2. - LINE_HEIGHT)
+            .min(height - LINE_HEIGHT * (ITEMS.len() as f32 + 1.2))
+            .max(height / 5. + LINE_HEIGHT * 2.);
+        for (index, item) in ITEMS.iter().enumerate() {
+            let text = match item {
+                Item::Play => locale.text("main_menu.play").to_string(),
+                Item::PlayAi => match profiles.get(self.ai) {
+                    Some(profile) => locale.format("main_menu.play_ai", &[("ai", &profile.name)]),
+                    None => locale.text("main_menu.no_ai").to_string(),
+                },
+                Item::Difficulty => locale.format(
+                    "main_menu.difficulty",
+                    &[("difficulty", &locale.text(self.difficulty.label_key()))],
+                ),
+                Item::AiSide => locale
+                    .text(match self.ai_side {
+                        Side::Left => "main_menu.ai_left",
+                        Side::Right => "main_menu.ai_right",
+                    })
+                    .to_string(),
+                Item::SwapSides => {
+                    let pair = |up, down| {
+                        format!(
+                            "{}/{}",
+                            locale.text(&key_label(up)),
+                            locale.text(&key_label(down))
+                        )
+                    };
+                    locale.format(
+                        "main_menu.sides",
+                        &[
+                            ("left", &pair(keys.left_up, keys.left_down)),
+                            ("right", &pair(keys.right_up, keys.right_down)),
+                        ],
+                    )
+                }
+                Item::Drills => locale.text("main_menu.drills").to_string(),
+                Item::WinScore => {
+                    locale.format("main_menu.win_score", &[("points", &self.win_score)])
+                }
+                Item::RulesetCode => locale.text("main_menu.ruleset_code").to_string(),
+                Item::Quit => locale.text("main_menu.quit").to_string(),
+            };
+            let text_width = renderer.measure_text(&text, FONT_SIZE, LINE_HEIGHT);
+            let color = if index == self.selected {
+                glyphon::Color::rgb(255, 255, 255)
+            } else {
+                glyphon::Color::rgb(128, 128, 128)
+            };
+            renderer.draw_text(
+                &text,
+                Vector2::new(
+                    width / 2. - text_width / 2.,
+                    top + index as f32 * LINE_HEIGHT,
+                ),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(color),
+            );
+        }
+
+        prompts.draw_row(
+            renderer,
+            locale,
+            &[
+                (Action::Navigate, locale.text("main_menu.select")),
+                (Action::Adjust, locale.text("main_menu.change")),
+                (Action::Confirm, locale.text("main_menu.start")),
+            ],
+            Vector2::new(width / 2., height - LINE_HEIGHT * 1.2),
+            LINE_HEIGHT * 0.6,
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::sim::Side;
+
+mod game_over;
+mod intro;
+mod main_menu;
+
+pub use game_over::{GameOverAction, GameOverScreen};
+pub use intro::MatchIntro;
+pub use main_menu::{MainMenu, MainMenuAction};
+
+// How long the scorer is shown after a goal before play goes on
+static ROUND_OVER_DURATION: f32 = 1.2;
+// Points a match can be played to, picked on the main menu
+pub static WIN_SCORES: [u8; 6] = [3, 5, 7, 11, 15, 21];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameState {
+    // Title screen over the frozen field
+    MainMenu,
+    Playing,
+    // The pause menu is open
+    Paused,
+    // Short break after a goal, play goes on with the next serve
+    RoundOver { scorer: Side, remaining: f32 },
+    // Someone reached the win score, waiting for a rematch or the main menu
+    GameOver { winner: Side },
+}
+
+// Where the player is between starting the game and finishing a match. The
+// match is only simulated while `Playing`.
+pub struct GameFlow {
+    state: GameState,
+}
+
+impl GameFlow {
+    pub fn new() -> Self {
+        Self {
+            state: GameState::MainMenu,
+        }
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == GameState::Playing
+    }
+
+    pub fn play(&mut self) {
+        self.state = GameState::Playing;
+    }
+
+    pub fn main_menu(&mut self) {
+        self.state = GameState::MainMenu;
+    }
+
+    // Follows the pause menu. Only a match in progress can be paused, and it
+    // resumes with the next serve.
+    pub fn set_paused(&mut self, paused: bool) {
+        match (self.state, paused) {
+            (GameState::Playing | GameState::RoundOver { .. }, true) => {
+                self.state = GameState::Paused
+            }
+            (GameState::Paused, false) => self.state = GameState::Playing,
+            _ => (),
+        }
+    }
+
+    // `winner` is set once the goal decided the match.
+    pub fn on_goal(&mut self, scorer: Side, winner: Option<Side>) {
+        if self.state != GameState::Playing {
+            return;
+        }
+        self.state = match winner {
+            Some(winner) => GameState::GameOver { winner },
+            None => GameState::RoundOver {
+                scorer,
+                remaining: ROUND_OVER_DURATION,
+            },
+        };
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if let GameState::RoundOver { remaining, .. } = &mut self.state {
+            *remaining -= delta;
+            if *remaining <= 0.0 {
+                self.state = GameState::Playing;
+            }
+        }
+    }
+}
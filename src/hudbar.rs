@@ -0,0 +1,95 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+
+use crate::renderer::{Rect, Renderer};
+
+// How fast the shown value catches up with the real one, per second
+static CATCH_UP_RATE: f32 = 2.5;
+static SEGMENT_GAP: f32 = 2.0;
+
+// A horizontal bar for cooldowns, timers and durations, starting out full.
+// Rises are animated, drops show the lost part faded out for a moment
+// before it drains away.
+pub struct HudBar {
+    background: Srgba,
+    fill: Srgba,
+    // Split into this many blocks, e.g. for charges
+    segments: Option<u32>,
+    // 0..1
+    value: f32,
+    shown: f32,
+}
+
+impl HudBar {
+    pub fn new(fill: Srgba, segments: Option<u32>) -> Self {
+        Self {
+            background: Srgba::new(0.0, 0.0, 0.0, 0.5),
+            fill,
+            segments,
+            value: 1.0,
+            shown: 1.0,
+        }
+    }
+
+    pub fn set(&mut self, value: f32) {
+        self.value = value.clamp(0.0, 1.0);
+    }
+
+    // Jumps to `value` without animating, e.g. when a new timer starts.
+    pub fn reset(&mut self, value: f32) {
+        self.set(value);
+        self.shown = self.value;
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        let max_change = CATCH_UP_RATE * delta;
+        self.shown += (self.value - self.shown).clamp(-max_change, max_change);
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, rect: Rect) {
+        let fill = self.value.min(self.shown);
+        let lag = Srgba::new(
+            self.fill.red,
+            self.fill.green,
+            self.fill.blue,
+            self.fill.alpha * 0.4,
+        );
+
+        let segments = self.segments.unwrap_or(1).max(1);
+        let gap = if segments > 1 { SEGMENT_GAP } else { 0.0 };
+        let width = (rect.width - gap * (segments - 1) as f32) / segments as f32;
+        for index in 0..segments {
+            let x = rect.x + index as f32 * (width + gap);
+            // Part of the whole bar covered by this segment
+            let start = index as f32 / segments as f32;
+            let end = (index + 1) as f32 / segments as f32;
+            let covered = |value: f32| ((value - start) / (end - start)).clamp(0.0, 1.0);
+
+            renderer.draw_rectangle(
+                Vector2::new(x, rect.y),
+                width,
+                rect.height,
+                self.background,
+                Deg(0.),
+            );
+            if self.shown > self.value {
+                renderer.draw_rectangle(
+                    Vector2::new(x, rect.y),
+                    width * covered(self.shown),
+                    rect.height,
+                    lag,
+                    Deg(0.),
+                );
+            }
+            if fill > start {
+                renderer.draw_rectangle(
+                    Vector2::new(x, rect.y),
+                    width * covered(fill),
+                    rect.height,
+                    self.fill,
+                    Deg(0.),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use log::debug;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::Duration;
+
+use super::{DeviceEvent, GamepadAxis, GamepadButton, GamepadStyle};
+use crate::time::Instant;
+
+// Joystick devices of the Linux joystick API, checked for new controllers
+static DEVICE_COUNT: usize = 8;
+static SCAN_INTERVAL: Duration = Duration::from_secs(1);
+// Left stick and d-pad, vertical
+static STICK_AXIS: u8 = 1;
+static DPAD_AXIS: u8 = 7;
+
+// Event types of the joystick API
+static EVENT_BUTTON: u8 = 0x01;
+static EVENT_AXIS: u8 = 0x02;
+// Set on the events describing the state right after opening the device
+static EVENT_INIT: u8 = 0x80;
+
+// Button numbers of the Linux drivers, the face buttons come first on all
+// of them but the start button moves
+fn button(style: GamepadStyle, number: u8) -> Option<GamepadButton> {
+    match (style, number) {
+        (_, 0) => Some(GamepadButton::South),
+        (_, 1) => Some(GamepadButton::East),
+        (GamepadStyle::Xbox, 7) => Some(GamepadButton::Start),
+        (GamepadStyle::PlayStation | GamepadStyle::Nintendo, 9) => Some(GamepadButton::Start),
+        _ => None,
+    }
+}
+
+// The `/dev/input/js*` devices, read on background threads, one per device.
+// A device is known by the number in its name.
+pub struct Joysticks {
+    open: Vec<usize>,
+    sender: Sender<DeviceEvent>,
+    receiver: Receiver<DeviceEvent>,
+    last_scan: Option<Instant>,
+}
+
+impl Joysticks {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            open: Vec::new(),
+            sender,
+            receiver,
+            last_scan: None,
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<DeviceEvent> {
+        let mut events = Vec::new();
+        if self
+            .last_scan
+            .is_none_or(|last_scan| last_scan.elapsed() >= SCAN_INTERVAL)
+        {
+            self.last_scan = Some(Instant::now());
+            self.scan(&mut events);
+        }
+        while let Ok(event) = self.receiver.try_recv() {
+            if let DeviceEvent::Disconnected { id } = event {
+                self.open.retain(|open| *open != id);
+            }
+            events.push(event);
+        }
+        events
+    }
+
+    fn scan(&mut self, events: &mut Vec<DeviceEvent>) {
+        for id in 0..DEVICE_COUNT {
+            let path = format!("/dev/input/js{}", id);
+            if self.open.contains(&id) || !Path::new(&path).exists() {
+                continue;
+            }
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(err) => {
+                    debug!("Cannot open controller {}: {}", path, err);
+                    continue;
+                }
+            };
+
+            let name = fs::read_to_string(format!("/sys/class/input/js{}/device/name", id))
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            let style = GamepadStyle::from_name(&name);
+            let sender = self.sender.clone();
+            std::thread::spawn(move || read_device(file, id, style, sender));
+            self.open.push(id);
+            events.push(DeviceEvent::Connected {
+                id,
+                name: format!("{} ({})", path, name),
+            });
+        }
+    }
+}
+
+// Reads joystick events until the device goes away
+fn read_device(mut file: File, id: usize, style: GamepadStyle, sender: Sender<DeviceEvent>) {
+    // u32 timestamp, i16 value, u8 type, u8 number
+    let mut event = [0u8; 8];
+    while file.read_exact(&mut event).is_ok() {
+        let value = i16::from_ne_bytes([event[4], event[5]]);
+        let initial = event[6] & EVENT_INIT != 0;
+        let number = event[7];
+        let event = match event[6] & !EVENT_INIT {
+            kind if kind == EVENT_AXIS => {
+                let axis = match number {
+                    number if number == STICK_AXIS => GamepadAxis::Stick,
+                    number if number == DPAD_AXIS => GamepadAxis::Dpad,
+                    _ => continue,
+                };
+                DeviceEvent::Axis {
+                    id,
+                    axis,
+                    value: (value as f32 / i16::MAX as f32).max(-1.0),
+                    initial,
+                }
+            }
+            kind if kind == EVENT_BUTTON => DeviceEvent::Button {
+                id,
+                button: button(style, number),
+                pressed: value != 0,
+                initial,
+            },
+            _ => continue,
+        };
+        if sender.send(event).is_err() {
+            return;
+        }
+    }
+    let _ = sender.send(DeviceEvent::Disconnected { id });
+}
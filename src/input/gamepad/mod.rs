@@ -0,0 +1,297 @@
+use log::info;
+
+use super::DEAD_ZONE;
+
+// One backend per platform reads the controllers, the rest of the game only
+// sees the buttons and axes below
+#[cfg(target_os = "linux")]
+mod joystick;
+#[cfg(target_arch = "wasm32")]
+mod web;
+#[cfg(target_os = "windows")]
+mod xinput;
+
+#[cfg(target_os = "linux")]
+use joystick::Joysticks as Backend;
+#[cfg(target_arch = "wasm32")]
+use web::WebGamepads as Backend;
+#[cfg(target_os = "windows")]
+use xinput::XInput as Backend;
+
+pub enum GamepadEvent {
+    // A controller was connected and got the lowest free player slot
+    Connected { slot: usize },
+    Disconnected { slot: usize },
+    // On any of the controllers
+    Pressed { button: GamepadButton },
+}
+
+// The buttons the game uses, by where they sit on the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadButton {
+    // Bottom face button: A on Xbox, cross on PlayStation
+    South,
+    East,
+    Start,
+}
+
+// The vertical axes the game uses, from -1 up to 1 down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadAxis {
+    // Left stick
+    Stick,
+    // Only ever -1, 0 or 1
+    Dpad,
+}
+
+// Which family a controller belongs to, for its button prompts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadStyle {
+    Xbox,
+    PlayStation,
+    Nintendo,
+}
+
+impl GamepadStyle {
+    // Guessed from the name the driver reports, anything unknown is taken
+    // for an Xbox layout like most PC controllers
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        if ["sony", "playstation", "dualshock", "dualsense"]
+            .iter()
+            .any(|brand| name.contains(brand))
+        {
+            GamepadStyle::PlayStation
+        } else if ["nintendo", "pro controller", "joy-con"]
+            .iter()
+            .any(|brand| name.contains(brand))
+        {
+            GamepadStyle::Nintendo
+        } else {
+            GamepadStyle::Xbox
+        }
+    }
+}
+
+// What a backend reports about the controller it calls `id`
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "windows", target_arch = "wasm32")),
+    allow(dead_code)
+)]
+enum DeviceEvent {
+    Connected {
+        id: usize,
+        name: String,
+    },
+    // `initial` for the state reported right after connecting
+    Axis {
+        id: usize,
+        axis: GamepadAxis,
+        value: f32,
+        initial: bool,
+    },
+    // `button` is None for the buttons the game doesn't use
+    Button {
+        id: usize,
+        button: Option<GamepadButton>,
+        pressed: bool,
+        initial: bool,
+    },
+    Disconnected {
+        id: usize,
+    },
+}
+
+// Where controllers aren't supported yet
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_arch = "wasm32")))]
+struct Backend;
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_arch = "wasm32")))]
+impl Backend {
+    fn new() -> Self {
+        Self
+    }
+
+    fn poll(&mut self) -> Vec<DeviceEvent> {
+        Vec::new()
+    }
+}
+
+struct Device {
+    id: usize,
+    // Player the controller belongs to, 0 for the first one connected
+    slot: usize,
+    style: GamepadStyle,
+    stick: f32,
+    dpad: f32,
+}
+
+// The connected controllers and the players they belong to. The event loop
+// takes what the platform's backend read with `poll`.
+pub struct Gamepads {
+    backend: Backend,
+    devices: Vec<Device>,
+    // The last controller a player moved or pressed a button on
+    used: Option<GamepadStyle>,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        Self {
+            backend: Backend::new(),
+            devices: Vec::new(),
+            used: None,
+        }
+    }
+
+    // Applies what the controllers sent since the last call and picks up
+    // newly plugged in ones.
+    pub fn poll(&mut self) -> Vec<GamepadEvent> {
+        let events = self.backend.poll();
+        self.apply(events)
+    }
+
+    fn apply(&mut self, device_events: Vec<DeviceEvent>) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        for event in device_events {
+            match event {
+                DeviceEvent::Connected { id, name } => {
+                    if self.devices.iter().any(|device| device.id == id) {
+                        continue;
+                    }
+                    let slot = (0..)
+                        .find(|slot| self.devices.iter().all(|device| device.slot != *slot))
+                        .unwrap_or_default();
+                    info!("Controller {} connected as player {}", name, slot + 1);
+                    self.devices.push(Device {
+                        id,
+                        slot,
+                        style: GamepadStyle::from_name(&name),
+                        stick: 0.0,
+                        dpad: 0.0,
+                    });
+                    events.push(GamepadEvent::Connected { slot });
+                }
+                DeviceEvent::Axis {
+                    id,
+                    axis,
+                    value,
+                    initial,
+                } => {
+                    let Some(device) = self.devices.iter_mut().find(|device| device.id == id)
+                    else {
+                        continue;
+                    };
+                    match axis {
+                        GamepadAxis::Stick => device.stick = value,
+                        GamepadAxis::Dpad => device.dpad = value,
+                    }
+                    if !initial && value.abs() > DEAD_ZONE {
+                        self.used = Some(device.style);
+                    }
+                }
+                DeviceEvent::Button {
+                    id,
+                    button,
+                    pressed,
+                    initial,
+                } => {
+                    let Some(device) = self.devices.iter().find(|device| device.id == id) else {
+                        continue;
+                    };
+                    if !pressed || initial {
+                        continue;
+                    }
+                    self.used = Some(device.style);
+                    if let Some(button) = button {
+                        events.push(GamepadEvent::Pressed { button });
+                    }
+                }
+                DeviceEvent::Disconnected { id } => {
+                    let Some(index) = self.devices.iter().position(|device| device.id == id) else {
+                        continue;
+                    };
+                    let device = self.devices.remove(index);
+                    info!("Controller of player {} disconnected", device.slot + 1);
+                    events.push(GamepadEvent::Disconnected { slot: device.slot });
+                }
+            }
+        }
+        events
+    }
+
+    // Position of an axis from -1 to 1, 0 if the player has no controller
+    pub fn axis(&self, slot: usize, axis: GamepadAxis) -> f32 {
+        self.devices
+            .iter()
+            .find(|device| device.slot == slot)
+            .map_or(0.0, |device| match axis {
+                GamepadAxis::Stick => device.stick,
+                GamepadAxis::Dpad => device.dpad,
+            })
+    }
+
+    // Which kind of controller was used since the last call, if any
+    pub fn take_used(&mut self) -> Option<GamepadStyle> {
+        self.used.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connected(id: usize, name: &str) -> DeviceEvent {
+        DeviceEvent::Connected {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn players_get_the_lowest_free_slot() {
+        let mut gamepads = Gamepads::new();
+        gamepads.apply(vec![connected(7, "Xbox"), connected(3, "DualSense")]);
+        gamepads.apply(vec![DeviceEvent::Axis {
+            id: 3,
+            axis: GamepadAxis::Stick,
+            value: 0.5,
+            initial: false,
+        }]);
+        assert_eq!(gamepads.axis(1, GamepadAxis::Stick), 0.5);
+        assert_eq!(gamepads.take_used(), Some(GamepadStyle::PlayStation));
+
+        let events = gamepads.apply(vec![DeviceEvent::Disconnected { id: 7 }, connected(9, "")]);
+        assert!(matches!(
+            events[..],
+            [
+                GamepadEvent::Disconnected { slot: 0 },
+                GamepadEvent::Connected { slot: 0 }
+            ]
+        ));
+    }
+
+    #[test]
+    fn only_fresh_presses_count() {
+        let mut gamepads = Gamepads::new();
+        gamepads.apply(vec![connected(0, "Pro Controller")]);
+        let button = |pressed, initial| DeviceEvent::Button {
+            id: 0,
+            button: Some(GamepadButton::Start),
+            pressed,
+            initial,
+        };
+        let events = gamepads.apply(vec![button(true, true), button(false, false)]);
+        assert!(events.is_empty());
+        assert_eq!(gamepads.take_used(), None);
+
+        let events = gamepads.apply(vec![button(true, false)]);
+        assert!(matches!(
+            events[..],
+            [GamepadEvent::Pressed {
+                button: GamepadButton::Start
+            }]
+        ));
+        assert_eq!(gamepads.take_used(), Some(GamepadStyle::Nintendo));
+    }
+}
@@ -0,0 +1,129 @@
+use wasm_bindgen::JsCast;
+
+use super::{DeviceEvent, GamepadAxis, GamepadButton};
+
+// Button and axis numbers of the browser's standard mapping
+static BUTTONS: [(u32, GamepadButton); 3] = [
+    (0, GamepadButton::South),
+    (1, GamepadButton::East),
+    (9, GamepadButton::Start),
+];
+static DPAD_UP: u32 = 12;
+static DPAD_DOWN: u32 = 13;
+static STICK_AXIS: u32 = 1;
+static BUTTON_COUNT: usize = 17;
+
+#[derive(Clone, PartialEq)]
+struct Pad {
+    stick: f32,
+    dpad: f32,
+    buttons: [bool; BUTTON_COUNT],
+}
+
+// The browser's Gamepad API, polled every frame. A controller is known by
+// its index on `navigator.getGamepads()`.
+pub struct WebGamepads {
+    connected: Vec<Option<Pad>>,
+}
+
+impl WebGamepads {
+    pub fn new() -> Self {
+        Self {
+            connected: Vec::new(),
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<DeviceEvent> {
+        let mut events = Vec::new();
+        let gamepads = web_sys::window()
+            .and_then(|window| window.navigator().get_gamepads().ok())
+            .map(|gamepads| gamepads.to_vec())
+            .unwrap_or_default();
+        let count = gamepads.len().max(self.connected.len());
+        self.connected.resize(count, None);
+
+        for id in 0..count {
+            let gamepad = gamepads
+                .get(id)
+                .and_then(|gamepad| gamepad.clone().dyn_into::<web_sys::Gamepad>().ok())
+                .filter(|gamepad| gamepad.connected());
+            let state = gamepad.as_ref().map(read);
+            match (self.connected[id].as_ref(), state.as_ref()) {
+                (None, Some(state)) => {
+                    let name = gamepad.map(|gamepad| gamepad.id()).unwrap_or_default();
+                    events.push(DeviceEvent::Connected { id, name });
+                    changes(id, None, state, &mut events);
+                }
+                (Some(previous), Some(state)) => changes(id, Some(previous), state, &mut events),
+                (Some(_), None) => events.push(DeviceEvent::Disconnected { id }),
+                (None, None) => (),
+            }
+            self.connected[id] = state;
+        }
+        events
+    }
+}
+
+fn read(gamepad: &web_sys::Gamepad) -> Pad {
+    let pressed = |number: u32| {
+        gamepad
+            .buttons()
+            .get(number)
+            .dyn_into::<web_sys::GamepadButton>()
+            .is_ok_and(|button| button.pressed())
+    };
+    let mut buttons = [false; BUTTON_COUNT];
+    for (number, button) in buttons.iter_mut().enumerate() {
+        *button = pressed(number as u32);
+    }
+    let dpad = if pressed(DPAD_UP) {
+        -1.0
+    } else if pressed(DPAD_DOWN) {
+        1.0
+    } else {
+        0.0
+    };
+    Pad {
+        stick: gamepad.axes().get(STICK_AXIS).as_f64().unwrap_or(0.0) as f32,
+        dpad,
+        buttons,
+    }
+}
+
+// What changed since `previous`, everything for a controller that was just
+// connected
+fn changes(id: usize, previous: Option<&Pad>, state: &Pad, events: &mut Vec<DeviceEvent>) {
+    let initial = previous.is_none();
+    if previous.is_none_or(|previous| previous.stick != state.stick) {
+        events.push(DeviceEvent::Axis {
+            id,
+            axis: GamepadAxis::Stick,
+            value: state.stick,
+            initial,
+        });
+    }
+    if previous.is_none_or(|previous| previous.dpad != state.dpad) {
+        events.push(DeviceEvent::Axis {
+            id,
+            axis: GamepadAxis::Dpad,
+            value: state.dpad,
+            initial,
+        });
+    }
+    for (number, &pressed) in state.buttons.iter().enumerate() {
+        let was_pressed = previous.is_some_and(|previous| previous.buttons[number]);
+        if pressed == was_pressed || [DPAD_UP, DPAD_DOWN].contains(&(number as u32)) {
+            continue;
+        }
+        let button = BUTTONS
+            .iter()
+            .find(|(index, _)| *index as usize == number)
+            .map(|(_, button)| *button);
+        events.push(DeviceEvent::Button {
+            id,
+            button,
+            pressed,
+            initial,
+        });
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use super::{DeviceEvent, GamepadAxis, GamepadButton};
+use crate::time::Instant;
+
+// XInput knows up to four controllers, by their user index
+static USER_COUNT: usize = 4;
+// Asking for a controller that isn't there is slow, so free indices are
+// only checked now and then
+static SCAN_INTERVAL: Duration = Duration::from_secs(1);
+static ERROR_SUCCESS: u32 = 0;
+
+static BUTTON_DPAD_UP: u16 = 0x0001;
+static BUTTON_DPAD_DOWN: u16 = 0x0002;
+static BUTTON_START: u16 = 0x0010;
+static BUTTON_A: u16 = 0x1000;
+static BUTTON_B: u16 = 0x2000;
+static BUTTONS: [(u16, GamepadButton); 3] = [
+    (BUTTON_A, GamepadButton::South),
+    (BUTTON_B, GamepadButton::East),
+    (BUTTON_START, GamepadButton::Start),
+];
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct XInputGamepad {
+    buttons: u16,
+    left_trigger: u8,
+    right_trigger: u8,
+    thumb_lx: i16,
+    thumb_ly: i16,
+    thumb_rx: i16,
+    thumb_ry: i16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct XInputState {
+    packet_number: u32,
+    gamepad: XInputGamepad,
+}
+
+#[link(name = "xinput")]
+unsafe extern "system" {
+    fn XInputGetState(user_index: u32, state: *mut XInputState) -> u32;
+}
+
+fn get_state(user: usize) -> Option<XInputGamepad> {
+    let mut state = XInputState::default();
+    // SAFETY: `state` is a valid XINPUT_STATE for the call to fill in
+    let result = unsafe { XInputGetState(user as u32, &mut state) };
+    (result == ERROR_SUCCESS).then_some(state.gamepad)
+}
+
+// Xbox and compatible controllers on Windows, polled every frame. A
+// controller is known by its user index.
+pub struct XInput {
+    connected: [Option<XInputGamepad>; USER_COUNT],
+    last_scan: Option<Instant>,
+}
+
+impl XInput {
+    pub fn new() -> Self {
+        Self {
+            connected: [None; USER_COUNT],
+            last_scan: None,
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<DeviceEvent> {
+        let mut events = Vec::new();
+        let scan = self
+            .last_scan
+            .is_none_or(|last_scan| last_scan.elapsed() >= SCAN_INTERVAL);
+        if scan {
+            self.last_scan = Some(Instant::now());
+        }
+
+        for id in 0..USER_COUNT {
+            let previous = self.connected[id];
+            if previous.is_none() && !scan {
+                continue;
+            }
+            let state = get_state(id);
+            match (previous, state) {
+                (None, Some(state)) => {
+                    events.push(DeviceEvent::Connected {
+                        id,
+                        name: format!("XInput {}", id + 1),
+                    });
+                    changes(id, None, state, &mut events);
+                }
+                (Some(previous), Some(state)) => changes(id, Some(previous), state, &mut events),
+                (Some(_), None) => events.push(DeviceEvent::Disconnected { id }),
+                (None, None) => (),
+            }
+            self.connected[id] = state;
+        }
+        events
+    }
+}
+
+// What changed since `previous`, everything for a controller that was just
+// connected
+fn changes(
+    id: usize,
+    previous: Option<XInputGamepad>,
+    state: XInputGamepad,
+    events: &mut Vec<DeviceEvent>,
+) {
+    let initial = previous.is_none();
+    let previous = previous.unwrap_or_default();
+
+    // Up is positive on XInput sticks
+    let stick = |gamepad: XInputGamepad| -(gamepad.thumb_ly as f32 / i16::MAX as f32).max(-1.0);
+    if initial || state.thumb_ly != previous.thumb_ly {
+        events.push(DeviceEvent::Axis {
+            id,
+            axis: GamepadAxis::Stick,
+            value: stick(state),
+            initial,
+        });
+    }
+    let dpad = |gamepad: XInputGamepad| {
+        if gamepad.buttons & BUTTON_DPAD_UP != 0 {
+            -1.0
+        } else if gamepad.buttons & BUTTON_DPAD_DOWN != 0 {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    if initial || dpad(state) != dpad(previous) {
+        events.push(DeviceEvent::Axis {
+            id,
+            axis: GamepadAxis::Dpad,
+            value: dpad(state),
+            initial,
+        });
+    }
+
+    let pressed = state.buttons & !previous.buttons;
+    let released = previous.buttons & !state.buttons;
+    for (mask, button) in BUTTONS {
+        if pressed & mask != 0 || released & mask != 0 {
+            events.push(DeviceEvent::Button {
+                id,
+                button: Some(button),
+                pressed: pressed & mask != 0,
+                initial,
+            });
+        }
+    }
+    // The others only tell which controller was used last
+    let others =
+        !(BUTTONS.iter().fold(0, |all, (mask, _)| all | mask) | BUTTON_DPAD_UP | BUTTON_DPAD_DOWN);
+    if pressed & others != 0 {
+        events.push(DeviceEvent::Button {
+            id,
+            button: None,
+            pressed: true,
+            initial,
+        });
+    }
+}
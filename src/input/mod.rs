@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+use winit::keyboard::KeyCode;
+
+mod gamepad;
+
+use gamepad::{GamepadAxis, Gamepads};
+pub use gamepad::{GamepadButton, GamepadEvent, GamepadStyle};
+
+// Sticks rest a little off center, anything closer counts as not moving
+static DEAD_ZONE: f32 = 0.2;
+
+// What the player last played with, for the button prompts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad(GamepadStyle),
+}
+
+// What the player is holding down, on the keyboard and on controllers.
+pub struct Input {
+    pub keys: HashSet<KeyCode>,
+    gamepads: Gamepads,
+    device: InputDevice,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self {
+            keys: HashSet::new(),
+            gamepads: Gamepads::new(),
+            device: InputDevice::default(),
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<GamepadEvent> {
+        let events = self.gamepads.poll();
+        if let Some(style) = self.gamepads.take_used() {
+            self.device = InputDevice::Gamepad(style);
+        }
+        events
+    }
+
+    pub fn device(&self) -> InputDevice {
+        self.device
+    }
+
+    // A key was pressed, the prompts switch back to the keyboard
+    pub fn use_keyboard(&mut self) {
+        self.device = InputDevice::Keyboard;
+    }
+
+    // How far the controller in `slot` is pushed, from -1 up to 1 down. The
+    // d-pad always goes at full speed.
+    pub fn stick(&self, slot: usize) -> f32 {
+        let dpad = self.gamepads.axis(slot, GamepadAxis::Dpad);
+        if dpad.abs() > DEAD_ZONE {
+            return dpad.signum();
+        }
+        let stick = self.gamepads.axis(slot, GamepadAxis::Stick);
+        if stick.abs() <= DEAD_ZONE {
+            return 0.0;
+        }
+        stick.signum() * (stick.abs() - DEAD_ZONE) / (1.0 - DEAD_ZONE)
+    }
+}
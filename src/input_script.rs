@@ -0,0 +1,223 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use winit::keyboard::KeyCode;
+
+use crate::config::BINDABLE_KEYS;
+use crate::timestep::FixedTimestep;
+
+// Keys a script may use besides the bindable ones
+static SCRIPT_KEYS: [KeyCode; 15] = [
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptAction {
+    // Pressed and released right away, e.g. to pick a menu item
+    Press(KeyCode),
+    // Held down until released, e.g. to move a paddle
+    Hold(KeyCode),
+    Release(KeyCode),
+    Quit,
+}
+
+// One step as written in the script: wait `after` ticks since the step
+// before, then do one of the others
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ScriptStep {
+    after: u64,
+    press: Option<String>,
+    hold: Option<String>,
+    release: Option<String>,
+    quit: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptFile {
+    steps: Vec<ScriptStep>,
+}
+
+// Scripted key input for exercising menus and matches without a person
+// at the keyboard, see `--input-script`. Ticks are the match's steps, and
+// while it doesn't step but waits for the player (menus, the tutorial) they
+// are counted at the tick rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputScript {
+    // Tick each action is due at
+    actions: Vec<(u64, ScriptAction)>,
+}
+
+impl InputScript {
+    // TOML, or JSON for files ending in .json
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("cannot read input script {}", path.display()))?;
+        let file: ScriptFile = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&data)?
+        } else {
+            toml::from_str(&data)?
+        };
+        Self::from_steps(file.steps).with_context(|| format!("invalid {}", path.display()))
+    }
+
+    fn from_steps(steps: Vec<ScriptStep>) -> Result<Self> {
+        let mut tick = 0;
+        let mut actions = Vec::new();
+        for (index, step) in steps.into_iter().enumerate() {
+            tick += step.after;
+            let action = match (step.press, step.hold, step.release, step.quit) {
+                (Some(key), None, None, false) => ScriptAction::Press(key_code(&key)?),
+                (None, Some(key), None, false) => ScriptAction::Hold(key_code(&key)?),
+                (None, None, Some(key), false) => ScriptAction::Release(key_code(&key)?),
+                (None, None, None, true) => ScriptAction::Quit,
+                _ => bail!(
+                    "step {} needs exactly one of press, hold, release or quit",
+                    index + 1
+                ),
+            };
+            actions.push((tick, action));
+        }
+        Ok(Self { actions })
+    }
+}
+
+fn key_code(name: &str) -> Result<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .chain(SCRIPT_KEYS.iter())
+        .copied()
+        .find(|key| format!("{:?}", key) == name)
+        .with_context(|| format!("unknown key {:?}", name))
+}
+
+// Hands out the actions of a script as their ticks come.
+pub struct ScriptPlayer {
+    script: InputScript,
+    next: usize,
+    tick: u64,
+    // Turns frame times into ticks while the match doesn't step
+    waiting: FixedTimestep,
+}
+
+impl ScriptPlayer {
+    pub fn new(script: InputScript) -> Self {
+        Self {
+            script,
+            next: 0,
+            tick: 0,
+            waiting: FixedTimestep::new(1),
+        }
+    }
+
+    // The actions due at the current tick, to do before it's stepped
+    pub fn step(&mut self) -> Vec<ScriptAction> {
+        let due = self.due_at(self.tick);
+        self.tick += 1;
+        due
+    }
+
+    // How many ticks to step for a frame of `delta` seconds in which the
+    // match didn't step
+    pub fn wait(&mut self, delta: f32, rate: u32) -> u32 {
+        self.waiting.set_rate(rate);
+        self.waiting.advance(delta)
+    }
+
+    fn due_at(&mut self, tick: u64) -> Vec<ScriptAction> {
+        let due: Vec<ScriptAction> = self.script.actions[self.next..]
+            .iter()
+            .take_while(|(at, _)| *at <= tick)
+            .map(|(_, action)| *action)
+            .collect();
+        self.next += due.len();
+        due
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.script.actions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SCRIPT: &str = r#"
+        [[steps]]
+        after = 60
+        press = "Enter"
+
+        [[steps]]
+        hold = "KeyW"
+
+        [[steps]]
+        after = 120
+        release = "KeyW"
+
+        [[steps]]
+        after = 10
+        quit = true
+    "#;
+
+    fn parse(data: &str) -> Result<InputScript> {
+        let file: ScriptFile = toml::from_str(data)?;
+        InputScript::from_steps(file.steps)
+    }
+
+    #[test]
+    fn steps_wait_after_the_one_before() {
+        let script = parse(SCRIPT).expect("script parses");
+        assert_eq!(
+            script.actions,
+            vec![
+                (60, ScriptAction::Press(KeyCode::Enter)),
+                (60, ScriptAction::Hold(KeyCode::KeyW)),
+                (180, ScriptAction::Release(KeyCode::KeyW)),
+                (190, ScriptAction::Quit),
+            ]
+        );
+    }
+
+    #[test]
+    fn due_hands_out_each_action_once() {
+        let mut player = ScriptPlayer::new(parse(SCRIPT).expect("script parses"));
+        assert!(player.due_at(59).is_empty());
+        assert_eq!(player.due_at(60).len(), 2);
+        assert!(player.due_at(100).is_empty());
+        assert_eq!(player.due_at(1000).len(), 2);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn steps_count_ticks() {
+        let mut player = ScriptPlayer::new(parse(SCRIPT).expect("script parses"));
+        let due: Vec<usize> = (0..200).map(|_| player.step().len()).collect();
+        assert_eq!(due.iter().sum::<usize>(), 4);
+        assert_eq!(due[60], 2);
+        assert_eq!(due[180], 1);
+        assert_eq!(due[190], 1);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_mixed_steps() {
+        assert!(parse("[[steps]]\npress = \"Hyper\"").is_err());
+        assert!(parse("[[steps]]\npress = \"Enter\"\nquit = true").is_err());
+    }
+}
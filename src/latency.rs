@@ -0,0 +1,87 @@
+use cgmath::Vector2;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::renderer::Renderer;
+use crate::time::Instant;
+
+static FONT_SIZE: f32 = 20.;
+static LINE_HEIGHT: f32 = 24.;
+// Statistics cover the last this many measurements
+static MAX_SAMPLES: usize = 120;
+
+// Diagnostic overlay measuring the time from a key event arriving to the first
+// frame presented after it. This is the part of the latency the game controls;
+// compositor and display scanout come on top.
+pub struct LatencyOverlay {
+    // Oldest key event not yet visible on screen
+    pending_input: Option<Instant>,
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyOverlay {
+    pub fn new() -> Self {
+        Self {
+            pending_input: None,
+            samples: VecDeque::with_capacity(MAX_SAMPLES),
+        }
+    }
+
+    pub fn input_received(&mut self, at: Instant) {
+        self.pending_input.get_or_insert(at);
+    }
+
+    pub fn frame_presented(&mut self, at: Instant) {
+        let Some(input) = self.pending_input.take() else {
+            return;
+        };
+
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(at.duration_since(input));
+    }
+
+    // `sync` describes the display, see `DisplaySync::describe`
+    pub fn draw(&self, renderer: &mut Renderer, pacing: &str, sync: &[String]) {
+        let mut lines = vec![
+            format!("Present mode: {:?}", renderer.present_mode()),
+            format!("Surface format: {}", renderer.surface_format().describe()),
+            format!("Frame pacing: {}", pacing),
+        ];
+        lines.extend(sync.iter().cloned());
+
+        match (self.samples.iter().min(), self.samples.iter().max()) {
+            (Some(min), Some(max)) => {
+                let sum: Duration = self.samples.iter().sum();
+                let average = sum / self.samples.len() as u32;
+                lines.push(format!(
+                    "Input to present: {:.1} ms",
+                    millis(self.samples.back().copied().unwrap_or_default())
+                ));
+                lines.push(format!(
+                    "avg {:.1} / min {:.1} / max {:.1} ms ({} samples)",
+                    millis(average),
+                    millis(*min),
+                    millis(*max),
+                    self.samples.len()
+                ));
+            }
+            _ => lines.push("Input to present: press a key".to_string()),
+        }
+
+        let text = lines.join("\n");
+        let area = renderer.safe_area();
+        renderer.draw_text(
+            &text,
+            Vector2::new(area.x, area.y + LINE_HEIGHT * 2.5),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            Some(glyphon::Color::rgb(255, 255, 0)),
+        );
+    }
+}
+
+fn millis(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.0
+}
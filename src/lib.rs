@@ -0,0 +1,4 @@
+// The renderer on its own, for other games and tools drawing 2D shapes,
+// textures and text with wgpu. The game itself is the binary in main.rs.
+pub mod renderer;
+pub mod time;
@@ -0,0 +1,245 @@
+use cgmath::Vector2;
+use glyphon::FontSystem;
+use log::{error, info};
+use palette::Srgba;
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use crate::arena::{Arena, ArenaPixels, load_arenas};
+use crate::audio::Sounds;
+use crate::hudbar::HudBar;
+use crate::renderer::{Rect, Renderer};
+use crate::time::Instant;
+
+static BACKGROUND: Srgba = Srgba::new(0.1, 0.1, 0.1, 1.0);
+static BAR_WIDTH: f32 = 480.;
+static BAR_HEIGHT: f32 = 10.;
+static FONT_SIZE: f32 = 20.;
+static LINE_HEIGHT: f32 = 28.;
+
+// Everything read from disk and decoded before the main menu shows.
+pub struct Assets {
+    pub arenas: Vec<Arena>,
+    // Of the arena at the same index
+    pub arena_pixels: Vec<ArenaPixels>,
+    pub sounds: Sounds,
+}
+
+// One part of the assets, in the order they're loaded. The fonts come first
+// so the loading screen has text as early as possible.
+enum Step {
+    Fonts,
+    Arenas,
+    ArenaImages(Box<Arena>),
+    Sounds,
+}
+
+enum Loaded {
+    Fonts(Box<FontSystem>),
+    Arenas(Vec<Arena>),
+    ArenaImages(ArenaPixels),
+    Sounds(Sounds),
+}
+
+// The steps left, there are more once the arenas are known
+struct Steps {
+    queue: VecDeque<Step>,
+    total: usize,
+}
+
+impl Steps {
+    fn new() -> Self {
+        let queue = VecDeque::from([Step::Fonts, Step::Arenas, Step::Sounds]);
+        Self {
+            total: queue.len(),
+            queue,
+        }
+    }
+
+    // None once every step is done
+    fn run_next(&mut self) -> Option<Loaded> {
+        Some(match self.queue.pop_front()? {
+            Step::Fonts => Loaded::Fonts(Box::new(FontSystem::new())),
+            Step::Arenas => {
+                let arenas = load_arenas();
+                for arena in arenas.iter().rev() {
+                    self.queue
+                        .push_front(Step::ArenaImages(Box::new(arena.clone())));
+                }
+                self.total += arenas.len();
+                Loaded::Arenas(arenas)
+            }
+            Step::ArenaImages(arena) => Loaded::ArenaImages(arena.decode_images()),
+            Step::Sounds => Loaded::Sounds(Sounds::decode()),
+        })
+    }
+}
+
+// Loads the fonts, arena images and sounds on a thread of their own while a
+// progress bar shows, so the window doesn't freeze at startup with large
+// arena packs. The browser has no threads, there a step runs every frame.
+pub struct Loading {
+    // Steps and the total so far from the loading thread
+    receiver: Option<Receiver<(Loaded, usize)>>,
+    // Run here when there is no loading thread
+    steps: Option<Steps>,
+    done: usize,
+    total: usize,
+    finished: bool,
+    arenas: Vec<Arena>,
+    arena_pixels: Vec<ArenaPixels>,
+    sounds: Option<Sounds>,
+    bar: HudBar,
+    last_frame: Instant,
+}
+
+impl Loading {
+    pub fn start() -> Self {
+        let mut bar = HudBar::new(Srgba::new(1.0, 1.0, 1.0, 0.9), None);
+        bar.reset(0.0);
+        let mut loading = Self {
+            receiver: None,
+            steps: None,
+            done: 0,
+            total: Steps::new().total,
+            finished: false,
+            arenas: Vec::new(),
+            arena_pixels: Vec::new(),
+            sounds: None,
+            bar,
+            last_frame: Instant::now(),
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        match spawn() {
+            Ok(receiver) => loading.receiver = Some(receiver),
+            Err(err) => error!("Failed to start the loading thread: {:#}", err),
+        }
+        if loading.receiver.is_none() {
+            loading.steps = Some(Steps::new());
+        }
+        loading
+    }
+
+    // Takes the steps done since the last call, handing the fonts to the
+    // renderer right away. Returns the assets once everything is loaded.
+    pub fn poll(&mut self, renderer: &mut Renderer) -> Option<Assets> {
+        let mut loaded = Vec::new();
+        if let Some(receiver) = self.receiver.as_ref() {
+            loop {
+                match receiver.try_recv() {
+                    Ok((step, total)) => {
+                        loaded.push(step);
+                        self.total = total;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.finished = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(steps) = self.steps.as_mut() {
+            match steps.run_next() {
+                Some(step) => {
+                    loaded.push(step);
+                    self.total = steps.total;
+                }
+                None => self.finished = true,
+            }
+        }
+
+        for step in loaded {
+            self.done += 1;
+            match step {
+                Loaded::Fonts(fonts) => renderer.set_font_system(*fonts),
+                Loaded::Arenas(arenas) => self.arenas = arenas,
+                Loaded::ArenaImages(pixels) => self.arena_pixels.push(pixels),
+                Loaded::Sounds(sounds) => self.sounds = Some(sounds),
+            }
+        }
+        if !self.finished {
+            return None;
+        }
+
+        // Whatever a failed loading thread left out is loaded here
+        if self.done < self.total {
+            error!(
+                "Loading stopped after {} of {} steps, loading the rest right away",
+                self.done, self.total
+            );
+        }
+        if !renderer.has_fonts() {
+            renderer.set_font_system(FontSystem::new());
+        }
+        if self.arenas.is_empty() {
+            self.arenas = load_arenas();
+        }
+        self.arena_pixels
+            .resize_with(self.arenas.len(), ArenaPixels::default);
+        info!("Loaded {} arenas", self.arenas.len());
+        Some(Assets {
+            arenas: std::mem::take(&mut self.arenas),
+            arena_pixels: std::mem::take(&mut self.arena_pixels),
+            sounds: self.sounds.take().unwrap_or_else(Sounds::decode),
+        })
+    }
+
+    // A bar in the middle of the window, with the share loaded under it once
+    // there are fonts
+    pub fn draw(&mut self, renderer: &mut Renderer) {
+        let now = Instant::now();
+        let delta = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        let progress = self.done as f32 / self.total.max(1) as f32;
+        self.bar.set(progress);
+        self.bar.update(delta);
+
+        renderer.begin_drawing();
+        renderer.clear_color(BACKGROUND);
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        let bar_width = BAR_WIDTH.min(width * 0.6);
+        self.bar.draw(
+            renderer,
+            Rect::new(
+                width / 2. - bar_width / 2.,
+                height / 2. - BAR_HEIGHT / 2.,
+                bar_width,
+                BAR_HEIGHT,
+            ),
+        );
+        if renderer.has_fonts() {
+            let text = format!("{:.0} %", progress * 100.);
+            let text_width = renderer.measure_text(&text, FONT_SIZE, LINE_HEIGHT);
+            renderer.draw_text(
+                &text,
+                Vector2::new(width / 2. - text_width / 2., height / 2. + BAR_HEIGHT),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(glyphon::Color::rgb(200, 200, 200)),
+            );
+        }
+        if let Err(err) = renderer.end_drawing() {
+            error!("Failed to draw the loading screen: {:#}", err);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn() -> std::io::Result<Receiver<(Loaded, usize)>> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("loading".to_string())
+        .spawn(move || {
+            let mut steps = Steps::new();
+            while let Some(step) = steps.run_next() {
+                // The window was closed while loading
+                if sender.send((step, steps.total)).is_err() {
+                    return;
+                }
+            }
+        })?;
+    Ok(receiver)
+}
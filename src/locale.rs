@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use log::{error, info};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use crate::assets::assets_dir;
+
+static LOCALE_DIR: &str = "locales";
+// Used for every string a translation is missing
+static FALLBACK_LANGUAGE: &str = "en";
+
+// Always available, even without an assets directory
+static BUILTIN_ENGLISH: &str = include_str!("../assets/locales/en.toml");
+
+// One `assets/locales/<code>.toml` file. Nested tables are flattened into
+// dotted keys, so `[menu] quit = "Quit"` is looked up as "menu.quit".
+pub struct Language {
+    pub code: String,
+    pub name: String,
+    // Replacements for 0 to 9 from the optional top level `digits` key, for
+    // languages that don't write numbers with Western Arabic digits
+    digits: Option<Vec<char>>,
+    strings: HashMap<String, String>,
+}
+
+impl Language {
+    pub fn parse(code: &str, data: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(data)?;
+        let mut strings = HashMap::new();
+        flatten("", &table, &mut strings);
+
+        let name = strings
+            .remove("name")
+            .with_context(|| format!("language {:?} has no name", code))?;
+        let digits = match strings.remove("digits") {
+            Some(digits) if digits.chars().count() == 10 => Some(digits.chars().collect()),
+            Some(digits) => anyhow::bail!("language {:?} has {:?} as its digits", code, digits),
+            None => None,
+        };
+        Ok(Self {
+            code: code.to_string(),
+            name,
+            digits,
+            strings,
+        })
+    }
+}
+
+fn flatten(prefix: &str, table: &toml::Table, strings: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::String(text) => {
+                strings.insert(key, text.clone());
+            }
+            toml::Value::Table(table) => flatten(&key, table, strings),
+            _ => error!("Ignoring non-string translation {:?}", key),
+        }
+    }
+}
+
+// All installed languages and the one currently shown.
+pub struct Locale {
+    languages: Vec<Language>,
+    current: usize,
+    // The built-in English strings, even if the English file on disk is incomplete
+    fallback: Language,
+}
+
+impl Locale {
+    // Picks `code` if it is installed, English otherwise.
+    pub fn load(code: Option<&str>) -> Self {
+        let fallback = Language::parse(FALLBACK_LANGUAGE, BUILTIN_ENGLISH).unwrap_or_else(|err| {
+            error!("Invalid built-in language: {:#}", err);
+            Language {
+                code: FALLBACK_LANGUAGE.to_string(),
+                name: "English".to_string(),
+                digits: None,
+                strings: HashMap::new(),
+            }
+        });
+
+        let dir = assets_dir().join(LOCALE_DIR);
+        let mut languages = match read_locale_dir(&dir) {
+            Ok(languages) => languages,
+            Err(err) => {
+                info!("No languages loaded from {}: {:#}", dir.display(), err);
+                Vec::new()
+            }
+        };
+        if !languages
+            .iter()
+            .any(|language| language.code == FALLBACK_LANGUAGE)
+        {
+            languages.insert(
+                0,
+                Language {
+                    code: fallback.code.clone(),
+                    name: fallback.name.clone(),
+                    digits: None,
+                    strings: HashMap::new(),
+                },
+            );
+        }
+
+        let selected = code.unwrap_or(FALLBACK_LANGUAGE);
+        let current = languages
+            .iter()
+            .position(|language| language.code == selected)
+            .or_else(|| {
+                languages
+                    .iter()
+                    .position(|language| language.code == FALLBACK_LANGUAGE)
+            })
+            .unwrap_or(0);
+
+        Self {
+            languages,
+            current,
+            fallback,
+        }
+    }
+
+    pub fn language(&self) -> &Language {
+        &self.languages[self.current]
+    }
+
+    // Switches to the next installed language.
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.languages.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.current = (self.current + self.languages.len() - 1) % self.languages.len();
+    }
+
+    // The translation of `key`, or the English text, or the key itself if
+    // even that is missing so the gap is visible on screen.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.language()
+            .strings
+            .get(key)
+            .or_else(|| self.fallback.strings.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    // `value` written with the digits of the current language.
+    pub fn number(&self, value: impl Display) -> String {
+        let text = value.to_string();
+        match self.language().digits.as_ref() {
+            Some(digits) => text
+                .chars()
+                .map(|c| c.to_digit(10).map_or(c, |digit| digits[digit as usize]))
+                .collect(),
+            None => text,
+        }
+    }
+
+    // `text` with each "{name}" replaced by its argument.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.text(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+fn read_locale_dir(dir: &Path) -> Result<Vec<Language>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("cannot read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    paths.sort();
+
+    let mut languages = Vec::new();
+    for path in paths {
+        let Some(code) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Language::parse(code, &data))
+        {
+            Ok(language) => {
+                info!(
+                    "Loaded language {:?} from {}",
+                    language.name,
+                    path.display()
+                );
+                languages.push(language);
+            }
+            Err(err) => error!("Invalid language {}: {:#}", path.display(), err),
+        }
+    }
+
+    Ok(languages)
+}
@@ -1,16 +1,209 @@
+// The browser build has no command line, which leaves the tools behind it unused
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
+
+use anyhow::Result;
 use app::App;
-use winit::error::EventLoopError;
+use cli::Command;
+use input_script::{InputScript, ScriptPlayer};
+use renderer::{FrameDump, Renderer};
+use replay::Replay;
+use session_log::{SessionLog, SessionPlayer, SessionRecorder};
+use std::time::Duration;
 use winit::event_loop::{ControlFlow, EventLoop};
+// The renderer is a library of its own, see lib.rs
+use wgpu_pong::{renderer, time};
 
+mod achievements;
+mod ai;
 mod app;
-mod renderer;
+mod arena;
+mod assets;
+mod audio;
+mod calibration;
+mod cli;
+mod clock;
+mod colorpicker;
+mod compact;
+mod config;
+mod daily;
+mod director;
+mod display_sync;
+mod drills;
+mod edge_indicator;
+mod export;
+mod gallery;
+mod game;
+mod hudbar;
+mod input;
+mod input_script;
+mod latency;
+mod loading;
+mod locale;
+mod menu;
+mod mutators;
+mod name_entry;
+mod net;
+mod options;
+mod pacing;
+mod paddle_colors;
+mod paddle_trail;
+mod particles;
+mod photo;
+mod playback;
+mod progress;
+mod prompts;
+mod replay;
+mod resolution;
+mod rewind;
+mod ruleset;
+mod score;
+mod screenshot;
+mod session_log;
+mod sim;
+mod simulate;
+mod skins;
+mod splitscreen;
+mod stream_overlay;
+mod strict_math;
+mod telemetry;
+mod textfield;
+mod timeline;
+mod timestep;
+mod toast;
+mod transition;
+mod tutorial;
+mod tween;
+#[cfg(target_arch = "wasm32")]
+mod web;
+mod webhook;
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    web::run();
+}
 
-fn main() -> Result<(), EventLoopError> {
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> Result<()> {
     env_logger::init();
 
-    let event_loop = EventLoop::new()?;
-    event_loop.set_control_flow(ControlFlow::Poll);
+    match cli::parse()? {
+        Command::Play => {
+            let event_loop = EventLoop::new()?;
+            event_loop.set_control_flow(ControlFlow::Poll);
+
+            let mut app = App::default();
+            event_loop.run_app(&mut app)?;
+        }
+        Command::Watch(path) => {
+            let replay = Replay::load(&path)?;
+            let event_loop = EventLoop::new()?;
+            event_loop.set_control_flow(ControlFlow::Poll);
+
+            let mut app = App::watch(replay);
+            event_loop.run_app(&mut app)?;
+        }
+        Command::RecordSession(path) => {
+            let event_loop = EventLoop::new()?;
+            event_loop.set_control_flow(ControlFlow::Poll);
+
+            let mut app = App::default().record_session(SessionRecorder::new(path));
+            event_loop.run_app(&mut app)?;
+        }
+        Command::InputScript(path) => {
+            let script = InputScript::load(&path)?;
+            let event_loop = EventLoop::new()?;
+            event_loop.set_control_flow(ControlFlow::Poll);
+
+            let mut app = App::default().input_script(ScriptPlayer::new(script));
+            event_loop.run_app(&mut app)?;
+        }
+        Command::ReplaySession(path) => {
+            let session = SessionLog::load(&path)?;
+            let event_loop = EventLoop::new()?;
+            event_loop.set_control_flow(ControlFlow::Poll);
+
+            let mut app = App::default().replay_session(SessionPlayer::new(session));
+            event_loop.run_app(&mut app)?;
+        }
+        Command::Online {
+            role,
+            rendezvous,
+            session,
+        } => {
+            let link = net::connect(&rendezvous, &session)?;
+            let event_loop = EventLoop::new()?;
+            event_loop.set_control_flow(ControlFlow::Poll);
+
+            let mut app = App::online(link, role);
+            event_loop.run_app(&mut app)?;
+        }
+        Command::ReplayInfo(path) => {
+            let replay = Replay::load(&path)?;
+            let header = &replay.header;
+            println!(
+                "format:   v{} (game {})",
+                header.format_version, header.game_version
+            );
+            println!("players:  {} vs {}", header.players[0], header.players[1]);
+            println!("recorded: {} (unix time)", header.recorded_at);
+            println!("seed:     {}", header.seed);
+            println!("settings: {:?}", header.settings);
+            println!(
+                "length:   {} ticks, {:.1}s",
+                replay.body.ticks.len(),
+                replay.duration()
+            );
+            for (index, point) in replay.body.points.iter().enumerate() {
+                println!(
+                    "point {:>3}: at {:>6.1}s, {:?} scored after {} hits in {:.1}s",
+                    index + 1,
+                    point.start_time,
+                    point.scorer(),
+                    point.hits(),
+                    point.duration
+                );
+            }
+        }
+        Command::Simulate {
+            matches,
+            left,
+            right,
+        } => simulate::run(
+            matches,
+            left.as_deref(),
+            right.as_deref().or(left.as_deref()),
+        )?,
+        Command::RenderFrame { dump, out } => {
+            let dump = FrameDump::load(&dump)?;
+            let mut renderer = pollster::block_on(Renderer::headless(dump.width, dump.height))?;
+            renderer.replay_frame(&dump);
+            renderer.render_offscreen()?.save_png(&out)?;
+            println!(
+                "rendered {} draw calls to {}",
+                dump.commands.len(),
+                out.display()
+            );
+        }
+        Command::ExportReplay { replay, out, fps } => export::run(&replay, &out, fps)?,
+        Command::RendezvousServer(bind) => net::run_server(&bind)?,
+        Command::NatTest {
+            rendezvous,
+            session,
+        } => {
+            let mut link = net::connect(&rendezvous, &session)?;
+            match link.route() {
+                net::Route::Direct(addr) => println!("direct connection to {}", addr),
+                net::Route::Relayed(addr) => println!("relayed through {}", addr),
+            }
+
+            // Both sides send a greeting and wait for the other's
+            link.send(b"hello")?;
+            match link.recv(Duration::from_secs(5))? {
+                Some(payload) => println!("received {:?}", String::from_utf8_lossy(payload)),
+                None => println!("connected, but no data arrived from the peer"),
+            }
+        }
+    }
 
-    let mut app = App::default();
-    event_loop.run_app(&mut app)
+    Ok(())
 }
@@ -0,0 +1,172 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::keyboard::KeyCode;
+
+use crate::locale::Locale;
+use crate::progress::Progress;
+use crate::renderer::Renderer;
+
+static FONT_SIZE: f32 = 40.;
+static LINE_HEIGHT: f32 = 56.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Resume,
+    DailyChallenge,
+    Tutorial,
+    Achievements,
+    PlayerName,
+    PaddleColors,
+    BallSkin,
+    Arena,
+    Language,
+    MasterVolume,
+    SfxVolume,
+    ScreenShake,
+    ReducedMotion,
+    PaddleTrails,
+    Options,
+    Calibration,
+    MainMenu,
+    Quit,
+}
+
+static ITEMS: [MenuItem; 18] = [
+    MenuItem::Resume,
+    MenuItem::DailyChallenge,
+    MenuItem::Tutorial,
+    MenuItem::Achievements,
+    MenuItem::PlayerName,
+    MenuItem::PaddleColors,
+    MenuItem::BallSkin,
+    MenuItem::Arena,
+    MenuItem::Language,
+    MenuItem::MasterVolume,
+    MenuItem::SfxVolume,
+    MenuItem::ScreenShake,
+    MenuItem::ReducedMotion,
+    MenuItem::PaddleTrails,
+    MenuItem::Options,
+    MenuItem::Calibration,
+    MenuItem::MainMenu,
+    MenuItem::Quit,
+];
+
+impl MenuItem {
+    fn label(self, progress: &Progress, arena: &str, locale: &Locale) -> String {
+        match self {
+            MenuItem::Resume => locale.text("menu.resume").to_string(),
+            MenuItem::DailyChallenge => locale.text("menu.daily_challenge").to_string(),
+            MenuItem::Tutorial => locale.text("menu.tutorial").to_string(),
+            MenuItem::Achievements => locale.text("menu.achievements").to_string(),
+            MenuItem::PlayerName => {
+                locale.format("menu.player_name", &[("name", &progress.player_name())])
+            }
+            MenuItem::PaddleColors => locale.text("menu.paddle_colors").to_string(),
+            MenuItem::BallSkin => locale.format(
+                "menu.ball_skin",
+                &[("skin", &locale.text(progress.ball_skin.label_key()))],
+            ),
+            MenuItem::Arena => locale.format("menu.arena", &[("arena", &arena)]),
+            MenuItem::Language => {
+                locale.format("menu.language", &[("language", &locale.language().name)])
+            }
+            MenuItem::MasterVolume => locale.format(
+                "menu.master_volume",
+                &[("volume", &progress.master_volume())],
+            ),
+            MenuItem::SfxVolume => {
+                locale.format("menu.sfx_volume", &[("volume", &progress.sfx_volume())])
+            }
+            MenuItem::ScreenShake if progress.reduced_motion() => {
+                locale.text("menu.screen_shake_reduced").to_string()
+            }
+            MenuItem::ScreenShake => locale.format(
+                "menu.screen_shake",
+                &[(
+                    "intensity",
+                    &locale.text(progress.shake_intensity().label_key()),
+                )],
+            ),
+            MenuItem::ReducedMotion => locale
+                .text(if progress.reduced_motion() {
+                    "menu.reduced_motion_on"
+                } else {
+                    "menu.reduced_motion_off"
+                })
+                .to_string(),
+            MenuItem::PaddleTrails => locale
+                .text(if progress.paddle_trails() {
+                    "menu.paddle_trails_on"
+                } else {
+                    "menu.paddle_trails_off"
+                })
+                .to_string(),
+            MenuItem::Options => locale.text("menu.options").to_string(),
+            MenuItem::Calibration => locale.text("menu.calibration").to_string(),
+            MenuItem::MainMenu => locale.text("menu.main_menu").to_string(),
+            MenuItem::Quit => locale.text("menu.quit").to_string(),
+        }
+    }
+}
+
+// Pause menu drawn over the frozen match.
+pub struct Menu {
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    // Returns the item that was chosen, Escape counts as Resume.
+    pub fn handle_key(&mut self, key_code: KeyCode) -> Option<MenuItem> {
+        match key_code {
+            KeyCode::ArrowUp | KeyCode::KeyW => {
+                self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len();
+            }
+            KeyCode::ArrowDown | KeyCode::KeyS => {
+                self.selected = (self.selected + 1) % ITEMS.len();
+            }
+            KeyCode::Enter | KeyCode::Space => return Some(ITEMS[self.selected]),
+            KeyCode::Escape => return Some(MenuItem::Resume),
+            _ => (),
+        }
+        None
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, progress: &Progress, arena: &str, locale: &Locale) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.7),
+            Deg(0.),
+        );
+
+        let top = height / 2. - LINE_HEIGHT * ITEMS.len() as f32 / 2.;
+        for (index, item) in ITEMS.iter().enumerate() {
+            let text = item.label(progress, arena, locale);
+            let text_width = renderer.measure_text(&text, FONT_SIZE, LINE_HEIGHT);
+            let color = if index == self.selected {
+                glyphon::Color::rgb(255, 255, 255)
+            } else {
+                glyphon::Color::rgb(128, 128, 128)
+            };
+            renderer.draw_text(
+                &text,
+                Vector2::new(
+                    width / 2. - text_width / 2.,
+                    top + index as f32 * LINE_HEIGHT,
+                ),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(color),
+            );
+        }
+    }
+}
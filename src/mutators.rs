@@ -0,0 +1,181 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::renderer::Renderer;
+use crate::sim::{PADDLE_SPEED, Side};
+
+static PORTAL_FLASH_DURATION: f32 = 0.35;
+static PORTAL_WIDTH: f32 = 80.0;
+static PORTAL_HEIGHT: f32 = 6.0;
+// Rubber band strengths to pick from, off first
+pub static RUBBER_BAND_STRENGTHS: [f32; 4] = [0.0, 0.1, 0.2, 0.3];
+// Impact transfer coefficients to pick from, off first
+pub static IMPACT_TRANSFERS: [f32; 4] = [0.0, 0.15, 0.3, 0.5];
+// Paddle accelerations to pick from in pixels per second², off first, then
+// increasingly heavy paddles
+pub static MOMENTUM_ACCELERATIONS: [f32; 4] = [0.0, 10000.0, 5000.0, 2500.0];
+// Wall dampings to pick from, off first, then losing more speed and last
+// one that gains speed
+pub static WALL_DAMPINGS: [f32; 4] = [0.0, 0.1, 0.25, -0.1];
+
+// Optional gameplay rules that change how a match plays out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mutators {
+    // The top and bottom walls teleport the ball to the opposite edge instead of bouncing.
+    pub portal_walls: bool,
+    // The left (player one) paddle is shorter, for a handicap.
+    pub small_paddle: bool,
+    // Time slows down briefly when the ball is about to pass a player who
+    // isn't moving, giving beginners a chance to react.
+    pub left_save_assist: bool,
+    pub right_save_assist: bool,
+    // Comeback help: the ball is up to this much faster while the leading
+    // player serves and slower while the trailing player serves. 0 is off.
+    pub rubber_band: f32,
+    // A paddle moving while it hits the ball gives it spin, curving its path
+    // the way the paddle moved.
+    pub spin: bool,
+    // The right paddle covers the whole goal line, for practice drills
+    pub practice_wall: bool,
+    // Paddles speed up and slow down at this acceleration instead of moving at
+    // full speed right away, and a fast moving paddle returns the ball faster.
+    // 0 is off.
+    pub momentum: f32,
+    // Share of the paddle's velocity at impact added to the ball's outgoing
+    // velocity, 0 is off and only the hit position sets the angle.
+    pub impact_transfer: f32,
+    // Share of the ball's speed into the top and bottom walls lost on each
+    // bounce, negative gains speed instead. 0 is off.
+    pub wall_damping: f32,
+}
+
+impl Mutators {
+    pub fn save_assist(&self, side: Side) -> bool {
+        match side {
+            Side::Left => self.left_save_assist,
+            Side::Right => self.right_save_assist,
+        }
+    }
+
+    // Coefficient of restitution of the top and bottom walls, 1 bounces the
+    // ball off as fast as it came in
+    pub fn wall_restitution(&self) -> f32 {
+        1.0 - self.wall_damping
+    }
+
+    // Names of the enabled mutators in the current language
+    pub fn active_names(&self, locale: &Locale) -> Vec<String> {
+        let mut keys = Vec::new();
+        if self.portal_walls {
+            keys.push("mutator.portal_walls");
+        }
+        if self.small_paddle {
+            keys.push("mutator.small_paddle");
+        }
+        if self.left_save_assist {
+            keys.push("mutator.save_assist_left");
+        }
+        if self.right_save_assist {
+            keys.push("mutator.save_assist_right");
+        }
+        if self.spin {
+            keys.push("mutator.spin");
+        }
+        if self.practice_wall {
+            keys.push("mutator.practice_wall");
+        }
+        let mut names: Vec<String> = keys
+            .into_iter()
+            .map(|key| locale.text(key).to_string())
+            .collect();
+        if self.momentum > 0.0 {
+            names.push(locale.format(
+                "mutator.momentum",
+                &[("time", &format!("{:.1}", PADDLE_SPEED / self.momentum))],
+            ));
+        }
+        if self.impact_transfer > 0.0 {
+            names.push(locale.format(
+                "mutator.impact_transfer",
+                &[("transfer", &format!("{:.0}", self.impact_transfer * 100.0))],
+            ));
+        }
+        if self.wall_damping > 0.0 {
+            names.push(locale.format(
+                "mutator.wall_damping",
+                &[("damping", &format!("{:.0}", self.wall_damping * 100.0))],
+            ));
+        } else if self.wall_damping < 0.0 {
+            names.push(locale.format(
+                "mutator.wall_boost",
+                &[("boost", &format!("{:.0}", -self.wall_damping * 100.0))],
+            ));
+        }
+        if self.rubber_band > 0.0 {
+            names.push(locale.format(
+                "mutator.rubber_band",
+                &[("strength", &format!("{:.0}", self.rubber_band * 100.0))],
+            ));
+        }
+        names
+    }
+}
+
+// Short-lived visual shown on both edges when the ball passes through a portal wall.
+pub struct PortalFlash {
+    entry: Vector2<f32>,
+    exit: Vector2<f32>,
+    // +1 if the ball leaves the exit portal moving down, -1 if moving up
+    direction: f32,
+    remaining: f32,
+}
+
+impl PortalFlash {
+    pub fn new(entry: Vector2<f32>, exit: Vector2<f32>, direction: f32) -> Self {
+        Self {
+            entry,
+            exit,
+            direction,
+            remaining: PORTAL_FLASH_DURATION,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.remaining -= delta;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining <= 0.0
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer) {
+        let t = (self.remaining / PORTAL_FLASH_DURATION).clamp(0.0, 1.0);
+        let width = PORTAL_WIDTH * (0.5 + 0.5 * t);
+        let color = Srgba::new(0.6, 0.3, 1.0, t);
+
+        for center in [self.entry, self.exit] {
+            renderer.draw_rectangle(
+                Vector2::new(center.x - width / 2.0, center.y - PORTAL_HEIGHT / 2.0),
+                width,
+                PORTAL_HEIGHT,
+                color,
+                Deg(0.),
+            );
+        }
+
+        // Arrow on the exit portal pointing in the direction the ball travels
+        let tip = self.exit + Vector2::new(0.0, self.direction * PORTAL_HEIGHT * 4.0);
+        let left = Vector2::new(self.exit.x - PORTAL_HEIGHT * 2.0, self.exit.y);
+        let right = Vector2::new(self.exit.x + PORTAL_HEIGHT * 2.0, self.exit.y);
+        // Keep counter-clockwise winding so the triangle isn't back-face culled
+        let (a, b) = if self.direction > 0.0 {
+            (right, left)
+        } else {
+            (left, right)
+        };
+        renderer.draw_triangle(a, b, tip, color, Deg(0.));
+    }
+}
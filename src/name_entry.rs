@@ -0,0 +1,157 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::event::{Ime, KeyEvent};
+
+use crate::locale::Locale;
+use crate::renderer::{Rect, Renderer};
+use crate::textfield::{TextField, TextFieldAction};
+
+static FONT_SIZE: f32 = 32.;
+static LINE_HEIGHT: f32 = 40.;
+static FIELD_WIDTH: f32 = 480.;
+static CODE_FIELD_WIDTH: f32 = 880.;
+static FIELD_HEIGHT: f32 = 56.;
+pub static MAX_NAME_CHARS: usize = 16;
+// Room for a ruleset code pasted with spaces around it
+static MAX_CODE_CHARS: usize = 64;
+
+// What the typed text is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    PlayerName,
+    RulesetCode,
+}
+
+// Screen for typing the player name, opened from the menu, or a ruleset
+// code from the main menu.
+pub struct NameEntry {
+    kind: EntryKind,
+    field: TextField,
+    // Why the last submitted text wasn't taken
+    error: Option<String>,
+    // Where the caret was last drawn, for placing the IME candidate window
+    caret: Rect,
+}
+
+impl NameEntry {
+    pub fn new(kind: EntryKind, text: &str) -> Self {
+        let max_chars = match kind {
+            EntryKind::PlayerName => MAX_NAME_CHARS,
+            EntryKind::RulesetCode => MAX_CODE_CHARS,
+        };
+        Self {
+            kind,
+            field: TextField::new(text, max_chars),
+            error: None,
+            caret: Rect::new(0., 0., 0., 0.),
+        }
+    }
+
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        self.field.text().trim()
+    }
+
+    pub fn selected_text(&self) -> &str {
+        self.field.selected_text()
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        self.field.paste(text);
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub fn caret(&self) -> Rect {
+        self.caret
+    }
+
+    pub fn handle_key(
+        &mut self,
+        event: &KeyEvent,
+        shift: bool,
+        ctrl: bool,
+    ) -> Option<TextFieldAction> {
+        self.field.handle_key(event, shift, ctrl)
+    }
+
+    pub fn handle_ime(&mut self, ime: &Ime) {
+        self.field.handle_ime(ime);
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.field.update(delta);
+    }
+
+    pub fn draw(&mut self, renderer: &mut Renderer, locale: &Locale) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.85),
+            Deg(0.),
+        );
+
+        let top = height / 2. - FIELD_HEIGHT;
+        let (title, controls) = match self.kind {
+            EntryKind::PlayerName => ("name_entry.title", "name_entry.controls"),
+            EntryKind::RulesetCode => ("name_entry.ruleset_title", "name_entry.ruleset_controls"),
+        };
+        let title = locale.text(title);
+        let title_width = renderer.measure_text(title, FONT_SIZE, LINE_HEIGHT);
+        renderer.draw_text(
+            title,
+            Vector2::new(width / 2. - title_width / 2., top - LINE_HEIGHT * 1.5),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            None,
+        );
+
+        let field_width = match self.kind {
+            EntryKind::PlayerName => FIELD_WIDTH,
+            EntryKind::RulesetCode => CODE_FIELD_WIDTH.min(width - FIELD_HEIGHT),
+        };
+        let field = Rect::new(
+            width / 2. - field_width / 2.,
+            top,
+            field_width,
+            FIELD_HEIGHT,
+        );
+        self.caret = self.field.draw(renderer, field, FONT_SIZE, LINE_HEIGHT);
+
+        let hint = locale.text(controls);
+        let hint_width = renderer.measure_text(hint, FONT_SIZE * 0.6, LINE_HEIGHT * 0.6);
+        renderer.draw_text(
+            hint,
+            Vector2::new(
+                width / 2. - hint_width / 2.,
+                top + FIELD_HEIGHT + LINE_HEIGHT * 0.5,
+            ),
+            FONT_SIZE * 0.6,
+            LINE_HEIGHT * 0.6,
+            Some(glyphon::Color::rgb(160, 160, 160)),
+        );
+
+        if let Some(error) = &self.error {
+            let error_width = renderer.measure_text(error, FONT_SIZE * 0.6, LINE_HEIGHT * 0.6);
+            renderer.draw_text(
+                error,
+                Vector2::new(
+                    width / 2. - error_width / 2.,
+                    top + FIELD_HEIGHT + LINE_HEIGHT * 1.2,
+                ),
+                FONT_SIZE * 0.6,
+                LINE_HEIGHT * 0.6,
+                Some(glyphon::Color::rgb(255, 110, 110)),
+            );
+        }
+    }
+}
@@ -0,0 +1,6 @@
+mod online;
+mod protocol;
+mod rendezvous;
+
+pub use online::{OnlineEvent, OnlineMatch, Role};
+pub use rendezvous::{PeerLink, Route, connect, run_server};
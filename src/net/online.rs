@@ -0,0 +1,600 @@
+// Online matches between two players, on top of a `PeerLink`.
+//
+// The host runs the simulation like a local match, with the client's latest
+// input driving the right paddle, and sends snapshots of the match 60 times
+// a second. The client doesn't simulate: it shows the host's match a little
+// in the past, moving between the two snapshots around that time, and plays
+// the events they carry. Only its own paddle moves right away, and drifts
+// back to where the host has it once the player lets go.
+//
+// To find netcode bugs, the snapshots also carry every step of the host's
+// sim with the hash of its state afterwards. The client runs a copy of the
+// host's sim with the same steps and reports the first one where its state
+// hashes differently; both sides log their state. It then asks for all of
+// the host's sim and continues from there, as it does when snapshots got
+// lost on the way.
+//
+// That copy is also what the client plays on with when the host goes away in
+// the middle of a match: its game takes over the sim from the last state both
+// agreed on, see `OnlineEvent::Migrated`.
+
+use cgmath::{InnerSpace, Vector2};
+use log::{error, info, warn};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::protocol::{Message, NetEvent, NetTick, PROTOCOL_VERSION, Snapshot};
+use super::rendezvous::{MAX_PAYLOAD_SIZE, PeerLink};
+use crate::mutators::Mutators;
+use crate::replay::{TickInput, analog_speed, speed_share};
+use crate::score::Score;
+use crate::sim::{GameSim, Side, SimEvent, SimState};
+use crate::time::Instant;
+
+static HELLO_INTERVAL: Duration = Duration::from_millis(250);
+// The other side counts as gone after this long without a packet
+static PEER_TIMEOUT: Duration = Duration::from_secs(5);
+// Seconds between two snapshots from the host
+static SNAPSHOT_INTERVAL: f64 = 1.0 / 60.0;
+// The client shows the host's match this many seconds in the past, so a
+// newer snapshot has usually arrived to move toward
+static INTERPOLATION_DELAY: f64 = 0.1;
+// Further off than this the client's clock jumps instead of catching up
+static MAX_CLOCK_DRIFT: f64 = 0.5;
+// Share of the clock drift made up per second
+static CLOCK_CATCH_UP: f64 = 2.0;
+// Share of the distance to the host's position the client's own paddle
+// makes up per second while it stands still
+static PADDLE_CORRECTION: f32 = 8.0;
+// Moves longer than this share of the field width between two snapshots are
+// teleports (serve, portals) and are not smoothed
+static TELEPORT_SHARE: f32 = 0.25;
+static MAX_SNAPSHOTS: usize = 64;
+// States of the host's sim kept to compare with the client's report, two
+// seconds at the tick rate
+static MAX_HISTORY: usize = 240;
+// Least time between two requests for the host's sim
+static RESYNC_RETRY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Host,
+    Client,
+}
+
+impl Role {
+    // The host plays on the left
+    pub fn side(self) -> Side {
+        match self {
+            Role::Host => Side::Left,
+            Role::Client => Side::Right,
+        }
+    }
+}
+
+pub enum OnlineEvent {
+    // The other player's hello arrived, the client plays by the host's rules
+    Joined {
+        name: String,
+        mutators: Mutators,
+        win_score: u8,
+        tick_rate: u32,
+    },
+    // The host started a new match
+    Restarted,
+    // The client's copy of the host's sim went its own way and is synced again
+    Desynced,
+    // The host went away and the client's game simulates the match from now
+    // on, from `state` after the events that weren't shown yet
+    Migrated {
+        state: Box<SimState>,
+        events: Vec<SimEvent>,
+    },
+    Left,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct RemoteInput {
+    seq: u64,
+    up: bool,
+    down: bool,
+    speed: Option<u8>,
+}
+
+pub struct OnlineMatch {
+    link: PeerLink,
+    role: Role,
+    name: String,
+    // Ours, the host's once its hello arrived on the client
+    tick_rate: u32,
+    // Set once the other player's hello arrived
+    peer_name: Option<String>,
+    last_hello: Option<Instant>,
+    last_received: Instant,
+    left: bool,
+    // Seconds since the match started on the host's clock. The client's
+    // runs INTERPOLATION_DELAY behind the newest snapshot.
+    time: f64,
+    // Host: when the last snapshot went out, the events since then and the
+    // client's paddle
+    last_snapshot: f64,
+    events: Vec<NetEvent>,
+    remote: RemoteInput,
+    // Host: the sim's steps since the last snapshot, the first of them, and
+    // the states after the last MAX_HISTORY steps
+    ticks: Vec<NetTick>,
+    first_tick: u64,
+    history: VecDeque<SimState>,
+    // Client: the last input sent and the snapshots around `time`
+    seq: u64,
+    snapshots: VecDeque<Snapshot>,
+    // Time of the newest snapshot whose events were played
+    played: f64,
+    // Client: the copy of the host's sim, None until the host sent it
+    shadow: Option<GameSim>,
+    last_resync_request: Option<Instant>,
+}
+
+impl OnlineMatch {
+    pub fn new(link: PeerLink, role: Role, name: String, tick_rate: u32) -> Self {
+        Self {
+            link,
+            role,
+            name,
+            tick_rate,
+            peer_name: None,
+            last_hello: None,
+            last_received: Instant::now(),
+            left: false,
+            time: 0.0,
+            last_snapshot: 0.0,
+            events: Vec::new(),
+            remote: RemoteInput::default(),
+            ticks: Vec::new(),
+            first_tick: 0,
+            history: VecDeque::new(),
+            seq: 0,
+            snapshots: VecDeque::new(),
+            played: 0.0,
+            shadow: None,
+            last_resync_request: None,
+        }
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    // Sim steps per second of the match
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    pub fn peer_name(&self) -> Option<&str> {
+        self.peer_name.as_deref()
+    }
+
+    // Whether both players are there, the host holds the match until then
+    pub fn is_connected(&self) -> bool {
+        self.peer_name.is_some() && !self.left
+    }
+
+    // Handles everything that arrived since the last frame. `sim` is the
+    // host's match, its rules go out with the hello.
+    pub fn poll(&mut self, sim: &GameSim) -> Vec<OnlineEvent> {
+        let mut events = Vec::new();
+        if self.left {
+            return events;
+        }
+
+        loop {
+            let message = match self.link.try_recv() {
+                Ok(Some(payload)) => Message::decode(payload),
+                Ok(None) => break,
+                Err(err) => {
+                    warn!("Failed to receive from peer: {:#}", err);
+                    break;
+                }
+            };
+            let message = match message {
+                Ok(message) => message,
+                Err(err) => {
+                    warn!("{:#}", err);
+                    continue;
+                }
+            };
+            self.last_received = Instant::now();
+
+            match message {
+                Message::Hello {
+                    version,
+                    host,
+                    seen,
+                    name,
+                    mutators,
+                    win_score,
+                    tick_rate,
+                } => {
+                    if version != PROTOCOL_VERSION {
+                        error!(
+                            "{} plays online version {}, this game version {}",
+                            name, version, PROTOCOL_VERSION
+                        );
+                        return self.leave(events);
+                    }
+                    if host == (self.role == Role::Host) {
+                        error!(
+                            "Both players chose to {}",
+                            if host { "host" } else { "join" }
+                        );
+                        return self.leave(events);
+                    }
+                    if self.peer_name.is_none() {
+                        if host && tick_rate != self.tick_rate {
+                            info!(
+                                "Simulating at the host's {} Hz instead of {} Hz",
+                                tick_rate, self.tick_rate
+                            );
+                            self.tick_rate = tick_rate;
+                        }
+                        self.peer_name = Some(name.clone());
+                        events.push(OnlineEvent::Joined {
+                            name,
+                            mutators,
+                            win_score,
+                            tick_rate: self.tick_rate,
+                        });
+                    }
+                    // Ours got lost or crossed theirs
+                    if !seen {
+                        self.send_hello(sim);
+                    }
+                }
+                Message::Input {
+                    seq,
+                    up,
+                    down,
+                    speed,
+                } if self.role == Role::Host && seq > self.remote.seq => {
+                    self.remote = RemoteInput {
+                        seq,
+                        up,
+                        down,
+                        speed,
+                    };
+                }
+                Message::State(snapshot) if self.role == Role::Client => {
+                    let newest = self.snapshots.back();
+                    if newest.is_some_and(|newest| newest.time >= snapshot.time) {
+                        continue;
+                    }
+                    let total = |score: [Score; 2]| {
+                        u64::from(score[0].points()) + u64::from(score[1].points())
+                    };
+                    if newest.is_some_and(|newest| total(snapshot.score) < total(newest.score)) {
+                        events.push(OnlineEvent::Restarted);
+                    }
+                    if !self.verify(&snapshot) {
+                        events.push(OnlineEvent::Desynced);
+                    }
+                    self.snapshots.push_back(snapshot);
+                    if self.snapshots.len() > MAX_SNAPSHOTS {
+                        self.snapshots.pop_front();
+                    }
+                }
+                Message::Desync { state } if self.role == Role::Host => {
+                    self.log_desync(&state);
+                }
+                Message::RequestResync if self.role == Role::Host => {
+                    self.send(&Message::Resync { state: sim.state() });
+                }
+                Message::Resync { state } if self.role == Role::Client => {
+                    info!("Synced with the host's match at step {}", state.ticks);
+                    self.shadow = Some(GameSim::from_state(&state));
+                }
+                Message::Leave => {
+                    self.left = true;
+                    return self.take_over(events);
+                }
+                _ => (),
+            }
+        }
+
+        if self.last_received.elapsed() > PEER_TIMEOUT {
+            warn!("No packets from the other player for {:?}", PEER_TIMEOUT);
+            self.send(&Message::Leave);
+            self.left = true;
+            return self.take_over(events);
+        }
+        if self.peer_name.is_none()
+            && self
+                .last_hello
+                .is_none_or(|sent| sent.elapsed() >= HELLO_INTERVAL)
+        {
+            self.send_hello(sim);
+        }
+        events
+    }
+
+    fn leave(&mut self, mut events: Vec<OnlineEvent>) -> Vec<OnlineEvent> {
+        self.send(&Message::Leave);
+        self.left = true;
+        events.push(OnlineEvent::Left);
+        events
+    }
+
+    // Client: the host is gone, continues from the copy of its sim if it's
+    // in sync. Otherwise, and on the host, the match is over.
+    fn take_over(&mut self, mut events: Vec<OnlineEvent>) -> Vec<OnlineEvent> {
+        let shadow = match self.role {
+            Role::Client => self.shadow.take(),
+            Role::Host => None,
+        };
+        let Some(shadow) = shadow else {
+            events.push(OnlineEvent::Left);
+            return events;
+        };
+
+        let state = shadow.state();
+        info!("Taking over the host's match at step {}", state.ticks);
+        let unplayed = self
+            .snapshots
+            .iter()
+            .filter(|snapshot| snapshot.time > self.played)
+            .flat_map(|snapshot| snapshot.events.iter().copied().map(SimEvent::from))
+            .collect();
+        events.push(OnlineEvent::Migrated {
+            state: Box::new(state),
+            events: unplayed,
+        });
+        events
+    }
+
+    fn send_hello(&mut self, sim: &GameSim) {
+        self.send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+            host: self.role == Role::Host,
+            seen: self.peer_name.is_some(),
+            name: self.name.clone(),
+            mutators: sim.mutators,
+            win_score: sim.win_score,
+            tick_rate: self.tick_rate,
+        });
+        self.last_hello = Some(Instant::now());
+    }
+
+    fn send(&self, message: &Message) {
+        let result = message.encode().and_then(|packet| self.link.send(&packet));
+        if let Err(err) = result {
+            warn!("Failed to send to peer: {:#}", err);
+        }
+    }
+
+    // Host: where the client wants its paddle to go, like a controller
+    pub fn remote_direction(&self) -> f32 {
+        let speed = speed_share(self.remote.speed);
+        match (self.remote.up, self.remote.down) {
+            (true, false) => -speed,
+            (false, true) => speed,
+            _ => 0.0,
+        }
+    }
+
+    // Host: events of a sim step, sent with the next snapshot
+    pub fn record_events(&mut self, events: &[SimEvent]) {
+        if self.role == Role::Host {
+            self.events
+                .extend(events.iter().copied().map(NetEvent::from));
+        }
+    }
+
+    // Host: a step of `sim` that took `input`, sent with the next snapshot
+    pub fn record_tick(&mut self, sim: &GameSim, dt: f32, input: TickInput) {
+        if self.role != Role::Host {
+            return;
+        }
+        let state = sim.state();
+        // A new sim was started since the last step
+        let tick = state.ticks - 1;
+        if self.first_tick + self.ticks.len() as u64 != tick {
+            self.ticks.clear();
+            self.first_tick = tick;
+        }
+        self.ticks.push(NetTick {
+            dt,
+            input,
+            hash: state.hash(),
+        });
+        self.history.push_back(state);
+        if self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    // Host: the client's copy computed `client` where our sim had another
+    // state, logs both to compare
+    fn log_desync(&self, client: &SimState) {
+        let json = |state: &SimState| serde_json::to_string(state).unwrap_or_default();
+        let host = self
+            .history
+            .iter()
+            .find(|state| state.seed == client.seed && state.ticks == client.ticks);
+        match host {
+            Some(host) => error!(
+                "The client went out of sync at step {}\n  host:   {}\n  client: {}",
+                client.ticks,
+                json(host),
+                json(client)
+            ),
+            None => error!(
+                "The client went out of sync at step {}, no longer known here\n  client: {}",
+                client.ticks,
+                json(client)
+            ),
+        }
+    }
+
+    // Client: steps the copy of the host's sim through the snapshot's steps
+    // and compares the hashes. Returns false on the first that differs, the
+    // copy is then dropped and the host's sim requested like whenever it
+    // doesn't line up with the snapshots.
+    fn verify(&mut self, snapshot: &Snapshot) -> bool {
+        let shadow = match self.shadow.as_mut() {
+            Some(shadow)
+                if shadow.seed() == snapshot.seed && shadow.ticks() >= snapshot.first_tick =>
+            {
+                shadow
+            }
+            _ => {
+                self.shadow = None;
+                self.request_resync();
+                return true;
+            }
+        };
+
+        let skip = (shadow.ticks() - snapshot.first_tick) as usize;
+        for tick in snapshot.ticks.iter().skip(skip) {
+            shadow.step(tick.dt, tick.input);
+            let state = shadow.state();
+            if state.hash() != tick.hash {
+                error!(
+                    "Out of sync with the host at step {}, hash {:016x} instead of {:016x}\n  client: {}",
+                    state.ticks,
+                    state.hash(),
+                    tick.hash,
+                    serde_json::to_string(&state).unwrap_or_default()
+                );
+                self.send(&Message::Desync { state });
+                self.shadow = None;
+                self.request_resync();
+                return false;
+            }
+        }
+        true
+    }
+
+    fn request_resync(&mut self) {
+        if self
+            .last_resync_request
+            .is_none_or(|sent| sent.elapsed() >= RESYNC_RETRY)
+        {
+            self.send(&Message::RequestResync);
+            self.last_resync_request = Some(Instant::now());
+        }
+    }
+
+    // Host: sends the match once a snapshot is due. The clock keeps running
+    // while the match is held, so the client's stays in step.
+    pub fn host_update(&mut self, delta: f32, sim: &GameSim) {
+        self.time += f64::from(delta);
+        if !self.is_connected() || self.time - self.last_snapshot < SNAPSHOT_INTERVAL {
+            return;
+        }
+        self.last_snapshot = self.time;
+        let events = std::mem::take(&mut self.events);
+        let ticks = std::mem::take(&mut self.ticks);
+        let first_tick = self.first_tick;
+        self.first_tick += ticks.len() as u64;
+        let mut snapshot = Snapshot::of(sim, self.time, events, first_tick, ticks);
+
+        // After a long frame at a high tick rate the steps may not fit into
+        // one packet. Without them the client asks for the whole sim instead.
+        let fits = match Message::State(snapshot.clone()).encode() {
+            Ok(packet) => packet.len() <= MAX_PAYLOAD_SIZE,
+            Err(_) => true,
+        };
+        if !fits {
+            snapshot.ticks.clear();
+        }
+        self.send(&Message::State(snapshot));
+    }
+
+    // Client: sends the player's paddle `direction`, moves the own paddle
+    // right away and the rest of `sim` to where the host's match was a
+    // moment ago. Returns the events played on the way.
+    pub fn client_update(
+        &mut self,
+        delta: f32,
+        direction: f32,
+        sim: &mut GameSim,
+    ) -> Vec<SimEvent> {
+        self.seq += 1;
+        self.send(&Message::Input {
+            seq: self.seq,
+            up: direction < 0.0,
+            down: direction > 0.0,
+            speed: analog_speed(direction),
+        });
+        let side = self.role.side();
+        sim.move_paddle(side, direction, delta);
+
+        let Some(newest) = self.snapshots.back() else {
+            return Vec::new();
+        };
+        let own_y = newest.paddles[1][1];
+        let target = newest.time - INTERPOLATION_DELAY;
+        let drift = target - self.time;
+        if drift.abs() > MAX_CLOCK_DRIFT {
+            self.time = target;
+        } else {
+            self.time += f64::from(delta) + drift * (CLOCK_CATCH_UP * f64::from(delta)).min(1.0);
+        }
+
+        let mut events = Vec::new();
+        for snapshot in &self.snapshots {
+            if snapshot.time > self.played && snapshot.time <= self.time {
+                events.extend(snapshot.events.iter().copied().map(SimEvent::from));
+                self.played = snapshot.time;
+            }
+        }
+        while self.snapshots.len() > 2 && self.snapshots[1].time <= self.time {
+            self.snapshots.pop_front();
+        }
+
+        let (from, to) = match (self.snapshots.front(), self.snapshots.get(1)) {
+            (Some(from), Some(to)) if from.time <= self.time => (from, to),
+            (Some(from), _) => (from, from),
+            (None, _) => return events,
+        };
+        let alpha = if to.time > from.time {
+            ((self.time - from.time) / (to.time - from.time)).clamp(0.0, 1.0) as f32
+        } else {
+            1.0
+        };
+        let teleport = to.field[0] * TELEPORT_SHARE;
+        let lerp = |from: [f32; 2], to: [f32; 2]| {
+            let (from, to) = (Vector2::from(from), Vector2::from(to));
+            if (to - from).magnitude() > teleport {
+                to
+            } else {
+                from + (to - from) * alpha
+            }
+        };
+
+        if sim.width != to.field[0] || sim.height != to.field[1] {
+            sim.resize(to.field[0], to.field[1]);
+        }
+        sim.left.height = to.paddle_heights[0];
+        sim.right.height = to.paddle_heights[1];
+        sim.left.score = from.score[0];
+        sim.right.score = from.score[1];
+        sim.pending_serve = from.pending_serve();
+        sim.ball.pos = lerp(from.ball, to.ball);
+        sim.ball.velocity = Vector2::from(to.ball_velocity);
+        sim.left.pos = lerp(from.paddles[0], to.paddles[0]);
+
+        let paddle = &mut sim.right;
+        if paddle.idle > 0.0 {
+            paddle.pos.y += (own_y - paddle.pos.y) * (PADDLE_CORRECTION * delta).min(1.0);
+        }
+        events
+    }
+}
+
+impl Drop for OnlineMatch {
+    fn drop(&mut self) {
+        if !self.left {
+            self.send(&Message::Leave);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::mutators::Mutators;
+use crate::replay::TickInput;
+use crate::score::Score;
+use crate::sim::{GameSim, PendingServe, Side, SimEvent, SimState};
+
+// Bumped whenever a message changes, peers with another version can't play
+pub static PROTOCOL_VERSION: u32 = 3;
+
+// Datagrams between the two players of an online match, one message each.
+// The host simulates the match and sends its state, the client only sends
+// its paddle input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    // Repeated by both sides until the other's arrives, and answered while
+    // `seen` is false. The host's also carries the rules of the match.
+    Hello {
+        version: u32,
+        host: bool,
+        // Whether the sender has the other side's hello
+        seen: bool,
+        name: String,
+        mutators: Mutators,
+        win_score: u8,
+        // Sim steps per second, the host's is the match's
+        tick_rate: u32,
+    },
+    // The client's paddle, `seq` counts up so late packets can be dropped
+    Input {
+        seq: u64,
+        up: bool,
+        down: bool,
+        speed: Option<u8>,
+    },
+    State(Snapshot),
+    // The client's copy of the host's sim computed another state after
+    // `state.ticks` steps, for the host's log
+    Desync {
+        state: SimState,
+    },
+    // The client wants all of the host's sim, e.g. after a desync or when
+    // snapshots got lost
+    RequestResync,
+    Resync {
+        state: SimState,
+    },
+    // The sender quit the match
+    Leave,
+}
+
+impl Message {
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        serde_json::from_slice(payload).context("invalid message from peer")
+    }
+}
+
+// The host's match at `time` seconds after it started, with what happened
+// since the previous snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub time: f64,
+    // Of the host's sim, and the steps it took since the previous snapshot
+    // starting with step `first_tick`, for checking the client's copy
+    pub seed: u64,
+    pub first_tick: u64,
+    pub ticks: Vec<NetTick>,
+    pub field: [f32; 2],
+    pub paddles: [[f32; 2]; 2],
+    pub paddle_heights: [f32; 2],
+    pub ball: [f32; 2],
+    pub ball_velocity: [f32; 2],
+    pub score: [Score; 2],
+    // Direction, aim and time left of a ball waiting to be served
+    pub serve: Option<(Side, f32, f32)>,
+    pub events: Vec<NetEvent>,
+}
+
+impl Snapshot {
+    pub fn of(
+        sim: &GameSim,
+        time: f64,
+        events: Vec<NetEvent>,
+        first_tick: u64,
+        ticks: Vec<NetTick>,
+    ) -> Self {
+        Self {
+            time,
+            seed: sim.seed(),
+            first_tick,
+            ticks,
+            field: [sim.width, sim.height],
+            paddles: [sim.left.pos.into(), sim.right.pos.into()],
+            paddle_heights: [sim.left.height, sim.right.height],
+            ball: sim.ball.pos.into(),
+            ball_velocity: sim.ball.velocity.into(),
+            score: [sim.left.score, sim.right.score],
+            serve: sim
+                .pending_serve
+                .map(|serve| (serve.toward, serve.angle, serve.remaining)),
+            events,
+        }
+    }
+
+    pub fn pending_serve(&self) -> Option<PendingServe> {
+        self.serve.map(|(toward, angle, remaining)| PendingServe {
+            toward,
+            angle,
+            remaining,
+        })
+    }
+}
+
+// A step of the host's sim, with the hash of its state afterwards.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct NetTick {
+    pub dt: f32,
+    pub input: TickInput,
+    pub hash: u64,
+}
+
+// A SimEvent that can be sent over the wire.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum NetEvent {
+    PaddleHit {
+        side: Side,
+        pos: [f32; 2],
+        speed: f32,
+    },
+    WallBounce,
+    Serve {
+        toward: Side,
+    },
+    Portal {
+        entry: [f32; 2],
+        exit: [f32; 2],
+        direction: f32,
+    },
+    Goal {
+        scorer: Side,
+    },
+}
+
+impl From<SimEvent> for NetEvent {
+    fn from(event: SimEvent) -> Self {
+        match event {
+            SimEvent::PaddleHit { side, pos, speed } => NetEvent::PaddleHit {
+                side,
+                pos: pos.into(),
+                speed,
+            },
+            SimEvent::WallBounce => NetEvent::WallBounce,
+            SimEvent::Serve { toward } => NetEvent::Serve { toward },
+            SimEvent::Portal {
+                entry,
+                exit,
+                direction,
+            } => NetEvent::Portal {
+                entry: entry.into(),
+                exit: exit.into(),
+                direction,
+            },
+            SimEvent::Goal { scorer } => NetEvent::Goal { scorer },
+        }
+    }
+}
+
+impl From<NetEvent> for SimEvent {
+    fn from(event: NetEvent) -> Self {
+        match event {
+            NetEvent::PaddleHit { side, pos, speed } => SimEvent::PaddleHit {
+                side,
+                pos: Vector2::from(pos),
+                speed,
+            },
+            NetEvent::WallBounce => SimEvent::WallBounce,
+            NetEvent::Serve { toward } => SimEvent::Serve { toward },
+            NetEvent::Portal {
+                entry,
+                exit,
+                direction,
+            } => SimEvent::Portal {
+                entry: Vector2::from(entry),
+                exit: Vector2::from(exit),
+                direction,
+            },
+            NetEvent::Goal { scorer } => SimEvent::Goal { scorer },
+        }
+    }
+}
@@ -0,0 +1,448 @@
+// Connection setup for internet matches without manual port forwarding.
+//
+// Both peers register the same session code with a public rendezvous server,
+// which tells each of them the other's public address. The peers then send
+// punch packets to each other until the NAT mappings open up. Each peer
+// then tells the server whether its punch got through, and the server picks
+// the route for both: direct if both punches did, otherwise (e.g. symmetric
+// NATs) the traffic of both is relayed through the rendezvous server.
+
+use anyhow::{Context, Result, bail};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use crate::time::Instant;
+
+static REGISTER_TIMEOUT: Duration = Duration::from_secs(60);
+static PUNCH_TIMEOUT: Duration = Duration::from_secs(5);
+// Covers the other peer still punching, it may give up a whole
+// `PUNCH_TIMEOUT` later
+static ROUTE_TIMEOUT: Duration = Duration::from_secs(10);
+static RESEND_INTERVAL: Duration = Duration::from_millis(250);
+static SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+static MAX_PACKET_SIZE: usize = 1500;
+// Longest payload a `PeerLink` gets across, after the tag byte
+pub static MAX_PAYLOAD_SIZE: usize = MAX_PACKET_SIZE - 1;
+
+// Packet tags. Every datagram starts with one of these bytes.
+const REGISTER: u8 = 0x01;
+const PEER: u8 = 0x02;
+const PUNCH: u8 = 0x03;
+const PUNCH_ACK: u8 = 0x04;
+const RELAY_OK: u8 = 0x06;
+const ROUTE: u8 = 0x07;
+const ROUTE_DIRECT: u8 = 0x08;
+const DATA: u8 = 0x10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    // Packets travel straight to the peer
+    Direct(SocketAddr),
+    // Packets are forwarded by the rendezvous server
+    Relayed(SocketAddr),
+}
+
+pub struct PeerLink {
+    socket: UdpSocket,
+    route: Route,
+    buffer: Vec<u8>,
+}
+
+impl PeerLink {
+    pub fn route(&self) -> Route {
+        self.route
+    }
+
+    pub fn send(&self, payload: &[u8]) -> Result<()> {
+        let mut packet = Vec::with_capacity(payload.len() + 1);
+        packet.push(DATA);
+        packet.extend_from_slice(payload);
+
+        let target = match self.route {
+            Route::Direct(addr) | Route::Relayed(addr) => addr,
+        };
+        self.socket.send_to(&packet, target)?;
+        Ok(())
+    }
+
+    // Returns the next data payload that already arrived, without waiting.
+    pub fn try_recv(&mut self) -> Result<Option<&[u8]>> {
+        let expected = match self.route {
+            Route::Direct(addr) | Route::Relayed(addr) => addr,
+        };
+
+        self.socket.set_nonblocking(true)?;
+        let received = loop {
+            let (len, from) = match self.socket.recv_from(&mut self.buffer) {
+                Ok(received) => received,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => break Ok(None),
+                Err(err) => break Err(err),
+            };
+            if from != expected || len == 0 {
+                continue;
+            }
+            match self.buffer[0] {
+                DATA => break Ok(Some(len)),
+                PUNCH => {
+                    if let Err(err) = self.socket.send_to(&[PUNCH_ACK], from) {
+                        break Err(err);
+                    }
+                }
+                _ => (),
+            }
+        };
+        self.socket.set_nonblocking(false)?;
+        Ok(received?.map(|len| &self.buffer[1..len]))
+    }
+
+    // Returns the next data payload, or None if nothing arrived within `timeout`.
+    pub fn recv(&mut self, timeout: Duration) -> Result<Option<&[u8]>> {
+        let deadline = Instant::now() + timeout;
+        let expected = match self.route {
+            Route::Direct(addr) | Route::Relayed(addr) => addr,
+        };
+
+        loop {
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                return Ok(None);
+            };
+            let Some((len, from)) = recv_packet(&self.socket, &mut self.buffer, remaining)? else {
+                return Ok(None);
+            };
+            if from != expected || len == 0 {
+                continue;
+            }
+
+            match self.buffer[0] {
+                DATA => return Ok(Some(&self.buffer[1..len])),
+                // Late punches from the peer still need an answer, otherwise
+                // its side of the handshake may never complete.
+                PUNCH => {
+                    self.socket.send_to(&[PUNCH_ACK], from)?;
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// Registers `session` with the rendezvous server and establishes a link to
+// the other peer that registers the same session.
+pub fn connect(rendezvous: &str, session: &str) -> Result<PeerLink> {
+    validate_session(session)?;
+
+    let server = rendezvous
+        .to_socket_addrs()
+        .with_context(|| format!("cannot resolve rendezvous server {}", rendezvous))?
+        .next()
+        .context("rendezvous server has no address")?;
+
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    let mut buffer = vec![0; MAX_PACKET_SIZE];
+
+    info!("Waiting for a peer in session {:?} via {}", session, server);
+    let peer = exchange(
+        &socket,
+        &mut buffer,
+        server,
+        &packet(REGISTER, session.as_bytes()),
+        REGISTER_TIMEOUT,
+        |tag, payload| {
+            if tag != PEER {
+                return None;
+            }
+            std::str::from_utf8(payload).ok()?.parse().ok()
+        },
+    )?
+    .context("no peer joined the session in time")?;
+
+    info!("Peer found at {}, punching", peer);
+    let punched = punch(&socket, &mut buffer, peer)?;
+    if !punched {
+        warn!("Hole punching to {} failed", peer);
+    }
+
+    // A punch may only get through one way, so neither peer goes direct on
+    // its own
+    let mut request = vec![u8::from(punched)];
+    request.extend_from_slice(session.as_bytes());
+    let direct = confirm_route(&socket, &mut buffer, server, peer, &packet(ROUTE, &request))?
+        .context("rendezvous server did not confirm a route")?;
+
+    let route = if direct {
+        info!("Direct connection to {} established", peer);
+        Route::Direct(peer)
+    } else {
+        warn!("Relaying the connection to {} through {}", peer, server);
+        Route::Relayed(server)
+    };
+    Ok(PeerLink {
+        socket,
+        route,
+        buffer,
+    })
+}
+
+// Reports the punch result until the server answers with the route both
+// peers use, true for direct. Late punches from the peer are still answered
+// meanwhile, it may be waiting for one to get through.
+fn confirm_route(
+    socket: &UdpSocket,
+    buffer: &mut [u8],
+    server: SocketAddr,
+    peer: SocketAddr,
+    request: &[u8],
+) -> Result<Option<bool>> {
+    let deadline = Instant::now() + ROUTE_TIMEOUT;
+    let mut last_send: Option<Instant> = None;
+
+    while Instant::now() < deadline {
+        if last_send.is_none_or(|sent| sent.elapsed() >= RESEND_INTERVAL * 4) {
+            socket.send_to(request, server)?;
+            last_send = Some(Instant::now());
+        }
+
+        let Some((len, from)) = recv_packet(socket, buffer, RESEND_INTERVAL)? else {
+            continue;
+        };
+        if len == 0 {
+            continue;
+        }
+        match (from, buffer[0]) {
+            (from, PUNCH) if from == peer => {
+                socket.send_to(&[PUNCH_ACK], peer)?;
+            }
+            (from, ROUTE_DIRECT) if from == server => return Ok(Some(true)),
+            (from, RELAY_OK) if from == server => return Ok(Some(false)),
+            _ => (),
+        }
+    }
+
+    Ok(None)
+}
+
+fn punch(socket: &UdpSocket, buffer: &mut [u8], peer: SocketAddr) -> Result<bool> {
+    let deadline = Instant::now() + PUNCH_TIMEOUT;
+    let mut last_send: Option<Instant> = None;
+    let mut received_punch = false;
+
+    while Instant::now() < deadline {
+        if last_send.is_none_or(|sent| sent.elapsed() >= RESEND_INTERVAL) {
+            socket.send_to(&[PUNCH], peer)?;
+            last_send = Some(Instant::now());
+        }
+
+        let Some((len, from)) = recv_packet(socket, buffer, RESEND_INTERVAL)? else {
+            continue;
+        };
+        if from != peer || len == 0 {
+            continue;
+        }
+
+        match buffer[0] {
+            PUNCH => {
+                socket.send_to(&[PUNCH_ACK], peer)?;
+                received_punch = true;
+            }
+            // Our punch made it through, and so did theirs
+            PUNCH_ACK => {
+                if !received_punch {
+                    socket.send_to(&[PUNCH_ACK], peer)?;
+                }
+                return Ok(true);
+            }
+            _ => (),
+        }
+    }
+
+    Ok(false)
+}
+
+// Sends `request` to `server` until `accept` returns a value for one of its replies.
+fn exchange<T>(
+    socket: &UdpSocket,
+    buffer: &mut [u8],
+    server: SocketAddr,
+    request: &[u8],
+    timeout: Duration,
+    accept: impl Fn(u8, &[u8]) -> Option<T>,
+) -> Result<Option<T>> {
+    let deadline = Instant::now() + timeout;
+    let mut last_send: Option<Instant> = None;
+
+    while Instant::now() < deadline {
+        if last_send.is_none_or(|sent| sent.elapsed() >= RESEND_INTERVAL * 4) {
+            socket.send_to(request, server)?;
+            last_send = Some(Instant::now());
+        }
+
+        let Some((len, from)) = recv_packet(socket, buffer, RESEND_INTERVAL)? else {
+            continue;
+        };
+        if from != server || len == 0 {
+            continue;
+        }
+        if let Some(value) = accept(buffer[0], &buffer[1..len]) {
+            return Ok(Some(value));
+        }
+    }
+
+    Ok(None)
+}
+
+fn recv_packet(
+    socket: &UdpSocket,
+    buffer: &mut [u8],
+    timeout: Duration,
+) -> Result<Option<(usize, SocketAddr)>> {
+    socket.set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+    match socket.recv_from(buffer) {
+        Ok(received) => Ok(Some(received)),
+        Err(err)
+            if matches!(
+                err.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn packet(tag: u8, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(payload.len() + 1);
+    packet.push(tag);
+    packet.extend_from_slice(payload);
+    packet
+}
+
+struct Session {
+    members: Vec<SocketAddr>,
+    // Whether the punch of the member at the same index got through
+    punched: [Option<bool>; 2],
+    relayed: bool,
+    last_seen: Instant,
+}
+
+// Runs a rendezvous/relay server until the process is terminated.
+pub fn run_server(bind: &str) -> Result<()> {
+    let socket = UdpSocket::bind(bind).with_context(|| format!("cannot bind {}", bind))?;
+    info!("Rendezvous server listening on {}", socket.local_addr()?);
+
+    let mut sessions: HashMap<String, Session> = HashMap::new();
+    // Reverse lookup for relayed data packets
+    let mut member_session: HashMap<SocketAddr, String> = HashMap::new();
+    let mut buffer = vec![0; MAX_PACKET_SIZE];
+
+    // A bad packet or an unreachable peer must not take the server down for
+    // every other session
+    let send = |packet: &[u8], to: SocketAddr| {
+        if let Err(err) = socket.send_to(packet, to) {
+            warn!("Failed to send to {}: {:#}", to, err);
+        }
+    };
+
+    loop {
+        let (len, from) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(err) => {
+                warn!("Failed to receive a packet: {:#}", err);
+                continue;
+            }
+        };
+        if len == 0 {
+            continue;
+        }
+        let tag = buffer[0];
+        let payload = &buffer[1..len];
+
+        sessions.retain(|_, session| session.last_seen.elapsed() < SESSION_IDLE_TIMEOUT);
+        member_session.retain(|_, code| sessions.contains_key(code));
+
+        match tag {
+            REGISTER => {
+                let Ok(code) = std::str::from_utf8(payload) else {
+                    continue;
+                };
+                let session = sessions.entry(code.to_string()).or_insert(Session {
+                    members: Vec::new(),
+                    punched: [None; 2],
+                    relayed: false,
+                    last_seen: Instant::now(),
+                });
+                session.last_seen = Instant::now();
+
+                if !session.members.contains(&from) {
+                    if session.members.len() == 2 {
+                        debug!("Session {:?} is full, ignoring {}", code, from);
+                        continue;
+                    }
+                    session.members.push(from);
+                    member_session.insert(from, code.to_string());
+                }
+
+                if let [a, b] = session.members[..] {
+                    send(&packet(PEER, b.to_string().as_bytes()), a);
+                    send(&packet(PEER, a.to_string().as_bytes()), b);
+                }
+            }
+            // Answered once both members reported, each keeps asking until then
+            ROUTE => {
+                let Some((&punched, code)) = payload.split_first() else {
+                    continue;
+                };
+                let Ok(code) = std::str::from_utf8(code) else {
+                    continue;
+                };
+                let Some(session) = sessions.get_mut(code) else {
+                    continue;
+                };
+                let Some(index) = session.members.iter().position(|&member| member == from) else {
+                    continue;
+                };
+                session.punched[index] = Some(punched != 0);
+                session.last_seen = Instant::now();
+
+                let [Some(a), Some(b)] = session.punched else {
+                    continue;
+                };
+                if a && b {
+                    send(&[ROUTE_DIRECT], from);
+                } else {
+                    if !session.relayed {
+                        info!("Relaying session {:?}", code);
+                        session.relayed = true;
+                    }
+                    send(&[RELAY_OK], from);
+                }
+            }
+            DATA => {
+                let Some(session) = member_session
+                    .get(&from)
+                    .and_then(|code| sessions.get_mut(code))
+                else {
+                    continue;
+                };
+                if !session.relayed {
+                    continue;
+                }
+                session.last_seen = Instant::now();
+                if let Some(&other) = session.members.iter().find(|&&member| member != from) {
+                    send(&buffer[..len], other);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// Makes sure a session code fits into a single packet.
+fn validate_session(session: &str) -> Result<()> {
+    if session.is_empty() || session.len() > 64 {
+        bail!("session code must be between 1 and 64 characters");
+    }
+    Ok(())
+}
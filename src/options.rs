@@ -0,0 +1,471 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::keyboard::KeyCode;
+
+use crate::config::{
+    BALL_SPEED_RANGE, BINDINGS, Binding, CONTROL_LAYOUTS, FONT_SIZE_RANGE, IDLE_TIMEOUTS,
+    PADDLE_SPEED_RANGE, Settings, key_label,
+};
+use crate::locale::Locale;
+use crate::prompts::{Action, Prompts};
+use crate::renderer::Renderer;
+use crate::sim::Side;
+use crate::timestep::TICK_RATES;
+
+static FONT_SIZE: f32 = 26.;
+static LINE_HEIGHT: f32 = 36.;
+static SPEED_STEP: f32 = 100.0;
+static FONT_SIZE_STEP: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    PaddleSpeed,
+    BallSpeed,
+    FontSize,
+    Font,
+    Language,
+    Pacing,
+    MenuPowerSaving,
+    FitField,
+    TickRate,
+    IdleTakeover,
+    DynamicResolution,
+    Upscale,
+    PixelArt,
+    Scanlines,
+    Telemetry,
+    // Preset keys for a paddle
+    Layout(Side),
+    Key(Binding),
+    Reset,
+}
+
+static ITEMS: [Item; 23] = [
+    Item::PaddleSpeed,
+    Item::BallSpeed,
+    Item::FontSize,
+    Item::Font,
+    Item::Language,
+    Item::Pacing,
+    Item::MenuPowerSaving,
+    Item::FitField,
+    Item::TickRate,
+    Item::IdleTakeover,
+    Item::DynamicResolution,
+    Item::Upscale,
+    Item::PixelArt,
+    Item::Scanlines,
+    Item::Telemetry,
+    Item::Layout(Side::Left),
+    Item::Layout(Side::Right),
+    Item::Key(BINDINGS[0]),
+    Item::Key(BINDINGS[1]),
+    Item::Key(BINDINGS[2]),
+    Item::Key(BINDINGS[3]),
+    Item::Key(BINDINGS[4]),
+    Item::Reset,
+];
+
+pub enum OptionsAction {
+    // Applied and saved right away, like the toggles of the pause menu
+    Changed(Settings),
+    // The language is kept with the progress, like when it's picked in the
+    // pause menu
+    Language { forward: bool },
+    Close,
+}
+
+// Speeds, HUD text size, font, language, frame pacing, menu power saving, tick rate, idle
+// takeover, resolution scaling, pixel art, ball telemetry, control layouts and key bindings,
+// opened from the pause menu.
+pub struct OptionsScreen {
+    settings: Settings,
+    selected: usize,
+    // The next key pressed is bound to the selected item
+    rebinding: bool,
+}
+
+impl OptionsScreen {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            settings: settings.clone(),
+            selected: 0,
+            rebinding: false,
+        }
+    }
+
+    pub fn handle_key(&mut self, key_code: KeyCode) -> Option<OptionsAction> {
+        if self.rebinding {
+            if key_code == KeyCode::Escape {
+                self.rebinding = false;
+            } else if let Item::Key(binding) = ITEMS[self.selected]
+                && self.settings.keys.set(binding, key_code)
+            {
+                self.rebinding = false;
+                return Some(OptionsAction::Changed(self.settings.clone()));
+            }
+            return None;
+        }
+
+        match key_code {
+            KeyCode::ArrowUp | KeyCode::KeyW => {
+                self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len();
+            }
+            KeyCode::ArrowDown | KeyCode::KeyS => {
+                self.selected = (self.selected + 1) % ITEMS.len();
+            }
+            KeyCode::ArrowLeft | KeyCode::ArrowRight => {
+                let sign = if key_code == KeyCode::ArrowLeft {
+                    -1.0
+                } else {
+                    1.0
+                };
+                let step = |value: &mut f32, step: f32, (min, max): (f32, f32)| {
+                    *value = (*value + sign * step).clamp(min, max);
+                };
+                let settings = &mut self.settings;
+                match ITEMS[self.selected] {
+                    Item::PaddleSpeed => {
+                        step(&mut settings.paddle_speed, SPEED_STEP, PADDLE_SPEED_RANGE)
+                    }
+                    Item::BallSpeed => step(&mut settings.ball_speed, SPEED_STEP, BALL_SPEED_RANGE),
+                    Item::FontSize => {
+                        step(&mut settings.font_size, FONT_SIZE_STEP, FONT_SIZE_RANGE)
+                    }
+                    Item::Font => settings.font = settings.font.next(),
+                    Item::Language => {
+                        return Some(OptionsAction::Language {
+                            forward: sign > 0.0,
+                        });
+                    }
+                    Item::Pacing => settings.pacing = settings.pacing.next(),
+                    Item::MenuPowerSaving => {
+                        settings.menu_power_saving = !settings.menu_power_saving
+                    }
+                    Item::FitField => settings.fit_field = !settings.fit_field,
+                    Item::TickRate => {
+                        let index = TICK_RATES
+                            .iter()
+                            .position(|rate| *rate == settings.tick_rate)
+                            .unwrap_or(0);
+                        let count = TICK_RATES.len();
+                        let index = if sign < 0.0 {
+                            (index + count - 1) % count
+                        } else {
+                            (index + 1) % count
+                        };
+                        settings.tick_rate = TICK_RATES[index];
+                    }
+                    Item::IdleTakeover => step_idle_takeover(settings, sign),
+                    Item::DynamicResolution => {
+                        settings.dynamic_resolution = !settings.dynamic_resolution
+                    }
+                    Item::Upscale => settings.upscale = settings.upscale.next(),
+                    Item::PixelArt => settings.pixel_art = !settings.pixel_art,
+                    Item::Scanlines => settings.scanlines = !settings.scanlines,
+                    Item::Telemetry => settings.telemetry = !settings.telemetry,
+                    Item::Layout(side) => step_layout(settings, side, sign),
+                    Item::Key(_) | Item::Reset => return None,
+                }
+                return Some(OptionsAction::Changed(self.settings.clone()));
+            }
+            KeyCode::Enter | KeyCode::Space => match ITEMS[self.selected] {
+                Item::Key(_) => self.rebinding = true,
+                Item::Font => {
+                    self.settings.font = self.settings.font.next();
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::Language => return Some(OptionsAction::Language { forward: true }),
+                Item::Pacing => {
+                    self.settings.pacing = self.settings.pacing.next();
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::MenuPowerSaving => {
+                    self.settings.menu_power_saving = !self.settings.menu_power_saving;
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::FitField => {
+                    self.settings.fit_field = !self.settings.fit_field;
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::IdleTakeover => {
+                    self.settings.idle_takeover = !self.settings.idle_takeover;
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::DynamicResolution => {
+                    self.settings.dynamic_resolution = !self.settings.dynamic_resolution;
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::Upscale => {
+                    self.settings.upscale = self.settings.upscale.next();
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::PixelArt => {
+                    self.settings.pixel_art = !self.settings.pixel_art;
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::Scanlines => {
+                    self.settings.scanlines = !self.settings.scanlines;
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::Telemetry => {
+                    self.settings.telemetry = !self.settings.telemetry;
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::Layout(side) => {
+                    step_layout(&mut self.settings, side, 1.0);
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::Reset => {
+                    self.settings.reset_options();
+                    return Some(OptionsAction::Changed(self.settings.clone()));
+                }
+                Item::PaddleSpeed | Item::BallSpeed | Item::FontSize | Item::TickRate => (),
+            },
+            KeyCode::Escape => return Some(OptionsAction::Close),
+            _ => (),
+        }
+        None
+    }
+
+    fn label(&self, item: Item, locale: &Locale) -> String {
+        let settings = &self.settings;
+        match item {
+            Item::PaddleSpeed => locale.format(
+                "options.paddle_speed",
+                &[("speed", &locale.number(settings.paddle_speed.round()))],
+            ),
+            Item::BallSpeed => locale.format(
+                "options.ball_speed",
+                &[("speed", &locale.number(settings.ball_speed.round()))],
+            ),
+            Item::FontSize => locale.format(
+                "options.font_size",
+                &[("size", &locale.number(settings.font_size.round()))],
+            ),
+            Item::Font => locale.format(
+                "options.font",
+                &[("font", &locale.text(settings.font.label_key()))],
+            ),
+            Item::Language => {
+                locale.format("options.language", &[("language", &locale.language().name)])
+            }
+            Item::Pacing => locale.format(
+                "options.pacing",
+                &[("pacing", &locale.text(settings.pacing.label_key()))],
+            ),
+            Item::MenuPowerSaving => locale
+                .text(if settings.menu_power_saving {
+                    "options.menu_power_saving_on"
+                } else {
+                    "options.menu_power_saving_off"
+                })
+                .to_string(),
+            Item::FitField => locale
+                .text(if settings.fit_field {
+                    "options.fit_field_on"
+                } else {
+                    "options.fit_field_off"
+                })
+                .to_string(),
+            Item::TickRate => locale.format(
+                "options.tick_rate",
+                &[("rate", &locale.number(settings.tick_rate))],
+            ),
+            Item::IdleTakeover if settings.idle_takeover => locale.format(
+                "options.idle_takeover_on",
+                &[("seconds", &locale.number(settings.idle_timeout.round()))],
+            ),
+            Item::IdleTakeover => locale.text("options.idle_takeover_off").to_string(),
+            Item::DynamicResolution => locale
+                .text(if settings.dynamic_resolution {
+                    "options.dynamic_resolution_on"
+                } else {
+                    "options.dynamic_resolution_off"
+                })
+                .to_string(),
+            Item::Upscale => locale.format(
+                "options.upscale",
+                &[("upscale", &locale.text(settings.upscale.label_key()))],
+            ),
+            Item::PixelArt => locale
+                .text(if settings.pixel_art {
+                    "options.pixel_art_on"
+                } else {
+                    "options.pixel_art_off"
+                })
+                .to_string(),
+            Item::Scanlines => locale
+                .text(if settings.scanlines {
+                    "options.scanlines_on"
+                } else {
+                    "options.scanlines_off"
+                })
+                .to_string(),
+            Item::Telemetry => locale
+                .text(if settings.telemetry {
+                    "options.telemetry_on"
+                } else {
+                    "options.telemetry_off"
+                })
+                .to_string(),
+            Item::Layout(side) => {
+                let layout = match settings.keys.layout(side) {
+                    Some(layout) => locale.text(layout.label_key()),
+                    None => locale.text("options.layout_custom"),
+                };
+                locale.format(
+                    match side {
+                        Side::Left => "options.left_layout",
+                        Side::Right => "options.right_layout",
+                    },
+                    &[("layout", &layout)],
+                )
+            }
+            Item::Key(binding) => {
+                let key = if self.rebinding && ITEMS[self.selected] == item {
+                    locale.text("options.press_key").to_string()
+                } else {
+                    locale
+                        .text(&key_label(settings.keys.get(binding)))
+                        .to_string()
+                };
+                format!("{}: {}", locale.text(binding.label_key()), key)
+            }
+            Item::Reset => locale.text("options.reset").to_string(),
+        }
+    }
+
+    // `status` is shown above the prompts, the display's refresh and
+    // present rate with a fix for a mismatch
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        locale: &Locale,
+        prompts: &Prompts,
+        status: &[String],
+    ) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.85),
+            Deg(0.),
+        );
+
+        let text = locale.text("options.title");
+        let text_width = renderer.measure_text(text, FONT_SIZE * 1.5, LINE_HEIGHT * 1.5);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2. - text_width / 2., LINE_HEIGHT),
+            FONT_SIZE * 1.5,
+            LINE_HEIGHT * 1.5,
+            None,
+        );
+
+        // Below the title on small windows, scrolled to keep the selected
+        // item between the title and the status
+        let top = (height / 2. - LINE_HEIGHT * ITEMS.len() as f32 / 2.).max(LINE_HEIGHT * 3.);
+        let bottom = height - LINE_HEIGHT * (2.2 + 0.7 * status.len() as f32);
+        let rows = (((bottom - top) / LINE_HEIGHT).floor() as usize).clamp(1, ITEMS.len());
+        let first = self
+            .selected
+            .saturating_sub(rows / 2)
+            .min(ITEMS.len() - rows);
+        for (row, (index, item)) in ITEMS.iter().enumerate().skip(first).take(rows).enumerate() {
+            let text = self.label(*item, locale);
+            let text_width = renderer.measure_text(&text, FONT_SIZE, LINE_HEIGHT);
+            let color = if index == self.selected {
+                glyphon::Color::rgb(255, 255, 255)
+            } else {
+                glyphon::Color::rgb(128, 128, 128)
+            };
+            renderer.draw_text(
+                &text,
+                Vector2::new(width / 2. - text_width / 2., top + row as f32 * LINE_HEIGHT),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(color),
+            );
+        }
+
+        let bottom = height - LINE_HEIGHT * 2.2;
+        for (index, line) in status.iter().rev().enumerate() {
+            let (font_size, line_height) = (FONT_SIZE * 0.7, LINE_HEIGHT * 0.7);
+            let text_width = renderer.measure_text(line, font_size, line_height);
+            let color = if index == 0 && status.len() > 1 {
+                glyphon::Color::rgb(255, 200, 80)
+            } else {
+                glyphon::Color::rgb(160, 160, 160)
+            };
+            renderer.draw_text(
+                line,
+                Vector2::new(
+                    width / 2. - text_width / 2.,
+                    bottom - index as f32 * line_height,
+                ),
+                font_size,
+                line_height,
+                Some(color),
+            );
+        }
+
+        prompts.draw_row(
+            renderer,
+            locale,
+            &[
+                (Action::Navigate, locale.text("main_menu.select")),
+                (Action::Adjust, locale.text("main_menu.change")),
+                (Action::Confirm, locale.text("options.rebind")),
+                (Action::Back, locale.text("options.back")),
+            ],
+            Vector2::new(width / 2., height - LINE_HEIGHT * 1.2),
+            LINE_HEIGHT * 0.5,
+        );
+    }
+}
+
+// Cycles through the presets, from keys bound one by one to the first
+fn step_layout(settings: &mut Settings, side: Side, sign: f32) {
+    let count = CONTROL_LAYOUTS.len();
+    let index = match settings.keys.layout(side) {
+        Some(layout) => {
+            let index = CONTROL_LAYOUTS
+                .iter()
+                .position(|other| *other == layout)
+                .unwrap_or(0);
+            if sign < 0.0 {
+                (index + count - 1) % count
+            } else {
+                (index + 1) % count
+            }
+        }
+        None => 0,
+    };
+    settings.keys.set_layout(side, CONTROL_LAYOUTS[index]);
+}
+
+// Off, then the timeouts from the shortest to the longest
+fn step_idle_takeover(settings: &mut Settings, sign: f32) {
+    let timeout = settings.idle_timeout;
+    if sign > 0.0 {
+        if !settings.idle_takeover {
+            settings.idle_takeover = true;
+            settings.idle_timeout = IDLE_TIMEOUTS[0];
+        } else if let Some(next) = IDLE_TIMEOUTS.iter().find(|next| **next > timeout) {
+            settings.idle_timeout = *next;
+        }
+    } else if settings.idle_takeover {
+        match IDLE_TIMEOUTS
+            .iter()
+            .rev()
+            .find(|previous| **previous < timeout)
+        {
+            Some(previous) => settings.idle_timeout = *previous,
+            None => settings.idle_takeover = false,
+        }
+    }
+}
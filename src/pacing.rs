@@ -0,0 +1,155 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use winit::window::Window;
+
+use crate::time::Instant;
+
+// Assumed when the monitor doesn't report its refresh rate
+static FALLBACK_REFRESH_RATE: f32 = 60.0;
+// Capping a few frames below the maximum refresh rate keeps adaptive sync
+// (G-Sync/FreeSync) engaged instead of hitting the vsync ceiling.
+static VRR_HEADROOM_FPS: f32 = 3.0;
+// Cap while only menus are on screen, with vsync, so an idle game doesn't
+// keep a laptop's GPU busy
+static MENU_FPS: f32 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FramePacing {
+    // Classic vsync, presentation is paced by the display
+    Vsync,
+    // Tear-free on VRR displays: present without vsync and cap just below max refresh
+    VrrFriendly,
+    // Present as fast as possible
+    Uncapped,
+}
+
+impl FramePacing {
+    pub fn next(self) -> Self {
+        match self {
+            FramePacing::Vsync => FramePacing::VrrFriendly,
+            FramePacing::VrrFriendly => FramePacing::Uncapped,
+            FramePacing::Uncapped => FramePacing::Vsync,
+        }
+    }
+
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            FramePacing::Vsync => "pacing.vsync",
+            FramePacing::VrrFriendly => "pacing.vrr_friendly",
+            FramePacing::Uncapped => "pacing.uncapped",
+        }
+    }
+
+    // Present modes to try, in order of preference
+    pub fn present_modes(self) -> &'static [wgpu::PresentMode] {
+        match self {
+            FramePacing::Vsync => &[wgpu::PresentMode::AutoVsync],
+            // Immediate lets the display follow our frame rate, mailbox is
+            // the closest tear-free alternative where immediate isn't available.
+            FramePacing::VrrFriendly => &[
+                wgpu::PresentMode::Immediate,
+                wgpu::PresentMode::Mailbox,
+                wgpu::PresentMode::AutoVsync,
+            ],
+            FramePacing::Uncapped => &[wgpu::PresentMode::AutoNoVsync],
+        }
+    }
+}
+
+// Decides when the next frame should be started.
+pub struct FramePacer {
+    pacing: FramePacing,
+    // The menu cap takes the place of the pacing until the match shows again
+    in_menu: bool,
+    interval: Option<Duration>,
+    next_frame: Instant,
+}
+
+impl FramePacer {
+    pub fn new() -> Self {
+        Self {
+            pacing: FramePacing::Vsync,
+            in_menu: false,
+            interval: None,
+            next_frame: Instant::now(),
+        }
+    }
+
+    pub fn pacing(&self) -> FramePacing {
+        self.pacing
+    }
+
+    pub fn in_menu(&self) -> bool {
+        self.in_menu
+    }
+
+    // Present modes to try for the pacing in effect
+    pub fn present_modes(&self) -> &'static [wgpu::PresentMode] {
+        if self.in_menu {
+            &[wgpu::PresentMode::AutoVsync]
+        } else {
+            self.pacing.present_modes()
+        }
+    }
+
+    // Fps cap, if frames are paced by us instead of the display
+    pub fn target_fps(&self) -> Option<f32> {
+        self.interval.map(|interval| 1.0 / interval.as_secs_f32())
+    }
+
+    // Seconds a frame may take to keep up with the cap, or the display
+    pub fn frame_budget(&self, window: &Window) -> f32 {
+        self.interval.map_or_else(
+            || 1.0 / monitor_refresh_rate(window).unwrap_or(FALLBACK_REFRESH_RATE),
+            |interval| interval.as_secs_f32(),
+        )
+    }
+
+    // Keeps the menu cap while it is on, `set_menu` lifts it
+    pub fn configure(&mut self, pacing: FramePacing, window: &Window) {
+        self.pacing = pacing;
+        self.interval = match pacing {
+            _ if self.in_menu => Some(Duration::from_secs_f32(1.0 / MENU_FPS)),
+            FramePacing::VrrFriendly => {
+                let refresh_rate = monitor_refresh_rate(window).unwrap_or_else(|| {
+                    info!(
+                        "Monitor refresh rate unknown, assuming {} Hz",
+                        FALLBACK_REFRESH_RATE
+                    );
+                    FALLBACK_REFRESH_RATE
+                });
+                let fps = (refresh_rate - VRR_HEADROOM_FPS).max(1.0);
+                Some(Duration::from_secs_f32(1.0 / fps))
+            }
+            FramePacing::Vsync | FramePacing::Uncapped => None,
+        };
+        self.next_frame = Instant::now();
+    }
+
+    // Takes effect with the next `configure`
+    pub fn set_menu(&mut self, in_menu: bool) {
+        self.in_menu = in_menu;
+    }
+
+    // When the next frame is due, or None if a new frame may start right away.
+    pub fn next_frame(&self) -> Option<Instant> {
+        self.interval.map(|_| self.next_frame)
+    }
+
+    pub fn frame_started(&mut self, now: Instant) {
+        if let Some(interval) = self.interval {
+            // Don't try to catch up on frames we missed, just keep the cadence
+            self.next_frame = (self.next_frame + interval).max(now);
+        }
+    }
+}
+
+pub fn monitor_refresh_rate(window: &Window) -> Option<f32> {
+    window
+        .current_monitor()
+        .and_then(|monitor| monitor.refresh_rate_millihertz())
+        .map(|millihertz| millihertz as f32 / 1000.0)
+}
@@ -0,0 +1,193 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::keyboard::KeyCode;
+
+use crate::colorpicker::{ColorPicker, contrast_ratio};
+use crate::locale::Locale;
+use crate::renderer::Renderer;
+use crate::sim::Side;
+
+static FONT_SIZE: f32 = 28.;
+static LINE_HEIGHT: f32 = 32.;
+static PREVIEW_WIDTH: f32 = 360.;
+static PREVIEW_HEIGHT: f32 = 160.;
+// WCAG minimum for graphical objects, lower and a paddle blends into the field
+pub static MIN_CONTRAST: f32 = 3.0;
+
+pub enum PaddleColorAction {
+    // The picked color per side, None to use the arena's color
+    Save([Option<Srgba>; 2]),
+    Cancel,
+}
+
+struct Player {
+    picker: ColorPicker,
+    // False while the arena's own color is used
+    custom: bool,
+    arena_color: Srgba,
+}
+
+impl Player {
+    fn color(&self) -> Srgba {
+        if self.custom {
+            self.picker.color()
+        } else {
+            self.arena_color
+        }
+    }
+}
+
+// Screen for picking each player's paddle color, previewed on the current
+// arena's background.
+pub struct PaddleColorScreen {
+    players: [Player; 2],
+    selected: Side,
+    background: Srgba,
+}
+
+impl PaddleColorScreen {
+    // `colors` are the current paddle colors, None where the arena's is used
+    pub fn new(colors: [Option<Srgba>; 2], arena_colors: [Srgba; 2], background: Srgba) -> Self {
+        let player = |index: usize| Player {
+            picker: ColorPicker::new(colors[index].unwrap_or(arena_colors[index])),
+            custom: colors[index].is_some(),
+            arena_color: arena_colors[index],
+        };
+        Self {
+            players: [player(0), player(1)],
+            selected: Side::Left,
+            background,
+        }
+    }
+
+    fn player(&mut self) -> &mut Player {
+        match self.selected {
+            Side::Left => &mut self.players[0],
+            Side::Right => &mut self.players[1],
+        }
+    }
+
+    fn readable(&self, player: &Player) -> bool {
+        contrast_ratio(player.color(), self.background) >= MIN_CONTRAST
+    }
+
+    pub fn handle_key(&mut self, key_code: KeyCode) -> Option<PaddleColorAction> {
+        match key_code {
+            KeyCode::Tab => self.selected = self.selected.opponent(),
+            KeyCode::KeyR => self.player().custom = false,
+            KeyCode::Enter => {
+                // Colors that would be hard to see can't be saved
+                if self.players.iter().all(|player| self.readable(player)) {
+                    let picked = |player: &Player| player.custom.then(|| player.picker.color());
+                    return Some(PaddleColorAction::Save([
+                        picked(&self.players[0]),
+                        picked(&self.players[1]),
+                    ]));
+                }
+            }
+            KeyCode::Escape => return Some(PaddleColorAction::Cancel),
+            _ => {
+                let player = self.player();
+                if player.picker.handle_key(key_code) {
+                    player.custom = true;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, locale: &Locale) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        let left = width / 2. - PREVIEW_WIDTH / 2.;
+
+        renderer.draw_rectangle(
+            Vector2::new(0., 0.),
+            width,
+            height,
+            Srgba::new(0., 0., 0., 0.85),
+            Deg(0.),
+        );
+
+        renderer.draw_text(
+            locale.text("paddle_colors.title"),
+            Vector2::new(left, LINE_HEIGHT),
+            FONT_SIZE * 1.3,
+            LINE_HEIGHT * 1.3,
+            None,
+        );
+
+        // Both paddles and a ball on the arena's background
+        let top = LINE_HEIGHT * 3.;
+        renderer.draw_rectangle(
+            Vector2::new(left, top),
+            PREVIEW_WIDTH,
+            PREVIEW_HEIGHT,
+            self.background,
+            Deg(0.),
+        );
+        let paddle_height = PREVIEW_HEIGHT * 0.5;
+        for (index, player) in self.players.iter().enumerate() {
+            let x = if index == 0 {
+                left + 24.
+            } else {
+                left + PREVIEW_WIDTH - 24. - 12.
+            };
+            renderer.draw_rectangle(
+                Vector2::new(x, top + PREVIEW_HEIGHT / 2. - paddle_height / 2.),
+                12.,
+                paddle_height,
+                player.color(),
+                Deg(0.),
+            );
+        }
+        renderer.draw_circle(
+            Vector2::new(left + PREVIEW_WIDTH / 2., top + PREVIEW_HEIGHT / 2.),
+            8.,
+            Srgba::new(1.0, 1.0, 1.0, 1.0),
+        );
+
+        let (index, name) = match self.selected {
+            Side::Left => (0, "P1"),
+            Side::Right => (1, "P2"),
+        };
+        let player = &self.players[index];
+        let y = top + PREVIEW_HEIGHT + LINE_HEIGHT * 0.5;
+        let label = if player.custom {
+            name.to_string()
+        } else {
+            format!("{} ({})", name, locale.text("paddle_colors.arena_color"))
+        };
+        renderer.draw_text(&label, Vector2::new(left, y), FONT_SIZE, LINE_HEIGHT, None);
+        let used = player
+            .picker
+            .draw(renderer, Vector2::new(left, y + LINE_HEIGHT * 1.5), locale);
+
+        let ratio = format!("{:.1}", contrast_ratio(player.color(), self.background));
+        let (key, color) = if self.readable(player) {
+            ("paddle_colors.contrast", glyphon::Color::rgb(160, 220, 160))
+        } else {
+            (
+                "paddle_colors.contrast_low",
+                glyphon::Color::rgb(255, 120, 80),
+            )
+        };
+        renderer.draw_text(
+            &locale.format(key, &[("ratio", &ratio)]),
+            Vector2::new(left, y + LINE_HEIGHT * 1.5 + used),
+            FONT_SIZE * 0.8,
+            LINE_HEIGHT * 0.8,
+            Some(color),
+        );
+
+        let text = locale.text("paddle_colors.controls");
+        let text_width = renderer.measure_text(text, FONT_SIZE * 0.6, LINE_HEIGHT * 0.6);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2. - text_width / 2., height - LINE_HEIGHT * 1.5),
+            FONT_SIZE * 0.6,
+            LINE_HEIGHT * 0.6,
+            None,
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use rand::Rng;
+
+use crate::renderer::{Rect, Renderer};
+use crate::sim::{GameSim, Paddle, Side};
+use crate::tween::{Easing, Tween};
+
+// Share of the paddle speed from which paddles leave streaks
+static MIN_SPEED: f32 = 0.5;
+// Seconds between two afterimages of a paddle, and how long they last
+static GHOST_INTERVAL: f32 = 1.0 / 60.0;
+static GHOST_LIFE: f32 = 0.12;
+static GHOST_ALPHA: f32 = 0.35;
+// Speed lines behind the paddle per second at full speed
+static LINE_RATE: f32 = 90.0;
+static LINE_LIFE: f32 = 0.15;
+static LINE_WIDTH: f32 = 2.0;
+// Seconds of travel a speed line is long
+static LINE_LENGTH: f32 = 0.05;
+
+struct Streak {
+    rect: Rect,
+    side: Side,
+    // Opacity, fades out to 0
+    alpha: Tween,
+}
+
+// Afterimages and speed lines behind fast paddles, so quick moves stay easy
+// to follow. Purely visual like the particles.
+#[derive(Default)]
+pub struct PaddleTrail {
+    ghosts: Vec<Streak>,
+    lines: Vec<Streak>,
+    // Seconds since the last afterimage of each side
+    since_ghost: [f32; 2],
+    owed_lines: [f32; 2],
+}
+
+impl PaddleTrail {
+    pub fn update(&mut self, delta: f32, sim: &GameSim) {
+        for streak in self.ghosts.iter_mut().chain(self.lines.iter_mut()) {
+            streak.alpha.update(delta);
+        }
+        self.ghosts.retain(|streak| !streak.alpha.is_finished());
+        self.lines.retain(|streak| !streak.alpha.is_finished());
+
+        let mut rng = rand::rng();
+        for (index, side) in [Side::Left, Side::Right].into_iter().enumerate() {
+            let paddle = sim.paddle(side);
+            let speed = paddle.velocity.abs() / sim.paddle_speed;
+            if speed < MIN_SPEED {
+                self.since_ghost[index] = GHOST_INTERVAL;
+                self.owed_lines[index] = 0.0;
+                continue;
+            }
+            // Faster paddles leave brighter streaks
+            let strength = ((speed - MIN_SPEED) / (1.0 - MIN_SPEED)).clamp(0.2, 1.0);
+            let rect = paddle_rect(paddle, side);
+
+            self.since_ghost[index] += delta;
+            if self.since_ghost[index] >= GHOST_INTERVAL {
+                self.since_ghost[index] = 0.0;
+                self.ghosts.push(Streak {
+                    rect,
+                    side,
+                    alpha: fade(GHOST_ALPHA * strength, GHOST_LIFE),
+                });
+            }
+
+            self.owed_lines[index] += LINE_RATE * strength * delta;
+            while self.owed_lines[index] >= 1.0 {
+                self.owed_lines[index] -= 1.0;
+                let length = paddle.velocity.abs() * LINE_LENGTH;
+                let x = rect.x + rng.random_range(0.0..=(rect.width - LINE_WIDTH).max(0.0));
+                // Behind the paddle, the edge it moves away from
+                let y = if paddle.velocity > 0.0 {
+                    rect.y - length
+                } else {
+                    rect.y + rect.height
+                };
+                self.lines.push(Streak {
+                    rect: Rect::new(x, y, LINE_WIDTH, length),
+                    side,
+                    alpha: fade(0.6 * strength, LINE_LIFE),
+                });
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ghosts.clear();
+        self.lines.clear();
+    }
+
+    // `colors` of the left and right paddle
+    pub fn draw(&self, renderer: &mut Renderer, colors: [Srgba; 2]) {
+        for streak in self.ghosts.iter().chain(self.lines.iter()) {
+            let color = match streak.side {
+                Side::Left => colors[0],
+                Side::Right => colors[1],
+            };
+            renderer.draw_rectangle(
+                Vector2::new(streak.rect.x, streak.rect.y),
+                streak.rect.width,
+                streak.rect.height,
+                Srgba::new(
+                    color.red,
+                    color.green,
+                    color.blue,
+                    color.alpha * streak.alpha.value(),
+                ),
+                Deg(0.),
+            );
+        }
+    }
+}
+
+fn fade(alpha: f32, life: f32) -> Tween {
+    Tween::new(alpha, 0.0, life, Easing::EaseOutCubic)
+}
+
+// Where the paddle is drawn. The left paddle's position is its left edge,
+// the right one's its right edge.
+fn paddle_rect(paddle: &Paddle, side: Side) -> Rect {
+    let x = match side {
+        Side::Left => paddle.pos.x,
+        Side::Right => paddle.pos.x - paddle.width,
+    };
+    Rect::new(
+        x,
+        paddle.pos.y - paddle.height / 2.,
+        paddle.width,
+        paddle.height,
+    )
+}
@@ -0,0 +1,219 @@
+use cgmath::{InnerSpace, Vector2};
+use palette::{Mix, Srgba};
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::renderer::{Quad, Renderer};
+
+// Below this many live particles updating them on one thread is faster than
+// handing the work out
+static PARALLEL_PARTICLES: usize = 512;
+// Particles per parallel job
+static PARTICLE_CHUNK: usize = 256;
+// The pool is allocated once at this size, further particles aren't spawned.
+// Keeps a burst-heavy rally from growing without bound.
+static MAX_PARTICLES: usize = 4096;
+// Keeps a long frame from spawning a whole second of particles at once
+static MAX_SPAWN_DELTA: f32 = 0.1;
+
+// How an emitter spawns its particles and how they move.
+#[derive(Debug, Clone, Copy)]
+pub struct EmitterParams {
+    // Particles spawned right away
+    pub burst: usize,
+    // Particles per second after that, for `duration` seconds
+    pub rate: f32,
+    pub duration: f32,
+    // Ranges the particles pick from at random
+    pub lifetime: (f32, f32),
+    pub speed: (f32, f32),
+    pub size: (f32, f32),
+    // Sprayed around `direction` by up to `spread` radians. A zero direction
+    // sprays in all directions.
+    pub direction: Vector2<f32>,
+    pub spread: f32,
+    // Color at the start and the end of each particle's life, blended in
+    // between. The particles shrink over their life too.
+    pub color: (Srgba, Srgba),
+    // Pixels per second squared, downward
+    pub gravity: f32,
+    // Share of the velocity lost per second
+    pub drag: f32,
+}
+
+impl Default for EmitterParams {
+    fn default() -> Self {
+        let white = Srgba::new(1.0, 1.0, 1.0, 1.0);
+        Self {
+            burst: 0,
+            rate: 0.0,
+            duration: 0.0,
+            lifetime: (0.3, 0.7),
+            speed: (100.0, 300.0),
+            size: (3.0, 7.0),
+            direction: Vector2::new(0.0, 0.0),
+            spread: 0.0,
+            color: (white, Srgba::new(1.0, 1.0, 1.0, 0.0)),
+            gravity: 0.0,
+            drag: 2.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    pos: Vector2<f32>,
+    velocity: Vector2<f32>,
+    // Seconds left of `life`
+    remaining: f32,
+    life: f32,
+    size: f32,
+    color: (Srgba, Srgba),
+    gravity: f32,
+    drag: f32,
+}
+
+impl Particle {
+    fn update(&mut self, delta: f32) {
+        self.velocity *= (1.0 - self.drag * delta).max(0.0);
+        self.velocity.y += self.gravity * delta;
+        self.pos += self.velocity * delta;
+        self.remaining -= delta;
+    }
+
+    fn quad(&self) -> Quad {
+        // 1 when spawned, 0 when gone
+        let t = (self.remaining / self.life).clamp(0.0, 1.0);
+        Quad {
+            center: self.pos,
+            size: self.size * (0.4 + 0.6 * t),
+            color: self.color.0.mix(self.color.1, 1.0 - t),
+        }
+    }
+}
+
+// Keeps spawning at its rate until its time is up.
+struct Emitter {
+    params: EmitterParams,
+    pos: Vector2<f32>,
+    remaining: f32,
+    // Particles owed from earlier frames, spawned once they add up to one
+    owed: f32,
+}
+
+// Sparks from hits, bounces and goals. Purely visual, the sim doesn't know
+// about them.
+//
+// All particles live in one pool that is allocated up front and reused, dead
+// ones are swapped out in place. The squares to draw are built while
+// updating, into a buffer that is reused as well.
+pub struct Particles {
+    pool: Vec<Particle>,
+    emitters: Vec<Emitter>,
+    quads: Vec<Quad>,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self {
+            pool: Vec::with_capacity(MAX_PARTICLES),
+            emitters: Vec::new(),
+            quads: Vec::with_capacity(MAX_PARTICLES),
+        }
+    }
+}
+
+impl Particles {
+    // Spawns the burst of `params` at `pos`, and keeps an emitter there
+    // while it has a rate and duration.
+    pub fn emit(&mut self, pos: Vector2<f32>, params: EmitterParams) {
+        self.spawn(pos, &params, params.burst);
+        if params.rate > 0.0 && params.duration > 0.0 {
+            self.emitters.push(Emitter {
+                params,
+                pos,
+                remaining: params.duration,
+                owed: 0.0,
+            });
+        }
+    }
+
+    fn spawn(&mut self, pos: Vector2<f32>, params: &EmitterParams, count: usize) {
+        let count = count.min(MAX_PARTICLES - self.pool.len());
+        if count == 0 {
+            return;
+        }
+        let mut rng = rand::rng();
+        let (base, spread) = if params.direction.magnitude2() > 0.0 {
+            (params.direction.y.atan2(params.direction.x), params.spread)
+        } else {
+            (0.0, std::f32::consts::PI)
+        };
+        let range = |rng: &mut rand::rngs::ThreadRng, (min, max): (f32, f32)| {
+            if max > min {
+                rng.random_range(min..=max)
+            } else {
+                min
+            }
+        };
+        for _ in 0..count {
+            let angle = base + range(&mut rng, (-spread, spread));
+            let speed = range(&mut rng, params.speed);
+            let life = range(&mut rng, params.lifetime).max(f32::EPSILON);
+            self.pool.push(Particle {
+                pos,
+                velocity: Vector2::new(angle.cos(), angle.sin()) * speed,
+                remaining: life,
+                life,
+                size: range(&mut rng, params.size),
+                color: params.color,
+                gravity: params.gravity,
+                drag: params.drag,
+            });
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        let mut emitters = std::mem::take(&mut self.emitters);
+        for emitter in emitters.iter_mut() {
+            let active = delta.min(emitter.remaining).min(MAX_SPAWN_DELTA);
+            emitter.remaining -= delta;
+            emitter.owed += emitter.params.rate * active;
+            let count = emitter.owed as usize;
+            emitter.owed -= count as f32;
+            self.spawn(emitter.pos, &emitter.params, count);
+        }
+        emitters.retain(|emitter| emitter.remaining > 0.0);
+        self.emitters = emitters;
+
+        if self.pool.len() >= PARALLEL_PARTICLES {
+            self.pool
+                .par_chunks_mut(PARTICLE_CHUNK)
+                .for_each(|chunk| chunk.iter_mut().for_each(|particle| particle.update(delta)));
+        } else {
+            self.pool
+                .iter_mut()
+                .for_each(|particle| particle.update(delta));
+        }
+        let mut index = 0;
+        while index < self.pool.len() {
+            if self.pool[index].remaining > 0.0 {
+                index += 1;
+            } else {
+                self.pool.swap_remove(index);
+            }
+        }
+
+        self.quads.clear();
+        if self.pool.len() >= PARALLEL_PARTICLES {
+            self.quads
+                .par_extend(self.pool.par_iter().map(Particle::quad));
+        } else {
+            self.quads.extend(self.pool.iter().map(Particle::quad));
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer) {
+        renderer.draw_quads(&self.quads);
+    }
+}
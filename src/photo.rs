@@ -0,0 +1,128 @@
+use cgmath::Vector2;
+use std::collections::HashSet;
+use winit::keyboard::KeyCode;
+
+use crate::locale::Locale;
+use crate::renderer::{Camera2D, PostFilter, Renderer};
+
+static FONT_SIZE: f32 = 20.;
+static LINE_HEIGHT: f32 = 24.;
+// Pixels per second at zoom 1
+static PAN_SPEED: f32 = 600.0;
+// Zoom factor per second while a zoom key is held
+static ZOOM_SPEED: f32 = 2.0;
+static ZOOM_RANGE: (f32, f32) = (0.5, 6.0);
+// Photos are rendered at this multiple of the window size
+pub static PHOTO_SCALE: u32 = 4;
+
+pub enum PhotoAction {
+    Capture,
+    Exit,
+}
+
+// Freezes the match and lets the player frame a shot with a free camera.
+pub struct PhotoMode {
+    camera: Camera2D,
+    home: Camera2D,
+    filter: PostFilter,
+    hud_visible: bool,
+}
+
+impl PhotoMode {
+    // Starts out at `home`, the view of the match when it was frozen
+    pub fn new(home: Camera2D) -> Self {
+        Self {
+            camera: home,
+            home,
+            filter: PostFilter::None,
+            hud_visible: true,
+        }
+    }
+
+    pub fn camera(&self) -> Camera2D {
+        self.camera
+    }
+
+    pub fn filter(&self) -> PostFilter {
+        self.filter
+    }
+
+    pub fn hud_visible(&self) -> bool {
+        self.hud_visible
+    }
+
+    pub fn handle_key(&mut self, key_code: KeyCode) -> Option<PhotoAction> {
+        match key_code {
+            KeyCode::KeyF => self.filter = self.filter.next(),
+            KeyCode::KeyH => self.hud_visible = !self.hud_visible,
+            KeyCode::KeyR => self.camera = self.home,
+            KeyCode::Enter => return Some(PhotoAction::Capture),
+            KeyCode::Escape | KeyCode::F8 => return Some(PhotoAction::Exit),
+            _ => (),
+        }
+        None
+    }
+
+    // Pans and zooms while the keys are held.
+    pub fn update(&mut self, delta: f32, keys_pressed: &HashSet<KeyCode>) {
+        free_camera_input(&mut self.camera, delta, keys_pressed);
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, locale: &Locale) {
+        let text = locale.format(
+            "photo.hud",
+            &[
+                ("zoom", &format!("{:.1}", self.camera.zoom)),
+                ("filter", &locale.text(self.filter.label_key())),
+                ("scale", &PHOTO_SCALE),
+            ],
+        );
+        let area = renderer.safe_area();
+        renderer.draw_text(
+            &text,
+            Vector2::new(area.x, area.y + area.height - LINE_HEIGHT * 3.5),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            Some(glyphon::Color::rgb(255, 255, 0)),
+        );
+    }
+}
+
+// Arrows/WASD pan and Q/E zoom `camera`. Returns whether any of those keys are held.
+pub fn free_camera_input(
+    camera: &mut Camera2D,
+    delta: f32,
+    keys_pressed: &HashSet<KeyCode>,
+) -> bool {
+    let held = |keys: &[KeyCode]| keys.iter().any(|key| keys_pressed.contains(key));
+
+    let mut direction = Vector2::new(0.0, 0.0);
+    if held(&[KeyCode::ArrowLeft, KeyCode::KeyA]) {
+        direction.x -= 1.0;
+    }
+    if held(&[KeyCode::ArrowRight, KeyCode::KeyD]) {
+        direction.x += 1.0;
+    }
+    if held(&[KeyCode::ArrowUp, KeyCode::KeyW]) {
+        direction.y -= 1.0;
+    }
+    if held(&[KeyCode::ArrowDown, KeyCode::KeyS]) {
+        direction.y += 1.0;
+    }
+    // Pan at the same on-screen speed no matter the zoom
+    camera.center += direction * PAN_SPEED * delta / camera.zoom;
+
+    let zoom_in = held(&[KeyCode::KeyE, KeyCode::Equal]);
+    let zoom_out = held(&[KeyCode::KeyQ, KeyCode::Minus]);
+    if zoom_in {
+        camera.zoom *= ZOOM_SPEED.powf(delta);
+    }
+    if zoom_out {
+        camera.zoom /= ZOOM_SPEED.powf(delta);
+    }
+    if zoom_in || zoom_out {
+        camera.zoom = camera.zoom.clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+    }
+
+    direction != Vector2::new(0.0, 0.0) || zoom_in || zoom_out
+}
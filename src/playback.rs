@@ -0,0 +1,69 @@
+use crate::replay::{Replay, ReplayTick};
+
+// Feeds the ticks of a recorded replay back into the sim at their original pace.
+pub struct Playback {
+    replay: Replay,
+    cursor: usize,
+    // Real time that hasn't been simulated yet
+    pending: f32,
+    elapsed: f32,
+    paused: bool,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            cursor: 0,
+            pending: 0.0,
+            elapsed: 0.0,
+            paused: false,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.replay.body.ticks.len()
+    }
+
+    // Seconds of the replay played so far
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    // The recorded ticks that fit into `delta` seconds of real time.
+    pub fn advance(&mut self, delta: f32) -> Vec<ReplayTick> {
+        let mut due = Vec::new();
+        if self.paused {
+            return due;
+        }
+
+        self.pending += delta;
+        while let Some(tick) = self.replay.body.ticks.get(self.cursor) {
+            if tick.dt > self.pending {
+                break;
+            }
+            self.pending -= tick.dt;
+            self.elapsed += tick.dt;
+            self.cursor += 1;
+            due.push(*tick);
+        }
+
+        // Nothing left to catch up on once the replay is over
+        if self.is_finished() {
+            self.pending = 0.0;
+        }
+        due
+    }
+}
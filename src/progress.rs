@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use log::error;
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::ai::Difficulty;
+use crate::arena::{parse_hex, to_hex};
+use crate::drills::DrillRecord;
+use crate::score::Score;
+use crate::sim::{GAME_POINTS, Side};
+use crate::skins::BallSkin;
+
+static SAVE_DIR: &str = "save";
+static PROGRESS_FILE: &str = "progress.json";
+
+// Everything the game remembers about the player between sessions.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    pub tutorial_completed: bool,
+    pub stats: Stats,
+    // Achievement id -> unix time it was unlocked
+    pub achievements: BTreeMap<String, u64>,
+    // Best daily challenge result per date, "YYYY-MM-DD"
+    pub daily: BTreeMap<String, DailyResult>,
+    // Drill id -> passes, fails and best medal
+    pub drills: BTreeMap<String, DrillRecord>,
+    pub ball_skin: BallSkin,
+    // Name of the selected arena
+    pub arena: Option<String>,
+    // Code of the selected language, e.g. "de"
+    pub language: Option<String>,
+    // "#rrggbb", the arena's colors are used when unset
+    pub left_paddle_color: Option<String>,
+    pub right_paddle_color: Option<String>,
+    // Empty until the player picks one
+    pub player_name: String,
+    // Points a match is played to, GAME_POINTS when unset
+    pub win_score: Option<u8>,
+    pub ai_difficulty: Difficulty,
+    // Paddle the AI plays, the right one when unset
+    pub ai_side: Option<Side>,
+    // In percent, full volume when unset
+    pub master_volume: Option<u8>,
+    pub sfx_volume: Option<u8>,
+    // Off in saves from before `shake_intensity`, which takes its place
+    pub screen_shake: Option<bool>,
+    // Of the shake and zoom on goals and hard hits, high when unset
+    pub shake_intensity: Option<ShakeIntensity>,
+    // Keeps the camera still whatever the shake intensity, off when unset
+    pub reduced_motion: Option<bool>,
+    // Streaks behind fast paddles, on when unset
+    pub paddle_trails: Option<bool>,
+}
+
+// How much goals and hard hits shake the camera. Low is there for players
+// who get motion sick from the full shake but still want the feedback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShakeIntensity {
+    Off,
+    Low,
+    High,
+}
+
+impl ShakeIntensity {
+    pub fn next(self) -> Self {
+        match self {
+            ShakeIntensity::Off => ShakeIntensity::Low,
+            ShakeIntensity::Low => ShakeIntensity::High,
+            ShakeIntensity::High => ShakeIntensity::Off,
+        }
+    }
+
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            ShakeIntensity::Off => "shake.off",
+            ShakeIntensity::Low => "shake.low",
+            ShakeIntensity::High => "shake.high",
+        }
+    }
+
+    // Share of the full trauma and zoom, the shake itself grows with the
+    // square of the trauma so low moves the view a quarter as far
+    fn scale(self) -> f32 {
+        match self {
+            ShakeIntensity::Off => 0.0,
+            ShakeIntensity::Low => 0.5,
+            ShakeIntensity::High => 1.0,
+        }
+    }
+}
+
+// Lifetime totals for player one.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub points_won: u32,
+    pub points_lost: u32,
+    pub games_won: u32,
+    pub games_lost: u32,
+    pub paddle_hits: u32,
+    pub longest_rally: u32,
+}
+
+impl Progress {
+    // Invalid colors count as unset
+    pub fn paddle_colors(&self) -> [Option<Srgba>; 2] {
+        let parse = |color: &Option<String>| color.as_deref().and_then(|hex| parse_hex(hex).ok());
+        [
+            parse(&self.left_paddle_color),
+            parse(&self.right_paddle_color),
+        ]
+    }
+
+    pub fn set_paddle_colors(&mut self, colors: [Option<Srgba>; 2]) {
+        self.left_paddle_color = colors[0].map(to_hex);
+        self.right_paddle_color = colors[1].map(to_hex);
+    }
+
+    pub fn win_score(&self) -> u8 {
+        self.win_score.unwrap_or(GAME_POINTS).max(1)
+    }
+
+    pub fn ai_side(&self) -> Side {
+        self.ai_side.unwrap_or(Side::Right)
+    }
+
+    pub fn master_volume(&self) -> u8 {
+        self.master_volume.unwrap_or(100).min(100)
+    }
+
+    pub fn sfx_volume(&self) -> u8 {
+        self.sfx_volume.unwrap_or(100).min(100)
+    }
+
+    pub fn shake_intensity(&self) -> ShakeIntensity {
+        self.shake_intensity
+            .unwrap_or(if self.screen_shake == Some(false) {
+                ShakeIntensity::Off
+            } else {
+                ShakeIntensity::High
+            })
+    }
+
+    pub fn set_shake_intensity(&mut self, intensity: ShakeIntensity) {
+        self.shake_intensity = Some(intensity);
+        self.screen_shake = None;
+    }
+
+    pub fn reduced_motion(&self) -> bool {
+        self.reduced_motion.unwrap_or(false)
+    }
+
+    // Multiplies the shake and zoom of goals and hits, 0 with reduced motion
+    pub fn shake_scale(&self) -> f32 {
+        if self.reduced_motion() {
+            0.0
+        } else {
+            self.shake_intensity().scale()
+        }
+    }
+
+    pub fn paddle_trails(&self) -> bool {
+        self.paddle_trails.unwrap_or(true)
+    }
+
+    // Shown in the HUD and stored in replays
+    pub fn player_name(&self) -> &str {
+        if self.player_name.is_empty() {
+            "P1"
+        } else {
+            &self.player_name
+        }
+    }
+
+    // A missing or unreadable file starts fresh, like a first run
+    pub fn load() -> Self {
+        let path = Path::new(SAVE_DIR).join(PROGRESS_FILE);
+        let Ok(data) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        serde_json::from_str(&data).unwrap_or_else(|err| {
+            error!("Ignoring invalid {}: {}", path.display(), err);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all(SAVE_DIR)
+            .with_context(|| format!("cannot create save directory {}", SAVE_DIR))?;
+
+        let path = Path::new(SAVE_DIR).join(PROGRESS_FILE);
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(&path, data).with_context(|| format!("cannot write {}", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub points_for: Score,
+    pub points_against: Score,
+}
+
+impl DailyResult {
+    pub fn won(&self) -> bool {
+        self.points_for > self.points_against
+    }
+
+    // "won" or "lost" in the language files
+    pub fn label_key(&self) -> &'static str {
+        if self.won() {
+            "result.won"
+        } else {
+            "result.lost"
+        }
+    }
+
+    // Wins beat losses, then the larger point difference counts
+    pub fn is_better_than(&self, other: &DailyResult) -> bool {
+        let margin = |result: &DailyResult| {
+            (
+                result.won(),
+                result.points_for.lead_over(result.points_against),
+            )
+        };
+        margin(self) > margin(other)
+    }
+}
@@ -0,0 +1,298 @@
+use anyhow::Context;
+use cgmath::{Deg, Vector2};
+use log::error;
+use palette::Srgba;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::config::{KeyBindings, key_label};
+use crate::input::{GamepadStyle, InputDevice};
+use crate::locale::Locale;
+use crate::renderer::{Rect, Renderer, TextureHandle, TextureParams};
+
+static GLYPH_TABLE: &str = include_str!("../assets/prompts/glyphs.toml");
+static GLYPH_SHEET: &[u8] = include_bytes!("../assets/prompts/glyphs.png");
+// Label size as a share of the glyph's height
+static LABEL_SIZE: f32 = 0.55;
+// Space between the items of a row, as a share of the glyph's height
+static ROW_GAP: f32 = 0.8;
+
+// Something the player is asked to do, shown as the key or button that does
+// it on the device they last used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    Serve,
+    // Up and down through a menu
+    Navigate,
+    // Left and right on a menu item
+    Adjust,
+    Confirm,
+    Back,
+    Skip,
+}
+
+impl Action {
+    fn key(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::Serve => "serve",
+            Action::Navigate => "navigate",
+            Action::Adjust => "adjust",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+            Action::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Glyph {
+    cell: [u32; 2],
+    #[serde(default = "one")]
+    width: u32,
+    label: Option<String>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GlyphTable {
+    cell: u32,
+    #[serde(flatten)]
+    sets: HashMap<String, HashMap<String, Glyph>>,
+}
+
+// Key and button glyphs for the tutorial and the menus, from the sprite
+// sheet in assets/prompts. They follow whatever the player last touched.
+pub struct Prompts {
+    table: GlyphTable,
+    // None until the renderer is up, and when the sheet failed to load. The
+    // glyphs are drawn as plain keycaps then.
+    texture: Option<TextureHandle>,
+    loaded: bool,
+    device: InputDevice,
+    // Keyboard labels of the actions the player can rebind
+    bound: Vec<(Action, String)>,
+}
+
+impl Prompts {
+    pub fn new() -> Self {
+        let table = toml::from_str(GLYPH_TABLE)
+            .context("invalid prompt glyph table")
+            .inspect_err(|err| error!("{:#}", err))
+            .unwrap_or(GlyphTable {
+                cell: 1,
+                sets: HashMap::new(),
+            });
+        Self {
+            table,
+            texture: None,
+            loaded: false,
+            device: InputDevice::default(),
+            bound: Vec::new(),
+        }
+    }
+
+    pub fn load_texture(&mut self, renderer: &mut Renderer) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+        self.texture = renderer
+            .load_texture_from_bytes(GLYPH_SHEET)
+            .context("cannot load the prompt glyphs")
+            .inspect_err(|err| error!("{:#}", err))
+            .ok();
+    }
+
+    pub fn set_device(&mut self, device: InputDevice) {
+        self.device = device;
+    }
+
+    pub fn set_bindings(&mut self, keys: &KeyBindings) {
+        self.bound = [
+            (Action::MoveUp, keys.left_up),
+            (Action::MoveDown, keys.left_down),
+            (Action::Serve, keys.serve),
+        ]
+        .into_iter()
+        .map(|(action, key)| (action, key_label(key)))
+        .collect();
+    }
+
+    // The glyph sets to look in, most specific first
+    fn sets(&self) -> &'static [&'static str] {
+        match self.device {
+            InputDevice::Keyboard if cfg!(target_os = "macos") => &["keyboard_macos", "keyboard"],
+            InputDevice::Keyboard => &["keyboard"],
+            InputDevice::Gamepad(GamepadStyle::Xbox) => &["xbox", "keyboard"],
+            InputDevice::Gamepad(GamepadStyle::PlayStation) => &["playstation", "keyboard"],
+            InputDevice::Gamepad(GamepadStyle::Nintendo) => &["nintendo", "keyboard"],
+        }
+    }
+
+    fn glyph(&self, action: Action) -> Option<&Glyph> {
+        self.sets()
+            .iter()
+            .find_map(|set| self.table.sets.get(*set)?.get(action.key()))
+    }
+
+    // The bound key's name on the keyboard, the glyph's own label otherwise
+    fn label<'a>(&'a self, action: Action, glyph: &'a Glyph) -> Option<&'a str> {
+        let bound = self
+            .bound
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .filter(|_| self.device == InputDevice::Keyboard);
+        match bound {
+            Some((_, label)) => Some(label),
+            None => glyph.label.as_deref(),
+        }
+    }
+
+    // Width of the glyph for `action` drawn `height` pixels tall
+    pub fn measure(
+        &self,
+        renderer: &mut Renderer,
+        locale: &Locale,
+        action: Action,
+        height: f32,
+    ) -> f32 {
+        let Some(glyph) = self.glyph(action) else {
+            return 0.0;
+        };
+        let label = self.label(action, glyph).map_or(0.0, |label| {
+            self.measure_label(renderer, locale, label, height)
+        });
+        (glyph.width as f32 * height).max(label + height * 0.5)
+    }
+
+    fn measure_label(
+        &self,
+        renderer: &mut Renderer,
+        locale: &Locale,
+        label: &str,
+        height: f32,
+    ) -> f32 {
+        let size = height * LABEL_SIZE;
+        renderer.measure_text(locale.text(label), size, size)
+    }
+
+    // Draws the glyph for `action` around `center`, growing and brightening
+    // with `pulse` from 0 to 1.
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        locale: &Locale,
+        action: Action,
+        center: Vector2<f32>,
+        height: f32,
+        pulse: f32,
+    ) {
+        let Some(glyph) = self.glyph(action) else {
+            return;
+        };
+        let scale = 1.0 + pulse * 0.1;
+        let width = self.measure(renderer, locale, action, height) * scale;
+        let height = height * scale;
+        let dest = Rect::new(center.x - width / 2., center.y - height / 2., width, height);
+        let shade = 0.7 + pulse * 0.3;
+
+        match self.texture {
+            Some(texture) => {
+                let cell = self.table.cell as f32;
+                renderer.draw_texture_ex(
+                    texture,
+                    dest,
+                    &TextureParams {
+                        source: Some(Rect::new(
+                            glyph.cell[0] as f32 * cell,
+                            glyph.cell[1] as f32 * cell,
+                            glyph.width as f32 * cell,
+                            cell,
+                        )),
+                        tint: Srgba::new(shade, shade, shade, 1.0),
+                        ..Default::default()
+                    },
+                )
+            }
+            None => {
+                let shade = shade * 0.4;
+                renderer.draw_rectangle(
+                    Vector2::new(dest.x, dest.y),
+                    dest.width,
+                    dest.height,
+                    Srgba::new(shade, shade, shade, 1.0),
+                    Deg(0.),
+                )
+            }
+        }
+
+        if let Some(label) = self.label(action, glyph) {
+            let size = height * LABEL_SIZE;
+            let label_width = self.measure_label(renderer, locale, label, height);
+            renderer.draw_text(
+                locale.text(label),
+                // A little above center, the keycaps are taller at the bottom
+                Vector2::new(center.x - label_width / 2., center.y - size * 0.6),
+                size,
+                size,
+                None,
+            );
+        }
+    }
+
+    // A line of glyphs with what they do, e.g. the controls of a menu,
+    // centered on `center`.
+    pub fn draw_row(
+        &self,
+        renderer: &mut Renderer,
+        locale: &Locale,
+        items: &[(Action, &str)],
+        center: Vector2<f32>,
+        height: f32,
+    ) {
+        let size = height * LABEL_SIZE;
+        let gap = height * ROW_GAP;
+        let widths: Vec<(f32, f32)> = items
+            .iter()
+            .map(|&(action, text)| {
+                (
+                    self.measure(renderer, locale, action, height),
+                    renderer.measure_text(text, size, size),
+                )
+            })
+            .collect();
+        let total: f32 = widths
+            .iter()
+            .map(|(glyph, text)| glyph + gap * 0.3 + text)
+            .sum::<f32>()
+            + gap * (items.len().saturating_sub(1)) as f32;
+
+        let mut x = center.x - total / 2.;
+        for (&(action, text), (glyph_width, text_width)) in items.iter().zip(widths) {
+            self.draw(
+                renderer,
+                locale,
+                action,
+                Vector2::new(x + glyph_width / 2., center.y),
+                height,
+                0.0,
+            );
+            x += glyph_width + gap * 0.3;
+            renderer.draw_text(
+                text,
+                Vector2::new(x, center.y - size / 2.),
+                size,
+                size,
+                Some(glyphon::Color::rgb(180, 180, 180)),
+            );
+            x += text_width + gap;
+        }
+    }
+}
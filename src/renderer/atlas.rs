@@ -0,0 +1,172 @@
+use std::fmt;
+
+// Packs sprites into one texture in shelves: rows as tall as the first sprite
+// put into them, filled left to right. Shelves keep their place when the
+// atlas grows, so growing only needs the old pixels copied over.
+pub(super) struct Atlas {
+    size: u32,
+    max_size: u32,
+    shelves: Vec<Shelf>,
+    // Top of the free space below the last shelf
+    bottom: u32,
+    stats: AtlasStats,
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    // Left edge of the free space
+    x: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct AtlasStats {
+    pub size: u32,
+    pub sprites: u32,
+    // Pixels taken by sprites, including their borders
+    pub used: u64,
+    pub grown: u32,
+    // Sprites that didn't fit at the largest size and got their own texture
+    pub spilled: u32,
+}
+
+impl fmt::Display for AtlasStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let area = (self.size as u64 * self.size as u64).max(1);
+        write!(
+            f,
+            "{}x{}, {} sprites, {:.0}% used, grown {} times, {} spilled",
+            self.size,
+            self.size,
+            self.sprites,
+            self.used as f64 / area as f64 * 100.0,
+            self.grown,
+            self.spilled
+        )
+    }
+}
+
+impl Atlas {
+    pub fn new(size: u32, max_size: u32) -> Self {
+        Self {
+            size,
+            max_size: max_size.max(size),
+            shelves: Vec::new(),
+            bottom: 0,
+            stats: AtlasStats {
+                size,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn stats(&self) -> AtlasStats {
+        self.stats
+    }
+
+    pub fn spill(&mut self) {
+        self.stats.spilled += 1;
+    }
+
+    // Top left corner of free space for `width` x `height` pixels, doubling
+    // the atlas while nothing fits. None once it can't grow any further.
+    pub fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > self.max_size || height > self.max_size {
+            return None;
+        }
+        loop {
+            if let Some(corner) = self.place(width, height) {
+                self.stats.sprites += 1;
+                self.stats.used += width as u64 * height as u64;
+                return Some(corner);
+            }
+            if self.size >= self.max_size {
+                return None;
+            }
+            self.size = (self.size * 2).min(self.max_size);
+            self.stats.size = self.size;
+            self.stats.grown += 1;
+        }
+    }
+
+    fn place(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        // The shelf wasting the least height
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && shelf.x + width <= self.size)
+            .min_by_key(|shelf| shelf.height - height);
+        if let Some(shelf) = shelf {
+            let corner = (shelf.x, shelf.y);
+            shelf.x += width;
+            return Some(corner);
+        }
+
+        if width > self.size || self.bottom + height > self.size {
+            return None;
+        }
+        let corner = (0, self.bottom);
+        self.shelves.push(Shelf {
+            y: self.bottom,
+            height,
+            x: width,
+        });
+        self.bottom += height;
+        Some(corner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlap(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool {
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    #[test]
+    fn sprites_fit_without_overlapping() {
+        let mut atlas = Atlas::new(64, 64);
+        let mut placed = Vec::new();
+        for (width, height) in [(16, 16), (8, 16), (32, 8), (16, 12), (40, 20), (4, 4)] {
+            let (x, y) = atlas.allocate(width, height).expect("fits");
+            assert!(x + width <= 64 && y + height <= 64);
+            placed.push((x, y, width, height));
+        }
+        for (index, a) in placed.iter().enumerate() {
+            assert!(placed[index + 1..].iter().all(|b| !overlap(*a, *b)));
+        }
+        assert_eq!(atlas.stats().sprites, 6);
+        assert_eq!(atlas.stats().grown, 0);
+    }
+
+    #[test]
+    fn a_full_shelf_starts_the_next_one() {
+        let mut atlas = Atlas::new(64, 64);
+        assert_eq!(atlas.allocate(40, 16), Some((0, 0)));
+        // Shorter sprites go on the shelf while there is room on it
+        assert_eq!(atlas.allocate(20, 10), Some((40, 0)));
+        assert_eq!(atlas.allocate(20, 16), Some((0, 16)));
+        assert_eq!(atlas.allocate(4, 16), Some((60, 0)));
+    }
+
+    #[test]
+    fn grows_until_the_largest_size_then_gives_up() {
+        let mut atlas = Atlas::new(32, 64);
+        assert!(atlas.allocate(32, 32).is_some());
+        // Grows to the right of the first shelf before starting a second
+        assert_eq!(atlas.allocate(32, 32), Some((32, 0)));
+        assert_eq!(atlas.size(), 64);
+        assert_eq!(atlas.stats().grown, 1);
+        assert_eq!(atlas.allocate(32, 32), Some((0, 32)));
+        assert_eq!(atlas.allocate(32, 32), Some((32, 32)));
+        assert_eq!(atlas.allocate(1, 1), None);
+        assert_eq!(atlas.allocate(65, 1), None);
+        assert_eq!(atlas.size(), 64);
+        assert_eq!(atlas.stats().sprites, 4);
+    }
+}
@@ -0,0 +1,166 @@
+use cgmath::{Matrix4, Vector2};
+
+use super::Rect;
+
+// Longest a full screen shake moves the view, in window pixels
+static MAX_SHAKE: f32 = 18.0;
+// Shake and zoom punch lost per second
+static SHAKE_DECAY: f32 = 1.8;
+static PUNCH_DECAY: f32 = 0.5;
+static MAX_PUNCH: f32 = 0.08;
+// Rough oscillations per second of the shake
+static SHAKE_FREQUENCY: f32 = 31.0;
+
+/// A view onto the world: `center` ends up in the middle of the screen, and
+/// everything is scaled around it by `zoom`. Only shapes go through the
+/// camera, text stays in screen space.
+///
+/// ```
+/// # use cgmath::Vector2;
+/// use wgpu_pong::renderer::Camera2D;
+///
+/// let camera = Camera2D { center: Vector2::new(100., 50.), zoom: 2. };
+/// let screen = Vector2::new(800., 600.);
+/// assert_eq!(camera.world_to_screen(Vector2::new(100., 50.), screen), Vector2::new(400., 300.));
+/// assert_eq!(camera.world_to_screen(Vector2::new(110., 50.), screen), Vector2::new(420., 300.));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    pub center: Vector2<f32>,
+    pub zoom: f32,
+}
+
+impl Camera2D {
+    pub fn new(center: Vector2<f32>) -> Self {
+        Self { center, zoom: 1.0 }
+    }
+
+    /// Shows all of `field` as large as it fits on `screen`
+    ///
+    /// ```
+    /// # use cgmath::Vector2;
+    /// use wgpu_pong::renderer::Camera2D;
+    ///
+    /// let camera = Camera2D::fit(Vector2::new(1280., 720.), Vector2::new(640., 640.));
+    /// assert_eq!(camera.center, Vector2::new(640., 360.));
+    /// assert_eq!(camera.zoom, 0.5);
+    /// ```
+    pub fn fit(field: Vector2<f32>, screen: Vector2<f32>) -> Self {
+        Self {
+            center: field / 2.0,
+            zoom: (screen.x / field.x.max(1.0)).min(screen.y / field.y.max(1.0)),
+        }
+    }
+
+    pub fn world_to_screen(&self, pos: Vector2<f32>, screen: Vector2<f32>) -> Vector2<f32> {
+        (pos - self.center) * self.zoom + screen / 2.0
+    }
+
+    // `world_to_screen` for the shader
+    pub fn matrix(&self, screen: Vector2<f32>) -> Matrix4<f32> {
+        Matrix4::from_translation((screen / 2.0).extend(0.0))
+            * Matrix4::from_nonuniform_scale(self.zoom, self.zoom, 1.0)
+            * Matrix4::from_translation((-self.center).extend(0.0))
+    }
+}
+
+/// The game is played on a field of a fixed size, its virtual resolution. This
+/// is the largest part of `screen` with the field's aspect ratio, centered
+/// between bars on the other sides, and the camera that fills it with the
+/// field. Draw the field with both set.
+///
+/// ```
+/// # use cgmath::Vector2;
+/// use wgpu_pong::renderer::{Rect, letterbox};
+///
+/// let (viewport, camera) = letterbox(Vector2::new(1280., 720.), Vector2::new(800., 600.));
+/// assert_eq!(viewport, Rect::new(0., 75., 800., 450.));
+/// assert_eq!(camera.zoom, 0.625);
+/// ```
+pub fn letterbox(field: Vector2<f32>, screen: Vector2<f32>) -> (Rect, Camera2D) {
+    let camera = Camera2D::fit(field, screen);
+    let size = field * camera.zoom;
+    let viewport = Rect::new(
+        (screen.x - size.x) / 2.0,
+        (screen.y - size.y) / 2.0,
+        size.x,
+        size.y,
+    );
+    (viewport, camera)
+}
+
+/// Like `letterbox`, but the field is shown at a whole multiple of
+/// `resolution`, on a grid of that many window pixels, for pixel art that is
+/// blown up with nearest neighbor. Also returns the multiple.
+///
+/// ```
+/// # use cgmath::Vector2;
+/// use wgpu_pong::renderer::{Rect, pixel_letterbox};
+///
+/// let (field, resolution) = (Vector2::new(1280., 720.), Vector2::new(320., 180.));
+/// let (viewport, camera, scale) = pixel_letterbox(field, resolution, Vector2::new(800., 600.));
+/// assert_eq!(scale, 2);
+/// assert_eq!(viewport, Rect::new(80., 120., 640., 360.));
+/// assert_eq!(camera.zoom, 0.5);
+/// ```
+pub fn pixel_letterbox(
+    field: Vector2<f32>,
+    resolution: Vector2<f32>,
+    screen: Vector2<f32>,
+) -> (Rect, Camera2D, u32) {
+    let scale = (screen.x / resolution.x.max(1.0))
+        .min(screen.y / resolution.y.max(1.0))
+        .floor()
+        .max(1.0);
+    let size = resolution * scale;
+    let snap = |offset: f32| ((offset / 2.0) / scale).floor() * scale;
+    let viewport = Rect::new(
+        snap(screen.x - size.x),
+        snap(screen.y - size.y),
+        size.x,
+        size.y,
+    );
+    (viewport, Camera2D::fit(field, size), scale as u32)
+}
+
+// Screen shake and zoom punches on top of a camera, e.g. for goals and hard
+// hits. Both wear off on their own.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CameraEffects {
+    // 0..1, the shake grows with its square so small hits stay subtle
+    trauma: f32,
+    punch: f32,
+    time: f32,
+}
+
+impl CameraEffects {
+    pub fn shake(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    // Zooms in by `amount` (a share of the zoom) and eases back out
+    pub fn punch(&mut self, amount: f32) {
+        self.punch = self.punch.max(amount).min(MAX_PUNCH);
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.trauma = (self.trauma - SHAKE_DECAY * delta).max(0.0);
+        self.punch = (self.punch - PUNCH_DECAY * MAX_PUNCH * delta).max(0.0);
+        self.time += delta;
+    }
+
+    pub fn apply(&self, camera: Camera2D) -> Camera2D {
+        let shake = self.trauma * self.trauma * MAX_SHAKE;
+        // Two sines out of step wander less regularly than one
+        let t = self.time * SHAKE_FREQUENCY;
+        let offset = Vector2::new(
+            (t.sin() + (t * 2.3 + 1.1).sin()) / 2.0,
+            ((t * 1.3 + 2.7).sin() + (t * 2.9).sin()) / 2.0,
+        ) * shake;
+        let zoom = camera.zoom * (1.0 + self.punch);
+        Camera2D {
+            center: camera.center + offset / zoom,
+            zoom,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{Context, Result, bail};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureKind {
+    // The frame as shown on screen, including the clear color and text
+    Opaque,
+    // Only the shapes on a transparent background, e.g. for thumbnails
+    Transparent,
+}
+
+pub struct CapturedImage {
+    pub width: u32,
+    pub height: u32,
+    // Tightly packed, straight (not premultiplied) sRGB RGBA8
+    pub rgba: Vec<u8>,
+}
+
+impl CapturedImage {
+    /// RGBA of the pixel at `x`, `y` from the top left
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// renderer.clear_color(Srgba::new(1., 1., 1., 1.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(63, 63), [255, 255, 255, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let start = ((y * self.width + x) * 4) as usize;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.rgba[start..start + 4]);
+        pixel
+    }
+
+    /// Writes the image as an sRGB PNG file.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let path = std::env::temp_dir().join("wgpu-pong-doc-capture.png");
+    /// renderer.render_offscreen()?.save_png(&path)?;
+    /// assert!(path.exists());
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.rgba)?;
+        Ok(())
+    }
+}
+
+// Copies `texture` into CPU memory. Blocks until the GPU is done.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    mut encoder: wgpu::CommandEncoder,
+    texture: &wgpu::Texture,
+    premultiplied: bool,
+) -> Result<CapturedImage> {
+    let width = texture.width();
+    let height = texture.height();
+    let format = texture.format();
+
+    let bgra = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => bail!("capturing {:?} textures is not supported", format),
+    };
+
+    // Rows of a texture copy must be aligned to 256 bytes
+    let unpadded_bytes_per_row = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture Buffer"),
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit([encoder.finish()]);
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .context("capture buffer was never mapped")?
+        .context("cannot map capture buffer")?;
+
+    let mut rgba = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks(padded_bytes_per_row as usize) {
+            rgba.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+    }
+    buffer.unmap();
+
+    for pixel in rgba.chunks_exact_mut(4) {
+        if bgra {
+            pixel.swap(0, 2);
+        }
+        if premultiplied {
+            unpremultiply(pixel);
+        }
+    }
+
+    Ok(CapturedImage {
+        width,
+        height,
+        rgba,
+    })
+}
+
+// Blending happens in linear space, so undo the premultiplication there.
+fn unpremultiply(pixel: &mut [u8]) {
+    let alpha = pixel[3] as f32 / 255.0;
+    if alpha <= 0.0 {
+        pixel[..3].fill(0);
+        return;
+    }
+
+    for channel in pixel[..3].iter_mut() {
+        let linear = srgb_to_linear(*channel as f32 / 255.0) / alpha;
+        *channel = (linear_to_srgb(linear.min(1.0)) * 255.0).round() as u8;
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,41 @@
+use glyphon::Family;
+use serde::{Deserialize, Serialize};
+
+// Typeface of all text, picked from the fonts installed on the system. A
+// family the system lacks falls back to whatever font has the glyphs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UiFont {
+    #[default]
+    SansSerif,
+    Serif,
+    // Every letter equally wide, easier to tell apart for some readers
+    Monospace,
+}
+
+impl UiFont {
+    pub fn next(self) -> Self {
+        match self {
+            UiFont::SansSerif => UiFont::Serif,
+            UiFont::Serif => UiFont::Monospace,
+            UiFont::Monospace => UiFont::SansSerif,
+        }
+    }
+
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            UiFont::SansSerif => "font.sans_serif",
+            UiFont::Serif => "font.serif",
+            UiFont::Monospace => "font.monospace",
+        }
+    }
+
+    pub(super) fn family(self) -> Family<'static> {
+        match self {
+            UiFont::SansSerif => Family::SansSerif,
+            UiFont::Serif => Family::Serif,
+            UiFont::Monospace => Family::Monospace,
+        }
+    }
+}
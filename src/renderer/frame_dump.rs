@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Camera2D, Layer, LineJoin, Quad, Rect, TextureParams};
+
+// One call into the renderer, with everything needed to make it again.
+// Positions are in window pixels, colors are straight sRGB.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+pub enum DrawCommand {
+    ClearColor {
+        color: [f32; 4],
+    },
+    Camera {
+        // Center and zoom
+        camera: Option<([f32; 2], f32)>,
+    },
+    Layer {
+        // Offset and opacity
+        layer: Option<([f32; 2], f32)>,
+    },
+    Viewport {
+        viewport: Option<[f32; 4]>,
+    },
+    // Start of the HUD
+    Hud,
+    Rectangle {
+        pos: [f32; 2],
+        width: f32,
+        height: f32,
+        color: [f32; 4],
+        rotation: f32,
+    },
+    Quads {
+        // Center, size and color of each square
+        quads: Vec<([f32; 2], f32, [f32; 4])>,
+    },
+    Triangle {
+        points: [[f32; 2]; 3],
+        color: [f32; 4],
+        rotation: f32,
+    },
+    Circle {
+        center: [f32; 2],
+        radius: f32,
+        color: [f32; 4],
+    },
+    Line {
+        start: [f32; 2],
+        end: [f32; 2],
+        thickness: f32,
+        color: [f32; 4],
+    },
+    DashedLine {
+        start: [f32; 2],
+        end: [f32; 2],
+        thickness: f32,
+        dash: f32,
+        gap: f32,
+        color: [f32; 4],
+    },
+    Polyline {
+        points: Vec<[f32; 2]>,
+        thickness: f32,
+        color: [f32; 4],
+        join: LineJoin,
+    },
+    Ring {
+        center: [f32; 2],
+        radius: f32,
+        thickness: f32,
+        color: [f32; 4],
+    },
+    RectangleOutline {
+        pos: [f32; 2],
+        width: f32,
+        height: f32,
+        thickness: f32,
+        color: [f32; 4],
+    },
+    Texture {
+        // Index into `FrameDump::textures`
+        texture: usize,
+        dest: [f32; 4],
+        source: Option<[f32; 4]>,
+        tint: [f32; 4],
+        rotation: f32,
+        flip_x: bool,
+        flip_y: bool,
+    },
+    Text {
+        text: String,
+        pos: [f32; 2],
+        font_size: f32,
+        line_height: f32,
+        // RGBA8, the default white when unset
+        color: Option<[u8; 4]>,
+    },
+}
+
+// Every draw call of one frame, written by `Renderer::request_frame_dump`
+// and drawn again by `Renderer::replay_frame`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameDump {
+    pub width: u32,
+    pub height: u32,
+    // File each texture handle was loaded from, None for the built-in white
+    // and textures loaded from memory
+    pub textures: Vec<Option<PathBuf>>,
+    pub commands: Vec<DrawCommand>,
+}
+
+impl FrameDump {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data =
+            fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("invalid frame dump {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("cannot create {}", dir.display()))?;
+        }
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(path, data).with_context(|| format!("cannot write {}", path.display()))
+    }
+}
+
+pub(super) fn vector(vector: Vector2<f32>) -> [f32; 2] {
+    vector.into()
+}
+
+pub(super) fn color(color: Srgba) -> [f32; 4] {
+    [color.red, color.green, color.blue, color.alpha]
+}
+
+pub(super) fn rect(rect: Rect) -> [f32; 4] {
+    [rect.x, rect.y, rect.width, rect.height]
+}
+
+pub(super) fn to_vector(vector: [f32; 2]) -> Vector2<f32> {
+    vector.into()
+}
+
+pub(super) fn to_color(color: [f32; 4]) -> Srgba {
+    Srgba::new(color[0], color[1], color[2], color[3])
+}
+
+pub(super) fn to_rect(rect: [f32; 4]) -> Rect {
+    Rect::new(rect[0], rect[1], rect[2], rect[3])
+}
+
+pub(super) fn camera(camera: Option<Camera2D>) -> Option<([f32; 2], f32)> {
+    camera.map(|camera| (vector(camera.center), camera.zoom))
+}
+
+pub(super) fn to_camera(camera: Option<([f32; 2], f32)>) -> Option<Camera2D> {
+    camera.map(|(center, zoom)| Camera2D {
+        center: to_vector(center),
+        zoom,
+    })
+}
+
+pub(super) fn layer(layer: Option<Layer>) -> Option<([f32; 2], f32)> {
+    layer.map(|layer| (vector(layer.offset), layer.opacity))
+}
+
+pub(super) fn to_layer(layer: Option<([f32; 2], f32)>) -> Option<Layer> {
+    layer.map(|(offset, opacity)| Layer {
+        offset: to_vector(offset),
+        opacity,
+    })
+}
+
+pub(super) fn quads(quads: &[Quad]) -> Vec<([f32; 2], f32, [f32; 4])> {
+    quads
+        .iter()
+        .map(|quad| (vector(quad.center), quad.size, color(quad.color)))
+        .collect()
+}
+
+pub(super) fn to_quads(quads: &[([f32; 2], f32, [f32; 4])]) -> Vec<Quad> {
+    quads
+        .iter()
+        .map(|(center, size, quad_color)| Quad {
+            center: to_vector(*center),
+            size: *size,
+            color: to_color(*quad_color),
+        })
+        .collect()
+}
+
+pub(super) fn texture_params(
+    source: Option<[f32; 4]>,
+    tint: [f32; 4],
+    rotation: f32,
+    flip_x: bool,
+    flip_y: bool,
+) -> TextureParams {
+    TextureParams {
+        source: source.map(to_rect),
+        tint: to_color(tint),
+        rotation: Deg(rotation),
+        flip_x,
+        flip_y,
+    }
+}
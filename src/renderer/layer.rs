@@ -0,0 +1,32 @@
+use cgmath::Vector2;
+use palette::Srgba;
+
+// Moves and fades everything drawn while it is set, shapes and text alike.
+// Used to animate whole screens in and out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layer {
+    // In window pixels, applied after the camera
+    pub offset: Vector2<f32>,
+    // Multiplies the alpha of every color, 0..1
+    pub opacity: f32,
+}
+
+impl Layer {
+    pub fn apply_color(&self, color: Srgba) -> Srgba {
+        Srgba::new(
+            color.red,
+            color.green,
+            color.blue,
+            color.alpha * self.opacity,
+        )
+    }
+
+    pub fn apply_text_color(&self, color: glyphon::Color) -> glyphon::Color {
+        glyphon::Color::rgba(
+            color.r(),
+            color.g(),
+            color.b(),
+            (f32::from(color.a()) * self.opacity) as u8,
+        )
+    }
+}
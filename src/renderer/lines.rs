@@ -0,0 +1,433 @@
+use cgmath::{InnerSpace, Vector2};
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+
+use super::frame_dump::{self, DrawCommand};
+use super::{BATCH_VERTICES, Renderer, Vertex, texture};
+
+// Sharper corners than this many half thicknesses of miter are beveled
+// instead, or they would spike far past the line
+static MITER_LIMIT: f32 = 4.0;
+static RING_SEGMENTS: u16 = 48;
+// Radians per triangle of a round join
+static ROUND_STEP: f32 = 0.3;
+
+// How a polyline turns its corners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineJoin {
+    // Edges extended until they meet, beveled when that's too far out
+    #[default]
+    Miter,
+    Round,
+}
+
+// Triangles built up on the CPU and pushed into the shape buffers at once.
+#[derive(Default)]
+struct Mesh {
+    positions: Vec<Vector2<f32>>,
+    indices: Vec<u16>,
+}
+
+impl Mesh {
+    fn vertex(&mut self, pos: Vector2<f32>) -> u16 {
+        self.positions.push(pos);
+        (self.positions.len() - 1) as u16
+    }
+
+    fn quad(&mut self, corners: [Vector2<f32>; 4]) {
+        let first = self.positions.len() as u16;
+        self.positions.extend(corners);
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|offset| first + offset));
+    }
+
+    // A fan around `center` from `from` to `to`, along the shorter arc
+    fn arc(&mut self, center: Vector2<f32>, from: Vector2<f32>, to: Vector2<f32>, round: bool) {
+        let center_index = self.vertex(center);
+        let (start, end) = (from - center, to - center);
+        let angle = start.y.atan2(start.x);
+        let mut sweep = end.y.atan2(end.x) - angle;
+        if sweep > std::f32::consts::PI {
+            sweep -= std::f32::consts::TAU;
+        } else if sweep < -std::f32::consts::PI {
+            sweep += std::f32::consts::TAU;
+        }
+        let steps = if round {
+            (sweep.abs() / ROUND_STEP).ceil().max(1.0) as usize
+        } else {
+            1
+        };
+        let radius = start.magnitude();
+        let mut previous = self.vertex(from);
+        for step in 1..=steps {
+            let next = if step == steps {
+                self.vertex(to)
+            } else {
+                let angle = angle + sweep * step as f32 / steps as f32;
+                self.vertex(center + Vector2::new(angle.cos(), angle.sin()) * radius)
+            };
+            self.indices.extend([center_index, previous, next]);
+            previous = next;
+        }
+    }
+}
+
+// Left normal of the segment from `a` to `b`, zero when they're the same
+fn normal(a: Vector2<f32>, b: Vector2<f32>) -> Vector2<f32> {
+    let direction = b - a;
+    if direction.magnitude2() <= f32::EPSILON {
+        return Vector2::new(0.0, 0.0);
+    }
+    let direction = direction.normalize();
+    Vector2::new(-direction.y, direction.x)
+}
+
+// A line `thickness` wide through `points`, the last point joined back to
+// the first when `closed`. Ends are cut off square at the points.
+fn polyline_mesh(points: &[Vector2<f32>], thickness: f32, join: LineJoin, closed: bool) -> Mesh {
+    let mut mesh = Mesh::default();
+    // Repeated points would have no direction
+    let mut points: Vec<Vector2<f32>> = points.to_vec();
+    points.dedup_by(|a, b| (*a - *b).magnitude2() <= f32::EPSILON);
+    if closed
+        && points.len() > 2
+        && (points[0] - points[points.len() - 1]).magnitude2() <= f32::EPSILON
+    {
+        points.pop();
+    }
+    let count = points.len();
+    if count < 2 || thickness <= 0.0 {
+        return mesh;
+    }
+    let half = thickness / 2.0;
+    let segments = if closed && count > 2 {
+        count
+    } else {
+        count - 1
+    };
+    let normals: Vec<Vector2<f32>> = (0..segments)
+        .map(|index| normal(points[index], points[(index + 1) % count]))
+        .collect();
+
+    // Offset of both edges at each point when the segments meeting there
+    // share it, None where the corner is filled by a join instead
+    let offsets: Vec<Option<Vector2<f32>>> = (0..count)
+        .map(|index| {
+            let before = if index > 0 {
+                Some(index - 1)
+            } else if segments == count {
+                Some(segments - 1)
+            } else {
+                None
+            };
+            let after = (index < segments).then_some(index);
+            match (before.map(|i| normals[i]), after.map(|i| normals[i])) {
+                (Some(n0), Some(n1)) => {
+                    let miter = n0 + n1;
+                    if miter.magnitude2() <= f32::EPSILON {
+                        // Doubles straight back
+                        return None;
+                    }
+                    let miter = miter.normalize();
+                    let length = half / miter.dot(n0).max(f32::EPSILON);
+                    let straight = n0.dot(n1) > 0.9999;
+                    let miter_fits = join == LineJoin::Miter && length <= half * MITER_LIMIT;
+                    (straight || miter_fits).then(|| miter * length)
+                }
+                (Some(n), None) | (None, Some(n)) => Some(n * half),
+                (None, None) => None,
+            }
+        })
+        .collect();
+
+    for (start, normal) in normals.iter().enumerate() {
+        let end = (start + 1) % count;
+        let edge = normal * half;
+        let start_offset = offsets[start].unwrap_or(edge);
+        let end_offset = offsets[end].unwrap_or(edge);
+        mesh.quad([
+            points[start] + start_offset,
+            points[end] + end_offset,
+            points[end] - end_offset,
+            points[start] - start_offset,
+        ]);
+    }
+
+    for index in 0..count {
+        if offsets[index].is_some() {
+            continue;
+        }
+        let before = (index + segments - 1) % segments;
+        let (n0, n1) = (normals[before], normals[index % segments]);
+        // The outer side of the corner is where the edges leave a gap
+        let direction = points[(index + 1) % count] - points[index];
+        let side = if n0.dot(direction) > 0.0 { -1.0 } else { 1.0 };
+        mesh.arc(
+            points[index],
+            points[index] + n0 * half * side,
+            points[index] + n1 * half * side,
+            join == LineJoin::Round,
+        );
+    }
+    mesh
+}
+
+impl Renderer {
+    /// A straight line `thickness` wide, centered on the points.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let white = Srgba::new(1., 1., 1., 1.);
+    /// renderer.draw_line(Vector2::new(0., 32.), Vector2::new(64., 32.), 4., white);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(32, 32), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(32, 40), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_line(
+        &mut self,
+        start: Vector2<f32>,
+        end: Vector2<f32>,
+        thickness: f32,
+        color: Srgba,
+    ) {
+        self.record(|| DrawCommand::Line {
+            start: frame_dump::vector(start),
+            end: frame_dump::vector(end),
+            thickness,
+            color: frame_dump::color(color),
+        });
+        self.draw_mesh(
+            &polyline_mesh(&[start, end], thickness, LineJoin::Miter, false),
+            color,
+        );
+    }
+
+    /// Dashes `dash` long with `gap` between them, starting with a dash.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let white = Srgba::new(1., 1., 1., 1.);
+    /// let (start, end) = (Vector2::new(0., 32.), Vector2::new(64., 32.));
+    /// renderer.draw_dashed_line(start, end, 4., 8., 8., white);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(4, 32), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(12, 32), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_dashed_line(
+        &mut self,
+        start: Vector2<f32>,
+        end: Vector2<f32>,
+        thickness: f32,
+        dash: f32,
+        gap: f32,
+        color: Srgba,
+    ) {
+        self.record(|| DrawCommand::DashedLine {
+            start: frame_dump::vector(start),
+            end: frame_dump::vector(end),
+            thickness,
+            dash,
+            gap,
+            color: frame_dump::color(color),
+        });
+        let length = (end - start).magnitude();
+        if length <= f32::EPSILON || dash <= 0.0 {
+            return;
+        }
+        let direction = (end - start) / length;
+        let mut mesh = Mesh::default();
+        let mut distance = 0.0;
+        while distance < length {
+            let dash_end = (distance + dash).min(length);
+            let part = polyline_mesh(
+                &[start + direction * distance, start + direction * dash_end],
+                thickness,
+                LineJoin::Miter,
+                false,
+            );
+            let first = mesh.positions.len() as u16;
+            mesh.positions.extend(part.positions);
+            mesh.indices
+                .extend(part.indices.into_iter().map(|index| first + index));
+            distance = dash_end + gap.max(0.0);
+            // Long lines are split into meshes a batch can index
+            if mesh.positions.len() + 4 > BATCH_VERTICES {
+                self.draw_mesh(&std::mem::take(&mut mesh), color);
+            }
+        }
+        self.draw_mesh(&mesh, color);
+    }
+
+    /// A line through all `points`, e.g. a predicted path.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// use wgpu_pong::renderer::LineJoin;
+    ///
+    /// let points = [Vector2::new(8., 8.), Vector2::new(56., 8.), Vector2::new(56., 56.)];
+    /// renderer.draw_polyline(&points, 4., Srgba::new(1., 1., 1., 1.), LineJoin::Round);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(32, 8), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(56, 32), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(16, 48), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_polyline(
+        &mut self,
+        points: &[Vector2<f32>],
+        thickness: f32,
+        color: Srgba,
+        join: LineJoin,
+    ) {
+        self.record(|| DrawCommand::Polyline {
+            points: points.iter().copied().map(frame_dump::vector).collect(),
+            thickness,
+            color: frame_dump::color(color),
+            join,
+        });
+        self.draw_mesh(&polyline_mesh(points, thickness, join, false), color);
+    }
+
+    /// A circle outline `thickness` wide, centered on `radius`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// renderer.draw_ring(Vector2::new(32., 32.), 16., 4., Srgba::new(1., 1., 1., 1.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(48, 32), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(32, 32), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_ring(&mut self, center: Vector2<f32>, radius: f32, thickness: f32, color: Srgba) {
+        self.record(|| DrawCommand::Ring {
+            center: frame_dump::vector(center),
+            radius,
+            thickness,
+            color: frame_dump::color(color),
+        });
+        let mut mesh = Mesh::default();
+        let (outer, inner) = (
+            radius + thickness / 2.0,
+            (radius - thickness / 2.0).max(0.0),
+        );
+        for segment in 0..RING_SEGMENTS {
+            let angle = std::f32::consts::TAU * segment as f32 / RING_SEGMENTS as f32;
+            let point = Vector2::new(angle.cos(), angle.sin());
+            mesh.positions.push(center + point * outer);
+            mesh.positions.push(center + point * inner);
+        }
+        for segment in 0..RING_SEGMENTS {
+            let next = (segment + 1) % RING_SEGMENTS;
+            let (a, b, c, d) = (segment * 2, segment * 2 + 1, next * 2, next * 2 + 1);
+            mesh.indices.extend([a, c, b, b, c, d]);
+        }
+        self.draw_mesh(&mesh, color);
+    }
+
+    /// The border of the rectangle at `pos`, `thickness` wide inside it.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let white = Srgba::new(1., 1., 1., 1.);
+    /// renderer.draw_rectangle_outline(Vector2::new(8., 8.), 48., 48., 4., white);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(10, 32), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(32, 32), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_rectangle_outline(
+        &mut self,
+        pos: Vector2<f32>,
+        width: f32,
+        height: f32,
+        thickness: f32,
+        color: Srgba,
+    ) {
+        self.record(|| DrawCommand::RectangleOutline {
+            pos: frame_dump::vector(pos),
+            width,
+            height,
+            thickness,
+            color: frame_dump::color(color),
+        });
+        let inset = (thickness / 2.0).min(width / 2.0).min(height / 2.0);
+        let (left, top) = (pos.x + inset, pos.y + inset);
+        let (right, bottom) = (pos.x + width - inset, pos.y + height - inset);
+        let corners = [
+            Vector2::new(left, top),
+            Vector2::new(right, top),
+            Vector2::new(right, bottom),
+            Vector2::new(left, bottom),
+        ];
+        self.draw_mesh(
+            &polyline_mesh(&corners, thickness, LineJoin::Miter, true),
+            color,
+        );
+    }
+
+    // Pushes the triangles of `mesh` in one color, skipped like any other
+    // shape when they don't fit into this frame's budget.
+    fn draw_mesh(&mut self, mesh: &Mesh, color: Srgba) {
+        let count = mesh.positions.len();
+        if count == 0 || count > BATCH_VERTICES || !self.reserve(count, mesh.indices.len()) {
+            return;
+        }
+        self.use_texture(texture::WHITE);
+        let color: [f32; 4] = self.layer_color(color).into();
+        self.vertices
+            .extend(mesh.positions.iter().map(|&position| Vertex {
+                position: position.into(),
+                color,
+                uv: [0.0, 0.0],
+            }));
+        for triangle in mesh.indices.chunks_exact(3) {
+            // Back faces are culled, so every triangle is turned the same
+            // way as draw_rectangle's
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            let (pa, pb, pc) = (
+                mesh.positions[a as usize],
+                mesh.positions[b as usize],
+                mesh.positions[c as usize],
+            );
+            let cross = (pb.x - pa.x) * (pc.y - pa.y) - (pb.y - pa.y) * (pc.x - pa.x);
+            let (b, c) = if cross > 0.0 { (c, b) } else { (b, c) };
+            for index in [a, b, c] {
+                self.indices.push(self.current_index + index);
+            }
+        }
+        self.current_index += count as u16;
+    }
+}
@@ -0,0 +1,2424 @@
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use cgmath::{Deg, Matrix2, Matrix4, SquareMatrix, Vector2};
+use glyphon::{
+    Attrs, Buffer, Cache, FontSystem, Metrics, Resolution, Shaping, SwashCache, TextArea,
+    TextAtlas, TextBounds, TextRenderer, Viewport, fontdb,
+};
+use palette::Srgba;
+use rayon::prelude::*;
+use winit::window::Window;
+
+use crate::time::Instant;
+use atlas::Atlas;
+use frame_dump::DrawCommand;
+use post::{PostProcess, PostSettings};
+use shape_buffer::ShapeBuffer;
+use upscale::{UpscaleFilter, Upscaler};
+use view::ViewUniforms;
+
+pub use camera::{Camera2D, CameraEffects, letterbox, pixel_letterbox};
+pub use capture::{CaptureKind, CapturedImage};
+pub use font::UiFont;
+pub use frame_dump::FrameDump;
+pub use layer::Layer;
+pub use lines::LineJoin;
+pub use post::PostFilter;
+pub use quality::Quality;
+pub use rect::Rect;
+pub use safe_area::MAX_INSET;
+pub use surface_format::SurfaceFormat;
+pub use texture::{DecodedImage, TextureHandle, TextureParams};
+pub use upscale::Upscale;
+
+mod atlas;
+mod camera;
+mod capture;
+mod font;
+mod frame_dump;
+mod layer;
+mod lines;
+mod post;
+mod quality;
+mod rect;
+mod safe_area;
+mod shape_buffer;
+mod surface_format;
+mod texture;
+mod upscale;
+mod view;
+
+// Most vertices and indices per frame, further shapes are skipped
+static MAX_VERTICES: usize = 1 << 18;
+static MAX_INDICES: usize = 3 << 17;
+// Vertices an index can address, a batch is split when it runs out
+static BATCH_VERTICES: usize = u16::MAX as usize;
+// The buffers start at this many vertices and indices and grow as needed
+static INITIAL_CAPACITY: usize = 4096;
+// Size of the reused buffers shapes are uploaded through
+static STAGING_CHUNK: wgpu::BufferAddress = 1 << 16;
+// Textures up to this size on both sides share the atlas, larger ones get
+// their own texture
+static ATLAS_SPRITE_MAX: u32 = 256;
+static ATLAS_SIZE: u32 = 512;
+static ATLAS_MAX_SIZE: u32 = 4096;
+// Squares per parallel job in `draw_quads`
+static QUAD_CHUNK: usize = 256;
+// Stands in for textures a replayed frame dump cannot load
+static MISSING_TEXTURE: [u8; 4] = [255, 0, 255, 255];
+// Lowest fraction of the window resolution the scene is rendered at
+pub static MIN_RENDER_SCALE: f32 = 0.5;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 2],
+    color: [f32; 4],
+    // Texture coordinates, untextured shapes sample the atlas' white pixel
+    uv: [f32; 2],
+}
+
+// Axis aligned square for `Renderer::draw_quads`.
+#[derive(Debug, Clone, Copy)]
+pub struct Quad {
+    pub center: Vector2<f32>,
+    pub size: f32,
+    pub color: Srgba,
+}
+
+// Shapes from `start` up to the next batch are drawn into `viewport` with
+// the texture `page`, projected by `view`. Their indices count from
+// `base_vertex`.
+struct Batch {
+    start: u32,
+    base_vertex: i32,
+    viewport: Option<Rect>,
+    page: usize,
+    view: Matrix4<f32>,
+}
+
+struct Text {
+    buffer: Buffer,
+    position: Vector2<f32>,
+    bounds: TextBounds,
+    color: glyphon::Color,
+}
+
+pub struct Renderer {
+    // None when rendering offscreen, see `headless`
+    window: Option<Arc<Window>>,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pub size: winit::dpi::PhysicalSize<u32>,
+    // Dropped while the app is suspended, everything else survives
+    surface: Option<wgpu::Surface<'static>>,
+    surface_format: SurfaceFormat,
+    present_modes: Vec<wgpu::PresentMode>,
+    present_mode: wgpu::PresentMode,
+
+    clear_color: Option<Srgba>,
+    // Applied to shapes when set, see Camera2D
+    camera: Option<Camera2D>,
+    // Part of the window shapes are drawn into, the whole window when None
+    viewport: Option<Rect>,
+    // Applied to shapes and text when set
+    layer: Option<Layer>,
+    // Texture sampled by the following shapes, see `draw_texture`
+    page: usize,
+    // First vertex of the current batch
+    base_vertex: i32,
+    batches: Vec<Batch>,
+
+    // 2d rendering
+    render_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: ShapeBuffer,
+    index_buffer: ShapeBuffer,
+    views: ViewUniforms,
+    staging_belt: wgpu::util::StagingBelt,
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    // Index of the next vertex, relative to `base_vertex`
+    current_index: u16,
+
+    // Textures, the first one is the atlas shared by untextured shapes and
+    // small sprites. Glyphs are cached by glyphon in an atlas of its own,
+    // sharing this one would mean replacing glyphon's text renderer.
+    texture_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    pages: Vec<texture::Texture>,
+    atlas: Atlas,
+    // Indexed by TextureHandle, the first one is plain white
+    images: Vec<texture::Image>,
+    // File each image was loaded from, kept for frame dumps
+    texture_sources: Vec<Option<PathBuf>>,
+
+    // text rendering
+    font_system: FontSystem,
+    font: UiFont,
+    swash_cache: SwashCache,
+    text_viewport: Viewport,
+    text_atlas: TextAtlas,
+    text_renderer: TextRenderer,
+    text: Vec<Text>,
+
+    // post processing, dropped when memory runs low
+    post: Option<PostProcess>,
+    post_settings: PostSettings,
+    // Extra safe area inset chosen by the player, see `safe_area`
+    hud_inset: f32,
+    // See `acquire_wait`
+    acquire_wait: Duration,
+    // Fraction of the window resolution the scene is rendered at, see
+    // `set_render_scale`. The target is created on first use.
+    render_scale: f32,
+    upscale: Upscale,
+    // Whole window pixels per scene pixel in the pixel art mode, which
+    // replaces the render scale, see `set_pixel_art`
+    pixel_scale: Option<u32>,
+    scanlines: bool,
+    upscaler: Option<Upscaler>,
+    // First batch of the HUD, which stays at the window resolution
+    hud_batch: Option<usize>,
+
+    // Set from the device error callback when an allocation fails
+    out_of_memory: Arc<AtomicBool>,
+    quality: Quality,
+    quality_changed: bool,
+
+    // Shape pipeline for transparent captures, created on first use
+    capture_pipeline: Option<wgpu::RenderPipeline>,
+
+    // Draw calls of the current frame while a dump was requested
+    dump_requested: bool,
+    recording: Option<Vec<DrawCommand>>,
+    frame_dump: Option<FrameDump>,
+}
+
+impl Renderer {
+    /// A renderer drawing into `window`, see `headless` for one without.
+    pub async fn new(window: Arc<Window>) -> Result<Self> {
+        let size = window.inner_size();
+        Self::create(Some(window), size, FontSystem::new()).await
+    }
+
+    /// Like `new`, but without the fonts installed on the system, which take
+    /// a while to find and load. No text shows until they're handed over with
+    /// `set_font_system`, e.g. once another thread has loaded them.
+    pub async fn without_fonts(window: Arc<Window>) -> Result<Self> {
+        let size = window.inner_size();
+        let font_system =
+            FontSystem::new_with_locale_and_db("en-US".to_string(), fontdb::Database::new());
+        Self::create(Some(window), size, font_system).await
+    }
+
+    /// A renderer without a window, frames are only read back with
+    /// `render_offscreen`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// use wgpu_pong::renderer::Renderer;
+    ///
+    /// let mut renderer = pollster::block_on(Renderer::headless(320, 180))?;
+    /// renderer.begin_drawing();
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!((image.width, image.height), (320, 180));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn headless(width: u32, height: u32) -> Result<Self> {
+        let mut renderer = Self::create(
+            None,
+            winit::dpi::PhysicalSize::new(width.max(1), height.max(1)),
+            FontSystem::new(),
+        )
+        .await?;
+        renderer.resize(renderer.size);
+        Ok(renderer)
+    }
+
+    async fn create(
+        window: Option<Arc<Window>>,
+        size: winit::dpi::PhysicalSize<u32>,
+        font_system: FontSystem,
+    ) -> Result<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptionsBase::default())
+            .await
+            .context("cannot create adapter from wgpu instance")?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor::default(),
+                None, // Trace path
+            )
+            .await?;
+
+        // Report allocation failures instead of panicking, so we can step
+        // down the quality. Other errors are still bugs and get logged.
+        let out_of_memory = Arc::new(AtomicBool::new(false));
+        let flag = out_of_memory.clone();
+        device.on_uncaptured_error(Box::new(move |err| match err {
+            wgpu::Error::OutOfMemory { .. } => flag.store(true, Ordering::Relaxed),
+            err => error!("wgpu error: {}", err),
+        }));
+
+        let surface = match window.clone() {
+            Some(window) => Some(instance.create_surface(window)?),
+            None => None,
+        };
+        let (surface_format, present_modes) = match surface.as_ref() {
+            Some(surface) => {
+                let cap = surface.get_capabilities(&adapter);
+                let format =
+                    surface_format::choose(&cap.formats).context("surface supports no formats")?;
+                if format.is_srgb() {
+                    info!("Drawing in {}", format.describe());
+                } else {
+                    warn!(
+                        "No sRGB format among {:?}, colors may look washed out in {}",
+                        cap.formats,
+                        format.describe()
+                    );
+                }
+                (format, cap.present_modes.clone())
+            }
+            None => (
+                SurfaceFormat::same(wgpu::TextureFormat::Rgba8UnormSrgb),
+                Vec::new(),
+            ),
+        };
+
+        let vertex_buffer = ShapeBuffer::new(
+            &device,
+            "Vertex Buffer",
+            wgpu::BufferUsages::VERTEX,
+            (INITIAL_CAPACITY * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+        );
+        let index_buffer = ShapeBuffer::new(
+            &device,
+            "Index Buffer",
+            wgpu::BufferUsages::INDEX,
+            (INITIAL_CAPACITY * std::mem::size_of::<u16>()) as wgpu::BufferAddress,
+        );
+
+        let texture_layout = texture::create_bind_group_layout(&device);
+        let sampler = texture::create_sampler(&device);
+        let max_atlas_size = device.limits().max_texture_dimension_2d.min(ATLAS_MAX_SIZE);
+        let mut atlas = Atlas::new(ATLAS_SIZE.min(max_atlas_size), max_atlas_size);
+        let atlas_page = texture::create_texture(
+            &device,
+            &texture_layout,
+            &sampler,
+            atlas.size(),
+            atlas.size(),
+        );
+        // The first spot is always the top left corner
+        atlas.allocate(2, 2);
+        texture::write_pixels(&queue, &atlas_page, 0, 0, 2, 2, &[255; 16]);
+        let views = ViewUniforms::new(&device);
+        let render_pipeline = create_shape_pipeline(
+            &device,
+            surface_format.view,
+            wgpu::BlendState::ALPHA_BLENDING,
+            &texture_layout,
+            views.layout(),
+        );
+
+        // Glyphon Text Renderer:
+        let swash_cache = SwashCache::new();
+        let text_cache = Cache::new(&device);
+        let text_viewport = Viewport::new(&device, &text_cache);
+        let mut text_atlas = TextAtlas::new(&device, &queue, &text_cache, surface_format.view);
+        let text_renderer = TextRenderer::new(
+            &mut text_atlas,
+            &device,
+            wgpu::MultisampleState::default(),
+            None,
+        );
+
+        let post = PostProcess::new(&device, surface_format.view, size.width, size.height);
+
+        let renderer = Self {
+            window,
+            instance,
+            adapter,
+            device,
+            queue,
+            size,
+            surface,
+            surface_format,
+            present_modes,
+            present_mode: wgpu::PresentMode::AutoVsync,
+
+            clear_color: None,
+            camera: None,
+            viewport: None,
+            layer: None,
+            page: 0,
+            base_vertex: 0,
+            batches: Vec::new(),
+
+            render_pipeline,
+            vertex_buffer,
+            index_buffer,
+            views,
+            staging_belt: wgpu::util::StagingBelt::new(STAGING_CHUNK),
+
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            current_index: 0, // the current vertex index. Will be used to create indicies
+
+            texture_layout,
+            sampler,
+            pages: vec![atlas_page],
+            atlas,
+            images: vec![texture::Image {
+                page: 0,
+                x: 0,
+                y: 0,
+                width: 1,
+                height: 1,
+            }],
+            texture_sources: vec![None],
+
+            // text renderer
+            font_system,
+            font: UiFont::default(),
+            swash_cache,
+            text_viewport,
+            text_atlas,
+            text_renderer,
+            text: Vec::new(),
+
+            post: Some(post),
+            post_settings: PostSettings::default(),
+            hud_inset: 0.0,
+            acquire_wait: Duration::ZERO,
+            render_scale: 1.0,
+            upscale: Upscale::Bilinear,
+            pixel_scale: None,
+            scanlines: false,
+            upscaler: None,
+            hud_batch: None,
+
+            out_of_memory,
+            quality: Quality::High,
+            quality_changed: false,
+
+            capture_pipeline: None,
+
+            dump_requested: false,
+            recording: None,
+            frame_dump: None,
+        };
+
+        renderer.configure_surface();
+
+        Ok(renderer)
+    }
+
+    fn configure_surface(&self) {
+        // Configuring a zero sized surface panics, e.g. while minimized on Windows
+        let Some(surface) = self.surface.as_ref() else {
+            return;
+        };
+        if self.size.width == 0 || self.size.height == 0 {
+            return;
+        }
+
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: self.surface_format.surface,
+            // Request compatibility with the sRGB-format texture view we‘re going to create later.
+            view_formats: vec![self.surface_format.view],
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            width: self.size.width,
+            height: self.size.height,
+            desired_maximum_frame_latency: 2,
+            present_mode: self.present_mode,
+        };
+
+        surface.configure(&self.device, &surface_config);
+    }
+
+    // Drops the surface. Required on Android, where the native window is
+    // destroyed while the app is in the background.
+    pub fn suspend(&mut self) {
+        self.surface = None;
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    // Creates a new surface for the window, keeping the device, pipelines and
+    // text atlas. Fails if the new surface can't use the old format.
+    pub fn resume(&mut self) -> Result<()> {
+        let Some(window) = self.window.clone() else {
+            return Ok(());
+        };
+        if self.surface.is_some() {
+            return Ok(());
+        }
+
+        let surface = self.instance.create_surface(window.clone())?;
+        let cap = surface.get_capabilities(&self.adapter);
+        if !cap.formats.contains(&self.surface_format.surface) {
+            anyhow::bail!(
+                "surface no longer supports {:?} after resume",
+                self.surface_format.surface
+            );
+        }
+
+        self.present_modes = cap.present_modes.clone();
+        if !self.supports_present_mode(self.present_mode) {
+            self.present_mode = wgpu::PresentMode::AutoVsync;
+        }
+        self.surface = Some(surface);
+
+        // The window may have changed size while we had no surface
+        self.resize(window.inner_size());
+        Ok(())
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        self.size = new_size;
+
+        self.text_viewport.update(
+            &self.queue,
+            Resolution {
+                width: self.size.width,
+                height: self.size.height,
+            },
+        );
+
+        // reconfigure the surface
+        self.configure_surface();
+        if let Some(post) = self.post.as_mut() {
+            post.resize(&self.device, self.size.width, self.size.height);
+        }
+        if let Some(upscaler) = self.upscaler.as_mut() {
+            upscaler.resize(&self.device, self.size.width, self.size.height);
+        }
+    }
+
+    // Switches to the first of `preferred` the surface supports, falling back to vsync.
+    pub fn set_present_mode(&mut self, preferred: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+        let mode = preferred
+            .iter()
+            .copied()
+            .find(|mode| self.supports_present_mode(*mode))
+            .unwrap_or(wgpu::PresentMode::AutoVsync);
+
+        if mode != self.present_mode {
+            self.present_mode = mode;
+            self.configure_surface();
+        }
+        mode
+    }
+
+    // The Auto modes are always accepted and resolve to a supported mode, the
+    // surface never lists them
+    fn supports_present_mode(&self, mode: wgpu::PresentMode) -> bool {
+        matches!(
+            mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+        ) || self.present_modes.contains(&mode)
+    }
+
+    // Time the last frame waited for a surface texture
+    pub fn acquire_wait(&self) -> Duration {
+        self.acquire_wait
+    }
+
+    pub fn surface_format(&self) -> SurfaceFormat {
+        self.surface_format
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.present_mode
+    }
+
+    pub fn font(&self) -> UiFont {
+        self.font
+    }
+
+    /// Switches the typeface of all text from the next `draw_text` on, also in
+    /// the middle of a frame. Text is laid out anew on every call, so widths
+    /// from `measure_text` follow right away. The glyphs rasterized for the
+    /// old font are dropped.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// use wgpu_pong::renderer::{Renderer, UiFont};
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// renderer.set_font(UiFont::Monospace);
+    /// assert_eq!(renderer.font(), UiFont::Monospace);
+    /// assert!(renderer.measure_text("iiii", 24., 32.) > 0.);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_font(&mut self, font: UiFont) {
+        if font == self.font {
+            return;
+        }
+        self.font = font;
+        // The atlas lets go of glyphs once a frame goes by without them, the
+        // rasterized images are only kept here
+        self.swash_cache = SwashCache::new();
+    }
+
+    /// Replaces the fonts all text is drawn with, e.g. the system's fonts
+    /// loaded on another thread with `FontSystem::new` for a renderer made
+    /// `without_fonts`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// use glyphon::FontSystem;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let fonts = std::thread::spawn(FontSystem::new).join().expect("fonts load");
+    /// renderer.set_font_system(fonts);
+    /// assert!(renderer.has_fonts());
+    /// assert!(renderer.measure_text("Pong", 24., 32.) > 0.);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_font_system(&mut self, font_system: FontSystem) {
+        self.font_system = font_system;
+        self.swash_cache = SwashCache::new();
+    }
+
+    /// A copy of the fonts text is drawn with, for another renderer's
+    /// `set_font_system`. The fonts aren't looked up again.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// let mut other = pollster::block_on(Renderer::headless(64, 64))?;
+    /// other.set_font_system(renderer.clone_font_system());
+    /// assert_eq!(other.has_fonts(), renderer.has_fonts());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clone_font_system(&self) -> FontSystem {
+        FontSystem::new_with_locale_and_db(
+            self.font_system.locale().to_string(),
+            self.font_system.db().clone(),
+        )
+    }
+
+    /// Whether there are fonts to draw text with, see `without_fonts`.
+    pub fn has_fonts(&self) -> bool {
+        !self.font_system.db().is_empty()
+    }
+
+    // Exponent applied to the final image, 1.0 leaves colors untouched
+    pub fn gamma(&self) -> f32 {
+        self.post_settings.gamma
+    }
+
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.post_settings.gamma = gamma.max(0.01);
+    }
+
+    // Multiplier applied to the final image, 1.0 leaves colors untouched
+    pub fn brightness(&self) -> f32 {
+        self.post_settings.brightness
+    }
+
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.post_settings.brightness = brightness.max(0.0);
+    }
+
+    // Where HUD elements go, inset from the window edges
+    pub fn safe_area(&self) -> Rect {
+        safe_area::safe_area(
+            self.size.width as f32,
+            self.size.height as f32,
+            self.window
+                .as_ref()
+                .map_or(1.0, |window| window.scale_factor() as f32),
+            self.hud_inset,
+        )
+    }
+
+    pub fn hud_inset(&self) -> f32 {
+        self.hud_inset
+    }
+
+    pub fn set_hud_inset(&mut self, inset: f32) {
+        self.hud_inset = inset.clamp(0.0, MAX_INSET);
+    }
+
+    // Filters need post processing, so they are ignored at reduced quality
+    pub fn set_filter(&mut self, filter: PostFilter) {
+        self.post_settings.filter = filter;
+    }
+
+    // Renders the scene below the window resolution and stretches it back
+    // up with `upscale`, while the HUD and text stay sharp. Like filters
+    // this needs post processing and is ignored at reduced quality.
+    pub fn set_render_scale(&mut self, scale: f32, upscale: Upscale) {
+        self.render_scale = scale.clamp(MIN_RENDER_SCALE, 1.0);
+        self.upscale = upscale;
+        if self.render_scale < 1.0 {
+            self.create_upscaler();
+        }
+    }
+
+    // Renders the scene at a `scale`th of the window resolution and blows
+    // it up with nearest neighbor, optionally with scanlines, for a retro
+    // look. The HUD and text stay sharp like with `set_render_scale`.
+    pub fn set_pixel_art(&mut self, scale: Option<u32>, scanlines: bool) {
+        self.pixel_scale = scale.map(|scale| scale.max(1));
+        self.scanlines = scanlines;
+        if self.pixel_scale.is_some() {
+            self.create_upscaler();
+        }
+    }
+
+    fn create_upscaler(&mut self) {
+        if self.upscaler.is_none() && self.post.is_some() {
+            self.upscaler = Some(Upscaler::new(
+                &self.device,
+                self.surface_format.view,
+                self.size.width,
+                self.size.height,
+            ));
+        }
+    }
+
+    // Size of the scene relative to the window and how it's stretched back
+    // up, None when it's drawn at the window resolution
+    fn scene_scale(&self) -> Option<(f32, UpscaleFilter)> {
+        match self.pixel_scale {
+            Some(scale) => Some((
+                1.0 / scale as f32,
+                UpscaleFilter::Pixelated {
+                    scanlines: self.scanlines,
+                },
+            )),
+            None => (self.render_scale < 1.0)
+                .then_some((self.render_scale, UpscaleFilter::Smooth(self.upscale))),
+        }
+    }
+
+    /// Everything drawn after this in the frame is HUD and not affected by the
+    /// render scale.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// renderer.set_render_scale(0.5, wgpu_pong::renderer::Upscale::Bilinear);
+    /// renderer.draw_circle(Vector2::new(32., 32.), 16., Srgba::new(1., 1., 1., 1.));
+    /// renderer.begin_hud();
+    /// renderer.draw_rectangle(Vector2::new(0., 0.), 8., 8., Srgba::new(1., 0., 0., 1.), Deg(0.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(4, 4), [255, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn begin_hud(&mut self) {
+        self.record(|| DrawCommand::Hud);
+        if self.hud_batch.is_none() {
+            self.push_batch();
+            self.hud_batch = Some(self.batches.len() - 1);
+        }
+    }
+
+    /// Draws the following shapes through `camera`, or in window pixels for
+    /// None. Text is not affected.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// use wgpu_pong::renderer::Camera2D;
+    ///
+    /// // The world around 8, 8 twice as large
+    /// let camera = Camera2D { center: Vector2::new(8., 8.), zoom: 2. };
+    /// renderer.set_camera(Some(camera));
+    /// renderer.draw_rectangle(Vector2::new(8., 8.), 4., 4., Srgba::new(1., 0., 0., 1.), Deg(0.));
+    /// renderer.set_camera(None);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(38, 38), [255, 0, 0, 255]);
+    /// assert_eq!(image.pixel(10, 10), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_camera(&mut self, camera: Option<Camera2D>) {
+        self.record(|| DrawCommand::Camera {
+            camera: frame_dump::camera(camera),
+        });
+        if camera == self.camera {
+            return;
+        }
+        self.camera = camera;
+        self.push_batch();
+    }
+
+    /// Moves and fades everything drawn after it, see `Layer`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// use wgpu_pong::renderer::Layer;
+    ///
+    /// renderer.set_layer(Some(Layer { offset: Vector2::new(32., 0.), opacity: 0.5 }));
+    /// let white = Srgba::new(1., 1., 1., 1.);
+    /// renderer.draw_rectangle(Vector2::new(0., 0.), 16., 16., white, Deg(0.));
+    /// renderer.set_layer(None);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(8, 8), [0, 0, 0, 255]);
+    /// let [red, ..] = image.pixel(40, 8);
+    /// assert!(red > 0 && red < 255);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_layer(&mut self, layer: Option<Layer>) {
+        self.record(|| DrawCommand::Layer {
+            layer: frame_dump::layer(layer),
+        });
+        if layer == self.layer {
+            return;
+        }
+        self.layer = layer;
+        self.push_batch();
+    }
+
+    /// Draws the following shapes into `viewport`, e.g. one view per player for
+    /// split-screen. Shape positions (and the camera) are then relative to the
+    /// viewport, and anything outside of it is clipped. Text is not affected.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// use wgpu_pong::renderer::Rect;
+    ///
+    /// renderer.set_viewport(Some(Rect::new(32., 0., 32., 64.)));
+    /// let red = Srgba::new(1., 0., 0., 1.);
+    /// renderer.draw_rectangle(Vector2::new(0., 0.), 64., 8., red, Deg(0.));
+    /// renderer.set_viewport(None);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(16, 4), [0, 0, 0, 255]);
+    /// assert_eq!(image.pixel(48, 4), [255, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_viewport(&mut self, viewport: Option<Rect>) {
+        self.record(|| DrawCommand::Viewport {
+            viewport: viewport.map(frame_dump::rect),
+        });
+        if viewport == self.viewport {
+            return;
+        }
+        self.viewport = viewport;
+        self.push_batch();
+    }
+
+    // Samples `texture` in the following shapes. Untextured shapes switch
+    // back to the white texture, so batches only break where needed.
+    fn use_texture(&mut self, texture: TextureHandle) {
+        let page = self.images.get(texture.0).map_or(0, |image| image.page);
+        if page == self.page {
+            return;
+        }
+        self.page = page;
+        self.push_batch();
+    }
+
+    // Starts a batch at the next shape with the current viewport, texture,
+    // camera and layer
+    fn push_batch(&mut self) {
+        self.batches.push(Batch {
+            start: self.indices.len() as u32,
+            base_vertex: self.base_vertex,
+            viewport: self.viewport,
+            page: self.page,
+            view: self.view_matrix(),
+        });
+    }
+
+    // Maps shape coordinates to clip space: through the camera, moved by
+    // the layer and projected onto the viewport (or the window)
+    fn view_matrix(&self) -> Matrix4<f32> {
+        let size = match self.viewport {
+            Some(viewport) => Vector2::new(viewport.width, viewport.height),
+            None => Vector2::new(self.size.width as f32, self.size.height as f32),
+        };
+        let size = Vector2::new(size.x.max(1.0), size.y.max(1.0));
+        let camera = self
+            .camera
+            .map_or(Matrix4::identity(), |camera| camera.matrix(size));
+        let offset = self
+            .layer
+            .map_or(Vector2::new(0.0, 0.0), |layer| layer.offset);
+        cgmath::ortho(0.0, size.x, size.y, 0.0, -1.0, 1.0)
+            * Matrix4::from_translation(offset.extend(0.0))
+            * camera
+    }
+
+    /// Decodes a PNG file and uploads it for `draw_texture`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let path = std::env::temp_dir().join("wgpu-pong-doc-texture.png");
+    /// renderer.clear_color(Srgba::new(1., 0., 0., 1.));
+    /// renderer.render_offscreen()?.save_png(&path)?;
+    ///
+    /// let texture = renderer.load_texture(&path)?;
+    /// assert_eq!(renderer.texture_size(texture), (64, 64));
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_texture(&mut self, path: &Path) -> Result<TextureHandle> {
+        let image = DecodedImage::load(path)?;
+        self.upload_image(&image)
+            .with_context(|| format!("cannot load texture {}", path.display()))
+    }
+
+    /// Decodes PNG data, e.g. from `include_bytes!`, and uploads it for
+    /// `draw_texture`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// # let mut png = Vec::new();
+    /// # {
+    /// #     let mut encoder = png::Encoder::new(&mut png, 2, 1);
+    /// #     encoder.set_color(png::ColorType::Rgba);
+    /// #     encoder.set_depth(png::BitDepth::Eight);
+    /// #     encoder.write_header()?.write_image_data(&[255, 0, 0, 255, 0, 0, 255, 255])?;
+    /// # }
+    ///
+    /// // Two pixels, red and blue
+    /// let texture = renderer.load_texture_from_bytes(&png)?;
+    /// assert_eq!(renderer.texture_size(texture), (2, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_texture_from_bytes(&mut self, bytes: &[u8]) -> Result<TextureHandle> {
+        self.upload_image(&DecodedImage::from_png(bytes)?)
+    }
+
+    /// Uploads an image decoded ahead of time for `draw_texture`. Only the
+    /// upload has to happen here, large images can be read and decoded on
+    /// another thread.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use palette::Srgba;
+    /// use wgpu_pong::renderer::{DecodedImage, Renderer};
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let path = std::env::temp_dir().join("wgpu-pong-doc-upload.png");
+    /// renderer.clear_color(Srgba::new(0., 1., 0., 1.));
+    /// renderer.render_offscreen()?.save_png(&path)?;
+    ///
+    /// let decoding = std::thread::spawn({
+    ///     let path = path.clone();
+    ///     move || DecodedImage::load(&path)
+    /// });
+    /// let image = decoding.join().expect("decoding finishes")?;
+    /// let texture = renderer.upload_image(&image)?;
+    /// assert_eq!(renderer.texture_size(texture), (64, 64));
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn upload_image(&mut self, image: &DecodedImage) -> Result<TextureHandle> {
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        if image.width > max_size || image.height > max_size {
+            anyhow::bail!(
+                "texture is {}x{}, the GPU supports up to {}x{}",
+                image.width,
+                image.height,
+                max_size,
+                max_size
+            );
+        }
+        let texture = self.add_texture(image.width, image.height, &image.rgba);
+        self.texture_sources[texture.0] = image.source.clone();
+        Ok(texture)
+    }
+
+    // Uploads decoded RGBA pixels, `width` and `height` must be in the
+    // device limits.
+    fn add_texture(&mut self, width: u32, height: u32, rgba: &[u8]) -> TextureHandle {
+        let image = match self.add_to_atlas(width, height, rgba) {
+            Some(image) => image,
+            None => {
+                let page = texture::create_texture(
+                    &self.device,
+                    &self.texture_layout,
+                    &self.sampler,
+                    width,
+                    height,
+                );
+                texture::write_pixels(&self.queue, &page, 0, 0, width, height, rgba);
+                self.pages.push(page);
+                texture::Image {
+                    page: self.pages.len() - 1,
+                    x: 0,
+                    y: 0,
+                    width,
+                    height,
+                }
+            }
+        };
+        self.images.push(image);
+        self.texture_sources.push(None);
+        TextureHandle(self.images.len() - 1)
+    }
+
+    // Packs a small texture into the atlas, None if it belongs on its own page
+    fn add_to_atlas(&mut self, width: u32, height: u32, rgba: &[u8]) -> Option<texture::Image> {
+        if width > ATLAS_SPRITE_MAX || height > ATLAS_SPRITE_MAX {
+            return None;
+        }
+        let Some((x, y)) = self.atlas.allocate(width + 2, height + 2) else {
+            self.atlas.spill();
+            info!("Sprite atlas is full: {}", self.atlas.stats());
+            return None;
+        };
+        if self.atlas.size() > self.pages[0].width {
+            self.grow_atlas();
+        }
+        let bordered = texture::with_border(width, height, rgba);
+        texture::write_pixels(
+            &self.queue,
+            &self.pages[0],
+            x,
+            y,
+            width + 2,
+            height + 2,
+            &bordered,
+        );
+        Some(texture::Image {
+            page: 0,
+            x: x + 1,
+            y: y + 1,
+            width,
+            height,
+        })
+    }
+
+    // Moves the atlas to a texture of its new size, sprites keep their place
+    fn grow_atlas(&mut self) {
+        let size = self.atlas.size();
+        let page = texture::create_texture(
+            &self.device,
+            &self.texture_layout,
+            &self.sampler,
+            size,
+            size,
+        );
+        let old = &self.pages[0];
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_texture(
+            old.texture.as_image_copy(),
+            page.texture.as_image_copy(),
+            wgpu::Extent3d {
+                width: old.width,
+                height: old.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit([encoder.finish()]);
+        self.pages[0] = page;
+        info!("Sprite atlas grew: {}", self.atlas.stats());
+    }
+
+    // Width and height in texture pixels
+    pub fn texture_size(&self, texture: TextureHandle) -> (u32, u32) {
+        self.images
+            .get(texture.0)
+            .map_or((1, 1), |image| (image.width, image.height))
+    }
+
+    // Returns the new quality once after it was lowered, for notifying the player.
+    pub fn take_quality_change(&mut self) -> Option<Quality> {
+        std::mem::take(&mut self.quality_changed).then_some(self.quality)
+    }
+
+    // Steps down one quality level and frees what it no longer needs.
+    fn degrade(&mut self) {
+        let Some(quality) = self.quality.lower() else {
+            return warn!("Out of GPU memory at the lowest quality");
+        };
+        warn!("Out of GPU memory, lowering quality to {:?}", quality);
+
+        self.quality = quality;
+        self.quality_changed = true;
+        if !quality.post_processing() {
+            self.post = None;
+            self.upscaler = None;
+        }
+    }
+
+    // Whether a shape with this many vertices and indices still fits into
+    // this frame's budget. Shapes that don't are skipped.
+    fn has_room(&self, vertices: usize, indices: usize) -> bool {
+        let (max_vertices, max_indices) = self.shape_limits();
+        self.vertices.len() + vertices <= max_vertices
+            // one spare index for the u16 padding
+            && self.indices.len() + indices < max_indices
+    }
+
+    fn shape_limits(&self) -> (usize, usize) {
+        let budget = self.quality.shape_budget();
+        (
+            (MAX_VERTICES as f32 * budget) as usize,
+            (MAX_INDICES as f32 * budget) as usize,
+        )
+    }
+
+    // Checks `has_room` and makes sure the shape's vertices can be indexed,
+    // splitting off a new batch once the current one has used up the u16
+    // indices.
+    fn reserve(&mut self, vertices: usize, indices: usize) -> bool {
+        if !self.has_room(vertices, indices) {
+            return false;
+        }
+        if self.current_index as usize + vertices > BATCH_VERTICES {
+            self.base_vertex = self.vertices.len() as i32;
+            self.current_index = 0;
+            self.push_batch();
+        }
+        true
+    }
+
+    /// The background of the frame, kept for the next frames until it is set
+    /// again.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// renderer.clear_color(Srgba::new(0., 1., 0., 1.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(0, 0), [0, 255, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clear_color(&mut self, color: Srgba) {
+        self.record(|| DrawCommand::ClearColor {
+            color: frame_dump::color(color),
+        });
+        self.clear_color = Some(color);
+    }
+
+    /// Starts a new frame, dropping the shapes and text of the last one. Every
+    /// frame is drawn between this and `end_drawing`, or `render_offscreen`
+    /// without a window.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// renderer.draw_circle(Vector2::new(32., 32.), 8., Srgba::new(1., 1., 1., 1.));
+    /// renderer.begin_drawing();
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(32, 32), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn begin_drawing(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.current_index = 0;
+        self.text.clear();
+        self.viewport = None;
+        self.layer = None;
+        self.page = 0;
+        self.base_vertex = 0;
+        self.batches.clear();
+        self.push_batch();
+        self.hud_batch = None;
+
+        // The camera and clear color carry over from the last frame
+        if std::mem::take(&mut self.dump_requested) {
+            let mut commands = vec![DrawCommand::Camera {
+                camera: frame_dump::camera(self.camera),
+            }];
+            if let Some(color) = self.clear_color {
+                commands.push(DrawCommand::ClearColor {
+                    color: frame_dump::color(color),
+                });
+            }
+            self.recording = Some(commands);
+        }
+    }
+
+    // Records every draw call of the next frame, see `take_frame_dump`.
+    pub fn request_frame_dump(&mut self) {
+        self.dump_requested = true;
+    }
+
+    // The frame recorded after `request_frame_dump`, once it was drawn.
+    pub fn take_frame_dump(&mut self) -> Option<FrameDump> {
+        self.frame_dump.take()
+    }
+
+    fn finish_recording(&mut self) {
+        if let Some(commands) = self.recording.take() {
+            self.frame_dump = Some(FrameDump {
+                width: self.size.width,
+                height: self.size.height,
+                textures: self.texture_sources.clone(),
+                commands,
+            });
+        }
+    }
+
+    fn record(&mut self, command: impl FnOnce() -> DrawCommand) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(command());
+        }
+    }
+
+    // Makes the draw calls of a dump again, textures are loaded from their
+    // files first. Call `end_drawing` or `render_offscreen` afterwards.
+    pub fn replay_frame(&mut self, dump: &FrameDump) {
+        let textures: Vec<TextureHandle> = dump
+            .textures
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                if index == texture::WHITE.0 {
+                    return texture::WHITE;
+                }
+                let loaded = match path {
+                    Some(path) => self
+                        .load_texture(path)
+                        .map_err(|err| warn!("{:#}", err))
+                        .ok(),
+                    None => None,
+                };
+                loaded.unwrap_or_else(|| self.add_texture(1, 1, &MISSING_TEXTURE))
+            })
+            .collect();
+
+        self.begin_drawing();
+        for command in &dump.commands {
+            match command {
+                DrawCommand::ClearColor { color } => self.clear_color(frame_dump::to_color(*color)),
+                DrawCommand::Camera { camera } => self.set_camera(frame_dump::to_camera(*camera)),
+                DrawCommand::Layer { layer } => self.set_layer(frame_dump::to_layer(*layer)),
+                DrawCommand::Viewport { viewport } => {
+                    self.set_viewport(viewport.map(frame_dump::to_rect))
+                }
+                DrawCommand::Hud => self.begin_hud(),
+                DrawCommand::Rectangle {
+                    pos,
+                    width,
+                    height,
+                    color,
+                    rotation,
+                } => self.draw_rectangle(
+                    frame_dump::to_vector(*pos),
+                    *width,
+                    *height,
+                    frame_dump::to_color(*color),
+                    Deg(*rotation),
+                ),
+                DrawCommand::Quads { quads } => self.draw_quads(&frame_dump::to_quads(quads)),
+                DrawCommand::Triangle {
+                    points,
+                    color,
+                    rotation,
+                } => self.draw_triangle(
+                    frame_dump::to_vector(points[0]),
+                    frame_dump::to_vector(points[1]),
+                    frame_dump::to_vector(points[2]),
+                    frame_dump::to_color(*color),
+                    Deg(*rotation),
+                ),
+                DrawCommand::Circle {
+                    center,
+                    radius,
+                    color,
+                } => self.draw_circle(
+                    frame_dump::to_vector(*center),
+                    *radius,
+                    frame_dump::to_color(*color),
+                ),
+                DrawCommand::Line {
+                    start,
+                    end,
+                    thickness,
+                    color,
+                } => self.draw_line(
+                    frame_dump::to_vector(*start),
+                    frame_dump::to_vector(*end),
+                    *thickness,
+                    frame_dump::to_color(*color),
+                ),
+                DrawCommand::DashedLine {
+                    start,
+                    end,
+                    thickness,
+                    dash,
+                    gap,
+                    color,
+                } => self.draw_dashed_line(
+                    frame_dump::to_vector(*start),
+                    frame_dump::to_vector(*end),
+                    *thickness,
+                    *dash,
+                    *gap,
+                    frame_dump::to_color(*color),
+                ),
+                DrawCommand::Polyline {
+                    points,
+                    thickness,
+                    color,
+                    join,
+                } => {
+                    let points: Vec<Vector2<f32>> =
+                        points.iter().copied().map(frame_dump::to_vector).collect();
+                    self.draw_polyline(&points, *thickness, frame_dump::to_color(*color), *join)
+                }
+                DrawCommand::Ring {
+                    center,
+                    radius,
+                    thickness,
+                    color,
+                } => self.draw_ring(
+                    frame_dump::to_vector(*center),
+                    *radius,
+                    *thickness,
+                    frame_dump::to_color(*color),
+                ),
+                DrawCommand::RectangleOutline {
+                    pos,
+                    width,
+                    height,
+                    thickness,
+                    color,
+                } => self.draw_rectangle_outline(
+                    frame_dump::to_vector(*pos),
+                    *width,
+                    *height,
+                    *thickness,
+                    frame_dump::to_color(*color),
+                ),
+                DrawCommand::Texture {
+                    texture,
+                    dest,
+                    source,
+                    tint,
+                    rotation,
+                    flip_x,
+                    flip_y,
+                } => {
+                    let texture = textures.get(*texture).copied().unwrap_or(texture::WHITE);
+                    let params =
+                        frame_dump::texture_params(*source, *tint, *rotation, *flip_x, *flip_y);
+                    self.draw_texture_ex(texture, frame_dump::to_rect(*dest), &params);
+                }
+                DrawCommand::Text {
+                    text,
+                    pos,
+                    font_size,
+                    line_height,
+                    color,
+                } => self.draw_text(
+                    text,
+                    frame_dump::to_vector(*pos),
+                    *font_size,
+                    *line_height,
+                    color.map(|[r, g, b, a]| glyphon::Color::rgba(r, g, b, a)),
+                ),
+            }
+        }
+    }
+
+    pub fn end_drawing(&mut self) -> Result<()> {
+        if self.out_of_memory.swap(false, Ordering::Relaxed) {
+            self.degrade();
+        }
+        self.finish_recording();
+
+        // Nothing to draw to while suspended
+        let Some(surface) = self.surface.as_ref() else {
+            return Ok(());
+        };
+        // Blocks under vsync until the display takes a frame
+        let acquire_started = Instant::now();
+        let surface_texture = surface.get_current_texture();
+        self.acquire_wait = acquire_started.elapsed();
+        let surface_texture = match surface_texture {
+            Ok(surface_texture) => surface_texture,
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                self.degrade();
+                return Err(wgpu::SurfaceError::OutOfMemory.into());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let texture_view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor {
+                // Without the sRGB view the image we will be working with
+                // might not be "gamma correct".
+                format: Some(self.surface_format.view),
+                ..Default::default()
+            });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        self.prepare_frame(&mut encoder)?;
+
+        // Without post processing the scene goes straight to the surface
+        let target = match self.post.as_ref() {
+            Some(post) => post.target(),
+            None => &texture_view,
+        };
+        let mut load = wgpu::LoadOp::Clear(self.wgpu_clear_color());
+        let mut hud = 0..self.batches.len();
+
+        // A lowered resolution renders the scene into a part of its own
+        // target first, which is then stretched over the full size one
+        if let Some(upscaler) = self.upscaler.as_ref()
+            && self.post.is_some()
+            && let Some((scale, filter)) = self.scene_scale()
+        {
+            let scene = 0..self.hud_batch.unwrap_or(self.batches.len());
+            hud = scene.end..self.batches.len();
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Scaled Scene Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: upscaler.target(),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.draw_shapes(&mut render_pass, &self.render_pipeline, scale, scene);
+            drop(render_pass);
+
+            upscaler.render(&self.queue, &mut encoder, target, scale, filter);
+            load = wgpu::LoadOp::Load;
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        // Drawing:
+        self.draw_shapes(&mut render_pass, &self.render_pipeline, 1.0, hud);
+
+        // Draw Text
+        self.text_renderer
+            .render(&self.text_atlas, &self.text_viewport, &mut render_pass)?;
+
+        // End the renderpass.
+        drop(render_pass);
+
+        // Copy the scene onto the surface with brightness/gamma applied
+        if let Some(post) = self.post.as_ref() {
+            post.render(
+                &self.queue,
+                &mut encoder,
+                &texture_view,
+                &self.post_settings,
+            );
+        }
+
+        // Submit the command in the queue to execute
+        self.staging_belt.finish();
+        self.queue.submit([encoder.finish()]);
+        self.staging_belt.recall();
+        if let Some(window) = self.window.as_ref() {
+            window.pre_present_notify();
+        }
+        surface_texture.present();
+
+        // Trim the text_atlas to free up unused space
+        self.text_atlas.trim();
+
+        Ok(())
+    }
+
+    // Uploads this frame's shapes and lays out its text.
+    fn prepare_frame(&mut self, encoder: &mut wgpu::CommandEncoder) -> Result<()> {
+        let text_areas: Vec<TextArea> = self
+            .text
+            .iter()
+            .map(|element| TextArea {
+                buffer: &element.buffer,
+                left: element.position.x,
+                top: element.position.y,
+                scale: 1.0,
+                bounds: element.bounds,
+                default_color: element.color,
+                custom_glyphs: &[],
+            })
+            .collect();
+
+        // Only prepare text renderer if we have text to render
+        if !text_areas.is_empty() {
+            self.text_renderer.prepare(
+                &self.device,
+                &self.queue,
+                &mut self.font_system,
+                &mut self.text_atlas,
+                &self.text_viewport,
+                text_areas,
+                &mut self.swash_cache,
+            )?;
+        }
+
+        // Update Drawing Data with vertices & indices:
+        if !self.indices.len().is_multiple_of(2) {
+            // pad indicies to align with u16
+            self.indices.push(0)
+        }
+        self.vertex_buffer.write(
+            &self.device,
+            encoder,
+            &mut self.staging_belt,
+            bytemuck::cast_slice(&self.vertices),
+        );
+        self.index_buffer.write(
+            &self.device,
+            encoder,
+            &mut self.staging_belt,
+            bytemuck::cast_slice(&self.indices),
+        );
+        let views: Vec<Matrix4<f32>> = self.batches.iter().map(|batch| batch.view).collect();
+        self.views
+            .write(&self.device, encoder, &mut self.staging_belt, &views);
+        Ok(())
+    }
+
+    /// Draws the frame into a texture instead of the surface and reads it back,
+    /// for renderers without a window. Call instead of `end_drawing`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let red = Srgba::new(1., 0., 0., 1.);
+    /// renderer.draw_rectangle(Vector2::new(0., 0.), 32., 64., red, Deg(0.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(16, 32), [255, 0, 0, 255]);
+    /// assert_eq!(image.pixel(48, 32), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_offscreen(&mut self) -> Result<CapturedImage> {
+        self.finish_recording();
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        self.prepare_frame(&mut encoder)?;
+        self.staging_belt.finish();
+        self.queue.submit([encoder.finish()]);
+        self.staging_belt.recall();
+        self.capture(CaptureKind::Opaque, 1)
+    }
+
+    fn wgpu_clear_color(&self) -> wgpu::Color {
+        let clear_color = self
+            .clear_color
+            .unwrap_or(Srgba::new(0., 0., 0., 1.))
+            .into_linear();
+
+        wgpu::Color {
+            r: clear_color.red as f64,
+            g: clear_color.green as f64,
+            b: clear_color.blue as f64,
+            a: clear_color.alpha,
+        }
+    }
+
+    /// Renders the last finished frame again into an offscreen texture and
+    /// reads it back. Call after `end_drawing`. Shapes are stored in normalized
+    /// coordinates, so `scale` renders them at a multiple of the window size.
+    /// Text is laid out for the window and only included at scale 1.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// use wgpu_pong::renderer::CaptureKind;
+    ///
+    /// renderer.clear_color(Srgba::new(0., 0., 1., 1.));
+    /// renderer.draw_circle(Vector2::new(32., 32.), 16., Srgba::new(1., 1., 1., 1.));
+    /// renderer.render_offscreen()?;
+    /// let image = renderer.capture(CaptureKind::Transparent, 2)?;
+    /// assert_eq!((image.width, image.height), (128, 128));
+    /// assert_eq!(image.pixel(0, 0)[3], 0);
+    /// assert_eq!(image.pixel(64, 64), [255, 255, 255, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn capture(&mut self, kind: CaptureKind, scale: u32) -> Result<CapturedImage> {
+        // Transparent captures use an RGBA target no matter the surface format,
+        // so the alpha channel survives.
+        let format = match kind {
+            CaptureKind::Opaque => self.surface_format.view,
+            CaptureKind::Transparent => wgpu::TextureFormat::Rgba8UnormSrgb,
+        };
+        if kind == CaptureKind::Transparent && self.capture_pipeline.is_none() {
+            self.capture_pipeline = Some(create_shape_pipeline(
+                &self.device,
+                format,
+                wgpu::BlendState::ALPHA_BLENDING,
+                &self.texture_layout,
+                self.views.layout(),
+            ));
+        }
+
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        let largest_side = self.size.width.max(self.size.height).max(1);
+        let scale = scale.clamp(1, (max_size / largest_side).max(1));
+        let size = wgpu::Extent3d {
+            width: self.size.width.max(1) * scale,
+            height: self.size.height.max(1) * scale,
+            depth_or_array_layers: 1,
+        };
+        let create_target = |label| {
+            self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+        };
+        let texture = create_target("Capture Target");
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let (clear, pipeline) = match (kind, self.capture_pipeline.as_ref()) {
+            (CaptureKind::Transparent, Some(pipeline)) => (wgpu::Color::TRANSPARENT, pipeline),
+            _ => (self.wgpu_clear_color(), &self.render_pipeline),
+        };
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Capture Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(clear),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            // The vertex and index buffers still hold the last frame
+            self.draw_shapes(
+                &mut render_pass,
+                pipeline,
+                scale as f32,
+                0..self.batches.len(),
+            );
+
+            // Text is prepared for the surface format and size only
+            if kind == CaptureKind::Opaque && scale == 1 {
+                self.text_renderer.render(
+                    &self.text_atlas,
+                    &self.text_viewport,
+                    &mut render_pass,
+                )?;
+            }
+        }
+
+        // Opaque captures look like the screen, including brightness/gamma and filters
+        let post = self.post.as_ref().filter(|_| kind == CaptureKind::Opaque);
+        let texture = match post {
+            Some(post) => {
+                let output = create_target("Capture Post Target");
+                post.render_source(
+                    &self.device,
+                    &self.queue,
+                    &mut encoder,
+                    &view,
+                    &output.create_view(&wgpu::TextureViewDescriptor::default()),
+                    &self.post_settings,
+                );
+                output
+            }
+            None => texture,
+        };
+
+        capture::read_texture(
+            &self.device,
+            &self.queue,
+            encoder,
+            &texture,
+            kind == CaptureKind::Transparent,
+        )
+    }
+
+    // Issues the buffered shapes batch by batch, each into its viewport.
+    // `scale` is the size of the target relative to the window, `batches`
+    // the part of the frame to draw.
+    fn draw_shapes(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        pipeline: &wgpu::RenderPipeline,
+        scale: f32,
+        batches: Range<usize>,
+    ) {
+        if self.indices.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.buffer().slice(..));
+        render_pass.set_index_buffer(
+            self.index_buffer.buffer().slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+
+        let full = Rect::new(0.0, 0.0, self.size.width as f32, self.size.height as f32);
+        for (index, batch) in self.batches.iter().enumerate() {
+            if !batches.contains(&index) {
+                continue;
+            }
+            let end = self
+                .batches
+                .get(index + 1)
+                .map_or(self.indices.len() as u32, |next| next.start);
+            // wgpu rejects viewports that leave the target
+            let rect = batch.viewport.unwrap_or(full).intersect(&full);
+            if end > batch.start
+                && rect.width > 0.0
+                && rect.height > 0.0
+                && let Some(texture) = self.pages.get(batch.page)
+            {
+                render_pass.set_viewport(
+                    rect.x * scale,
+                    rect.y * scale,
+                    rect.width * scale,
+                    rect.height * scale,
+                    0.0,
+                    1.0,
+                );
+                render_pass.set_bind_group(0, &texture.bind_group, &[]);
+                render_pass.set_bind_group(1, self.views.bind_group(), &[self.views.offset(index)]);
+                render_pass.draw_indexed(batch.start..end, batch.base_vertex, 0..1);
+            }
+        }
+
+        // Text is drawn into the whole window
+        render_pass.set_viewport(0.0, 0.0, full.width * scale, full.height * scale, 0.0, 1.0);
+    }
+
+    fn layer_color(&self, color: Srgba) -> Srgba {
+        layer_color(self.layer, color)
+    }
+
+    /// A filled rectangle with its top left corner at `pos`, turned by
+    /// `rotation` around its center.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let red = Srgba::new(1., 0., 0., 1.);
+    /// renderer.draw_rectangle(Vector2::new(16., 28.), 32., 8., red, Deg(90.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(32, 18), [255, 0, 0, 255]);
+    /// assert_eq!(image.pixel(20, 32), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_rectangle(
+        &mut self,
+        pos: Vector2<f32>,
+        width: f32,
+        height: f32,
+        color: Srgba,
+        rotation: Deg<f32>,
+    ) {
+        self.record(|| DrawCommand::Rectangle {
+            pos: frame_dump::vector(pos),
+            width,
+            height,
+            color: frame_dump::color(color),
+            rotation: rotation.0,
+        });
+        if !self.reserve(4, 6) {
+            return;
+        }
+        self.use_texture(texture::WHITE);
+        let color = self.layer_color(color);
+
+        // Define corners in local space (relative to center)
+        let origin = Vector2::new(pos.x + width / 2.0, pos.y + height / 2.0);
+        let half_width = width / 2.0;
+        let half_height = height / 2.0;
+        let local_top_left = Vector2::new(-half_width, -half_height);
+        let local_top_right = Vector2::new(half_width, -half_height);
+        let local_bottom_right = Vector2::new(half_width, half_height);
+        let local_bottom_left = Vector2::new(-half_width, half_height);
+
+        // Apply rotation and translate back to world space
+        let rotation_matrix = Matrix2::from_angle(rotation);
+        let rotated_top_left = rotation_matrix * local_top_left + origin;
+        let rotated_top_right = rotation_matrix * local_top_right + origin;
+        let rotated_bottom_right = rotation_matrix * local_bottom_right + origin;
+        let rotated_bottom_left = rotation_matrix * local_bottom_left + origin;
+
+        // Create Rectangle (Vertices):
+        self.vertices.push(Vertex {
+            position: (rotated_top_left).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+        self.vertices.push(Vertex {
+            position: (rotated_top_right).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+        self.vertices.push(Vertex {
+            position: (rotated_bottom_right).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+        self.vertices.push(Vertex {
+            position: (rotated_bottom_left).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+
+        // Create Rectangle (Indices)
+        self.indices.push(self.current_index + 2);
+        self.indices.push(self.current_index + 1);
+        self.indices.push(self.current_index);
+        self.indices.push(self.current_index + 3);
+        self.indices.push(self.current_index + 2);
+        self.indices.push(self.current_index);
+
+        self.current_index += 4;
+    }
+
+    /// Draws many axis aligned squares at once, e.g. particles. Vertices are
+    /// built in parallel chunks and appended in order. Squares that don't fit
+    /// into this frame's budget are skipped.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// use wgpu_pong::renderer::Quad;
+    ///
+    /// let quads: Vec<Quad> = (0..4)
+    ///     .map(|index| Quad {
+    ///         center: Vector2::new(8. + index as f32 * 16., 32.),
+    ///         size: 8.,
+    ///         color: Srgba::new(1., 1., 1., 1.),
+    ///     })
+    ///     .collect();
+    /// renderer.draw_quads(&quads);
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(56, 32), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(16, 32), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_quads(&mut self, quads: &[Quad]) {
+        self.record(|| DrawCommand::Quads {
+            quads: frame_dump::quads(quads),
+        });
+        let (max_vertices, max_indices) = self.shape_limits();
+        let room = (max_vertices.saturating_sub(self.vertices.len()) / 4)
+            // one spare index for the u16 padding
+            .min(max_indices.saturating_sub(self.indices.len() + 1) / 6);
+        let quads = &quads[..quads.len().min(room)];
+        if quads.is_empty() {
+            return;
+        }
+        self.use_texture(texture::WHITE);
+
+        let layer = self.layer;
+        // As many squares as one batch can index
+        for quads in quads.chunks(BATCH_VERTICES / 4) {
+            if !self.reserve(quads.len() * 4, quads.len() * 6) {
+                return;
+            }
+            let chunks: Vec<Vec<Vertex>> = quads
+                .par_chunks(QUAD_CHUNK)
+                .map(|chunk| {
+                    let mut vertices = Vec::with_capacity(chunk.len() * 4);
+                    for quad in chunk {
+                        let color: [f32; 4] = layer_color(layer, quad.color).into();
+                        let half = quad.size / 2.0;
+                        for (x, y) in [(-half, -half), (half, -half), (half, half), (-half, half)] {
+                            vertices.push(Vertex {
+                                position: (quad.center + Vector2::new(x, y)).into(),
+                                color,
+                                uv: [0.0, 0.0],
+                            });
+                        }
+                    }
+                    vertices
+                })
+                .collect();
+
+            for chunk in chunks {
+                self.vertices.extend(chunk);
+            }
+            for _ in quads {
+                // Same winding as draw_rectangle
+                for offset in [2, 1, 0, 3, 2, 0] {
+                    self.indices.push(self.current_index + offset);
+                }
+                self.current_index += 4;
+            }
+        }
+    }
+
+    /// A filled triangle, turned by `rotation` around its center. The corners
+    /// go counter-clockwise on screen, the other way around it is culled.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let (top_right, top_left, bottom) = (
+    ///     Vector2::new(56., 8.),
+    ///     Vector2::new(8., 8.),
+    ///     Vector2::new(32., 56.),
+    /// );
+    /// renderer.draw_triangle(top_right, top_left, bottom, Srgba::new(1., 1., 1., 1.), Deg(0.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(32, 16), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(8, 48), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_triangle(
+        &mut self,
+        v1: Vector2<f32>,
+        v2: Vector2<f32>,
+        v3: Vector2<f32>,
+        color: Srgba,
+        rotation: Deg<f32>,
+    ) {
+        self.record(|| DrawCommand::Triangle {
+            points: [v1, v2, v3].map(frame_dump::vector),
+            color: frame_dump::color(color),
+            rotation: rotation.0,
+        });
+        if !self.reserve(3, 3) {
+            return;
+        }
+        self.use_texture(texture::WHITE);
+        let color = self.layer_color(color);
+
+        let origin = Vector2::new((v1.x + v2.x + v3.x) / 3.0, (v1.y + v2.y + v3.y) / 3.0);
+
+        // Translate to origin
+        let local_v1 = v1 - origin;
+        let local_v2 = v2 - origin;
+        let local_v3 = v3 - origin;
+
+        // Apply rotation
+        let rotation_matrix = Matrix2::from_angle(rotation);
+        let r1 = rotation_matrix * local_v1 + origin;
+        let r2 = rotation_matrix * local_v2 + origin;
+        let r3 = rotation_matrix * local_v3 + origin;
+
+        self.vertices.push(Vertex {
+            position: (r1).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+        self.vertices.push(Vertex {
+            position: (r2).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+        self.vertices.push(Vertex {
+            position: (r3).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+
+        self.indices.push(self.current_index);
+        self.indices.push(self.current_index + 1);
+        self.indices.push(self.current_index + 2);
+
+        self.current_index += 3;
+    }
+
+    /// A filled circle.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// renderer.draw_circle(Vector2::new(32., 32.), 16., Srgba::new(1., 1., 1., 1.));
+    /// let image = renderer.render_offscreen()?;
+    /// assert_eq!(image.pixel(32, 32), [255, 255, 255, 255]);
+    /// assert_eq!(image.pixel(4, 4), [0, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_circle(&mut self, center: Vector2<f32>, radius: f32, color: Srgba) {
+        self.record(|| DrawCommand::Circle {
+            center: frame_dump::vector(center),
+            radius,
+            color: frame_dump::color(color),
+        });
+        const NUM_SEGMENTS: u16 = 32;
+        if !self.reserve(NUM_SEGMENTS as usize + 2, NUM_SEGMENTS as usize * 3) {
+            return;
+        }
+        self.use_texture(texture::WHITE);
+        let color = self.layer_color(color);
+
+        // Center vertex
+        self.vertices.push(Vertex {
+            position: (center).into(),
+            color: color.into(),
+            uv: [0.0, 0.0],
+        });
+
+        // Create vertices for the perimeter of the circle
+        for i in 0..=NUM_SEGMENTS {
+            let angle = 2.0 * std::f32::consts::PI * (i as f32) / (NUM_SEGMENTS as f32);
+            let x = center.x + radius * angle.cos();
+            let y = center.y + radius * angle.sin();
+
+            self.vertices.push(Vertex {
+                position: (Vector2::new(x, y)).into(),
+                color: color.into(),
+                uv: [0.0, 0.0],
+            });
+        }
+
+        // Create indices for triangles (connecting center to perimeter points)
+        for i in 0..NUM_SEGMENTS {
+            self.indices.push(self.current_index + i + 2); // Next perimeter point
+            self.indices.push(self.current_index + i + 1); // Current perimeter point
+            self.indices.push(self.current_index); // Center
+        }
+
+        self.current_index += NUM_SEGMENTS + 2;
+    }
+
+    /// Draws the whole texture stretched over the rectangle at `pos`.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// # let mut png = Vec::new();
+    /// # {
+    /// #     let mut encoder = png::Encoder::new(&mut png, 2, 1);
+    /// #     encoder.set_color(png::ColorType::Rgba);
+    /// #     encoder.set_depth(png::BitDepth::Eight);
+    /// #     encoder.write_header()?.write_image_data(&[255, 0, 0, 255, 0, 0, 255, 255])?;
+    /// # }
+    ///
+    /// let texture = renderer.load_texture_from_bytes(&png)?;
+    /// let white = Srgba::new(1., 1., 1., 1.);
+    /// renderer.draw_texture(texture, Vector2::new(0., 0.), 64., 64., white);
+    /// let image = renderer.render_offscreen()?;
+    /// let [red, _, blue, _] = image.pixel(4, 32);
+    /// assert!(red > blue);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_texture(
+        &mut self,
+        texture: TextureHandle,
+        pos: Vector2<f32>,
+        width: f32,
+        height: f32,
+        tint: Srgba,
+    ) {
+        self.draw_texture_ex(
+            texture,
+            Rect::new(pos.x, pos.y, width, height),
+            &TextureParams {
+                tint,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Draws part of a texture into `dest`, e.g. one sprite of an atlas.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// # let mut png = Vec::new();
+    /// # {
+    /// #     let mut encoder = png::Encoder::new(&mut png, 2, 1);
+    /// #     encoder.set_color(png::ColorType::Rgba);
+    /// #     encoder.set_depth(png::BitDepth::Eight);
+    /// #     encoder.write_header()?.write_image_data(&[255, 0, 0, 255, 0, 0, 255, 255])?;
+    /// # }
+    ///
+    /// use wgpu_pong::renderer::{Rect, TextureParams};
+    ///
+    /// let texture = renderer.load_texture_from_bytes(&png)?;
+    /// let params = TextureParams {
+    ///     flip_x: true,
+    ///     ..Default::default()
+    /// };
+    /// renderer.draw_texture_ex(texture, Rect::new(0., 0., 64., 64.), &params);
+    /// let image = renderer.render_offscreen()?;
+    /// let [red, _, blue, _] = image.pixel(4, 32);
+    /// assert!(blue > red);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_texture_ex(&mut self, texture: TextureHandle, dest: Rect, params: &TextureParams) {
+        self.record(|| DrawCommand::Texture {
+            texture: texture.0,
+            dest: frame_dump::rect(dest),
+            source: params.source.map(frame_dump::rect),
+            tint: frame_dump::color(params.tint),
+            rotation: params.rotation.0,
+            flip_x: params.flip_x,
+            flip_y: params.flip_y,
+        });
+        if !self.reserve(4, 6) {
+            return;
+        }
+        let Some(image) = self.images.get(texture.0).copied() else {
+            return;
+        };
+        let Some(page) = self.pages.get(image.page) else {
+            return;
+        };
+        let source =
+            params
+                .source
+                .unwrap_or(Rect::new(0.0, 0.0, image.width as f32, image.height as f32));
+        // From image pixels to UVs on its page
+        let (page_width, page_height) = (page.width as f32, page.height as f32);
+        let mut left = (image.x as f32 + source.x) / page_width;
+        let mut right = (image.x as f32 + source.x + source.width) / page_width;
+        let mut top = (image.y as f32 + source.y) / page_height;
+        let mut bottom = (image.y as f32 + source.y + source.height) / page_height;
+        if params.flip_x {
+            std::mem::swap(&mut left, &mut right);
+        }
+        if params.flip_y {
+            std::mem::swap(&mut top, &mut bottom);
+        }
+
+        self.use_texture(texture);
+        let color: [f32; 4] = self.layer_color(params.tint).into();
+        let origin = Vector2::new(dest.x + dest.width / 2.0, dest.y + dest.height / 2.0);
+        let half_width = dest.width / 2.0;
+        let half_height = dest.height / 2.0;
+        let rotation_matrix = Matrix2::from_angle(params.rotation);
+        let corners = [
+            (Vector2::new(-half_width, -half_height), [left, top]),
+            (Vector2::new(half_width, -half_height), [right, top]),
+            (Vector2::new(half_width, half_height), [right, bottom]),
+            (Vector2::new(-half_width, half_height), [left, bottom]),
+        ];
+        for (corner, uv) in corners {
+            self.vertices.push(Vertex {
+                position: (rotation_matrix * corner + origin).into(),
+                color,
+                uv,
+            });
+        }
+
+        // Same winding as draw_rectangle
+        for offset in [2, 1, 0, 3, 2, 0] {
+            self.indices.push(self.current_index + offset);
+        }
+        self.current_index += 4;
+    }
+
+    /// Lays out `text` with its top left corner at `pos` in window pixels, in
+    /// white when `color` is None. Text ignores the camera and the viewport,
+    /// and is drawn on top of the shapes.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let yellow = Some(glyphon::Color::rgb(255, 255, 0));
+    /// renderer.draw_text("Pong", Vector2::new(4., 4.), 24., 32., yellow);
+    /// let image = renderer.render_offscreen()?;
+    /// assert!(image.rgba.chunks(4).any(|pixel| pixel[0] > 128 && pixel[2] == 0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        pos: Vector2<f32>,
+        font_size: f32,
+        line_height: f32,
+        color: Option<glyphon::Color>,
+    ) {
+        self.record(|| DrawCommand::Text {
+            text: text.to_string(),
+            pos: frame_dump::vector(pos),
+            font_size,
+            line_height,
+            color: color.map(|color| [color.r(), color.g(), color.b(), color.a()]),
+        });
+        let metrics = Metrics::new(font_size, line_height);
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+
+        buffer.set_text(
+            &mut self.font_system,
+            text,
+            Attrs::new().family(self.font.family()),
+            Shaping::Advanced,
+        );
+
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let bounds = TextBounds {
+            left: 0,
+            top: 0,
+            right: self.size.width as i32,
+            bottom: self.size.height as i32,
+        };
+
+        let color = color.unwrap_or(glyphon::Color::rgb(255, 255, 255));
+        let (position, color) = match self.layer {
+            Some(layer) => (pos + layer.offset, layer.apply_text_color(color)),
+            None => (pos, color),
+        };
+        self.text.push({
+            Text {
+                buffer,
+                position,
+                bounds,
+                color,
+            }
+        })
+    }
+
+    /// Width `draw_text` would take for `text`, e.g. to center or right-align
+    /// it.
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use cgmath::{Deg, Vector2};
+    /// # use palette::Srgba;
+    /// # use wgpu_pong::renderer::Renderer;
+    /// # let mut renderer = pollster::block_on(Renderer::headless(64, 64))?;
+    /// # renderer.begin_drawing();
+    /// let width = renderer.measure_text("Pong", 24., 32.);
+    /// assert!(width > 0.);
+    /// assert!(renderer.measure_text("Pong Pong", 24., 32.) > width);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn measure_text(&mut self, text: &str, font_size: f32, line_height: f32) -> f32 {
+        let metrics = Metrics::new(font_size, line_height);
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+
+        buffer.set_text(
+            &mut self.font_system,
+            text,
+            Attrs::new().family(self.font.family()),
+            Shaping::Advanced,
+        );
+
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        // maximum text width
+        buffer
+            .layout_runs()
+            .flat_map(|run| run.glyphs.iter())
+            .map(|glyph| glyph.x + glyph.w)
+            .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .unwrap_or(0.0)
+    }
+}
+
+// Split off from the renderer so vertices can be built on other threads
+fn layer_color(layer: Option<Layer>, color: Srgba) -> Srgba {
+    match layer {
+        Some(layer) => layer.apply_color(color),
+        None => color,
+    }
+}
+
+// Pipeline for the batched, flat-colored shapes
+fn create_shape_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    blend: wgpu::BlendState,
+    texture_layout: &wgpu::BindGroupLayout,
+    view_layout: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("../shader/shader.wgsl").into()),
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[texture_layout, view_layout],
+        push_constant_ranges: &[],
+    });
+
+    let vertex_buffers = [wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            // Position
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            // Color
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                shader_location: 1,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+            // Texture coordinates
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                shader_location: 2,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+        ],
+    }];
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &vertex_buffers,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
@@ -0,0 +1,329 @@
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostParams {
+    brightness: f32,
+    gamma: f32,
+    filter: u32,
+    // uniform buffers need 16 byte alignment
+    _padding: f32,
+}
+
+// Color filter applied in the post pass, e.g. for photo mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PostFilter {
+    #[default]
+    None,
+    Grayscale,
+    Sepia,
+    Noir,
+    Vignette,
+}
+
+impl PostFilter {
+    pub fn next(self) -> Self {
+        match self {
+            PostFilter::None => PostFilter::Grayscale,
+            PostFilter::Grayscale => PostFilter::Sepia,
+            PostFilter::Sepia => PostFilter::Noir,
+            PostFilter::Noir => PostFilter::Vignette,
+            PostFilter::Vignette => PostFilter::None,
+        }
+    }
+
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            PostFilter::None => "filter.none",
+            PostFilter::Grayscale => "filter.grayscale",
+            PostFilter::Sepia => "filter.sepia",
+            PostFilter::Noir => "filter.noir",
+            PostFilter::Vignette => "filter.vignette",
+        }
+    }
+}
+
+// What the post pass does to the scene.
+#[derive(Debug, Clone, Copy)]
+pub struct PostSettings {
+    pub brightness: f32,
+    pub gamma: f32,
+    pub filter: PostFilter,
+}
+
+impl Default for PostSettings {
+    fn default() -> Self {
+        Self {
+            brightness: 1.0,
+            gamma: 1.0,
+            filter: PostFilter::None,
+        }
+    }
+}
+
+impl PostSettings {
+    fn params(&self) -> PostParams {
+        PostParams {
+            brightness: self.brightness,
+            gamma: self.gamma,
+            // Matches the numbering in post.wgsl
+            filter: self.filter as u32,
+            _padding: 0.0,
+        }
+    }
+}
+
+// The scene is rendered into an offscreen target first, then copied onto the
+// surface by a full-screen pass that applies brightness and gamma.
+pub struct PostProcess {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    params_buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    target_view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl PostProcess {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shader/post.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Post Params Buffer"),
+            contents: bytemuck::cast_slice(&[PostSettings::default().params()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let target_view = create_target(device, format, width, height);
+        let bind_group = create_bind_group(
+            device,
+            &bind_group_layout,
+            &target_view,
+            &sampler,
+            &params_buffer,
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            params_buffer,
+            format,
+            target_view,
+            bind_group,
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.target_view = create_target(device, self.format, width, height);
+        self.bind_group = create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.target_view,
+            &self.sampler,
+            &self.params_buffer,
+        );
+    }
+
+    // The view the scene should be rendered into
+    pub fn target(&self) -> &wgpu::TextureView {
+        &self.target_view
+    }
+
+    pub fn render(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        settings: &PostSettings,
+    ) {
+        self.render_from(queue, encoder, &self.bind_group, output, settings);
+    }
+
+    // Like `render`, but reads the scene from `source` instead of our own
+    // target, for offscreen captures at other resolutions.
+    pub fn render_source(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+        settings: &PostSettings,
+    ) {
+        let bind_group = create_bind_group(
+            device,
+            &self.bind_group_layout,
+            source,
+            &self.sampler,
+            &self.params_buffer,
+        );
+        self.render_from(queue, encoder, &bind_group, output, settings);
+    }
+
+    fn render_from(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        bind_group: &wgpu::BindGroup,
+        output: &wgpu::TextureView,
+        settings: &PostSettings,
+    ) {
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::cast_slice(&[settings.params()]),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_target(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Scene Target"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    target: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    params: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Post Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(target),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: params.as_entire_binding(),
+            },
+        ],
+    })
+}
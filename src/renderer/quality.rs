@@ -0,0 +1,41 @@
+// Rendering quality, stepped down when the GPU runs out of memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    High,
+    // No post processing, so the offscreen scene target is freed
+    Reduced,
+    // Additionally caps the number of shapes drawn per frame
+    Minimal,
+}
+
+impl Quality {
+    pub fn lower(self) -> Option<Self> {
+        match self {
+            Quality::High => Some(Quality::Reduced),
+            Quality::Reduced => Some(Quality::Minimal),
+            Quality::Minimal => None,
+        }
+    }
+
+    // Key of the description in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Quality::High => "quality.high",
+            Quality::Reduced => "quality.reduced",
+            Quality::Minimal => "quality.minimal",
+        }
+    }
+
+    pub fn post_processing(self) -> bool {
+        self == Quality::High
+    }
+
+    // Fraction of the shape limit that may be used each frame. Shapes are
+    // drawn in order, so the paddles and ball come before the effects.
+    pub fn shape_budget(self) -> f32 {
+        match self {
+            Quality::High | Quality::Reduced => 1.0,
+            Quality::Minimal => 0.5,
+        }
+    }
+}
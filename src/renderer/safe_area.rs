@@ -0,0 +1,30 @@
+use super::Rect;
+
+// Kept clear along every edge, in logical pixels so it scales with the display
+static MARGIN: f32 = 12.0;
+// The margin never takes more than this share of the shorter window side
+static MAX_MARGIN_SHARE: f32 = 0.05;
+// HUD elements stay within this aspect ratio, centered on wider windows, so
+// the scores don't end up in the far corners of an ultrawide monitor
+static MAX_ASPECT: f32 = 21.0 / 9.0;
+// Upper limit for the player's own inset, as a share of the shorter side
+pub static MAX_INSET: f32 = 0.1;
+
+// The part of a `width` x `height` window HUD text and widgets are placed in.
+// `inset` is extra room chosen by the player (a share of the shorter side),
+// for display cutouts like notches or TVs cropping the picture, which winit
+// does not report.
+pub fn safe_area(width: f32, height: f32, scale_factor: f32, inset: f32) -> Rect {
+    let shorter = width.min(height);
+    let margin = (MARGIN * scale_factor).min(shorter * MAX_MARGIN_SHARE)
+        + shorter * inset.clamp(0.0, MAX_INSET);
+
+    let content_width = width.min(height * MAX_ASPECT);
+    let x = (width - content_width) / 2.0 + margin;
+    Rect::new(
+        x,
+        margin,
+        (width - x * 2.0).max(0.0),
+        (height - margin * 2.0).max(0.0),
+    )
+}
@@ -0,0 +1,68 @@
+use log::debug;
+use wgpu::util::StagingBelt;
+
+// GPU buffer for one frame's vertices or indices. It's replaced by a larger
+// one whenever a frame doesn't fit, and never shrinks again.
+pub struct ShapeBuffer {
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+    buffer: wgpu::Buffer,
+}
+
+impl ShapeBuffer {
+    pub fn new(
+        device: &wgpu::Device,
+        label: &'static str,
+        usage: wgpu::BufferUsages,
+        size: wgpu::BufferAddress,
+    ) -> Self {
+        let usage = usage | wgpu::BufferUsages::COPY_DST;
+        Self {
+            label,
+            usage,
+            buffer: create_buffer(device, label, usage, size),
+        }
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    // Copies `data` to the start of the buffer through `belt`, growing it to
+    // the next power of two first if needed. `data` must be a multiple of
+    // four bytes long. Returns whether the buffer was replaced.
+    pub fn write(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        belt: &mut StagingBelt,
+        data: &[u8],
+    ) -> bool {
+        let Some(size) = wgpu::BufferSize::new(data.len() as wgpu::BufferAddress) else {
+            return false;
+        };
+        let grow = size.get() > self.buffer.size();
+        if grow {
+            let size = size.get().next_power_of_two();
+            debug!("Growing the {} to {} bytes", self.label, size);
+            self.buffer = create_buffer(device, self.label, self.usage, size);
+        }
+        belt.write_buffer(encoder, &self.buffer, 0, size, device)
+            .copy_from_slice(data);
+        grow
+    }
+}
+
+fn create_buffer(
+    device: &wgpu::Device,
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+    size: wgpu::BufferAddress,
+) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size,
+        usage,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,106 @@
+use wgpu::TextureFormat;
+
+// How frames reach the window. Everything is drawn in sRGB where the surface
+// allows it, so the colors come out as they are written down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SurfaceFormat {
+    // One of the formats the surface supports
+    pub surface: TextureFormat,
+    // Drawn into, an sRGB view of `surface` where it has no sRGB format
+    pub view: TextureFormat,
+}
+
+impl SurfaceFormat {
+    pub fn same(format: TextureFormat) -> Self {
+        Self {
+            surface: format,
+            view: format,
+        }
+    }
+
+    // Without sRGB the colors are written out as linear values and look
+    // washed out
+    pub fn is_srgb(&self) -> bool {
+        self.view.is_srgb()
+    }
+
+    // For the debug overlay
+    pub fn describe(&self) -> String {
+        if self.surface == self.view {
+            format!("{:?}", self.view)
+        } else {
+            format!("{:?} (view of {:?})", self.view, self.surface)
+        }
+    }
+}
+
+// Picks from the formats the surface supports, best first:
+// 8-bit sRGB, 8-bit through an sRGB view, any other sRGB format, then
+// whatever the surface lists first.
+pub fn choose(formats: &[TextureFormat]) -> Option<SurfaceFormat> {
+    let eight_bit = |format: &TextureFormat| {
+        matches!(
+            format.remove_srgb_suffix(),
+            TextureFormat::Bgra8Unorm | TextureFormat::Rgba8Unorm
+        )
+    };
+    formats
+        .iter()
+        .find(|format| format.is_srgb() && eight_bit(format))
+        .map(|format| SurfaceFormat::same(*format))
+        .or_else(|| {
+            formats
+                .iter()
+                .find(|format| eight_bit(format))
+                .map(|format| SurfaceFormat {
+                    surface: *format,
+                    view: format.add_srgb_suffix(),
+                })
+        })
+        .or_else(|| {
+            formats
+                .iter()
+                .find(|format| format.is_srgb())
+                .map(|format| SurfaceFormat::same(*format))
+        })
+        .or_else(|| formats.first().map(|format| SurfaceFormat::same(*format)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_eight_bit_srgb() {
+        let formats = [
+            TextureFormat::Rgba16Float,
+            TextureFormat::Bgra8Unorm,
+            TextureFormat::Bgra8UnormSrgb,
+        ];
+        assert_eq!(
+            choose(&formats),
+            Some(SurfaceFormat::same(TextureFormat::Bgra8UnormSrgb))
+        );
+    }
+
+    #[test]
+    fn views_eight_bit_formats_as_srgb() {
+        let formats = [TextureFormat::Rgb10a2Unorm, TextureFormat::Rgba8Unorm];
+        assert_eq!(
+            choose(&formats),
+            Some(SurfaceFormat {
+                surface: TextureFormat::Rgba8Unorm,
+                view: TextureFormat::Rgba8UnormSrgb,
+            })
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_first_format() {
+        let formats = [TextureFormat::Rgb10a2Unorm, TextureFormat::Rgba16Float];
+        let format = choose(&formats).expect("a format is chosen");
+        assert_eq!(format.view, TextureFormat::Rgb10a2Unorm);
+        assert!(!format.is_srgb());
+        assert_eq!(choose(&[]), None);
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{Context, Result, bail};
+use cgmath::Deg;
+use palette::Srgba;
+use std::path::{Path, PathBuf};
+
+use super::Rect;
+
+// A texture uploaded with `Renderer::load_texture`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureHandle(pub(super) usize);
+
+// PNG pixels decoded without the GPU, e.g. on another thread while the game
+// loads, for `Renderer::upload_image`.
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub(super) rgba: Vec<u8>,
+    // File the image was read from, kept for frame dumps
+    pub(super) source: Option<PathBuf>,
+}
+
+impl DecodedImage {
+    // PNG data, e.g. from `include_bytes!`
+    pub fn from_png(bytes: &[u8]) -> Result<Self> {
+        let (width, height, rgba) = decode_png(bytes)?;
+        Ok(Self {
+            width,
+            height,
+            rgba,
+            source: None,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let mut image = Self::from_png(&bytes)
+            .with_context(|| format!("cannot load texture {}", path.display()))?;
+        image.source = Some(path.to_path_buf());
+        Ok(image)
+    }
+}
+
+// Plain white, used for untextured shapes so one pipeline draws everything.
+// It's the atlas' top left pixel, which is what UV 0, 0 samples at any atlas
+// size.
+pub(super) static WHITE: TextureHandle = TextureHandle(0);
+
+// Where a loaded texture lives: its own page or a spot in the atlas (page 0).
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Image {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// How `Renderer::draw_texture_ex` draws a texture.
+#[derive(Debug, Clone, Copy)]
+pub struct TextureParams {
+    // Part of the texture in texture pixels, the whole texture when None
+    pub source: Option<Rect>,
+    // Multiplied with the texture's colors
+    pub tint: Srgba,
+    // Around the center of the destination
+    pub rotation: Deg<f32>,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Default for TextureParams {
+    fn default() -> Self {
+        Self {
+            source: None,
+            tint: Srgba::new(1.0, 1.0, 1.0, 1.0),
+            rotation: Deg(0.),
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+}
+
+pub(super) struct Texture {
+    pub texture: wgpu::Texture,
+    pub bind_group: wgpu::BindGroup,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub(super) fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Texture Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+pub(super) fn create_sampler(device: &wgpu::Device) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Texture Sampler"),
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    })
+}
+
+// An sRGB texture of transparent pixels, filled with `write_pixels`.
+pub(super) fn create_texture(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    width: u32,
+    height: u32,
+) -> Texture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Sprite Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        // Copied from when the atlas grows
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Texture Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    });
+
+    Texture {
+        texture,
+        bind_group,
+        width,
+        height,
+    }
+}
+
+// Uploads tightly packed RGBA8 pixels with their top left corner at `x`, `y`.
+pub(super) fn write_pixels(
+    queue: &wgpu::Queue,
+    texture: &Texture,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+) {
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        rgba,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+// Repeats the outermost pixels once around the image, so filtering at the
+// edge of an atlas sprite doesn't pick up its neighbors.
+pub(super) fn with_border(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut bordered = Vec::with_capacity((width + 2) * (height + 2) * 4);
+    for y in 0..height + 2 {
+        let row = y.saturating_sub(1).min(height - 1);
+        for x in 0..width + 2 {
+            let column = x.saturating_sub(1).min(width - 1);
+            let index = (row * width + column) * 4;
+            bordered.extend_from_slice(&rgba[index..index + 4]);
+        }
+    }
+    bordered
+}
+
+// Decodes a PNG into width, height and RGBA8 pixels, whatever its color type.
+pub(super) fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().context("invalid PNG header")?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .context("invalid PNG image data")?;
+    buffer.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer
+            .iter()
+            .flat_map(|&value| [value, value, value, 255])
+            .collect(),
+        png::ColorType::Indexed => bail!("PNG palette was not expanded"),
+    };
+    if info.width == 0 || info.height == 0 {
+        bail!("PNG has no pixels");
+    }
+    Ok((info.width, info.height, rgba))
+}
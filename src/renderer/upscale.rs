@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+// Strength of the sharpen filter, see upscale.wgsl
+static SHARPNESS: f32 = 0.6;
+// Share of the brightness scanlines take away
+static SCANLINES: f32 = 0.35;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct UpscaleParams {
+    extent: [f32; 2],
+    texel: [f32; 2],
+    sharpness: f32,
+    pixelated: u32,
+    scanlines: f32,
+    // uniform buffers need 16 byte alignment
+    _padding: f32,
+}
+
+// How a scene rendered below the window resolution is stretched back up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Upscale {
+    #[default]
+    Bilinear,
+    // Bilinear followed by a contrast adaptive sharpen, like FSR's RCAS
+    Sharpen,
+}
+
+impl Upscale {
+    pub fn next(self) -> Self {
+        match self {
+            Upscale::Bilinear => Upscale::Sharpen,
+            Upscale::Sharpen => Upscale::Bilinear,
+        }
+    }
+
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Upscale::Bilinear => "upscale.bilinear",
+            Upscale::Sharpen => "upscale.sharpen",
+        }
+    }
+}
+
+// How the upscale pass samples the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpscaleFilter {
+    Smooth(Upscale),
+    // Nearest neighbor for whole multiples of the scene's resolution
+    Pixelated { scanlines: bool },
+}
+
+// Target the scene is rendered into when the resolution is lowered. It has
+// the size of the window and the scene only fills its top left part, so the
+// scale can change every frame without allocating.
+pub struct Upscaler {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    params_buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    size: (u32, u32),
+    target_view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Upscaler {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Upscale Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shader/upscale.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Upscale Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Upscale Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Upscale Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Upscale Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Upscale Params Buffer"),
+            contents: bytemuck::cast_slice(&[params(
+                1.0,
+                (width, height),
+                UpscaleFilter::Smooth(Upscale::Bilinear),
+            )]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let target_view = create_target(device, format, width, height);
+        let bind_group = create_bind_group(
+            device,
+            &bind_group_layout,
+            &target_view,
+            &sampler,
+            &params_buffer,
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            params_buffer,
+            format,
+            size: (width.max(1), height.max(1)),
+            target_view,
+            bind_group,
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.size = (width.max(1), height.max(1));
+        self.target_view = create_target(device, self.format, width, height);
+        self.bind_group = create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.target_view,
+            &self.sampler,
+            &self.params_buffer,
+        );
+    }
+
+    // The view the scene should be rendered into, through a viewport
+    // `scale` times the window size
+    pub fn target(&self) -> &wgpu::TextureView {
+        &self.target_view
+    }
+
+    // Stretches the part of the target drawn at `scale` over all of `output`
+    pub fn render(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        scale: f32,
+        filter: UpscaleFilter,
+    ) {
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::cast_slice(&[params(scale, self.size, filter)]),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Upscale Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn params(scale: f32, (width, height): (u32, u32), filter: UpscaleFilter) -> UpscaleParams {
+    UpscaleParams {
+        extent: [scale, scale],
+        texel: [1.0 / width as f32, 1.0 / height as f32],
+        sharpness: match filter {
+            UpscaleFilter::Smooth(Upscale::Sharpen) => SHARPNESS,
+            _ => 0.0,
+        },
+        pixelated: matches!(filter, UpscaleFilter::Pixelated { .. }) as u32,
+        scanlines: match filter {
+            UpscaleFilter::Pixelated { scanlines: true } => SCANLINES,
+            _ => 0.0,
+        },
+        _padding: 0.0,
+    }
+}
+
+fn create_target(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Scaled Scene Target"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    target: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    params: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Upscale Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(target),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: params.as_entire_binding(),
+            },
+        ],
+    })
+}
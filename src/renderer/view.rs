@@ -0,0 +1,103 @@
+use cgmath::Matrix4;
+use wgpu::util::StagingBelt;
+
+use super::shape_buffer::ShapeBuffer;
+
+static MATRIX_SIZE: wgpu::BufferAddress =
+    std::mem::size_of::<[[f32; 4]; 4]>() as wgpu::BufferAddress;
+// Views the buffer has room for before it grows
+static INITIAL_VIEWS: wgpu::BufferAddress = 16;
+
+// Projection of every batch of shapes from window or world coordinates into
+// clip space, one matrix per batch picked with a dynamic offset.
+pub struct ViewUniforms {
+    layout: wgpu::BindGroupLayout,
+    buffer: ShapeBuffer,
+    bind_group: wgpu::BindGroup,
+    // Bytes from one matrix to the next, uniform offsets need to be aligned
+    stride: wgpu::BufferAddress,
+}
+
+impl ViewUniforms {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("View Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(MATRIX_SIZE),
+                },
+                count: None,
+            }],
+        });
+        let stride = MATRIX_SIZE.max(wgpu::BufferAddress::from(
+            device.limits().min_uniform_buffer_offset_alignment,
+        ));
+        let buffer = ShapeBuffer::new(
+            device,
+            "View Buffer",
+            wgpu::BufferUsages::UNIFORM,
+            stride * INITIAL_VIEWS,
+        );
+        let bind_group = create_bind_group(device, &layout, &buffer);
+        Self {
+            layout,
+            buffer,
+            bind_group,
+            stride,
+        }
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    // Dynamic offset of the view at `index`
+    pub fn offset(&self, index: usize) -> u32 {
+        (index as wgpu::BufferAddress * self.stride) as u32
+    }
+
+    pub fn write(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        belt: &mut StagingBelt,
+        views: &[Matrix4<f32>],
+    ) {
+        let mut data = vec![0; views.len() * self.stride as usize];
+        for (view, slot) in views.iter().zip(data.chunks_mut(self.stride as usize)) {
+            let matrix: [[f32; 4]; 4] = (*view).into();
+            slot[..MATRIX_SIZE as usize].copy_from_slice(bytemuck::cast_slice(&matrix));
+        }
+        // The bind group still points at the old buffer
+        if self.buffer.write(device, encoder, belt, &data) {
+            self.bind_group = create_bind_group(device, &self.layout, &self.buffer);
+        }
+    }
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    buffer: &ShapeBuffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("View Bind Group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: buffer.buffer(),
+                offset: 0,
+                size: wgpu::BufferSize::new(MATRIX_SIZE),
+            }),
+        }],
+    })
+}
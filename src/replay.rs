@@ -0,0 +1,192 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::mutators::Mutators;
+use crate::strict_math::Math;
+use crate::time::{SystemTime, UNIX_EPOCH};
+use crate::timeline::PointRecord;
+
+static REPLAY_MAGIC: &str = "wgpu-pong-replay";
+static REPLAY_DIR: &str = "replays";
+
+// Bumped whenever the replay layout changes.
+pub static REPLAY_FORMAT_VERSION: u32 = 5;
+
+// Oldest reader that is able to play back replays written by this version.
+// Only bump this when a change can't be ignored by older readers
+// (e.g. the meaning of tick inputs changes). Purely additive fields must
+// use `#[serde(default)]` instead, so older replays keep loading.
+static REPLAY_MIN_READER_VERSION: u32 = 4;
+
+// Oldest format this version plays back the same as it was recorded. Older
+// replays were recorded under other rules (e.g. before aimed serves) and
+// would play out differently, so they are rejected.
+static REPLAY_MIN_PLAYABLE_VERSION: u32 = 4;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub body: ReplayBody,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub magic: String,
+    pub format_version: u32,
+    pub min_reader_version: u32,
+    pub game_version: String,
+    pub players: [String; 2],
+    pub settings: ReplaySettings,
+    pub seed: u64,
+    // Seconds since the unix epoch
+    pub recorded_at: u64,
+}
+
+// Everything besides the inputs that influences the simulation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplaySettings {
+    pub field_width: f32,
+    pub field_height: f32,
+    pub paddle_speed: f32,
+    pub ball_speed: f32,
+    pub ball_radius: f32,
+    #[serde(default)]
+    pub mutators: Mutators,
+    #[serde(default)]
+    pub math: Math,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayBody {
+    pub ticks: Vec<ReplayTick>,
+    // Event log of every finished point, for seeking and stats
+    #[serde(default)]
+    pub points: Vec<PointRecord>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayTick {
+    pub dt: f32,
+    pub input: TickInput,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickInput {
+    pub left_up: bool,
+    pub left_down: bool,
+    pub right_up: bool,
+    pub right_down: bool,
+    pub reset_ball: bool,
+    // Share of the full paddle speed out of 255 for a partly pushed stick,
+    // full speed when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left_speed: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right_speed: Option<u8>,
+}
+
+// Stores how far a paddle is moved, see `TickInput::left_speed`.
+pub fn analog_speed(direction: f32) -> Option<u8> {
+    let speed = (direction.abs().min(1.0) * 255.0).round() as u8;
+    (speed > 0 && speed < 255).then_some(speed)
+}
+
+pub fn speed_share(speed: Option<u8>) -> f32 {
+    speed.map_or(1.0, |speed| speed as f32 / 255.0)
+}
+
+impl Replay {
+    pub fn new(players: [String; 2], settings: ReplaySettings, seed: u64) -> Self {
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            header: ReplayHeader {
+                magic: REPLAY_MAGIC.to_string(),
+                format_version: REPLAY_FORMAT_VERSION,
+                min_reader_version: REPLAY_MIN_READER_VERSION,
+                game_version: env!("CARGO_PKG_VERSION").to_string(),
+                players,
+                settings,
+                seed,
+                recorded_at,
+            },
+            body: ReplayBody::default(),
+        }
+    }
+
+    pub fn record(&mut self, dt: f32, input: TickInput) {
+        self.body.ticks.push(ReplayTick { dt, input });
+    }
+
+    pub fn record_point(&mut self, point: PointRecord) {
+        self.body.points.push(point);
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.body.ticks.iter().map(|tick| tick.dt).sum()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("cannot read replay {}", path.display()))?;
+        Self::from_json(&data)
+    }
+
+    pub fn from_json(data: &str) -> Result<Self> {
+        // Check the header on its own first, so replays from newer versions
+        // give a helpful error instead of a confusing parse failure in the body.
+        #[derive(Deserialize)]
+        struct Probe {
+            header: ProbeHeader,
+        }
+        #[derive(Deserialize)]
+        struct ProbeHeader {
+            magic: String,
+            format_version: u32,
+            #[serde(default)]
+            min_reader_version: u32,
+            #[serde(default)]
+            game_version: String,
+        }
+
+        let probe: Probe = serde_json::from_str(data).context("not a replay file")?;
+        if probe.header.magic != REPLAY_MAGIC {
+            bail!("not a replay file (magic: {:?})", probe.header.magic);
+        }
+        if probe.header.min_reader_version > REPLAY_FORMAT_VERSION {
+            bail!(
+                "replay format v{} (game {}) requires a newer version of the game (supports up to v{})",
+                probe.header.format_version,
+                probe.header.game_version,
+                REPLAY_FORMAT_VERSION
+            );
+        }
+        if probe.header.format_version < REPLAY_MIN_PLAYABLE_VERSION {
+            bail!(
+                "replay format v{} (game {}) was recorded under older rules and can't be played back (needs v{} or newer)",
+                probe.header.format_version,
+                probe.header.game_version,
+                REPLAY_MIN_PLAYABLE_VERSION
+            );
+        }
+
+        serde_json::from_str(data).context("replay file is corrupt")
+    }
+
+    pub fn save(&self) -> Result<PathBuf> {
+        fs::create_dir_all(REPLAY_DIR)
+            .with_context(|| format!("cannot create replay directory {}", REPLAY_DIR))?;
+
+        let path = Path::new(REPLAY_DIR).join(format!("replay-{}.json", self.header.recorded_at));
+        let data = serde_json::to_string(self)?;
+        fs::write(&path, data)
+            .with_context(|| format!("cannot write replay {}", path.display()))?;
+
+        Ok(path)
+    }
+}
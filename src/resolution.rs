@@ -0,0 +1,72 @@
+use crate::renderer::MIN_RENDER_SCALE;
+
+// Part of the window resolution added or removed per adjustment
+static SCALE_STEP: f32 = 0.1;
+// Weight of the newest frame in the averaged frame time
+static SMOOTHING: f32 = 0.1;
+// Frames this much over the budget lower the resolution...
+static OVER_BUDGET: f32 = 1.2;
+// ...and the resolution is raised again once they stay below this
+static UNDER_BUDGET: f32 = 1.05;
+// Seconds between adjustments, longer for raising so a scale that's just
+// too much isn't retried right away
+static LOWER_DELAY: f32 = 0.5;
+static RAISE_DELAY: f32 = 3.0;
+// Hitches like loading a texture don't count as one long frame
+static MAX_FRAME_TIME: f32 = 0.25;
+
+// Picks the render scale from the frame times, lowering it while frames
+// take longer than the display's refresh interval.
+pub struct ResolutionScaler {
+    // Seconds a frame may take
+    budget: f32,
+    average: f32,
+    scale: f32,
+    // Seconds since the last adjustment
+    since_change: f32,
+}
+
+impl ResolutionScaler {
+    pub fn new() -> Self {
+        Self {
+            budget: 1.0 / 60.0,
+            average: 1.0 / 60.0,
+            scale: 1.0,
+            since_change: 0.0,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget: f32) {
+        self.budget = budget.max(0.001);
+        self.reset();
+    }
+
+    // Back to the full resolution, e.g. when turned off
+    pub fn reset(&mut self) {
+        self.average = self.budget;
+        self.scale = 1.0;
+        self.since_change = 0.0;
+    }
+
+    // Takes the time the last frame took and returns the scale for the next
+    pub fn update(&mut self, frame_time: f32) -> f32 {
+        let frame_time = frame_time.clamp(0.0, MAX_FRAME_TIME);
+        self.average += (frame_time - self.average) * SMOOTHING;
+        self.since_change += frame_time;
+
+        if self.average > self.budget * OVER_BUDGET
+            && self.since_change >= LOWER_DELAY
+            && self.scale > MIN_RENDER_SCALE
+        {
+            self.scale = (self.scale - SCALE_STEP).max(MIN_RENDER_SCALE);
+            self.since_change = 0.0;
+        } else if self.average < self.budget * UNDER_BUDGET
+            && self.since_change >= RAISE_DELAY
+            && self.scale < 1.0
+        {
+            self.scale = (self.scale + SCALE_STEP).min(1.0);
+            self.since_change = 0.0;
+        }
+        self.scale
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use winit::keyboard::KeyCode;
+
+use crate::drills::DrillRun;
+use crate::sim::{GameSim, SimState};
+
+// Held during a drill to go back in time
+pub static REWIND_KEY: KeyCode = KeyCode::Backspace;
+// Seconds of the drill that can be taken back
+static REWIND_SECONDS: f32 = 5.0;
+// Rewinds this many times faster than the match was played
+static REWIND_SPEED: f32 = 2.0;
+
+// The match right before one step, and how far the replay and the drill
+// got by then
+pub struct Snapshot {
+    pub state: SimState,
+    pub replay_ticks: usize,
+    pub replay_points: usize,
+    pub drill: DrillRun,
+    dt: f32,
+}
+
+// A rolling buffer of the last seconds of a drill, to take back a
+// missed return and try it again.
+pub struct Rewind {
+    snapshots: VecDeque<Snapshot>,
+    // Seconds in the buffer
+    buffered: f32,
+    // Rewound time not yet taken off the buffer
+    pending: f32,
+}
+
+impl Rewind {
+    pub fn new() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            buffered: 0.0,
+            pending: 0.0,
+        }
+    }
+
+    // Call before stepping `sim` by `dt`
+    pub fn record(
+        &mut self,
+        sim: &GameSim,
+        dt: f32,
+        replay_ticks: usize,
+        replay_points: usize,
+        drill: &DrillRun,
+    ) {
+        self.pending = 0.0;
+        self.snapshots.push_back(Snapshot {
+            state: sim.state(),
+            replay_ticks,
+            replay_points,
+            drill: drill.clone(),
+            dt,
+        });
+        self.buffered += dt;
+        while self.buffered > REWIND_SECONDS
+            && let Some(oldest) = self.snapshots.pop_front()
+        {
+            self.buffered -= oldest.dt;
+        }
+    }
+
+    // Goes back by `delta` seconds of real time, returning the snapshot to
+    // continue from, if any was passed
+    pub fn rewind(&mut self, delta: f32) -> Option<Snapshot> {
+        self.pending += delta * REWIND_SPEED;
+        let mut restored = None;
+        while let Some(newest) = self.snapshots.back()
+            && newest.dt <= self.pending
+        {
+            self.pending -= newest.dt;
+            self.buffered -= newest.dt;
+            restored = self.snapshots.pop_back();
+        }
+        if self.snapshots.is_empty() {
+            self.pending = 0.0;
+        }
+        restored
+    }
+
+    // Seconds that can still be rewound
+    pub fn available(&self) -> f32 {
+        self.buffered.max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Vector2;
+
+    use crate::drills::Drill;
+    use crate::mutators::Mutators;
+    use crate::sim::{FIELD_HEIGHT, FIELD_WIDTH, Side, SimEvent};
+
+    static DT: f32 = 1.0 / 120.0;
+
+    fn left_hit() -> SimEvent {
+        SimEvent::PaddleHit {
+            side: Side::Left,
+            pos: Vector2::new(0.0, 0.0),
+            speed: 400.0,
+        }
+    }
+
+    fn rewind_one_step(rewind: &mut Rewind) -> Snapshot {
+        rewind
+            .rewind(DT / REWIND_SPEED)
+            .expect("a step was recorded")
+    }
+
+    #[test]
+    fn rewinding_takes_back_a_miss() {
+        let sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), 1);
+        let mut drill = DrillRun::new(Drill::ReturnServes);
+        let serve = SimEvent::Serve { toward: Side::Left };
+        drill.on_event(&serve, &sim);
+        drill.on_event(&left_hit(), &sim);
+        drill.on_event(&serve, &sim);
+        let before_miss = drill.clone();
+
+        let mut rewind = Rewind::new();
+        rewind.record(&sim, DT, 0, 0, &drill);
+        drill.on_event(
+            &SimEvent::Goal {
+                scorer: Side::Right,
+            },
+            &sim,
+        );
+        assert_ne!(drill, before_miss);
+
+        assert_eq!(rewind_one_step(&mut rewind).drill, before_miss);
+    }
+
+    #[test]
+    fn rewinding_takes_back_a_success() {
+        let sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), 1);
+        let mut drill = DrillRun::new(Drill::CornerShots);
+        drill.on_event(&left_hit(), &sim);
+        let before_hit = drill.clone();
+
+        let mut rewind = Rewind::new();
+        rewind.record(&sim, DT, 0, 0, &drill);
+        let corner = SimEvent::PaddleHit {
+            side: Side::Right,
+            pos: Vector2::new(FIELD_WIDTH, 1.0),
+            speed: 400.0,
+        };
+        drill.on_event(&corner, &sim);
+        assert_ne!(drill, before_hit);
+
+        assert_eq!(rewind_one_step(&mut rewind).drill, before_hit);
+    }
+}
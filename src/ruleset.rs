@@ -0,0 +1,248 @@
+use anyhow::{Result, bail, ensure};
+use serde::{Deserialize, Serialize};
+
+use crate::compact;
+use crate::config::{BALL_SPEED_RANGE, PADDLE_SPEED_RANGE};
+use crate::mutators::{
+    IMPACT_TRANSFERS, MOMENTUM_ACCELERATIONS, Mutators, RUBBER_BAND_STRENGTHS, WALL_DAMPINGS,
+};
+use crate::timestep::TICK_RATES;
+
+// Bumped whenever CodeV2 changes, so old codes are rejected instead of read
+// wrong
+static VERSION: u8 = 2;
+// CodeV2 and the checksum
+static CODE_BYTES: usize = 34;
+static CODE_LENGTH: usize = 46;
+// URL safe, so a code survives being pasted into a link or a chat
+static ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Everything a local match is played with besides the players: the
+// mutators, the win score and the speeds and physics rate of the options
+// screen. Shared as a short code that another player enters on the main
+// menu to play by exactly the same rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ruleset {
+    pub mutators: Mutators,
+    pub win_score: u8,
+    pub paddle_speed: f32,
+    pub ball_speed: f32,
+    pub tick_rate: u32,
+}
+
+// What a code holds, written field by field with `compact`. The version
+// comes first so a code of another version is told apart before the rest
+// is read.
+#[derive(Serialize, Deserialize)]
+struct CodeV2 {
+    version: u8,
+    portal_walls: bool,
+    small_paddle: bool,
+    left_save_assist: bool,
+    right_save_assist: bool,
+    spin: bool,
+    rubber_band: f32,
+    momentum: f32,
+    impact_transfer: f32,
+    wall_damping: f32,
+    paddle_speed: f32,
+    ball_speed: f32,
+    win_score: u8,
+    // Every rate in TICK_RATES fits
+    tick_rate: u16,
+}
+
+impl Ruleset {
+    pub fn code(&self) -> String {
+        let mutators = &self.mutators;
+        let code = CodeV2 {
+            version: VERSION,
+            portal_walls: mutators.portal_walls,
+            small_paddle: mutators.small_paddle,
+            left_save_assist: mutators.left_save_assist,
+            right_save_assist: mutators.right_save_assist,
+            spin: mutators.spin,
+            rubber_band: mutators.rubber_band,
+            momentum: mutators.momentum,
+            impact_transfer: mutators.impact_transfer,
+            wall_damping: mutators.wall_damping,
+            paddle_speed: self.paddle_speed,
+            ball_speed: self.ball_speed,
+            win_score: self.win_score,
+            tick_rate: self.tick_rate.min(u16::MAX as u32) as u16,
+        };
+        let mut bytes = compact::to_bytes(&code).expect("CodeV2 only has supported types");
+        bytes.push(checksum(&bytes));
+        encode(&bytes)
+    }
+
+    // Spaces around the code are ignored, anything else that doesn't match
+    // is an error so a typo never plays by other rules
+    pub fn from_code(code: &str) -> Result<Self> {
+        let bytes = decode(code.trim())?;
+        ensure!(
+            bytes.len() == CODE_BYTES,
+            "a ruleset code has {} characters",
+            CODE_LENGTH
+        );
+        let (data, sum) = bytes.split_at(CODE_BYTES - 1);
+        ensure!(
+            checksum(data) == sum[0],
+            "checksum doesn't match, is there a typo?"
+        );
+        if data[0] != VERSION {
+            bail!("made by another version of the game");
+        }
+
+        let code: CodeV2 = compact::from_bytes(data)?;
+        let ruleset = Self {
+            mutators: Mutators {
+                portal_walls: code.portal_walls,
+                small_paddle: code.small_paddle,
+                left_save_assist: code.left_save_assist,
+                right_save_assist: code.right_save_assist,
+                spin: code.spin,
+                practice_wall: false,
+                rubber_band: code.rubber_band,
+                momentum: code.momentum,
+                impact_transfer: code.impact_transfer,
+                wall_damping: code.wall_damping,
+            },
+            paddle_speed: code.paddle_speed,
+            ball_speed: code.ball_speed,
+            win_score: code.win_score,
+            tick_rate: code.tick_rate as u32,
+        };
+
+        // Only the strengths the mutators screen can pick, the sim isn't
+        // tuned for anything in between
+        let mutators = &ruleset.mutators;
+        ensure!(
+            RUBBER_BAND_STRENGTHS.contains(&mutators.rubber_band)
+                && MOMENTUM_ACCELERATIONS.contains(&mutators.momentum)
+                && IMPACT_TRANSFERS.contains(&mutators.impact_transfer)
+                && WALL_DAMPINGS.contains(&mutators.wall_damping),
+            "invalid mutator strength"
+        );
+        let in_range = |value: f32, (min, max): (f32, f32)| (min..=max).contains(&value);
+        ensure!(
+            in_range(ruleset.paddle_speed, PADDLE_SPEED_RANGE)
+                && in_range(ruleset.ball_speed, BALL_SPEED_RANGE),
+            "speeds out of range"
+        );
+        ensure!(ruleset.win_score > 0, "invalid win score");
+        ensure!(
+            TICK_RATES.contains(&ruleset.tick_rate),
+            "unsupported physics rate {}",
+            ruleset.tick_rate
+        );
+        Ok(ruleset)
+    }
+}
+
+// Catches mistyped characters and most swapped ones
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, byte| sum.rotate_left(3) ^ byte)
+}
+
+// base64 without padding
+fn encode(bytes: &[u8]) -> String {
+    let mut code = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, byte)| {
+            bits | (*byte as u32) << (16 - index * 8)
+        });
+        for index in 0..=chunk.len() {
+            code.push(ALPHABET[(bits >> (18 - index * 6)) as usize & 63] as char);
+        }
+    }
+    code
+}
+
+fn decode(code: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in code.bytes() {
+        let Some(value) = ALPHABET.iter().position(|letter| *letter == c) else {
+            bail!("{:?} can't be part of a ruleset code", c as char);
+        };
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom() -> Ruleset {
+        Ruleset {
+            mutators: Mutators {
+                portal_walls: true,
+                spin: true,
+                right_save_assist: true,
+                rubber_band: 0.2,
+                momentum: 5000.0,
+                impact_transfer: 0.15,
+                wall_damping: -0.1,
+                ..Mutators::default()
+            },
+            win_score: 11,
+            paddle_speed: 850.0,
+            ball_speed: 425.5,
+            tick_rate: 240,
+        }
+    }
+
+    #[test]
+    fn codes_round_trip_exactly() {
+        let ruleset = custom();
+        let code = ruleset.code();
+        assert_eq!(code.len(), CODE_LENGTH);
+        assert_eq!(
+            Ruleset::from_code(&format!("  {}\n", code)).ok(),
+            Some(ruleset)
+        );
+    }
+
+    #[test]
+    fn rejects_typos_and_foreign_codes() {
+        let code = custom().code();
+        let mut typo = code.clone().into_bytes();
+        typo[10] = if typo[10] == b'A' { b'B' } else { b'A' };
+        let typo = String::from_utf8(typo).expect("still ascii");
+        assert!(Ruleset::from_code(&typo).is_err());
+        assert!(Ruleset::from_code(&code[..CODE_LENGTH - 1]).is_err());
+        assert!(Ruleset::from_code("hello world").is_err());
+        assert!(Ruleset::from_code("").is_err());
+    }
+
+    #[test]
+    fn rejects_strengths_the_menu_cant_pick() {
+        let mut ruleset = custom();
+        ruleset.mutators.rubber_band = 0.25;
+        assert!(Ruleset::from_code(&ruleset.code()).is_err());
+
+        let mut ruleset = custom();
+        ruleset.mutators.momentum = f32::NAN;
+        assert!(Ruleset::from_code(&ruleset.code()).is_err());
+
+        let mut ruleset = custom();
+        ruleset.mutators.wall_damping = -0.5;
+        assert!(Ruleset::from_code(&ruleset.code()).is_err());
+    }
+
+    #[test]
+    fn encodes_like_base64() {
+        assert_eq!(encode(b"Pong"), "UG9uZw");
+        assert_eq!(decode("UG9uZw").ok(), Some(b"Pong".to_vec()));
+    }
+}
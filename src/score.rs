@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::locale::Locale;
+
+// Points of one side in a match. Play can go on past the win score, so this
+// doesn't stop at 255 like the `u8` it replaced. Saved and sent as a plain
+// number.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Score(u32);
+
+impl Score {
+    pub fn points(self) -> u32 {
+        self.0
+    }
+
+    pub fn add_point(&mut self) {
+        self.0 = self.0.saturating_add(1);
+    }
+
+    pub fn has_reached(self, win_score: u8) -> bool {
+        self.0 >= u32::from(win_score)
+    }
+
+    // Points ahead of `other`, negative when behind
+    pub fn lead_over(self, other: Score) -> i64 {
+        i64::from(self.0) - i64::from(other.0)
+    }
+
+    // For the screen, in the digits of the current language
+    pub fn localized(self, locale: &Locale) -> String {
+        locale.number(self.0)
+    }
+}
+
+impl From<u8> for Score {
+    fn from(points: u8) -> Self {
+        Self(u32::from(points))
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
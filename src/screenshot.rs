@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::renderer::{CaptureKind, CapturedImage, FrameDump};
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+static SCREENSHOT_DIR: &str = "screenshots";
+static FRAME_DUMP_DIR: &str = "frames";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    File,
+    Clipboard,
+}
+
+pub fn save(image: &CapturedImage, kind: CaptureKind) -> Result<PathBuf> {
+    fs::create_dir_all(SCREENSHOT_DIR)
+        .with_context(|| format!("cannot create screenshot directory {}", SCREENSHOT_DIR))?;
+
+    let timestamp = timestamp();
+    let suffix = match kind {
+        CaptureKind::Opaque => "",
+        CaptureKind::Transparent => "-transparent",
+    };
+
+    let path = Path::new(SCREENSHOT_DIR).join(format!("screenshot-{}{}.png", timestamp, suffix));
+    image.save_png(&path)?;
+    Ok(path)
+}
+
+// Writes the draw calls of one frame, see `--render-frame` to draw it again.
+pub fn save_frame_dump(dump: &FrameDump) -> Result<PathBuf> {
+    let path = Path::new(FRAME_DUMP_DIR).join(format!("frame-{}.json", timestamp()));
+    dump.save(&path)?;
+    Ok(path)
+}
+
+fn timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0)
+}
+
+// The system clipboard, opened on first use. It is kept alive because on X11
+// the image or text is served by this process and disappears once it is dropped.
+#[derive(Default)]
+pub struct Clipboard {
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    // Pages can only write images to the clipboard from a user gesture
+    // through the async clipboard API, which isn't wired up
+    #[cfg(target_arch = "wasm32")]
+    pub fn copy(&mut self, _image: &CapturedImage) -> Result<()> {
+        anyhow::bail!("copying images is not supported in the browser")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn copy(&mut self, image: &CapturedImage) -> Result<()> {
+        self.open()?
+            .set_image(arboard::ImageData {
+                width: image.width as usize,
+                height: image.height as usize,
+                bytes: std::borrow::Cow::Borrowed(&image.rgba),
+            })
+            .context("cannot copy image to clipboard")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn copy_text(&mut self, _text: &str) -> Result<()> {
+        anyhow::bail!("the clipboard is not supported in the browser")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn copy_text(&mut self, text: &str) -> Result<()> {
+        self.open()?
+            .set_text(text)
+            .context("cannot copy text to clipboard")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn paste_text(&mut self) -> Result<String> {
+        anyhow::bail!("the clipboard is not supported in the browser")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn paste_text(&mut self) -> Result<String> {
+        self.open()?.get_text().context("no text in the clipboard")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open(&mut self) -> Result<&mut arboard::Clipboard> {
+        Ok(match &mut self.inner {
+            Some(clipboard) => clipboard,
+            inner => inner.insert(arboard::Clipboard::new().context("cannot open clipboard")?),
+        })
+    }
+}
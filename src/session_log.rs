@@ -0,0 +1,304 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use winit::dpi::PhysicalSize;
+use winit::event::WindowEvent;
+
+use crate::time::Instant;
+
+// A window or surface event that matters for lifecycle bugs: minimizing,
+// moving between monitors, losing the surface on mobile.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    Resized { width: u32, height: u32 },
+    Focused { focused: bool },
+    ScaleFactorChanged { scale_factor: f64 },
+    Occluded { occluded: bool },
+    // The surface was dropped and created again
+    Suspended,
+    Resumed,
+}
+
+impl LifecycleEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::Resized(size) => Some(LifecycleEvent::Resized {
+                width: size.width,
+                height: size.height,
+            }),
+            WindowEvent::Focused(focused) => Some(LifecycleEvent::Focused { focused: *focused }),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                Some(LifecycleEvent::ScaleFactorChanged {
+                    scale_factor: *scale_factor,
+                })
+            }
+            WindowEvent::Occluded(occluded) => Some(LifecycleEvent::Occluded {
+                occluded: *occluded,
+            }),
+            _ => None,
+        }
+    }
+
+    // The window event to hand to the app when replaying. Scale factor
+    // changes need winit's size writer and can only be applied to
+    // `WindowLifecycle`, like the surface events.
+    pub fn to_window_event(self) -> Option<WindowEvent> {
+        match self {
+            LifecycleEvent::Resized { width, height } => {
+                Some(WindowEvent::Resized(PhysicalSize::new(width, height)))
+            }
+            LifecycleEvent::Focused { focused } => Some(WindowEvent::Focused(focused)),
+            LifecycleEvent::Occluded { occluded } => Some(WindowEvent::Occluded(occluded)),
+            LifecycleEvent::ScaleFactorChanged { .. }
+            | LifecycleEvent::Suspended
+            | LifecycleEvent::Resumed => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    // Seconds since the recording started
+    pub time: f32,
+    #[serde(flatten)]
+    pub event: LifecycleEvent,
+}
+
+// Lifecycle events of one run, written with `--record-session` and played
+// against the app with `--replay-session`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionLog {
+    pub events: Vec<RecordedEvent>,
+}
+
+impl SessionLog {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data =
+            fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("invalid session log {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(path, data).with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    // Events up to `time` seconds that `next` hasn't reached yet, moving it on.
+    pub fn due(&self, next: &mut usize, time: f32) -> &[RecordedEvent] {
+        let start = (*next).min(self.events.len());
+        let end = start
+            + self.events[start..]
+                .iter()
+                .take_while(|recorded| recorded.time <= time)
+                .count();
+        *next = end;
+        &self.events[start..end]
+    }
+}
+
+pub struct SessionRecorder {
+    path: PathBuf,
+    start: Instant,
+    log: SessionLog,
+}
+
+impl SessionRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            start: Instant::now(),
+            log: SessionLog::default(),
+        }
+    }
+
+    pub fn record(&mut self, event: LifecycleEvent) {
+        self.log.events.push(RecordedEvent {
+            time: self.start.elapsed().as_secs_f32(),
+            event,
+        });
+    }
+
+    pub fn save(&self) -> Result<&Path> {
+        self.log.save(&self.path)?;
+        Ok(&self.path)
+    }
+}
+
+// Plays a session log against the running app, at the pace it was recorded.
+pub struct SessionPlayer {
+    log: SessionLog,
+    next: usize,
+    start: Instant,
+}
+
+impl SessionPlayer {
+    pub fn new(log: SessionLog) -> Self {
+        Self {
+            log,
+            next: 0,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn due(&mut self) -> Vec<LifecycleEvent> {
+        let time = self.start.elapsed().as_secs_f32();
+        self.log
+            .due(&mut self.next, time)
+            .iter()
+            .map(|recorded| recorded.event)
+            .collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.log.events.len()
+    }
+}
+
+// What the lifecycle events say about the window right now.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowLifecycle {
+    pub size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+    pub focused: bool,
+    pub occluded: bool,
+    pub suspended: bool,
+}
+
+impl Default for WindowLifecycle {
+    fn default() -> Self {
+        Self {
+            size: PhysicalSize::new(0, 0),
+            scale_factor: 1.0,
+            focused: true,
+            occluded: false,
+            suspended: false,
+        }
+    }
+}
+
+impl WindowLifecycle {
+    pub fn apply(&mut self, event: LifecycleEvent) {
+        match event {
+            LifecycleEvent::Resized { width, height } => {
+                self.size = PhysicalSize::new(width, height)
+            }
+            LifecycleEvent::Focused { focused } => self.focused = focused,
+            LifecycleEvent::ScaleFactorChanged { scale_factor } => self.scale_factor = scale_factor,
+            LifecycleEvent::Occluded { occluded } => self.occluded = occluded,
+            LifecycleEvent::Suspended => self.suspended = true,
+            LifecycleEvent::Resumed => self.suspended = false,
+        }
+    }
+
+    // Minimized windows report a zero size on Windows and occlusion on
+    // macOS, there is nothing to draw into then.
+    pub fn is_visible(&self) -> bool {
+        !self.suspended && !self.occluded && self.size.width > 0 && self.size.height > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(events: &[(f32, LifecycleEvent)]) -> SessionLog {
+        SessionLog {
+            events: events
+                .iter()
+                .map(|&(time, event)| RecordedEvent { time, event })
+                .collect(),
+        }
+    }
+
+    // Runs a whole session, e.g. one attached to a bug report
+    fn replay(session: &SessionLog) -> WindowLifecycle {
+        let mut lifecycle = WindowLifecycle::default();
+        for recorded in &session.events {
+            lifecycle.apply(recorded.event);
+        }
+        lifecycle
+    }
+
+    fn resized(width: u32, height: u32) -> LifecycleEvent {
+        LifecycleEvent::Resized { width, height }
+    }
+
+    #[test]
+    fn minimize_and_restore_on_windows() {
+        let session = log(&[
+            (0.0, resized(1280, 720)),
+            (1.0, LifecycleEvent::Focused { focused: false }),
+            (1.0, resized(0, 0)),
+            (4.0, resized(1280, 720)),
+            (4.1, LifecycleEvent::Focused { focused: true }),
+        ]);
+        let lifecycle = replay(&session);
+        assert!(lifecycle.is_visible());
+        assert!(lifecycle.focused);
+        assert_eq!(lifecycle.size, PhysicalSize::new(1280, 720));
+    }
+
+    #[test]
+    fn hidden_while_occluded_or_suspended() {
+        let mut lifecycle = WindowLifecycle::default();
+        lifecycle.apply(resized(800, 600));
+        assert!(lifecycle.is_visible());
+        lifecycle.apply(LifecycleEvent::Occluded { occluded: true });
+        assert!(!lifecycle.is_visible());
+        lifecycle.apply(LifecycleEvent::Occluded { occluded: false });
+        lifecycle.apply(LifecycleEvent::Suspended);
+        assert!(!lifecycle.is_visible());
+        lifecycle.apply(LifecycleEvent::Resumed);
+        assert!(lifecycle.is_visible());
+    }
+
+    #[test]
+    fn moving_to_a_hidpi_monitor() {
+        let session = log(&[
+            (0.0, resized(1280, 720)),
+            (
+                2.0,
+                LifecycleEvent::ScaleFactorChanged { scale_factor: 2.0 },
+            ),
+            (2.0, resized(2560, 1440)),
+        ]);
+        let lifecycle = replay(&session);
+        assert_eq!(lifecycle.scale_factor, 2.0);
+        assert_eq!(lifecycle.size, PhysicalSize::new(2560, 1440));
+    }
+
+    #[test]
+    fn due_hands_out_each_event_once() {
+        let session = log(&[
+            (0.0, resized(1280, 720)),
+            (0.5, LifecycleEvent::Suspended),
+            (0.5, LifecycleEvent::Resumed),
+            (2.0, resized(640, 480)),
+        ]);
+        let mut next = 0;
+        assert_eq!(session.due(&mut next, 0.1).len(), 1);
+        assert_eq!(session.due(&mut next, 0.1).len(), 0);
+        assert_eq!(session.due(&mut next, 1.0).len(), 2);
+        assert_eq!(session.due(&mut next, 5.0), &session.events[3..]);
+        assert!(session.due(&mut next, 10.0).is_empty());
+    }
+
+    #[test]
+    fn survives_a_round_trip_through_json() {
+        let session = log(&[
+            (0.0, resized(1280, 720)),
+            (
+                1.5,
+                LifecycleEvent::ScaleFactorChanged { scale_factor: 1.25 },
+            ),
+            (3.0, LifecycleEvent::Occluded { occluded: true }),
+        ]);
+        let json = serde_json::to_string(&session).expect("session log serializes");
+        assert!(json.contains(r#""event":"scale_factor_changed""#));
+        let loaded: SessionLog = serde_json::from_str(&json).expect("session log parses");
+        assert_eq!(loaded, session);
+    }
+}
@@ -0,0 +1,54 @@
+// Final full-screen pass applied to the rendered scene
+
+struct PostParams {
+    brightness: f32,
+    gamma: f32,
+    // 0 = none, 1 = grayscale, 2 = sepia, 3 = noir, 4 = vignette
+    color_filter: u32,
+};
+
+@group(0) @binding(0) var scene_texture: texture_2d<f32>;
+@group(0) @binding(1) var scene_sampler: sampler;
+@group(0) @binding(2) var<uniform> params: PostParams;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// A single triangle covering the whole screen, no vertex buffer needed
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(scene_texture, scene_sampler, in.uv);
+    let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+
+    var rgb = color.rgb;
+    switch params.color_filter {
+        case 1u: {
+            rgb = vec3<f32>(luminance);
+        }
+        case 2u: {
+            rgb = luminance * vec3<f32>(1.07, 0.74, 0.43);
+        }
+        case 3u: {
+            rgb = vec3<f32>(smoothstep(0.05, 0.6, luminance));
+        }
+        case 4u: {
+            rgb = rgb * (1.0 - smoothstep(0.35, 0.85, distance(in.uv, vec2<f32>(0.5))));
+        }
+        default: {}
+    }
+
+    let adjusted = pow(max(rgb, vec3<f32>(0.0)), vec3<f32>(1.0 / params.gamma)) * params.brightness;
+    return vec4<f32>(adjusted, color.a);
+}
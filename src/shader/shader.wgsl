@@ -2,25 +2,38 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
 };
 
+// Camera, layer offset and orthographic projection of the batch
+@group(1) @binding(0)
+var<uniform> view: mat4x4<f32>;
+
 @vertex
 fn vs_main(
     model: VertexInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.clip_position = view * vec4<f32>(model.position, 0.0, 1.0);
     out.color = model.color;
+    out.uv = model.uv;
     return out;
 }
 
 // Fragment shader
+// Untextured shapes sample a plain white texture
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return in.color;
+    return textureSample(t_diffuse, s_diffuse, in.uv) * in.color;
 }
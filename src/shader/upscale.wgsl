@@ -0,0 +1,88 @@
+// Stretches the scene, rendered into the top left part of its target at a
+// lower resolution, over the whole window
+
+struct UpscaleParams {
+    // Part of the target the scene covers
+    extent: vec2<f32>,
+    // Size of one texel of the target
+    texel: vec2<f32>,
+    // 0 = bilinear only, up to 1 = strongest sharpening
+    sharpness: f32,
+    // 1 = nearest neighbor, for whole multiples of the scene's resolution
+    pixelated: u32,
+    // How much darker the last rows of each scene pixel get, 0 = no scanlines
+    scanlines: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0) var scene_texture: texture_2d<f32>;
+@group(0) @binding(1) var scene_sampler: sampler;
+@group(0) @binding(2) var<uniform> params: UpscaleParams;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// A single triangle covering the whole screen, no vertex buffer needed
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn scene(uv: vec2<f32>) -> vec4<f32> {
+    // Half a texel in, so the unused part of the target never bleeds in
+    let uv_clamped = clamp(uv, params.texel * 0.5, params.extent - params.texel * 0.5);
+    return textureSampleLevel(scene_texture, scene_sampler, uv_clamped, 0.0);
+}
+
+// The scene pixel under `uv`, in blocks of whole window pixels
+fn pixelated(uv: vec2<f32>, position: vec2<f32>) -> vec4<f32> {
+    let texel = vec2<i32>(floor(uv / params.texel));
+    let color = textureLoad(scene_texture, texel, 0);
+    if params.scanlines <= 0.0 {
+        return color;
+    }
+
+    // Every scene row ends in a darker third, or every other window row
+    // at the scene's own resolution
+    let factor = max(u32(round(1.0 / params.extent.y)), 1u);
+    let row = u32(position.y) % max(factor, 2u);
+    let dark = row >= max(factor, 2u) - max(factor / 3u, 1u);
+    return vec4<f32>(color.rgb * select(1.0, 1.0 - params.scanlines, dark), color.a);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = in.uv * params.extent;
+    if params.pixelated == 1u {
+        return pixelated(uv, in.clip_position.xy);
+    }
+    let center = scene(uv);
+    if params.sharpness <= 0.0 {
+        return center;
+    }
+
+    // Contrast adaptive sharpening: the center is pushed away from its
+    // neighbours, less where it's already close to the brightest or darkest
+    // of them so edges don't ring
+    let up = scene(uv - vec2<f32>(0.0, params.texel.y)).rgb;
+    let down = scene(uv + vec2<f32>(0.0, params.texel.y)).rgb;
+    let left = scene(uv - vec2<f32>(params.texel.x, 0.0)).rgb;
+    let right = scene(uv + vec2<f32>(params.texel.x, 0.0)).rgb;
+
+    let lowest = min(center.rgb, min(min(up, down), min(left, right)));
+    let highest = max(center.rgb, max(max(up, down), max(left, right)));
+    let headroom = min(lowest, 1.0 - highest) / max(highest, vec3<f32>(0.0001));
+    let amount = sqrt(clamp(headroom, vec3<f32>(0.0), vec3<f32>(1.0))) * params.sharpness;
+    // Weight of the neighbours, between -1/8 and -1/5
+    let weight = -amount / mix(8.0, 5.0, params.sharpness);
+
+    let rgb = (center.rgb + (up + down + left + right) * weight) / (1.0 + 4.0 * weight);
+    return vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), center.a);
+}
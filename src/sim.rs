@@ -0,0 +1,1257 @@
+use cgmath::{InnerSpace, Vector2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiView;
+use crate::mutators::Mutators;
+use crate::replay::{ReplayHeader, ReplaySettings, TickInput, analog_speed, speed_share};
+use crate::score::Score;
+use crate::strict_math::Math;
+
+// Size of the field every match is played on, whatever the window size. The
+// renderer scales it to the window.
+pub static FIELD_WIDTH: f32 = 1280.0;
+pub static FIELD_HEIGHT: f32 = 720.0;
+// Windows wider than ULTRAWIDE_ASPECT or narrower than PORTRAIT_ASPECT get a
+// field of their own shape instead of wide bars around the standard one, up
+// to MAX_FIELD_ASPECT and MIN_FIELD_ASPECT
+static ULTRAWIDE_ASPECT: f32 = 2.0;
+static PORTRAIT_ASPECT: f32 = 1.0;
+static MAX_FIELD_ASPECT: f32 = 32.0 / 9.0;
+static MIN_FIELD_ASPECT: f32 = 9.0 / 16.0;
+pub static PADDLE_SPEED: f32 = 1000.0;
+pub static BALL_SPEED: f32 = 400.0;
+pub static BALL_RADIUS: f32 = 20.0;
+// The first side to this many points wins the game, unless the player picked another win score
+pub static GAME_POINTS: u8 = 11;
+static PADDLE_WIDTH: f32 = 20.0;
+pub static PADDLE_HEIGHT: f32 = 100.0;
+static SMALL_PADDLE_HEIGHT: f32 = 60.0;
+// The ball is held at the center this long before it is served
+pub static SERVE_DELAY: f32 = 0.8;
+// Furthest the serve can be aimed from horizontal, in radians
+pub static SERVE_AIM_LIMIT: f32 = std::f32::consts::PI / 4.0;
+static SERVE_AIM_SPEED: f32 = std::f32::consts::PI / 2.0;
+// Save assist: kicks in when the ball is this close to the goal line of a
+// player that has been still for SAVE_ASSIST_IDLE seconds
+static SAVE_ASSIST_DISTANCE: f32 = 180.0;
+static SAVE_ASSIST_IDLE: f32 = 0.3;
+static SAVE_ASSIST_TIME_SCALE: f32 = 0.35;
+// Longest slow motion per approach of the ball, in game seconds
+static SAVE_ASSIST_DURATION: f32 = 0.5;
+// How fast the time scale eases in and out, per second
+static TIME_SCALE_RATE: f32 = 6.0;
+// Rubber band: a lead of this many points gets the mutator's full strength
+static RUBBER_BAND_LEAD: i64 = 3;
+// Spin given by a moving paddle, in radians per second the ball's direction turns
+pub static MAX_SPIN: f32 = 1.5;
+// Share of the spin lost per second
+static SPIN_DECAY: f32 = 0.6;
+// Momentum: a paddle hitting the ball at full speed returns it this much faster
+static MOMENTUM_TRANSFER: f32 = 0.25;
+// Steepest a return can get from the paddle's movement, from horizontal
+static MAX_TRANSFER_ANGLE: f32 = std::f32::consts::PI / 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn opponent(self) -> Self {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+// Things that happened during a simulation step, for effects, AI and stats.
+#[derive(Debug, Clone, Copy)]
+pub enum SimEvent {
+    PaddleHit {
+        side: Side,
+        pos: Vector2<f32>,
+        // Ball speed leaving the paddle
+        speed: f32,
+    },
+    WallBounce,
+    // The ball was put back into play, heading toward `toward`
+    Serve {
+        toward: Side,
+    },
+    Portal {
+        entry: Vector2<f32>,
+        exit: Vector2<f32>,
+        direction: f32,
+    },
+    Goal {
+        scorer: Side,
+    },
+}
+
+pub struct Paddle {
+    pub score: Score,
+    pub pos: Vector2<f32>,
+    pub width: f32,
+    pub height: f32,
+    // Seconds since the paddle last moved
+    pub idle: f32,
+    // -1 moving up, 1 moving down, 0 standing still
+    pub direction: f32,
+    // Pixels per second, positive is down
+    pub velocity: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ball {
+    pub pos: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+    pub radius: f32,
+    // Radians per second the velocity turns, positive is clockwise on screen
+    pub spin: f32,
+}
+
+impl Ball {
+    fn reset(&mut self, screen_width: f32, screen_height: f32) {
+        self.pos = Vector2::new(screen_width / 2.0, screen_height / 2.0);
+        self.velocity = Vector2::new(0.0, 0.0);
+        self.spin = 0.0;
+    }
+
+    // Adds `transfer` of the hitting paddle's velocity to the outgoing
+    // velocity, so a paddle swung into the ball sends it off faster and steeper.
+    fn transfer_from(&mut self, paddle: &Paddle, transfer: f32, math: Math) {
+        if transfer <= 0.0 {
+            return;
+        }
+        self.velocity.y += paddle.velocity * transfer;
+
+        // Keep the return from going almost straight up or down
+        let max_y = self.velocity.x.abs() * math.tan(MAX_TRANSFER_ANGLE);
+        if self.velocity.y.abs() > max_y {
+            let speed = self.velocity.magnitude();
+            let (sin, cos) = math.sin_cos(MAX_TRANSFER_ANGLE);
+            self.velocity = Vector2::new(
+                self.velocity.x.signum() * speed * cos,
+                self.velocity.y.signum() * speed * sin,
+            );
+        }
+    }
+
+    // Bounces off a wall facing `normal`, 1 down or -1 up, keeping
+    // `restitution` of the speed into it
+    fn bounce(&mut self, normal: f32, restitution: f32, math: Math) {
+        self.velocity.y = normal * self.velocity.y.abs() * restitution;
+        self.spin = -self.spin;
+
+        // A wall that gains speed mustn't end in a ball going straight up
+        // and down
+        let max_y = self.velocity.x.abs() * math.tan(MAX_TRANSFER_ANGLE);
+        if restitution > 1.0 && self.velocity.y.abs() > max_y {
+            self.velocity.y = normal * max_y;
+        }
+    }
+
+    // Spin from a hit by a paddle moving in `direction`, curving the ball
+    // toward where the paddle was heading.
+    fn spin_from(&mut self, direction: f32) {
+        self.spin = direction * MAX_SPIN * self.velocity.x.signum();
+    }
+
+    fn launch(&mut self, toward: Side, angle: f32, speed: f32, math: Math) {
+        let direction = match toward {
+            Side::Left => -1.0,
+            Side::Right => 1.0,
+        };
+
+        let (sin, cos) = math.sin_cos(angle);
+        self.velocity = Vector2::new(direction * speed * cos, speed * sin);
+    }
+}
+
+// All of a `GameSim`, see `GameSim::state`. Sent to the other player of an
+// online match to check that both sims agree, or to bring them back in step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimState {
+    pub seed: u64,
+    pub ticks: u64,
+    pub serves: u64,
+    pub paddles: [PaddleState; 2],
+    pub ball: BallState,
+    pub mutators: Mutators,
+    pub field: [f32; 2],
+    // Direction, aim and time left of a ball waiting to be served
+    pub serve: Option<(Side, f32, f32)>,
+    pub win_score: u8,
+    pub math: Math,
+    pub paddle_speed: f32,
+    pub serve_speed: f32,
+    pub time_scale: f32,
+    pub assist_used: f32,
+    pub ball_speed: f32,
+}
+
+impl SimState {
+    // FNV-1a of the state as it's sent, the same on every machine. Floats
+    // are written to round trip, so any bit that differs changes the hash.
+    pub fn hash(&self) -> u64 {
+        let data = serde_json::to_vec(self).unwrap_or_default();
+        data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PaddleState {
+    pub pos: [f32; 2],
+    pub width: f32,
+    pub height: f32,
+    pub score: Score,
+    pub idle: f32,
+    pub direction: f32,
+    pub velocity: f32,
+}
+
+impl PaddleState {
+    fn of(paddle: &Paddle) -> Self {
+        Self {
+            pos: paddle.pos.into(),
+            width: paddle.width,
+            height: paddle.height,
+            score: paddle.score,
+            idle: paddle.idle,
+            direction: paddle.direction,
+            velocity: paddle.velocity,
+        }
+    }
+
+    fn paddle(self) -> Paddle {
+        Paddle {
+            score: self.score,
+            pos: self.pos.into(),
+            width: self.width,
+            height: self.height,
+            idle: self.idle,
+            direction: self.direction,
+            velocity: self.velocity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BallState {
+    pub pos: [f32; 2],
+    pub velocity: [f32; 2],
+    pub radius: f32,
+    pub spin: f32,
+}
+
+// A ball waiting at the center to be served. The serving player (the one
+// the ball is heading away from) aims it with up/down until `remaining` runs out.
+#[derive(Debug, Clone, Copy)]
+pub struct PendingServe {
+    pub toward: Side,
+    // Radians from horizontal, positive is downward
+    pub angle: f32,
+    pub remaining: f32,
+}
+
+impl PendingServe {
+    pub fn server(&self) -> Side {
+        self.toward.opponent()
+    }
+}
+
+// The field for a window of `aspect`, width over height. Ultrawide windows
+// get a wider field of the standard height, portrait ones a taller field of
+// the standard width, everything in between the standard field.
+pub fn fit_field(aspect: f32) -> (f32, f32) {
+    if !aspect.is_finite()
+        || aspect <= 0.0
+        || (PORTRAIT_ASPECT..=ULTRAWIDE_ASPECT).contains(&aspect)
+    {
+        (FIELD_WIDTH, FIELD_HEIGHT)
+    } else if aspect > ULTRAWIDE_ASPECT {
+        (FIELD_HEIGHT * aspect.min(MAX_FIELD_ASPECT), FIELD_HEIGHT)
+    } else {
+        (FIELD_WIDTH, FIELD_WIDTH / aspect.max(MIN_FIELD_ASPECT))
+    }
+}
+
+// How much the sizes and speeds of a field differ from the standard one, 1
+// for all of them there. The paddles keep their share of the goal line and
+// cross it as fast, the ball crosses the field as fast and grows with its
+// area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldScale {
+    pub paddle: f32,
+    pub ball: f32,
+    pub paddle_speed: f32,
+    pub ball_speed: f32,
+}
+
+impl FieldScale {
+    pub fn of(width: f32, height: f32) -> Self {
+        let across = width / FIELD_WIDTH;
+        let along = height / FIELD_HEIGHT;
+        Self {
+            paddle: along,
+            ball: (across * along).sqrt(),
+            paddle_speed: along,
+            ball_speed: across,
+        }
+    }
+}
+
+// The game rules, independent of windowing and rendering.
+pub struct GameSim {
+    pub left: Paddle,
+    pub right: Paddle,
+    pub ball: Ball,
+    pub mutators: Mutators,
+    pub width: f32,
+    pub height: f32,
+    pub pending_serve: Option<PendingServe>,
+    // Points needed to win, GAME_POINTS unless changed after creating the sim
+    pub win_score: u8,
+    // Native unless changed after creating the sim, see `Math`
+    pub math: Math,
+    // Pixels per second, PADDLE_SPEED and BALL_SPEED unless changed after
+    // creating the sim
+    pub paddle_speed: f32,
+    pub serve_speed: f32,
+    // 1.0 at normal speed, lower while the save assist slows the game down
+    time_scale: f32,
+    // Game seconds of slow motion used since the ball last changed direction
+    assist_used: f32,
+    // Speed of the ball for the current rally, see `Mutators::rubber_band`
+    ball_speed: f32,
+    seed: u64,
+    // Steps taken and balls served since the sim was created, see `state`
+    ticks: u64,
+    serves: u64,
+    rng: StdRng,
+}
+
+impl GameSim {
+    pub fn new(width: f32, height: f32, mutators: Mutators, seed: u64) -> Self {
+        let mut sim = Self {
+            left: Paddle {
+                pos: Vector2 {
+                    x: 0.0,
+                    y: height / 2.0,
+                },
+                score: Score::default(),
+                width: PADDLE_WIDTH,
+                height: if mutators.small_paddle {
+                    SMALL_PADDLE_HEIGHT
+                } else {
+                    PADDLE_HEIGHT
+                },
+                idle: 0.0,
+                direction: 0.0,
+                velocity: 0.0,
+            },
+            right: Paddle {
+                pos: Vector2 {
+                    x: width,
+                    y: height / 2.0,
+                },
+                score: Score::default(),
+                width: PADDLE_WIDTH,
+                height: if mutators.practice_wall {
+                    height
+                } else {
+                    PADDLE_HEIGHT
+                },
+                idle: 0.0,
+                direction: 0.0,
+                velocity: 0.0,
+            },
+            ball: Ball {
+                pos: Vector2 {
+                    x: width / 2.0,
+                    y: height / 2.0,
+                },
+                velocity: Vector2::new(0.0, 0.0),
+                radius: BALL_RADIUS,
+                spin: 0.0,
+            },
+            mutators,
+            width,
+            height,
+            pending_serve: None,
+            win_score: GAME_POINTS,
+            math: Math::Native,
+            paddle_speed: PADDLE_SPEED,
+            serve_speed: BALL_SPEED,
+            time_scale: 1.0,
+            assist_used: 0.0,
+            ball_speed: BALL_SPEED,
+            seed,
+            ticks: 0,
+            serves: 0,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        sim.serve(&mut Vec::new());
+        sim
+    }
+
+    // The sim a replay was recorded with, feeding it the replay's ticks
+    // plays the match again exactly as it happened.
+    pub fn from_replay(header: &ReplayHeader) -> Self {
+        let settings = &header.settings;
+        let mut sim = Self::new(
+            settings.field_width,
+            settings.field_height,
+            settings.mutators,
+            header.seed,
+        );
+        // The speeds were recorded fitted already
+        sim.scale_sizes(sim.field_scale());
+        sim.math = settings.math;
+        sim.paddle_speed = settings.paddle_speed;
+        sim.serve_speed = settings.ball_speed;
+        sim
+    }
+
+    // Scales the paddles, the ball and the speeds set so far to a field
+    // other than the standard one. Call once, after setting the speeds.
+    pub fn fit_to_field(&mut self) {
+        let scale = self.field_scale();
+        self.scale_sizes(scale);
+        self.paddle_speed *= scale.paddle_speed;
+        self.serve_speed *= scale.ball_speed;
+    }
+
+    pub fn field_scale(&self) -> FieldScale {
+        FieldScale::of(self.width, self.height)
+    }
+
+    // The practice wall already covers the whole goal line
+    fn scale_sizes(&mut self, scale: FieldScale) {
+        self.left.height *= scale.paddle;
+        if !self.mutators.practice_wall {
+            self.right.height *= scale.paddle;
+        }
+        self.ball.radius *= scale.ball;
+    }
+
+    // Of the momentum mutator, so paddles reach full speed as fast as on the
+    // standard field
+    fn acceleration(&self) -> f32 {
+        self.mutators.momentum * self.field_scale().paddle_speed
+    }
+
+    // A copy of everything, to compare sims or start another one from
+    pub fn state(&self) -> SimState {
+        SimState {
+            seed: self.seed,
+            ticks: self.ticks,
+            serves: self.serves,
+            paddles: [PaddleState::of(&self.left), PaddleState::of(&self.right)],
+            ball: BallState {
+                pos: self.ball.pos.into(),
+                velocity: self.ball.velocity.into(),
+                radius: self.ball.radius,
+                spin: self.ball.spin,
+            },
+            mutators: self.mutators,
+            field: [self.width, self.height],
+            serve: self
+                .pending_serve
+                .map(|serve| (serve.toward, serve.angle, serve.remaining)),
+            win_score: self.win_score,
+            math: self.math,
+            paddle_speed: self.paddle_speed,
+            serve_speed: self.serve_speed,
+            time_scale: self.time_scale,
+            assist_used: self.assist_used,
+            ball_speed: self.ball_speed,
+        }
+    }
+
+    // The sim `state` was taken from, continuing exactly like it would. The
+    // random generator is seeded again and wound forward past the serves.
+    pub fn from_state(state: &SimState) -> Self {
+        let mut rng = StdRng::seed_from_u64(state.seed);
+        for _ in 0..state.serves {
+            draw_serve(&mut rng, &state.mutators);
+        }
+        let [left, right] = state.paddles;
+        let ball = state.ball;
+        Self {
+            left: left.paddle(),
+            right: right.paddle(),
+            ball: Ball {
+                pos: ball.pos.into(),
+                velocity: ball.velocity.into(),
+                radius: ball.radius,
+                spin: ball.spin,
+            },
+            mutators: state.mutators,
+            width: state.field[0],
+            height: state.field[1],
+            pending_serve: state.serve.map(|(toward, angle, remaining)| PendingServe {
+                toward,
+                angle,
+                remaining,
+            }),
+            win_score: state.win_score,
+            math: state.math,
+            paddle_speed: state.paddle_speed,
+            serve_speed: state.serve_speed,
+            time_scale: state.time_scale,
+            assist_used: state.assist_used,
+            ball_speed: state.ball_speed,
+            seed: state.seed,
+            ticks: state.ticks,
+            serves: state.serves,
+            rng,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Steps taken since the sim was created
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn settings(&self) -> ReplaySettings {
+        ReplaySettings {
+            field_width: self.width,
+            field_height: self.height,
+            paddle_speed: self.paddle_speed,
+            ball_speed: self.serve_speed,
+            ball_radius: self.ball.radius,
+            mutators: self.mutators,
+            math: self.math,
+        }
+    }
+
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.width = width;
+        self.height = height;
+        self.right.pos.x = width;
+    }
+
+    pub fn paddle(&self, side: Side) -> &Paddle {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    // What an AI controlling `side` gets to see.
+    pub fn ai_view(&self, side: Side) -> AiView {
+        let paddle = self.paddle(side);
+        let face_x = match side {
+            Side::Left => paddle.pos.x + paddle.width,
+            Side::Right => paddle.pos.x - paddle.width,
+        };
+
+        AiView {
+            ball_pos: self.ball.pos,
+            ball_velocity: self.ball.velocity,
+            paddle_pos: Vector2::new(face_x, paddle.pos.y),
+            paddle_height: paddle.height,
+            field_height: self.height,
+            portal_walls: self.mutators.portal_walls,
+        }
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    // Share of the slow motion left for the ball's current direction, 0..1
+    pub fn save_assist_remaining(&self) -> f32 {
+        1.0 - (self.assist_used / SAVE_ASSIST_DURATION).min(1.0)
+    }
+
+    // Ball speed for a rally served by `server`, faster if they are ahead and
+    // slower if they are behind.
+    fn rally_speed(&self, server: Side) -> f32 {
+        let lead = self
+            .paddle(server)
+            .score
+            .lead_over(self.paddle(server.opponent()).score)
+            .clamp(-RUBBER_BAND_LEAD, RUBBER_BAND_LEAD);
+        let strength = self.mutators.rubber_band.clamp(0.0, 1.0);
+        self.serve_speed * (1.0 + strength * lead as f32 / RUBBER_BAND_LEAD as f32)
+    }
+
+    // Whether the save assist wants to slow down the game right now.
+    fn save_assist_active(&self) -> bool {
+        if self.pending_serve.is_some() || self.assist_used >= SAVE_ASSIST_DURATION {
+            return false;
+        }
+
+        let toward = self.serving_toward();
+        let paddle = self.paddle(toward);
+        let distance = match toward {
+            Side::Left => self.ball.pos.x,
+            Side::Right => self.width - self.ball.pos.x,
+        };
+        self.mutators.save_assist(toward)
+            && distance < SAVE_ASSIST_DISTANCE
+            && paddle.idle >= SAVE_ASSIST_IDLE
+    }
+
+    pub fn step(&mut self, delta: f32, input: TickInput) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        self.ticks += 1;
+
+        // The whole game runs slower while the assist is on, so the
+        // player has more real time to react
+        let target = if self.save_assist_active() {
+            SAVE_ASSIST_TIME_SCALE
+        } else {
+            1.0
+        };
+        let max_change = TIME_SCALE_RATE * delta;
+        self.time_scale += (target - self.time_scale).clamp(-max_change, max_change);
+        let delta = delta * self.time_scale;
+        if target < 1.0 {
+            self.assist_used += delta;
+        }
+
+        if input.reset_ball {
+            self.serve(&mut events);
+        }
+
+        // Input Handling:
+        {
+            let acceleration = self.acceleration();
+            move_paddle(
+                &mut self.left,
+                input.left_up,
+                input.left_down,
+                self.paddle_speed * speed_share(input.left_speed),
+                delta,
+                self.height,
+                acceleration,
+            );
+            move_paddle(
+                &mut self.right,
+                input.right_up,
+                input.right_down,
+                self.paddle_speed * speed_share(input.right_speed),
+                delta,
+                self.height,
+                acceleration,
+            );
+        }
+
+        // Serve aiming
+        if let Some(serve) = self.pending_serve.as_mut() {
+            let (up, down) = match serve.server() {
+                Side::Left => (input.left_up, input.left_down),
+                Side::Right => (input.right_up, input.right_down),
+            };
+            if up {
+                serve.angle -= SERVE_AIM_SPEED * delta;
+            }
+            if down {
+                serve.angle += SERVE_AIM_SPEED * delta;
+            }
+            serve.angle = serve.angle.clamp(-SERVE_AIM_LIMIT, SERVE_AIM_LIMIT);
+
+            serve.remaining -= delta;
+            if serve.remaining <= 0.0 {
+                let serve = *serve;
+                self.ball_speed = self.rally_speed(serve.server());
+                self.ball
+                    .launch(serve.toward, serve.angle, self.ball_speed, self.math);
+                self.pending_serve = None;
+            }
+        }
+
+        // Ball movement
+        let speed = self.ball_speed;
+        let momentum = self.mutators.momentum > 0.0;
+        let paddle_speed = self.paddle_speed;
+        let transfer = self.mutators.impact_transfer;
+        let spin = self.mutators.spin;
+        let restitution = self.mutators.wall_restitution();
+        let math = self.math;
+        let ball = &mut self.ball;
+        if ball.spin != 0.0 {
+            let (sin, cos) = math.sin_cos(ball.spin * delta);
+            ball.velocity = Vector2::new(
+                ball.velocity.x * cos - ball.velocity.y * sin,
+                ball.velocity.x * sin + ball.velocity.y * cos,
+            );
+            ball.spin *= (1.0 - SPIN_DECAY * delta).max(0.0);
+        }
+        let mut start = ball.pos;
+        ball.pos.x += ball.velocity.x * delta;
+        ball.pos.y += ball.velocity.y * delta;
+
+        if self.mutators.portal_walls {
+            // Ball teleports through the top and bottom walls
+            if ball.pos.y < 0.0 || ball.pos.y > self.height {
+                let entry = Vector2::new(ball.pos.x, ball.pos.y.clamp(0.0, self.height));
+                let wrapped = ball.pos.y.rem_euclid(self.height);
+                start.y += wrapped - ball.pos.y;
+                ball.pos.y = wrapped;
+                let exit = Vector2::new(ball.pos.x, self.height - entry.y);
+                events.push(SimEvent::Portal {
+                    entry,
+                    exit,
+                    direction: ball.velocity.y.signum(),
+                });
+            }
+        } else {
+            // Ball collision with top and bottom walls
+            if ball.pos.y - ball.radius < 0.0 {
+                ball.pos.y = ball.radius;
+                ball.bounce(1.0, restitution, math);
+                events.push(SimEvent::WallBounce);
+            }
+            if ball.pos.y + ball.radius > self.height {
+                ball.pos.y = self.height - ball.radius;
+                ball.bounce(-1.0, restitution, math);
+                events.push(SimEvent::WallBounce);
+            }
+        }
+
+        // Ball collision with left paddle, checked where the ball crossed the
+        // paddle's face so a fast ball can't pass through it
+        let left = &self.left;
+        let contact = left.pos.x + left.width + ball.radius;
+        if ball.velocity.x < 0.0
+            && let Some(y) = crossing(start, ball.pos, contact)
+            && y > left.pos.y - left.height / 2.0
+            && y < left.pos.y + left.height / 2.0
+        {
+            ball.pos = Vector2::new(contact, y);
+
+            // Bounce right with angle based on where the ball hit the paddle
+            let relative_intersect_y = left.pos.y - ball.pos.y;
+            let normalized_relative_intersection_y = relative_intersect_y / (left.height / 2.0);
+            let bounce_angle = normalized_relative_intersection_y * std::f32::consts::PI / 4.0;
+
+            let speed = impact_speed(speed, left, paddle_speed, momentum);
+            let (sin, cos) = math.sin_cos(bounce_angle);
+            ball.velocity.x = speed * cos;
+            ball.velocity.y = -speed * sin;
+            ball.transfer_from(left, transfer, math);
+            if spin {
+                ball.spin_from(left.direction);
+            }
+            self.assist_used = 0.0;
+            events.push(SimEvent::PaddleHit {
+                side: Side::Left,
+                pos: ball.pos,
+                speed: ball.velocity.magnitude(),
+            });
+        }
+
+        // Ball collision with right paddle
+        let right = &self.right;
+        let contact = right.pos.x - right.width - ball.radius;
+        if ball.velocity.x > 0.0
+            && let Some(y) = crossing(start, ball.pos, contact)
+            && y > right.pos.y - right.height / 2.0
+            && y < right.pos.y + right.height / 2.0
+        {
+            ball.pos = Vector2::new(contact, y);
+
+            // Bounce left with angle based on where the ball hit the paddle
+            let relative_intersect_y = right.pos.y - ball.pos.y;
+            let normalized_relative_intersection_y = relative_intersect_y / (right.height / 2.0);
+            let bounce_angle = normalized_relative_intersection_y * std::f32::consts::PI / 4.0;
+
+            let speed = impact_speed(speed, right, paddle_speed, momentum);
+            let (sin, cos) = math.sin_cos(bounce_angle);
+            ball.velocity.x = -speed * cos;
+            ball.velocity.y = -speed * sin;
+            ball.transfer_from(right, transfer, math);
+            if spin {
+                ball.spin_from(right.direction);
+            }
+            self.assist_used = 0.0;
+            events.push(SimEvent::PaddleHit {
+                side: Side::Right,
+                pos: ball.pos,
+                speed: ball.velocity.magnitude(),
+            });
+        }
+
+        // Scoring: ball out of bounds
+        if self.ball.pos.x < 0.0 {
+            self.right.score.add_point();
+            events.push(SimEvent::Goal {
+                scorer: Side::Right,
+            });
+            self.serve(&mut events);
+        }
+        if self.ball.pos.x > self.width {
+            self.left.score.add_point();
+            events.push(SimEvent::Goal { scorer: Side::Left });
+            self.serve(&mut events);
+        }
+
+        events
+    }
+
+    fn serve(&mut self, events: &mut Vec<SimEvent>) {
+        self.ball.reset(self.width, self.height);
+        self.assist_used = 0.0;
+
+        let (toward, angle) = draw_serve(&mut self.rng, &self.mutators);
+        self.serves += 1;
+        self.pending_serve = Some(PendingServe {
+            toward,
+            angle,
+            remaining: SERVE_DELAY,
+        });
+        events.push(SimEvent::Serve { toward });
+    }
+
+    // Moves one paddle ahead of the rest of the match, e.g. the local
+    // player's in an online match the other side simulates.
+    pub fn move_paddle(&mut self, side: Side, direction: f32, delta: f32) {
+        let acceleration = self.acceleration();
+        let paddle = match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        };
+        move_paddle(
+            paddle,
+            direction < 0.0,
+            direction > 0.0,
+            self.paddle_speed * speed_share(analog_speed(direction)),
+            delta,
+            self.height,
+            acceleration,
+        );
+    }
+
+    // The side that reached `win_score`, if any. Play may continue after that.
+    pub fn winner(&self) -> Option<Side> {
+        if self.left.score.has_reached(self.win_score) && self.left.score > self.right.score {
+            Some(Side::Left)
+        } else if self.right.score.has_reached(self.win_score) && self.right.score > self.left.score
+        {
+            Some(Side::Right)
+        } else {
+            None
+        }
+    }
+
+    // The side the ball is currently flying toward, or about to be served toward.
+    pub fn serving_toward(&self) -> Side {
+        if let Some(serve) = self.pending_serve {
+            serve.toward
+        } else if self.ball.velocity.x < 0.0 {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
+}
+
+// Random side and a random starting aim the server can then adjust. The
+// practice wall always serves to the player.
+fn draw_serve(rng: &mut StdRng, mutators: &Mutators) -> (Side, f32) {
+    let toward = if mutators.practice_wall {
+        Side::Left
+    } else if rng.random::<bool>() {
+        Side::Right
+    } else {
+        Side::Left
+    };
+    (toward, rng.random_range(-SERVE_AIM_LIMIT..SERVE_AIM_LIMIT))
+}
+
+// The height at which the ball's center reached `x` while moving from `start`
+// to `end`, if it did. Leaving `x` doesn't count, the ball is placed there
+// after a hit.
+fn crossing(start: Vector2<f32>, end: Vector2<f32>, x: f32) -> Option<f32> {
+    let reached = (start.x > x && end.x <= x) || (start.x < x && end.x >= x);
+    if !reached {
+        return None;
+    }
+    let t = (start.x - x) / (start.x - end.x);
+    Some(start.y + (end.y - start.y) * t)
+}
+
+// Outgoing ball speed for a hit, faster off a paddle moving at speed when
+// the momentum mutator is on.
+fn impact_speed(speed: f32, paddle: &Paddle, paddle_speed: f32, momentum: bool) -> f32 {
+    if !momentum {
+        return speed;
+    }
+    speed * (1.0 + MOMENTUM_TRANSFER * (paddle.velocity.abs() / paddle_speed).min(1.0))
+}
+
+// `acceleration` is in pixels per second², 0 moves at full speed right away.
+fn move_paddle(
+    paddle: &mut Paddle,
+    up: bool,
+    down: bool,
+    // Pixels per second up or down
+    speed: f32,
+    delta: f32,
+    field_height: f32,
+    acceleration: f32,
+) {
+    if up || down {
+        paddle.idle = 0.0;
+    } else {
+        paddle.idle += delta;
+    }
+    paddle.direction = match (up, down) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+
+    let target = paddle.direction * speed;
+    paddle.velocity = if acceleration > 0.0 {
+        let max_change = acceleration * delta;
+        paddle.velocity + (target - paddle.velocity).clamp(-max_change, max_change)
+    } else {
+        target
+    };
+    paddle.pos.y += paddle.velocity * delta;
+
+    // Stop at the walls
+    let half_height = paddle.height / 2.;
+    if paddle.pos.y < half_height {
+        paddle.pos.y = half_height;
+        paddle.velocity = paddle.velocity.max(0.0);
+    }
+    if paddle.pos.y > field_height - half_height {
+        paddle.pos.y = field_height - half_height;
+        paddle.velocity = paddle.velocity.min(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::Replay;
+    use crate::timestep::TICK_RATE;
+
+    // Paddles moving up and down at different rhythms, with a serve reset
+    // now and then, so rallies, wall bounces and goals all happen
+    fn scripted_input(tick: u32) -> TickInput {
+        TickInput {
+            left_up: tick % 90 < 40,
+            left_down: tick % 90 >= 50,
+            right_up: tick % 130 < 70,
+            right_down: tick % 130 >= 75,
+            reset_ball: tick % 2000 == 1999,
+            left_speed: (tick % 300 < 100).then_some(128),
+            right_speed: None,
+        }
+    }
+
+    // Everything that ends up on screen, as bits so -0.0 and NaN count too
+    fn snapshot(sim: &GameSim) -> Vec<u32> {
+        let mut state = vec![
+            sim.ball.pos.x.to_bits(),
+            sim.ball.pos.y.to_bits(),
+            sim.ball.velocity.x.to_bits(),
+            sim.ball.velocity.y.to_bits(),
+            sim.ball.spin.to_bits(),
+            sim.left.pos.y.to_bits(),
+            sim.right.pos.y.to_bits(),
+            sim.left.velocity.to_bits(),
+            sim.right.velocity.to_bits(),
+            sim.left.score.points(),
+            sim.right.score.points(),
+            sim.time_scale.to_bits(),
+        ];
+        if let Some(serve) = sim.pending_serve {
+            state.extend([serve.angle.to_bits(), serve.remaining.to_bits()]);
+        }
+        state
+    }
+
+    fn goals(events: &[SimEvent]) -> usize {
+        events
+            .iter()
+            .filter(|event| matches!(event, SimEvent::Goal { .. }))
+            .count()
+    }
+
+    // The ball in play at `pos`, moving at `velocity`
+    fn in_play(pos: Vector2<f32>, velocity: Vector2<f32>) -> GameSim {
+        let mut sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), 1);
+        sim.pending_serve = None;
+        sim.ball.pos = pos;
+        sim.ball.velocity = velocity;
+        sim
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_the_same_match() {
+        let mutators = Mutators {
+            spin: true,
+            momentum: 4000.0,
+            impact_transfer: 0.3,
+            ..Default::default()
+        };
+        let mut first = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, mutators, 42);
+        let mut second = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, mutators, 42);
+        let dt = 1.0 / TICK_RATE as f32;
+        let mut points = 0;
+        for tick in 0..20_000 {
+            points += goals(&first.step(dt, scripted_input(tick)));
+            second.step(dt, scripted_input(tick));
+            assert_eq!(
+                snapshot(&first),
+                snapshot(&second),
+                "diverged at tick {tick}"
+            );
+        }
+        assert!(points > 0, "the script never scores");
+    }
+
+    #[test]
+    fn other_seeds_serve_differently() {
+        let serves: Vec<f32> = (0..8)
+            .map(|seed| {
+                let sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), seed);
+                sim.pending_serve.map_or(0.0, |serve| serve.angle)
+            })
+            .collect();
+        assert!(serves.iter().any(|angle| *angle != serves[0]));
+    }
+
+    #[test]
+    fn restored_states_continue_the_same_match() {
+        let mut sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), 3);
+        let dt = 1.0 / TICK_RATE as f32;
+        for tick in 0..5_000 {
+            sim.step(dt, scripted_input(tick));
+        }
+
+        // Through JSON like the state sent to a client out of sync
+        let json = serde_json::to_string(&sim.state()).expect("state serializes");
+        let state: SimState = serde_json::from_str(&json).expect("state deserializes");
+        let mut restored = GameSim::from_state(&state);
+        assert_eq!(restored.state().hash(), sim.state().hash());
+        for tick in 5_000..15_000 {
+            sim.step(dt, scripted_input(tick));
+            restored.step(dt, scripted_input(tick));
+            assert_eq!(
+                sim.state().hash(),
+                restored.state().hash(),
+                "diverged at tick {tick}"
+            );
+        }
+    }
+
+    #[test]
+    fn replays_resimulate_bit_exactly() {
+        let mut sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), 7);
+        sim.math = Math::Strict;
+        sim.paddle_speed = 1200.0;
+        sim.serve_speed = 500.0;
+        let mut replay = Replay::new(["Left".into(), "Right".into()], sim.settings(), 7);
+
+        // Frame times vary like they do in the game
+        let mut recorded = Vec::new();
+        for tick in 0..10_000 {
+            let dt = [1.0 / 120.0, 1.0 / 144.0, 1.0 / 60.0][tick as usize % 3];
+            let input = scripted_input(tick);
+            replay.record(dt, input);
+            sim.step(dt, input);
+            recorded.push(snapshot(&sim));
+        }
+
+        let json = serde_json::to_string(&replay).expect("replay serializes");
+        let loaded = Replay::from_json(&json).expect("replay loads");
+        let mut replayed = GameSim::from_replay(&loaded.header);
+        for (index, tick) in loaded.body.ticks.iter().enumerate() {
+            replayed.step(tick.dt, tick.input);
+            assert_eq!(
+                snapshot(&replayed),
+                recorded[index],
+                "diverged at tick {index}"
+            );
+        }
+    }
+
+    #[test]
+    fn missed_ball_scores_for_the_other_side() {
+        let mut sim = in_play(Vector2::new(5.0, 40.0), Vector2::new(-BALL_SPEED, 0.0));
+        let events = sim.step(0.1, TickInput::default());
+        assert!(matches!(
+            events.as_slice(),
+            [
+                SimEvent::Goal {
+                    scorer: Side::Right
+                },
+                SimEvent::Serve { .. }
+            ]
+        ));
+        assert_eq!(sim.right.score.points(), 1);
+        assert_eq!(sim.left.score.points(), 0);
+        assert!(sim.pending_serve.is_some());
+    }
+
+    #[test]
+    fn paddle_returns_the_ball() {
+        let contact = PADDLE_WIDTH + BALL_RADIUS;
+        let mut sim = in_play(
+            Vector2::new(contact + 10.0, FIELD_HEIGHT / 2.0),
+            Vector2::new(-BALL_SPEED, 0.0),
+        );
+        let events = sim.step(0.05, TickInput::default());
+        assert!(matches!(
+            events.as_slice(),
+            [SimEvent::PaddleHit {
+                side: Side::Left,
+                ..
+            }]
+        ));
+        assert_eq!(sim.ball.pos.x, contact);
+        assert!(sim.ball.velocity.x > 0.0);
+    }
+
+    #[test]
+    fn fast_ball_cannot_pass_through_a_paddle() {
+        let mut sim = in_play(
+            Vector2::new(FIELD_WIDTH - 200.0, FIELD_HEIGHT / 2.0),
+            Vector2::new(20_000.0, 0.0),
+        );
+        let events = sim.step(0.05, TickInput::default());
+        assert!(matches!(
+            events.as_slice(),
+            [SimEvent::PaddleHit {
+                side: Side::Right,
+                ..
+            }]
+        ));
+        assert!(sim.ball.velocity.x < 0.0);
+    }
+
+    #[test]
+    fn ball_bounces_off_the_walls() {
+        let mut sim = in_play(
+            Vector2::new(FIELD_WIDTH / 2.0, BALL_RADIUS + 1.0),
+            Vector2::new(0.0, -BALL_SPEED),
+        );
+        let events = sim.step(0.05, TickInput::default());
+        assert!(matches!(events.as_slice(), [SimEvent::WallBounce]));
+        assert_eq!(sim.ball.pos.y, BALL_RADIUS);
+        assert!(sim.ball.velocity.y > 0.0);
+    }
+
+    #[test]
+    fn damped_walls_keep_part_of_the_speed_into_them() {
+        let mut sim = in_play(
+            Vector2::new(FIELD_WIDTH / 2.0, FIELD_HEIGHT - BALL_RADIUS - 1.0),
+            Vector2::new(BALL_SPEED, BALL_SPEED),
+        );
+        sim.mutators.wall_damping = 0.25;
+        sim.step(0.05, TickInput::default());
+        assert_eq!(
+            sim.ball.velocity,
+            Vector2::new(BALL_SPEED, -BALL_SPEED * 0.75)
+        );
+
+        // Gaining speed stops at a steep angle
+        sim.mutators.wall_damping = -0.5;
+        sim.ball.velocity = Vector2::new(BALL_SPEED, -BALL_SPEED);
+        sim.ball.pos.y = BALL_RADIUS + 1.0;
+        sim.step(0.05, TickInput::default());
+        assert!(sim.ball.velocity.y > BALL_SPEED);
+        assert!(sim.ball.velocity.y <= BALL_SPEED * MAX_TRANSFER_ANGLE.tan() + 0.01);
+    }
+
+    #[test]
+    fn first_to_the_win_score_wins() {
+        let mut sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), 3);
+        sim.win_score = 2;
+        sim.left.score = Score::from(2);
+        sim.right.score = Score::from(1);
+        assert_eq!(sim.winner(), Some(Side::Left));
+        sim.right.score = Score::from(2);
+        assert_eq!(sim.winner(), None);
+    }
+
+    #[test]
+    fn only_extreme_windows_change_the_field() {
+        assert_eq!(fit_field(16.0 / 9.0), (FIELD_WIDTH, FIELD_HEIGHT));
+        assert_eq!(fit_field(16.0 / 10.0), (FIELD_WIDTH, FIELD_HEIGHT));
+        assert_eq!(fit_field(f32::NAN), (FIELD_WIDTH, FIELD_HEIGHT));
+        assert_eq!(fit_field(21.0 / 9.0), (1680.0, FIELD_HEIGHT));
+        assert_eq!(fit_field(10.0), (2560.0, FIELD_HEIGHT));
+        let (width, height) = fit_field(9.0 / 16.0);
+        assert_eq!(width, FIELD_WIDTH);
+        assert!((height - FIELD_WIDTH * 16.0 / 9.0).abs() < 0.01);
+        assert_eq!(fit_field(0.1), fit_field(9.0 / 16.0));
+    }
+
+    #[test]
+    fn the_standard_field_is_not_scaled() {
+        let scale = FieldScale::of(FIELD_WIDTH, FIELD_HEIGHT);
+        assert_eq!(
+            scale,
+            FieldScale {
+                paddle: 1.0,
+                ball: 1.0,
+                paddle_speed: 1.0,
+                ball_speed: 1.0,
+            }
+        );
+        let mut sim = GameSim::new(FIELD_WIDTH, FIELD_HEIGHT, Mutators::default(), 5);
+        let before = snapshot(&sim);
+        sim.fit_to_field();
+        assert_eq!(snapshot(&sim), before);
+        assert_eq!(sim.left.height, PADDLE_HEIGHT);
+        assert_eq!(sim.ball.radius, BALL_RADIUS);
+        assert_eq!(sim.serve_speed, BALL_SPEED);
+    }
+
+    #[test]
+    fn fitted_fields_keep_the_proportions() {
+        let (width, height) = fit_field(21.0 / 9.0);
+        let mut sim = GameSim::new(width, height, Mutators::default(), 5);
+        sim.fit_to_field();
+        // Crossing the wider field takes as long, the paddles are unchanged
+        assert_eq!(width / sim.serve_speed, FIELD_WIDTH / BALL_SPEED);
+        assert_eq!(sim.left.height, PADDLE_HEIGHT);
+        assert_eq!(sim.paddle_speed, PADDLE_SPEED);
+        assert!(sim.ball.radius > BALL_RADIUS);
+
+        let (width, height) = fit_field(9.0 / 16.0);
+        let mut sim = GameSim::new(width, height, Mutators::default(), 5);
+        sim.fit_to_field();
+        // The paddles cover as much of the taller goal line and cross it as fast
+        assert!((sim.left.height / height - PADDLE_HEIGHT / FIELD_HEIGHT).abs() < 1e-6);
+        assert!((sim.right.height / height - PADDLE_HEIGHT / FIELD_HEIGHT).abs() < 1e-6);
+        assert!((height / sim.paddle_speed - FIELD_HEIGHT / PADDLE_SPEED).abs() < 1e-6);
+        assert_eq!(sim.serve_speed, BALL_SPEED);
+        assert!(sim.ball.radius > BALL_RADIUS);
+    }
+
+    #[test]
+    fn replays_keep_the_fitted_sizes() {
+        let (width, height) = fit_field(9.0 / 16.0);
+        let mut sim = GameSim::new(width, height, Mutators::default(), 9);
+        sim.fit_to_field();
+        let replay = Replay::new(["Left".into(), "Right".into()], sim.settings(), 9);
+        let played = GameSim::from_replay(&replay.header);
+        assert_eq!(played.left.height, sim.left.height);
+        assert_eq!(played.right.height, sim.right.height);
+        assert_eq!(played.ball.radius, sim.ball.radius);
+        assert_eq!(played.paddle_speed, sim.paddle_speed);
+        assert_eq!(played.serve_speed, sim.serve_speed);
+    }
+}
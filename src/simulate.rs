@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+
+use crate::ai::{AiController, AiProfile, Difficulty, load_profiles};
+use crate::mutators::Mutators;
+use crate::replay::TickInput;
+use crate::sim::{FIELD_HEIGHT, FIELD_WIDTH, GAME_POINTS, GameSim, Side, SimEvent};
+use crate::time::Instant;
+use crate::timestep::TICK_RATE;
+
+// Matches that take longer than this (in simulated seconds) are abandoned as draws
+static MATCH_TIMEOUT: f32 = 30.0 * 60.0;
+
+#[derive(Default)]
+struct Stats {
+    matches: u32,
+    wins: [u32; 2],
+    timeouts: u32,
+    points: u32,
+    sim_time: f32,
+    hits: u32,
+    longest_rally: u32,
+    wall_bounces: u32,
+    // Sum of |contact offset| from the paddle center, 0 = center, 1 = edge
+    hit_offset: f32,
+    // Points won by the side the ball was served toward
+    receiver_wins: u32,
+}
+
+// Runs `matches` AI-vs-AI matches as fast as possible (no window, no rendering)
+// and prints aggregate statistics, for tuning speeds, angles and AI profiles.
+pub fn run(matches: u32, left: Option<&str>, right: Option<&str>) -> Result<()> {
+    let profiles = load_profiles();
+    let left_profile = find_profile(&profiles, left)?;
+    let right_profile = find_profile(&profiles, right)?;
+
+    println!(
+        "Simulating {} matches: {} vs {} (first to {}, {} Hz)",
+        matches, left_profile.name, right_profile.name, GAME_POINTS, TICK_RATE
+    );
+
+    let started = Instant::now();
+    let mut stats = Stats::default();
+    for _ in 0..matches {
+        simulate_match(&mut stats, left_profile, right_profile);
+    }
+
+    print_stats(&stats, left_profile, right_profile);
+    println!("Finished in {:.2}s", started.elapsed().as_secs_f32());
+    Ok(())
+}
+
+fn find_profile<'a>(profiles: &'a [AiProfile], name: Option<&str>) -> Result<&'a AiProfile> {
+    match name {
+        Some(name) => profiles
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+            .with_context(|| {
+                let names: Vec<_> = profiles
+                    .iter()
+                    .map(|profile| profile.name.as_str())
+                    .collect();
+                format!(
+                    "unknown AI profile {:?}, available: {}",
+                    name,
+                    names.join(", ")
+                )
+            }),
+        None => profiles.first().context("no AI profiles available"),
+    }
+}
+
+fn simulate_match(stats: &mut Stats, left_profile: &AiProfile, right_profile: &AiProfile) {
+    let delta = 1.0 / TICK_RATE as f32;
+    let mut rng = rand::rng();
+    let mut sim = GameSim::new(
+        FIELD_WIDTH,
+        FIELD_HEIGHT,
+        Mutators::default(),
+        rand::random::<u64>(),
+    );
+    let mut left = AiController::new(left_profile.clone(), Difficulty::Normal, FIELD_HEIGHT);
+    let mut right = AiController::new(right_profile.clone(), Difficulty::Normal, FIELD_HEIGHT);
+
+    let mut receiver = sim.serving_toward();
+    let mut rally = 0;
+    let mut time = 0.0;
+
+    while !sim.left.score.has_reached(GAME_POINTS) && !sim.right.score.has_reached(GAME_POINTS) {
+        if time > MATCH_TIMEOUT {
+            // The rally still going counts too
+            stats.longest_rally = stats.longest_rally.max(rally);
+            stats.timeouts += 1;
+            break;
+        }
+
+        let left_direction = left.update(delta, &sim.ai_view(Side::Left), &mut rng);
+        let right_direction = right.update(delta, &sim.ai_view(Side::Right), &mut rng);
+        let input = TickInput {
+            left_up: left_direction < 0.0,
+            left_down: left_direction > 0.0,
+            right_up: right_direction < 0.0,
+            right_down: right_direction > 0.0,
+            ..Default::default()
+        };
+
+        for event in sim.step(delta, input) {
+            match event {
+                SimEvent::PaddleHit { side, pos, .. } => {
+                    let paddle = sim.paddle(side);
+                    stats.hits += 1;
+                    stats.hit_offset += ((pos.y - paddle.pos.y) / (paddle.height / 2.0)).abs();
+                    rally += 1;
+
+                    // Each AI learns from the other one's returns
+                    match side.opponent() {
+                        Side::Left => left.on_player_return(true),
+                        Side::Right => right.on_player_return(true),
+                    }
+                }
+                SimEvent::WallBounce => stats.wall_bounces += 1,
+                SimEvent::Goal { scorer } => {
+                    stats.points += 1;
+                    stats.longest_rally = stats.longest_rally.max(rally);
+                    if scorer == receiver {
+                        stats.receiver_wins += 1;
+                    }
+                    rally = 0;
+
+                    left.on_point(scorer == Side::Right, &mut rng);
+                    right.on_point(scorer == Side::Left, &mut rng);
+                    match scorer {
+                        Side::Left => right.on_player_return(false),
+                        Side::Right => left.on_player_return(false),
+                    }
+                }
+                SimEvent::Serve { toward } => receiver = toward,
+                SimEvent::Portal { .. } => (),
+            }
+        }
+
+        time += delta;
+    }
+
+    stats.matches += 1;
+    stats.sim_time += time;
+    if sim.left.score.has_reached(GAME_POINTS) {
+        stats.wins[0] += 1;
+    } else if sim.right.score.has_reached(GAME_POINTS) {
+        stats.wins[1] += 1;
+    }
+}
+
+fn print_stats(stats: &Stats, left_profile: &AiProfile, right_profile: &AiProfile) {
+    let matches = stats.matches.max(1) as f32;
+    let points = stats.points.max(1) as f32;
+    let hits = stats.hits.max(1) as f32;
+
+    println!();
+    println!(
+        "{:<24} {} ({:.1}%)",
+        format!("{} wins:", left_profile.name),
+        stats.wins[0],
+        stats.wins[0] as f32 / matches * 100.0
+    );
+    println!(
+        "{:<24} {} ({:.1}%)",
+        format!("{} wins:", right_profile.name),
+        stats.wins[1],
+        stats.wins[1] as f32 / matches * 100.0
+    );
+    println!("{:<24} {}", "Timeouts:", stats.timeouts);
+    println!(
+        "{:<24} {:.1}s",
+        "Avg match length:",
+        stats.sim_time / matches
+    );
+    println!(
+        "{:<24} {:.1}",
+        "Avg points per match:",
+        stats.points as f32 / matches
+    );
+    println!(
+        "{:<24} {:.2}",
+        "Avg hits per point:",
+        stats.hits as f32 / points
+    );
+    println!("{:<24} {}", "Longest rally:", stats.longest_rally);
+    println!(
+        "{:<24} {:.2}",
+        "Wall bounces per point:",
+        stats.wall_bounces as f32 / points
+    );
+    println!(
+        "{:<24} {:.2} (0 = center, 1 = edge)",
+        "Avg hit offset:",
+        stats.hit_offset / hits
+    );
+    println!(
+        "{:<24} {:.1}%",
+        "Receiver wins point:",
+        stats.receiver_wins as f32 / points * 100.0
+    );
+}
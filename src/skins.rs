@@ -0,0 +1,206 @@
+use cgmath::{Deg, InnerSpace, Vector2};
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::renderer::Renderer;
+use crate::sim::{Ball, MAX_SPIN};
+
+static TRAIL_LENGTH: usize = 12;
+// Spin below this is not shown
+static MIN_SHOWN_SPIN: f32 = 0.05;
+// The spin markers turn this many times faster than the ball's path curves
+static SPIN_MARKER_RATE: f32 = 8.0;
+// The curve preview shows where the ball goes during the next seconds
+static CURVE_PREVIEW_TIME: f32 = 0.4;
+static CURVE_PREVIEW_DOTS: usize = 8;
+static CURVE_DOT_SIZE: f32 = 6.0;
+
+// How the ball looks, picked from the pause menu and stored with the player's progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BallSkin {
+    #[default]
+    Classic,
+    Square,
+    Smiley,
+    // Striped ball that rolls with its speed
+    Spinner,
+    Comet,
+}
+
+impl BallSkin {
+    pub fn next(self) -> Self {
+        match self {
+            BallSkin::Classic => BallSkin::Square,
+            BallSkin::Square => BallSkin::Smiley,
+            BallSkin::Smiley => BallSkin::Spinner,
+            BallSkin::Spinner => BallSkin::Comet,
+            BallSkin::Comet => BallSkin::Classic,
+        }
+    }
+
+    // Key of the name in the language files
+    pub fn label_key(self) -> &'static str {
+        match self {
+            BallSkin::Classic => "skin.classic",
+            BallSkin::Square => "skin.square",
+            BallSkin::Smiley => "skin.smiley",
+            BallSkin::Spinner => "skin.spinner",
+            BallSkin::Comet => "skin.comet",
+        }
+    }
+
+    fn trail_color(self) -> Option<Srgba> {
+        match self {
+            BallSkin::Comet => Some(Srgba::new(1.0, 0.55, 0.1, 1.0)),
+            BallSkin::Spinner => Some(Srgba::new(0.3, 0.8, 1.0, 1.0)),
+            _ => None,
+        }
+    }
+}
+
+// Per-frame state the skins need: recent positions for the trail and the spin.
+#[derive(Default)]
+pub struct BallLook {
+    trail: VecDeque<Vector2<f32>>,
+    // Degrees
+    rotation: f32,
+    // Radians, turns with the spin mutator's spin
+    spin_angle: f32,
+}
+
+impl BallLook {
+    pub fn update(&mut self, delta: f32, ball: &Ball) {
+        // Don't draw a trail across the field when the ball is put back to the center
+        if self
+            .trail
+            .back()
+            .is_some_and(|last| (ball.pos - last).magnitude() > ball.radius * 4.0)
+        {
+            self.trail.clear();
+        }
+        self.trail.push_back(ball.pos);
+        if self.trail.len() > TRAIL_LENGTH {
+            self.trail.pop_front();
+        }
+
+        // Roll without slipping, direction follows the horizontal movement
+        let speed = ball.velocity.magnitude() * ball.velocity.x.signum();
+        self.rotation =
+            (self.rotation + (speed / ball.radius * delta).to_degrees()).rem_euclid(360.0);
+        self.spin_angle = (self.spin_angle + ball.spin * SPIN_MARKER_RATE * delta)
+            .rem_euclid(std::f32::consts::TAU);
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, skin: BallSkin, ball: &Ball) {
+        if let Some(color) = skin.trail_color() {
+            let count = self.trail.len() as f32;
+            for (index, pos) in self.trail.iter().enumerate() {
+                let t = (index + 1) as f32 / (count + 1.0);
+                renderer.draw_circle(
+                    *pos,
+                    ball.radius * t,
+                    Srgba::new(color.red, color.green, color.blue, color.alpha * t * 0.5),
+                );
+            }
+        }
+
+        let white = Srgba::new(1.0, 1.0, 1.0, 1.0);
+        let radius = ball.radius;
+        match skin {
+            BallSkin::Classic | BallSkin::Comet => renderer.draw_circle(ball.pos, radius, white),
+            BallSkin::Square => renderer.draw_rectangle(
+                ball.pos - Vector2::new(radius, radius),
+                radius * 2.0,
+                radius * 2.0,
+                white,
+                Deg(0.),
+            ),
+            BallSkin::Smiley => draw_smiley(renderer, ball.pos, radius),
+            BallSkin::Spinner => {
+                renderer.draw_circle(ball.pos, radius, white);
+                let stripe = Srgba::new(0.2, 0.6, 1.0, 1.0);
+                for offset in [0.0, 90.0] {
+                    renderer.draw_rectangle(
+                        ball.pos - Vector2::new(radius * 0.9, radius * 0.175),
+                        radius * 1.8,
+                        radius * 0.35,
+                        stripe,
+                        Deg(self.rotation + offset),
+                    );
+                }
+            }
+        }
+
+        if ball.spin.abs() > MIN_SHOWN_SPIN {
+            self.draw_spin(renderer, ball);
+        }
+    }
+
+    // Markers turning on the ball with its spin, and a fading dotted curve
+    // ahead of it showing which way it bends. Both get stronger with the spin.
+    fn draw_spin(&self, renderer: &mut Renderer, ball: &Ball) {
+        let strength = (ball.spin.abs() / MAX_SPIN).min(1.0);
+
+        let marker = ball.radius * 0.3;
+        for offset in [0.0, std::f32::consts::PI] {
+            let angle = self.spin_angle + offset;
+            let center = ball.pos + Vector2::new(angle.cos(), angle.sin()) * ball.radius * 0.6;
+            renderer.draw_rectangle(
+                center - Vector2::new(marker / 2.0, marker / 2.0),
+                marker,
+                marker,
+                Srgba::new(0.1, 0.1, 0.1, 0.4 + 0.6 * strength),
+                Deg(0.),
+            );
+        }
+
+        // Follows the spin as it is now, ignoring walls and the decay
+        let step = CURVE_PREVIEW_TIME / CURVE_PREVIEW_DOTS as f32;
+        let (sin, cos) = (ball.spin * step).sin_cos();
+        let mut pos = ball.pos;
+        let mut velocity = ball.velocity;
+        for index in 0..CURVE_PREVIEW_DOTS {
+            velocity = Vector2::new(
+                velocity.x * cos - velocity.y * sin,
+                velocity.x * sin + velocity.y * cos,
+            );
+            pos += velocity * step;
+            if (pos - ball.pos).magnitude() < ball.radius {
+                continue;
+            }
+            let fade = 1.0 - index as f32 / CURVE_PREVIEW_DOTS as f32;
+            renderer.draw_rectangle(
+                pos - Vector2::new(CURVE_DOT_SIZE / 2.0, CURVE_DOT_SIZE / 2.0),
+                CURVE_DOT_SIZE,
+                CURVE_DOT_SIZE,
+                Srgba::new(1.0, 0.8, 0.2, 0.8 * strength * fade),
+                Deg(45.),
+            );
+        }
+    }
+}
+
+fn draw_smiley(renderer: &mut Renderer, center: Vector2<f32>, radius: f32) {
+    let dark = Srgba::new(0.15, 0.1, 0.0, 1.0);
+    renderer.draw_circle(center, radius, Srgba::new(1.0, 0.85, 0.1, 1.0));
+
+    for side in [-1.0, 1.0] {
+        renderer.draw_circle(
+            center + Vector2::new(side * radius * 0.35, -radius * 0.3),
+            radius * 0.13,
+            dark,
+        );
+    }
+
+    // Mouth: dots along the lower half of a circle
+    for i in 0..7 {
+        let angle = std::f32::consts::PI * (0.2 + 0.6 * i as f32 / 6.0);
+        renderer.draw_circle(
+            center + Vector2::new(angle.cos(), angle.sin()) * radius * 0.55,
+            radius * 0.08,
+            dark,
+        );
+    }
+}
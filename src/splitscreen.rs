@@ -0,0 +1,58 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+
+use crate::renderer::{Camera2D, Rect, Renderer};
+use crate::sim::{GameSim, Side};
+
+// Zoom on top of fitting the whole field into one view
+static ZOOM: f32 = 1.6;
+// How far the view follows its paddle up and down, 0 = field center, 1 = on the paddle
+static FOLLOW: f32 = 0.5;
+static DIVIDER_WIDTH: f32 = 4.0;
+
+// One view per player, side by side: the left player's half of the window
+// follows the left paddle, the right half follows the right paddle.
+pub fn player_views(sim: &GameSim, screen: Vector2<f32>) -> [(Rect, Camera2D); 2] {
+    let half = Vector2::new(screen.x / 2.0, screen.y);
+    let view = |side: Side, x: f32| {
+        (
+            Rect::new(x, 0.0, half.x, half.y),
+            follow_paddle(sim, side, half),
+        )
+    };
+    [view(Side::Left, 0.0), view(Side::Right, half.x)]
+}
+
+fn follow_paddle(sim: &GameSim, side: Side, view: Vector2<f32>) -> Camera2D {
+    let field = Vector2::new(sim.width, sim.height);
+    let zoom = (view.x / field.x).min(view.y / field.y) * ZOOM;
+    let half_view = view / (2.0 * zoom);
+
+    // Keep the own goal line at the outer edge of the view
+    let x = match side {
+        Side::Left => half_view.x,
+        Side::Right => field.x - half_view.x,
+    };
+    let paddle = sim.paddle(side);
+    let y = field.y / 2.0 + (paddle.pos.y - field.y / 2.0) * FOLLOW;
+    let y = if half_view.y * 2.0 >= field.y {
+        field.y / 2.0
+    } else {
+        y.clamp(half_view.y, field.y - half_view.y)
+    };
+
+    Camera2D {
+        center: Vector2::new(x, y),
+        zoom,
+    }
+}
+
+pub fn draw_divider(renderer: &mut Renderer) {
+    renderer.draw_rectangle(
+        Vector2::new(renderer.size.width as f32 / 2.0 - DIVIDER_WIDTH / 2.0, 0.0),
+        DIVIDER_WIDTH,
+        renderer.size.height as f32,
+        Srgba::new(0.0, 0.0, 0.0, 1.0),
+        Deg(0.),
+    );
+}
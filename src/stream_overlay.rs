@@ -0,0 +1,179 @@
+use anyhow::Result;
+use cgmath::{Deg, Vector2};
+use glyphon::FontSystem;
+use palette::Srgba;
+use std::sync::Arc;
+use winit::dpi::{LogicalSize, PhysicalSize};
+use winit::event_loop::ActiveEventLoop;
+use winit::window::{Window, WindowId};
+
+use crate::locale::Locale;
+use crate::renderer::Renderer;
+use crate::score::Score;
+
+static WINDOW_WIDTH: f64 = 720.;
+static WINDOW_HEIGHT: f64 = 160.;
+// Pure green, keyed out in OBS with a chroma key filter
+static KEY_COLOR: Srgba = Srgba::new(0., 1., 0., 1.);
+static PANEL_COLOR: Srgba = Srgba::new(0.08, 0.08, 0.1, 1.);
+static MARGIN: f32 = 16.;
+static FONT_SIZE: f32 = 44.;
+static LINE_HEIGHT: f32 = 56.;
+static STATS_FONT_SIZE: f32 = 20.;
+static STATS_LINE_HEIGHT: f32 = 28.;
+// Width of the bar in each player's paddle color
+static STRIPE_WIDTH: f32 = 10.;
+
+// What the overlay shows. Redrawn only when it changes, the match time is
+// kept to whole seconds for that.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scoreboard {
+    pub names: [String; 2],
+    pub scores: [Score; 2],
+    pub colors: [Srgba; 2],
+    pub win_score: u8,
+    // Paddle hits in the current point, and in the longest point so far
+    pub rally: usize,
+    pub longest_rally: usize,
+    pub seconds: u32,
+}
+
+// A second window with the scoreboard on a chroma key background, for
+// streamers to capture and composite over the game. Shift + F6 opens it.
+pub struct StreamOverlay {
+    window: Arc<Window>,
+    renderer: Renderer,
+    shown: Option<Scoreboard>,
+}
+
+impl StreamOverlay {
+    // Draws with a copy of the game's `fonts`, looking up the system's fonts
+    // again would hold up the game
+    pub fn open(event_loop: &ActiveEventLoop, fonts: FontSystem) -> Result<Self> {
+        let window = event_loop.create_window(
+            Window::default_attributes()
+                .with_title("wgpu-pong scoreboard")
+                .with_inner_size(LogicalSize::new(WINDOW_WIDTH, WINDOW_HEIGHT)),
+        )?;
+        let window = Arc::new(window);
+        let mut renderer = create_renderer(window.clone(), fonts)?;
+        // Never wait for this window's vsync, the game's window paces the frames
+        renderer.set_present_mode(&[
+            wgpu::PresentMode::Mailbox,
+            wgpu::PresentMode::Immediate,
+            wgpu::PresentMode::AutoNoVsync,
+        ]);
+        Ok(Self {
+            window,
+            renderer,
+            shown: None,
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.renderer.resize(size);
+        self.shown = None;
+        self.window.request_redraw();
+    }
+
+    // Asks for a redraw when `board` differs from what is on screen
+    pub fn update(&mut self, board: &Scoreboard) {
+        if self.shown.as_ref() != Some(board) {
+            self.window.request_redraw();
+        }
+    }
+
+    pub fn draw(&mut self, board: Scoreboard, locale: &Locale) -> Result<()> {
+        let renderer = &mut self.renderer;
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        renderer.begin_drawing();
+        renderer.clear_color(KEY_COLOR);
+
+        let panel = Vector2::new(MARGIN, MARGIN);
+        let (panel_width, panel_height) = (width - MARGIN * 2., height - MARGIN * 2.);
+        renderer.draw_rectangle(panel, panel_width, panel_height, PANEL_COLOR, Deg(0.));
+        renderer.draw_rectangle(panel, STRIPE_WIDTH, panel_height, board.colors[0], Deg(0.));
+        renderer.draw_rectangle(
+            Vector2::new(panel.x + panel_width - STRIPE_WIDTH, panel.y),
+            STRIPE_WIDTH,
+            panel_height,
+            board.colors[1],
+            Deg(0.),
+        );
+
+        // Names on the outside, the scores in the middle
+        let y = panel.y + MARGIN;
+        let inset = panel.x + STRIPE_WIDTH + MARGIN;
+        let white = Some(glyphon::Color::rgb(255, 255, 255));
+        renderer.draw_text(
+            &board.names[0],
+            Vector2::new(inset, y),
+            FONT_SIZE * 0.7,
+            LINE_HEIGHT,
+            white,
+        );
+        let text_width = renderer.measure_text(&board.names[1], FONT_SIZE * 0.7, LINE_HEIGHT);
+        renderer.draw_text(
+            &board.names[1],
+            Vector2::new(width - inset - text_width, y),
+            FONT_SIZE * 0.7,
+            LINE_HEIGHT,
+            white,
+        );
+        let score = format!(
+            "{} : {}",
+            board.scores[0].localized(locale),
+            board.scores[1].localized(locale)
+        );
+        let text_width = renderer.measure_text(&score, FONT_SIZE, LINE_HEIGHT);
+        renderer.draw_text(
+            &score,
+            Vector2::new(width / 2. - text_width / 2., y),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            white,
+        );
+
+        let stats = locale.format(
+            "overlay.stats",
+            &[
+                ("points", &locale.number(board.win_score)),
+                ("rally", &locale.number(board.rally)),
+                ("longest", &locale.number(board.longest_rally)),
+                (
+                    "time",
+                    &format!("{}:{:02}", board.seconds / 60, board.seconds % 60),
+                ),
+            ],
+        );
+        let text_width = renderer.measure_text(&stats, STATS_FONT_SIZE, STATS_LINE_HEIGHT);
+        renderer.draw_text(
+            &stats,
+            Vector2::new(width / 2. - text_width / 2., y + LINE_HEIGHT * 1.1),
+            STATS_FONT_SIZE,
+            STATS_LINE_HEIGHT,
+            Some(glyphon::Color::rgb(190, 190, 190)),
+        );
+
+        self.shown = Some(board);
+        renderer.end_drawing()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn create_renderer(window: Arc<Window>, fonts: FontSystem) -> Result<Renderer> {
+    let mut renderer = pollster::block_on(Renderer::without_fonts(window))?;
+    renderer.set_font_system(fonts);
+    Ok(renderer)
+}
+
+// The browser can't block on the GPU, and has no second window to capture
+#[cfg(target_arch = "wasm32")]
+fn create_renderer(_window: Arc<Window>, _fonts: FontSystem) -> Result<Renderer> {
+    anyhow::bail!("the scoreboard window is not supported in the browser")
+}
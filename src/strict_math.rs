@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+static FRAC_PI_2: f64 = std::f64::consts::FRAC_PI_2;
+
+// How the simulation evaluates sine, cosine and tangent. Basic arithmetic
+// and square roots are IEEE 754 exact on every platform, but the trig
+// functions come from the system's libm and can differ in the last bits
+// between CPUs and operating systems, which adds up to a desync in lockstep
+// or rollback play. `Strict` uses polynomials built only from basic
+// arithmetic instead, so every machine computes the same bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Math {
+    #[default]
+    Native,
+    Strict,
+}
+
+impl Math {
+    pub fn sin_cos(self, x: f32) -> (f32, f32) {
+        match self {
+            Math::Native => x.sin_cos(),
+            Math::Strict => strict_sin_cos(x),
+        }
+    }
+
+    pub fn tan(self, x: f32) -> f32 {
+        match self {
+            Math::Native => x.tan(),
+            Math::Strict => {
+                let (sin, cos) = strict_sin_cos(x);
+                sin / cos
+            }
+        }
+    }
+}
+
+// Reduces `x` to [-π/4, π/4] around the nearest multiple of π/2 and evaluates
+// Taylor polynomials there in f64, which is well past f32 precision. Only
+// meant for the small angles the game uses, the reduction loses precision
+// far from zero.
+fn strict_sin_cos(x: f32) -> (f32, f32) {
+    if !x.is_finite() {
+        return (f32::NAN, f32::NAN);
+    }
+    let x = f64::from(x);
+    let quadrant = (x / FRAC_PI_2).round();
+    let r = x - quadrant * FRAC_PI_2;
+    let r2 = r * r;
+
+    // Horner form of the series, terms up to r^13 and r^14
+    let mut sin = 1.0;
+    for n in [13.0, 11.0, 9.0, 7.0, 5.0, 3.0] {
+        sin = 1.0 - r2 / (n * (n - 1.0)) * sin;
+    }
+    let sin = r * sin;
+    let mut cos = 1.0;
+    for n in [14.0, 12.0, 10.0, 8.0, 6.0, 4.0, 2.0] {
+        cos = 1.0 - r2 / (n * (n - 1.0)) * cos;
+    }
+
+    let (sin, cos) = match (quadrant as i64).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    };
+    (sin as f32, cos as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_matches_native_across_turns() {
+        let limit = 3.0 * std::f32::consts::TAU;
+        let steps = 100_000;
+        let mut max_error = 0.0f32;
+        for step in 0..=steps {
+            let x = -limit + 2.0 * limit * step as f32 / steps as f32;
+            let (sin, cos) = strict_sin_cos(x);
+            let (native_sin, native_cos) = x.sin_cos();
+            max_error = max_error
+                .max((sin - native_sin).abs())
+                .max((cos - native_cos).abs());
+        }
+        assert!(max_error <= 2.0 * f32::EPSILON, "max error {}", max_error);
+    }
+}
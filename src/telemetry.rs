@@ -0,0 +1,120 @@
+use cgmath::{InnerSpace, Vector2};
+
+use crate::locale::Locale;
+use crate::renderer::Renderer;
+use crate::sim::{GameSim, Side, SimEvent};
+
+static FONT_SIZE: f32 = 18.;
+static LINE_HEIGHT: f32 = 24.;
+
+// The last paddle hit, angles in degrees above the horizontal
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Hit {
+    side: Side,
+    approach: f32,
+    angle: f32,
+    // Leaving the paddle, pixels per second
+    speed: f32,
+    // Where the ball met the paddle, -100 at its bottom end to 100 at the top
+    contact: f32,
+}
+
+// Live ball speed and angle, and how the last return was hit, for training
+// and for tuning the physics. Turned on in the options screen.
+pub struct Telemetry {
+    // Of the ball after the step seen last, so before the hit of this one
+    velocity: Vector2<f32>,
+    last_hit: Option<Hit>,
+}
+
+impl Telemetry {
+    pub fn new() -> Self {
+        Self {
+            velocity: Vector2::new(0., 0.),
+            last_hit: None,
+        }
+    }
+
+    // Call with the events of every step
+    pub fn observe(&mut self, sim: &GameSim, events: &[SimEvent]) {
+        for event in events {
+            if let SimEvent::PaddleHit { side, pos, speed } = *event {
+                let paddle = match side {
+                    Side::Left => &sim.left,
+                    Side::Right => &sim.right,
+                };
+                let contact = (paddle.pos.y - pos.y) / (paddle.height / 2.) * 100.;
+                self.last_hit = Some(Hit {
+                    side,
+                    approach: angle(self.velocity),
+                    angle: angle(sim.ball.velocity),
+                    speed,
+                    contact: contact.clamp(-100., 100.),
+                });
+            }
+        }
+        self.velocity = sim.ball.velocity;
+    }
+
+    // In the bottom right corner, `name` is the player who hit last
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        locale: &Locale,
+        sim: &GameSim,
+        name: impl Fn(Side) -> String,
+    ) {
+        let mut lines = vec![locale.format(
+            "telemetry.ball",
+            &[
+                (
+                    "speed",
+                    &locale.number(sim.ball.velocity.magnitude().round()),
+                ),
+                ("angle", &locale.number(angle(sim.ball.velocity).round())),
+            ],
+        )];
+        match self.last_hit {
+            Some(hit) => {
+                lines.push(locale.format(
+                    "telemetry.hit",
+                    &[
+                        ("name", &name(hit.side)),
+                        ("approach", &locale.number(hit.approach.round())),
+                        ("angle", &locale.number(hit.angle.round())),
+                        ("speed", &locale.number(hit.speed.round())),
+                    ],
+                ));
+                lines.push(locale.format(
+                    "telemetry.contact",
+                    &[("offset", &locale.number(hit.contact.round()))],
+                ));
+            }
+            None => lines.push(locale.text("telemetry.no_hit").to_string()),
+        }
+
+        let area = renderer.safe_area();
+        let bottom = area.y + area.height - LINE_HEIGHT * 2.;
+        for (index, line) in lines.iter().rev().enumerate() {
+            let text_width = renderer.measure_text(line, FONT_SIZE, LINE_HEIGHT);
+            renderer.draw_text(
+                line,
+                Vector2::new(
+                    area.x + area.width - text_width,
+                    bottom - index as f32 * LINE_HEIGHT,
+                ),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(glyphon::Color::rgb(120, 220, 255)),
+            );
+        }
+    }
+}
+
+// Above the horizontal is positive, the field's y axis points down
+fn angle(velocity: Vector2<f32>) -> f32 {
+    if velocity.x == 0. && velocity.y == 0. {
+        return 0.;
+    }
+    (-velocity.y).atan2(velocity.x.abs()).to_degrees()
+}
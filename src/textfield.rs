@@ -0,0 +1,297 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use winit::event::{ElementState, Ime, KeyEvent};
+use winit::keyboard::{Key, NamedKey};
+
+use crate::renderer::{Rect, Renderer};
+
+static PADDING: f32 = 8.0;
+static CARET_WIDTH: f32 = 2.0;
+// Seconds per caret blink phase
+static BLINK: f32 = 0.5;
+
+pub enum TextFieldAction {
+    Submit,
+    Cancel,
+    // Ctrl+C and Ctrl+V, the clipboard is up to the owner of the field
+    Copy,
+    Paste,
+}
+
+// Single line text input. Typed text arrives through key events, composed
+// text (e.g. Japanese or Chinese input) through winit's `Ime` events.
+// Positions are byte offsets into `text` and always on char boundaries.
+pub struct TextField {
+    text: String,
+    max_chars: usize,
+    cursor: usize,
+    // Other end of the selection, the selection is empty when None
+    anchor: Option<usize>,
+    // Text being composed by the input method, not part of `text` yet
+    preedit: String,
+    blink: f32,
+}
+
+impl TextField {
+    pub fn new(text: &str, max_chars: usize) -> Self {
+        let text: String = text.chars().take(max_chars).collect();
+        Self {
+            cursor: text.len(),
+            text,
+            max_chars,
+            anchor: None,
+            preedit: String::new(),
+            blink: 0.0,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // `shift` extends the selection, `ctrl` moves by words
+    pub fn handle_key(
+        &mut self,
+        event: &KeyEvent,
+        shift: bool,
+        ctrl: bool,
+    ) -> Option<TextFieldAction> {
+        if event.state != ElementState::Pressed {
+            return None;
+        }
+        self.blink = 0.0;
+
+        match &event.logical_key {
+            Key::Named(NamedKey::Enter) => return Some(TextFieldAction::Submit),
+            Key::Named(NamedKey::Escape) => return Some(TextFieldAction::Cancel),
+            Key::Named(NamedKey::ArrowLeft) => {
+                let target = if ctrl {
+                    self.word_start(self.cursor)
+                } else {
+                    self.prev_boundary(self.cursor)
+                };
+                self.move_cursor(target, shift);
+            }
+            Key::Named(NamedKey::ArrowRight) => {
+                let target = if ctrl {
+                    self.word_end(self.cursor)
+                } else {
+                    self.next_boundary(self.cursor)
+                };
+                self.move_cursor(target, shift);
+            }
+            Key::Named(NamedKey::Home) => self.move_cursor(0, shift),
+            Key::Named(NamedKey::End) => self.move_cursor(self.text.len(), shift),
+            Key::Named(NamedKey::Backspace) => self.delete(false),
+            Key::Named(NamedKey::Delete) => self.delete(true),
+            Key::Character(c) if ctrl && c.eq_ignore_ascii_case("a") => {
+                self.anchor = Some(0);
+                self.cursor = self.text.len();
+            }
+            Key::Character(c) if ctrl && c.eq_ignore_ascii_case("c") => {
+                return Some(TextFieldAction::Copy);
+            }
+            Key::Character(c) if ctrl && c.eq_ignore_ascii_case("v") => {
+                return Some(TextFieldAction::Paste);
+            }
+            _ if !ctrl => {
+                if let Some(text) = event.text.as_ref() {
+                    self.insert(text);
+                }
+            }
+            _ => (),
+        }
+        None
+    }
+
+    pub fn handle_ime(&mut self, ime: &Ime) {
+        match ime {
+            Ime::Preedit(text, _) => self.preedit = text.clone(),
+            Ime::Commit(text) => {
+                self.preedit.clear();
+                self.insert(text);
+            }
+            Ime::Enabled | Ime::Disabled => self.preedit.clear(),
+        }
+    }
+
+    // The selection, or all of the text when nothing is selected
+    pub fn selected_text(&self) -> &str {
+        match self.selection() {
+            Some((start, end)) => &self.text[start..end],
+            None => &self.text,
+        }
+    }
+
+    // Replaces the selection, line breaks and other control characters
+    // are dropped
+    pub fn paste(&mut self, text: &str) {
+        self.blink = 0.0;
+        self.insert(text);
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.blink = (self.blink + delta) % (BLINK * 2.0);
+    }
+
+    fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor.filter(|anchor| *anchor != self.cursor)?;
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    fn move_cursor(&mut self, target: usize, extend_selection: bool) {
+        if extend_selection {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = target;
+    }
+
+    // Returns false if nothing was selected.
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            return false;
+        };
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+        self.anchor = None;
+        true
+    }
+
+    // Removes the selection, or the char before or after the cursor if there is none
+    fn delete(&mut self, forward: bool) {
+        if self.delete_selection() {
+            return;
+        }
+        if forward {
+            let end = self.next_boundary(self.cursor);
+            self.text.replace_range(self.cursor..end, "");
+        } else {
+            let start = self.prev_boundary(self.cursor);
+            self.text.replace_range(start..self.cursor, "");
+            self.cursor = start;
+        }
+    }
+
+    fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        self.anchor = None;
+
+        // Control characters also arrive as text, e.g. "\r" for Enter
+        let room = self.max_chars.saturating_sub(self.text.chars().count());
+        let text: String = text
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(room)
+            .collect();
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    fn prev_boundary(&self, pos: usize) -> usize {
+        self.text[..pos]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    fn next_boundary(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .chars()
+            .next()
+            .map_or(pos, |c| pos + c.len_utf8())
+    }
+
+    fn word_start(&self, pos: usize) -> usize {
+        let before = self.text[..pos].trim_end();
+        before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(index, c)| index + c.len_utf8())
+    }
+
+    fn word_end(&self, pos: usize) -> usize {
+        let after = &self.text[pos..];
+        let skipped = after.len() - after.trim_start().len();
+        after[skipped..]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(self.text.len(), |(index, _)| pos + skipped + index)
+    }
+
+    // Draws the field into `rect` and returns where the caret is, so the
+    // input method can place its candidate window next to it.
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        rect: Rect,
+        font_size: f32,
+        line_height: f32,
+    ) -> Rect {
+        renderer.draw_rectangle(
+            Vector2::new(rect.x, rect.y),
+            rect.width,
+            rect.height,
+            Srgba::new(0.15, 0.15, 0.15, 1.0),
+            Deg(0.),
+        );
+
+        let text_x = rect.x + PADDING;
+        let text_y = rect.y + (rect.height - line_height) / 2.0;
+        let offset = |renderer: &mut Renderer, end: usize| {
+            text_x + renderer.measure_text(&self.text[..end], font_size, line_height)
+        };
+
+        if let Some((start, end)) = self.selection() {
+            let left = offset(renderer, start);
+            let right = offset(renderer, end);
+            renderer.draw_rectangle(
+                Vector2::new(left, text_y),
+                right - left,
+                line_height,
+                Srgba::new(0.2, 0.4, 0.9, 0.8),
+                Deg(0.),
+            );
+        }
+
+        // The preedit text is shown at the caret, underlined, until it is committed
+        let caret_x = offset(renderer, self.cursor);
+        let mut shown = self.text.clone();
+        shown.insert_str(self.cursor, &self.preedit);
+        renderer.draw_text(
+            &shown,
+            Vector2::new(text_x, text_y),
+            font_size,
+            line_height,
+            None,
+        );
+
+        let caret_x = if self.preedit.is_empty() {
+            caret_x
+        } else {
+            let width = renderer.measure_text(&self.preedit, font_size, line_height);
+            renderer.draw_rectangle(
+                Vector2::new(caret_x, text_y + line_height - 2.0),
+                width,
+                2.0,
+                Srgba::new(1.0, 1.0, 1.0, 1.0),
+                Deg(0.),
+            );
+            caret_x + width
+        };
+
+        let caret = Rect::new(caret_x, text_y, CARET_WIDTH, line_height);
+        if self.blink < BLINK {
+            renderer.draw_rectangle(
+                Vector2::new(caret.x, caret.y),
+                caret.width,
+                caret.height,
+                Srgba::new(1.0, 1.0, 1.0, 1.0),
+                Deg(0.),
+            );
+        }
+        caret
+    }
+}
@@ -0,0 +1,6 @@
+// `std::time::Instant` and `SystemTime` panic in the browser, web-time has
+// the same types on top of `performance.now()` and `Date.now()`.
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
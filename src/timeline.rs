@@ -0,0 +1,249 @@
+use cgmath::{Deg, Vector2};
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::ArenaColors;
+use crate::locale::Locale;
+use crate::renderer::Renderer;
+use crate::sim::{Side, SimEvent};
+
+// How long the timeline strip stays up after a point
+static STRIP_DURATION: f32 = 3.5;
+static STRIP_WIDTH: f32 = 480.0;
+static STRIP_HEIGHT: f32 = 4.0;
+static FONT_SIZE: f32 = 20.;
+static LINE_HEIGHT: f32 = 24.;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PointEventKind {
+    Serve {
+        toward: Side,
+    },
+    Hit {
+        side: Side,
+        x: f32,
+        y: f32,
+        speed: f32,
+    },
+    WallBounce,
+    Portal,
+    Goal {
+        scorer: Side,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PointEvent {
+    // Seconds since the point started
+    pub time: f32,
+    #[serde(flatten)]
+    pub kind: PointEventKind,
+}
+
+// Everything that happened from one serve to the goal that ended it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PointRecord {
+    // Seconds since the match started
+    pub start_time: f32,
+    pub duration: f32,
+    pub events: Vec<PointEvent>,
+}
+
+impl PointRecord {
+    pub fn scorer(&self) -> Option<Side> {
+        self.events.iter().find_map(|event| match event.kind {
+            PointEventKind::Goal { scorer } => Some(scorer),
+            _ => None,
+        })
+    }
+
+    pub fn hits(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event.kind, PointEventKind::Hit { .. }))
+            .count()
+    }
+}
+
+// Builds the event log of the current point from sim events and shows a
+// strip of the last finished point.
+pub struct Timeline {
+    elapsed: f32,
+    current: PointRecord,
+    points: Vec<PointRecord>,
+    shown: Option<f32>,
+}
+
+impl Timeline {
+    // `toward` is the side the first serve heads to, as the sim doesn't
+    // report the serve it starts with.
+    pub fn new(toward: Side) -> Self {
+        let mut timeline = Self {
+            elapsed: 0.0,
+            current: PointRecord::default(),
+            points: Vec::new(),
+            shown: None,
+        };
+        timeline.start_point(toward);
+        timeline
+    }
+
+    // Advances match time by one sim step.
+    pub fn advance(&mut self, delta: f32) {
+        self.elapsed += delta;
+        self.current.duration = self.elapsed - self.current.start_time;
+    }
+
+    // Seconds since the match started
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    // Paddle hits in the point being played
+    pub fn rally(&self) -> usize {
+        self.current.hits()
+    }
+
+    pub fn longest_rally(&self) -> usize {
+        self.points
+            .iter()
+            .map(PointRecord::hits)
+            .max()
+            .unwrap_or(0)
+            .max(self.rally())
+    }
+
+    // Returns the point once a goal finishes it.
+    pub fn on_event(&mut self, event: &SimEvent) -> Option<&PointRecord> {
+        let kind = match *event {
+            // A serve without a goal restarts the point, e.g. when the ball is reset by hand
+            SimEvent::Serve { toward } => {
+                self.start_point(toward);
+                return None;
+            }
+            SimEvent::PaddleHit { side, pos, speed } => PointEventKind::Hit {
+                side,
+                x: pos.x,
+                y: pos.y,
+                speed,
+            },
+            SimEvent::WallBounce => PointEventKind::WallBounce,
+            SimEvent::Portal { .. } => PointEventKind::Portal,
+            SimEvent::Goal { scorer } => PointEventKind::Goal { scorer },
+        };
+        self.current.events.push(PointEvent {
+            time: self.current.duration,
+            kind,
+        });
+
+        if matches!(kind, PointEventKind::Goal { .. }) {
+            self.points.push(std::mem::take(&mut self.current));
+            self.shown = Some(STRIP_DURATION);
+            return self.points.last();
+        }
+        None
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if let Some(remaining) = self.shown.as_mut() {
+            *remaining -= delta;
+            if *remaining <= 0.0 {
+                self.shown = None;
+            }
+        }
+    }
+
+    fn start_point(&mut self, toward: Side) {
+        self.current = PointRecord {
+            start_time: self.elapsed,
+            duration: 0.0,
+            events: vec![PointEvent {
+                time: 0.0,
+                kind: PointEventKind::Serve { toward },
+            }],
+        };
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, colors: &ArenaColors, locale: &Locale) {
+        let (Some(remaining), Some(point)) = (self.shown, self.points.last()) else {
+            return;
+        };
+        let alpha = (remaining / 0.5).clamp(0.0, 1.0);
+        let area = renderer.safe_area();
+        let width = STRIP_WIDTH.min(area.width * 0.6);
+        let left = renderer.size.width as f32 / 2.0 - width / 2.0;
+        let y = area.y + LINE_HEIGHT * 4.5;
+        let duration = point.duration.max(f32::EPSILON);
+        let x_at = |time: f32| left + width * (time / duration).clamp(0.0, 1.0);
+
+        renderer.draw_rectangle(
+            Vector2::new(left, y - STRIP_HEIGHT / 2.0),
+            width,
+            STRIP_HEIGHT,
+            Srgba::new(0.5, 0.5, 0.5, 0.6 * alpha),
+            Deg(0.),
+        );
+
+        for event in point.events.iter() {
+            let x = x_at(event.time);
+            match event.kind {
+                PointEventKind::Serve { .. } => renderer.draw_rectangle(
+                    Vector2::new(x - 1.5, y - 10.0),
+                    3.0,
+                    20.0,
+                    Srgba::new(1.0, 1.0, 1.0, alpha),
+                    Deg(0.),
+                ),
+                PointEventKind::Hit { side, .. } => {
+                    renderer.draw_circle(Vector2::new(x, y), 6.0, side_color(colors, side, alpha))
+                }
+                PointEventKind::WallBounce | PointEventKind::Portal => {
+                    renderer.draw_circle(Vector2::new(x, y), 3.0, Srgba::new(0.8, 0.8, 0.8, alpha))
+                }
+                PointEventKind::Goal { scorer } => renderer.draw_rectangle(
+                    Vector2::new(x - 3.0, y - 12.0),
+                    6.0,
+                    24.0,
+                    side_color(colors, scorer, alpha),
+                    Deg(0.),
+                ),
+            }
+        }
+
+        let scorer = match point.scorer() {
+            Some(Side::Left) => "P1",
+            Some(Side::Right) => "P2",
+            None => locale.text("timeline.nobody"),
+        };
+        let text = locale.format(
+            "timeline.summary",
+            &[
+                ("point", &self.points.len()),
+                ("scorer", &scorer),
+                ("hits", &point.hits()),
+                ("duration", &format!("{:.1}", point.duration)),
+            ],
+        );
+        let text_width = renderer.measure_text(&text, FONT_SIZE, LINE_HEIGHT);
+        renderer.draw_text(
+            &text,
+            Vector2::new(
+                renderer.size.width as f32 / 2.0 - text_width / 2.0,
+                y + 16.0,
+            ),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            Some(glyphon::Color::rgba(255, 255, 255, (alpha * 255.0) as u8)),
+        );
+    }
+}
+
+// Same as the paddles
+fn side_color(colors: &ArenaColors, side: Side, alpha: f32) -> Srgba {
+    let color = match side {
+        Side::Left => colors.left_paddle,
+        Side::Right => colors.right_paddle,
+    };
+    Srgba::new(color.red, color.green, color.blue, color.alpha * alpha)
+}
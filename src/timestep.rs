@@ -0,0 +1,145 @@
+use cgmath::{InnerSpace, Vector2};
+
+use crate::sim::GameSim;
+
+// Sim steps per second, independent of the frame rate. Higher rates cost
+// more CPU for finer collisions and input, see the options screen.
+pub static TICK_RATE: u32 = 120;
+pub static TICK_RATES: [u32; 3] = [60, 120, 240];
+// After a long stall (window dragged, breakpoint) the missed time is dropped
+// instead of being simulated all at once. Frames shorter than this are caught
+// up in full at every rate, so slow frame rates don't play in slow motion.
+static MAX_CATCH_UP_SECONDS: f32 = 0.1;
+// Moves longer than this share of the field width in one tick are teleports
+// (serve, portals) and are not smoothed
+static TELEPORT_SHARE: f32 = 0.25;
+
+// Turns frame times into a whole number of fixed sim steps. The time left
+// over is carried into the next frame.
+pub struct FixedTimestep {
+    accumulator: f32,
+    rate: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            accumulator: 0.0,
+            rate: rate.max(1),
+        }
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn dt(&self) -> f32 {
+        1.0 / self.rate as f32
+    }
+
+    // The leftover time is dropped, a share of the old step would be drawn
+    // as a share of the new one
+    pub fn set_rate(&mut self, rate: u32) {
+        if rate.max(1) != self.rate {
+            self.rate = rate.max(1);
+            self.accumulator = 0.0;
+        }
+    }
+
+    fn max_ticks(&self) -> u32 {
+        (MAX_CATCH_UP_SECONDS * self.rate as f32).ceil() as u32
+    }
+
+    // How many steps to run for a frame that took `delta` seconds.
+    pub fn advance(&mut self, delta: f32) -> u32 {
+        self.accumulator += delta;
+        let mut ticks = 0;
+        while self.accumulator >= self.dt() {
+            self.accumulator -= self.dt();
+            ticks += 1;
+            if ticks == self.max_ticks() {
+                self.accumulator = 0.0;
+                break;
+            }
+        }
+        ticks
+    }
+
+    // Between the last step (0) and the next one (1), for smoothing positions.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.dt()).clamp(0.0, 1.0)
+    }
+
+    // Drops the leftover time, e.g. while the match is paused
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+// Paddle and ball positions at the end of a step, kept around to draw the
+// match between two steps.
+#[derive(Debug, Clone, Copy)]
+pub struct Positions {
+    pub left: Vector2<f32>,
+    pub right: Vector2<f32>,
+    pub ball: Vector2<f32>,
+}
+
+impl Positions {
+    pub fn of(sim: &GameSim) -> Self {
+        Self {
+            left: sim.left.pos,
+            right: sim.right.pos,
+            ball: sim.ball.pos,
+        }
+    }
+
+    // Positions `alpha` of the way from `self` to the sim's current state.
+    pub fn lerp(&self, sim: &GameSim, alpha: f32) -> Self {
+        let current = Self::of(sim);
+        let teleport = sim.width * TELEPORT_SHARE;
+        let lerp = |from: Vector2<f32>, to: Vector2<f32>| {
+            if (to - from).magnitude() > teleport {
+                to
+            } else {
+                from + (to - from) * alpha
+            }
+        };
+        Self {
+            left: lerp(self.left, current.left),
+            right: lerp(self.right, current.right),
+            ball: lerp(self.ball, current.ball),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_frames_keep_real_time_at_every_rate() {
+        for rate in TICK_RATES {
+            let mut timestep = FixedTimestep::new(rate);
+            // Two seconds at 20 fps
+            let ticks: u32 = (0..40).map(|_| timestep.advance(0.05)).sum();
+            assert!(
+                ticks.abs_diff(2 * rate) <= 1,
+                "{} ticks in two seconds at {} Hz",
+                ticks,
+                rate
+            );
+        }
+    }
+
+    #[test]
+    fn stalls_are_dropped_at_every_rate() {
+        for rate in TICK_RATES {
+            let mut timestep = FixedTimestep::new(rate);
+            let ticks = timestep.advance(2.0);
+            assert_eq!(ticks, (MAX_CATCH_UP_SECONDS * rate as f32).ceil() as u32);
+            assert_eq!(timestep.alpha(), 0.0);
+            assert_eq!(timestep.advance(0.0), 0);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use cgmath::Vector2;
+use std::collections::VecDeque;
+
+use crate::renderer::Renderer;
+
+static TOAST_DURATION: f32 = 2.5;
+static MAX_TOASTS: usize = 4;
+static FONT_SIZE: f32 = 24.;
+static LINE_HEIGHT: f32 = 28.;
+
+// Short notifications stacked at the bottom center of the screen.
+#[derive(Default)]
+pub struct Toasts {
+    items: VecDeque<(String, f32)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: impl Into<String>) {
+        self.items.push_back((message.into(), TOAST_DURATION));
+        if self.items.len() > MAX_TOASTS {
+            self.items.pop_front();
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        for (_, remaining) in self.items.iter_mut() {
+            *remaining -= delta;
+        }
+        self.items.retain(|(_, remaining)| *remaining > 0.0);
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer) {
+        let area = renderer.safe_area();
+        let bottom = area.y + area.height - LINE_HEIGHT * 2.5;
+        for (index, (message, remaining)) in self.items.iter().rev().enumerate() {
+            let alpha = (remaining / 0.5).clamp(0.0, 1.0);
+            let text_width = renderer.measure_text(message, FONT_SIZE, LINE_HEIGHT);
+            renderer.draw_text(
+                message,
+                Vector2::new(
+                    renderer.size.width as f32 / 2. - text_width / 2.,
+                    bottom - index as f32 * LINE_HEIGHT,
+                ),
+                FONT_SIZE,
+                LINE_HEIGHT,
+                Some(glyphon::Color::rgba(255, 255, 255, (alpha * 255.0) as u8)),
+            );
+        }
+    }
+}
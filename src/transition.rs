@@ -0,0 +1,129 @@
+use cgmath::Vector2;
+
+use crate::renderer::Layer;
+use crate::tween::{Easing, Tween};
+
+static TRANSITION_DURATION: f32 = 0.25;
+// Screens slide in from the right by this much of the window width
+static SLIDE_DISTANCE: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Menu,
+    Gallery,
+    Daily,
+    Calibration,
+    NameEntry,
+    PaddleColors,
+    MainMenu,
+    GameOver,
+    Drills,
+    Options,
+}
+
+struct Transition {
+    screen: Screen,
+    // 0 = hidden, 1 = fully shown
+    tween: Tween,
+    leaving: bool,
+}
+
+// Stack of the screens opened on top of the match, in the order they were
+// opened. Screens slide and fade in when opened, and stay on the stack while
+// they animate out after being closed, so they are still drawn but no longer
+// get input.
+#[derive(Default)]
+pub struct Transitions {
+    stack: Vec<Transition>,
+}
+
+impl Transitions {
+    pub fn open(&mut self, screen: Screen) {
+        let shown = self.progress(screen).unwrap_or(0.0);
+        self.stack.retain(|transition| transition.screen != screen);
+        self.stack.push(Transition {
+            screen,
+            tween: tween(shown, 1.0),
+            leaving: false,
+        });
+    }
+
+    // Starts the exit animation, the screen is returned by `update` once it is done.
+    pub fn close(&mut self, screen: Screen) {
+        if let Some(transition) = self
+            .stack
+            .iter_mut()
+            .find(|transition| transition.screen == screen && !transition.leaving)
+        {
+            transition.tween = tween(transition.tween.value(), 0.0);
+            transition.leaving = true;
+        }
+    }
+
+    pub fn is_leaving(&self, screen: Screen) -> bool {
+        self.stack
+            .iter()
+            .any(|transition| transition.screen == screen && transition.leaving)
+    }
+
+    // Returns the screens that finished animating out and can be dropped.
+    pub fn update(&mut self, delta: f32) -> Vec<Screen> {
+        let mut closed = Vec::new();
+        for transition in self.stack.iter_mut() {
+            transition.tween.update(delta);
+            if transition.leaving && transition.tween.is_finished() {
+                closed.push(transition.screen);
+            }
+        }
+        self.stack
+            .retain(|transition| !(transition.leaving && transition.tween.is_finished()));
+        closed
+    }
+
+    // How to draw `screen` this frame, or None if a fully shown screen above
+    // covers it. Screens that were never opened are drawn as they are.
+    pub fn layer(&self, screen: Screen, window_width: f32) -> Option<Layer> {
+        let Some(index) = self
+            .stack
+            .iter()
+            .position(|transition| transition.screen == screen)
+        else {
+            return Some(Layer {
+                offset: Vector2::new(0.0, 0.0),
+                opacity: 1.0,
+            });
+        };
+
+        let covered = self.stack[index + 1..].iter().any(|above| {
+            !above.leaving && above.tween.is_finished() && above.tween.target() >= 1.0
+        });
+        if covered {
+            return None;
+        }
+
+        let shown = self.stack[index].tween.value();
+        Some(Layer {
+            offset: Vector2::new((1.0 - shown) * SLIDE_DISTANCE * window_width, 0.0),
+            opacity: shown,
+        })
+    }
+
+    fn progress(&self, screen: Screen) -> Option<f32> {
+        self.stack
+            .iter()
+            .find(|transition| transition.screen == screen)
+            .map(|transition| transition.tween.value())
+    }
+}
+
+// The remaining way takes a matching share of the full duration. Screens
+// settle gently when opening and speed up when leaving.
+fn tween(from: f32, to: f32) -> Tween {
+    let duration = TRANSITION_DURATION * (to - from).abs();
+    let easing = if to > from {
+        Easing::EaseOutCubic
+    } else {
+        Easing::EaseInCubic
+    };
+    Tween::new(from, to, duration, easing)
+}
@@ -0,0 +1,203 @@
+use cgmath::Vector2;
+use palette::Srgba;
+
+use crate::locale::Locale;
+use crate::prompts::{Action, Prompts};
+use crate::renderer::{Camera2D, Rect, Renderer};
+use crate::sim::{GameSim, Side, SimEvent};
+
+static FONT_SIZE: f32 = 28.;
+static LINE_HEIGHT: f32 = 32.;
+static KEY_SIZE: f32 = 48.;
+// How long the final message stays up
+static DONE_DURATION: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Move,
+    Serve,
+    Return,
+    Score,
+    Done,
+}
+
+// First-run walkthrough of the controls and rules, drawn on top of the match.
+pub struct Tutorial {
+    step: Step,
+    // Seconds in the current step, drives the animations
+    time: f32,
+    missed: bool,
+}
+
+impl Tutorial {
+    pub fn new() -> Self {
+        Self {
+            step: Step::Move,
+            time: 0.0,
+            missed: false,
+        }
+    }
+
+    // The ball waits until the player has moved once
+    pub fn holds_ball(&self) -> bool {
+        self.step == Step::Move
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step == Step::Done && self.time > DONE_DURATION
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+    }
+
+    // What the player did, on any device
+    pub fn handle_action(&mut self, action: Action) {
+        match (self.step, action) {
+            (Step::Move, Action::MoveUp | Action::MoveDown) => self.advance(Step::Serve),
+            (Step::Serve, Action::Serve) => self.advance(Step::Return),
+            _ => (),
+        }
+    }
+
+    pub fn on_event(&mut self, event: &SimEvent) {
+        match (self.step, event) {
+            (
+                Step::Return,
+                SimEvent::PaddleHit {
+                    side: Side::Left, ..
+                },
+            ) => self.advance(Step::Score),
+            (
+                Step::Return | Step::Score,
+                SimEvent::Goal {
+                    scorer: Side::Right,
+                },
+            ) => self.missed = true,
+            (Step::Score, SimEvent::Goal { scorer: Side::Left }) => self.advance(Step::Done),
+            _ => (),
+        }
+    }
+
+    fn advance(&mut self, step: Step) {
+        self.step = step;
+        self.time = 0.0;
+        self.missed = false;
+    }
+
+    // `field` is where the field is drawn, to point at things on it
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        sim: &GameSim,
+        field: (Rect, Camera2D),
+        locale: &Locale,
+        prompts: &Prompts,
+    ) {
+        let width = renderer.size.width as f32;
+        let height = renderer.size.height as f32;
+        let (view, camera) = field;
+        let to_screen = |pos: Vector2<f32>| {
+            Vector2::new(view.x, view.y)
+                + camera.world_to_screen(pos, Vector2::new(view.width, view.height))
+        };
+        // 0..1, for pulsing prompts
+        let pulse = (self.time * std::f32::consts::TAU).sin() * 0.5 + 0.5;
+
+        let text = locale.text(match self.step {
+            Step::Move => "tutorial.move",
+            Step::Serve => "tutorial.serve",
+            Step::Return if self.missed => "tutorial.missed",
+            Step::Return => "tutorial.return",
+            Step::Score if self.missed => "tutorial.scored_against",
+            Step::Score => "tutorial.score",
+            Step::Done => "tutorial.done",
+        });
+        let text_width = renderer.measure_text(text, FONT_SIZE, LINE_HEIGHT);
+        renderer.draw_text(
+            text,
+            Vector2::new(width / 2. - text_width / 2., height / 4.),
+            FONT_SIZE,
+            LINE_HEIGHT,
+            None,
+        );
+
+        match self.step {
+            Step::Move => {
+                let left = &sim.left;
+                let center = to_screen(Vector2::new(left.pos.x + left.width, left.pos.y))
+                    + Vector2::new(KEY_SIZE, 0.);
+                prompts.draw(
+                    renderer,
+                    locale,
+                    Action::MoveUp,
+                    Vector2::new(center.x, center.y - KEY_SIZE * 1.2),
+                    KEY_SIZE,
+                    pulse,
+                );
+                prompts.draw(
+                    renderer,
+                    locale,
+                    Action::MoveDown,
+                    Vector2::new(center.x, center.y + KEY_SIZE * 1.2),
+                    KEY_SIZE,
+                    1.0 - pulse,
+                );
+                highlight(
+                    renderer,
+                    to_screen(Vector2::new(left.pos.x, left.pos.y - left.height / 2.)),
+                    left.width * camera.zoom,
+                    left.height * camera.zoom,
+                    pulse,
+                );
+            }
+            Step::Serve => {
+                prompts.draw(
+                    renderer,
+                    locale,
+                    Action::Serve,
+                    Vector2::new(width / 2., height / 4. + LINE_HEIGHT * 2.5),
+                    KEY_SIZE,
+                    pulse,
+                );
+            }
+            Step::Return => {
+                let left = &sim.left;
+                highlight(
+                    renderer,
+                    to_screen(Vector2::new(left.pos.x, left.pos.y - left.height / 2.)),
+                    left.width * camera.zoom,
+                    left.height * camera.zoom,
+                    pulse,
+                );
+            }
+            Step::Score => {
+                // Point at the goal line behind the opponent
+                let x = view.x + view.width - 4.0;
+                highlight(renderer, Vector2::new(x, view.y), 4.0, view.height, pulse);
+            }
+            Step::Done => (),
+        }
+
+        prompts.draw_row(
+            renderer,
+            locale,
+            &[(Action::Skip, locale.text("tutorial.skip"))],
+            Vector2::new(width / 2., height / 4. - LINE_HEIGHT * 0.6),
+            KEY_SIZE * 0.5,
+        );
+    }
+}
+
+// A pulsing yellow frame around a rectangle.
+fn highlight(renderer: &mut Renderer, pos: Vector2<f32>, width: f32, height: f32, pulse: f32) {
+    let border = 4.0;
+    let color = Srgba::new(1.0, 0.9, 0.2, 0.3 + pulse * 0.5);
+    renderer.draw_rectangle_outline(
+        Vector2::new(pos.x - border * 2., pos.y - border * 2.),
+        width + border * 4.,
+        height + border * 4.,
+        border,
+        color,
+    );
+}
@@ -0,0 +1,60 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    // Fast start, slow finish
+    EaseOutCubic,
+    // Slow start, fast finish
+    EaseInCubic,
+}
+
+impl Easing {
+    // Maps linear progress 0..1 onto the curve
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInCubic => t.powi(3),
+        }
+    }
+}
+
+// Animates a value from `from` to `to` over `duration` seconds.
+#[derive(Debug, Clone, Copy)]
+pub struct Tween {
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl Tween {
+    pub fn new(from: f32, to: f32, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+    }
+
+    pub fn value(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+        let t = self.easing.apply(self.elapsed / self.duration);
+        self.from + (self.to - self.from) * t
+    }
+
+    pub fn target(&self) -> f32 {
+        self.to
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
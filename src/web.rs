@@ -0,0 +1,66 @@
+// Entry point of the browser build, see the README. There are no command
+// line options there, the page always starts the game at the main menu.
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{HtmlCanvasElement, console};
+use winit::event_loop::EventLoop;
+use winit::platform::web::{EventLoopExtWebSys, WindowAttributesExtWebSys};
+use winit::window::WindowAttributes;
+
+use crate::app::App;
+
+// The canvas in index.html, one is added to the page if it's missing
+static CANVAS_ID: &str = "wgpu-pong";
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+struct ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = JsValue::from_str(&format!("[{}] {}", record.target(), record.args()));
+        match record.level() {
+            Level::Error => console::error_1(&message),
+            Level::Warn => console::warn_1(&message),
+            _ => console::log_1(&message),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn run() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+    // Panics only show up as "unreachable executed" otherwise
+    std::panic::set_hook(Box::new(|info| {
+        console::error_1(&JsValue::from_str(&info.to_string()));
+    }));
+
+    let event_loop = match EventLoop::new() {
+        Ok(event_loop) => event_loop,
+        Err(err) => return log::error!("Failed to create event loop: {}", err),
+    };
+    // Returns right away, the browser drives the loop from here on
+    event_loop.spawn_app(App::default());
+}
+
+pub fn canvas_attributes(attributes: WindowAttributes) -> WindowAttributes {
+    let canvas = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(CANVAS_ID))
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok());
+    match canvas {
+        Some(canvas) => attributes.with_canvas(Some(canvas)),
+        None => attributes.with_append(true),
+    }
+}
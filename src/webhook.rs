@@ -0,0 +1,439 @@
+use anyhow::{Context, Result, bail};
+use log::{error, info};
+use serde::Serialize;
+
+use crate::sim::{GameSim, Side, SimEvent};
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "webhook")]
+use enabled::{Poster, open};
+
+#[cfg(not(feature = "webhook"))]
+use disabled::{Poster, open};
+
+// What the webhook receives, one JSON object per request
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum WebhookEvent {
+    MatchStart {
+        players: [String; 2],
+        win_score: u8,
+    },
+    Score {
+        scorer: Side,
+        scorer_name: String,
+        score: [u32; 2],
+    },
+    MatchEnd {
+        winner: Side,
+        winner_name: String,
+        score: [u32; 2],
+    },
+}
+
+impl WebhookEvent {
+    // A line for chat, Discord only takes messages with one
+    fn summary(&self) -> String {
+        match self {
+            WebhookEvent::MatchStart { players, win_score } => {
+                format!("{} vs {}, first to {}", players[0], players[1], win_score)
+            }
+            WebhookEvent::Score {
+                scorer_name, score, ..
+            } => format!("{} scores, {} : {}", scorer_name, score[0], score[1]),
+            WebhookEvent::MatchEnd {
+                winner_name, score, ..
+            } => format!("{} wins {} : {}", winner_name, score[0], score[1]),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    #[serde(flatten)]
+    event: &'a WebhookEvent,
+    content: String,
+    // Seconds since the unix epoch
+    at: u64,
+}
+
+// Posts the start, the points and the end of local matches to the
+// `webhook_url` of the settings, for stream overlays and chat bots. Follows
+// the same events of the sim as the effects and the achievements.
+pub struct Webhook {
+    poster: Option<Poster>,
+    // Of the match seen last, to notice a new one
+    seed: u64,
+    ticks: u64,
+    finished: bool,
+}
+
+impl Webhook {
+    pub fn new(url: Option<&str>) -> Self {
+        let poster = url.and_then(|url| match open(url) {
+            Ok(poster) => {
+                info!("Posting match events to {}", url);
+                Some(poster)
+            }
+            Err(err) => {
+                error!("Cannot post match events to {}: {:#}", url, err);
+                None
+            }
+        });
+        Self {
+            poster,
+            seed: 0,
+            ticks: u64::MAX,
+            finished: false,
+        }
+    }
+
+    // Without a webhook there's no need to look up the names for `observe`
+    pub fn is_enabled(&self) -> bool {
+        self.poster.is_some()
+    }
+
+    // Call with the events of every step of a match that is played, not
+    // watched, and the names of the players
+    pub fn observe(&mut self, sim: &GameSim, events: &[SimEvent], names: [String; 2]) {
+        let Some(poster) = self.poster.as_ref() else {
+            return;
+        };
+
+        let started = sim.seed() != self.seed || sim.ticks() < self.ticks;
+        self.seed = sim.seed();
+        self.ticks = sim.ticks();
+        let goals: Vec<Side> = events
+            .iter()
+            .filter_map(|event| match event {
+                SimEvent::Goal { scorer } => Some(*scorer),
+                _ => None,
+            })
+            .collect();
+        if !started && goals.is_empty() {
+            return;
+        }
+
+        if started {
+            self.finished = false;
+            poster.send(&WebhookEvent::MatchStart {
+                players: names.clone(),
+                win_score: sim.win_score,
+            });
+        }
+        let score = [sim.left.score.points(), sim.right.score.points()];
+        for scorer in goals {
+            poster.send(&WebhookEvent::Score {
+                scorer,
+                scorer_name: names[slot(scorer)].clone(),
+                score,
+            });
+        }
+        if let Some(winner) = sim.winner()
+            && !self.finished
+        {
+            self.finished = true;
+            poster.send(&WebhookEvent::MatchEnd {
+                winner,
+                winner_name: names[slot(winner)].clone(),
+                score,
+            });
+        }
+    }
+}
+
+fn slot(side: Side) -> usize {
+    match side {
+        Side::Left => 0,
+        Side::Right => 1,
+    }
+}
+
+#[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+fn payload(event: &WebhookEvent) -> String {
+    let at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let content = event.summary();
+    serde_json::to_string(&Payload { event, content, at }).unwrap_or_default()
+}
+
+#[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+#[derive(Debug, PartialEq)]
+struct Url {
+    https: bool,
+    // Without the brackets of an IPv6 address
+    host: String,
+    port: u16,
+    path: String,
+}
+
+#[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+impl Url {
+    // The host as it goes into the Host header and back into a URL, with the
+    // port unless it's the scheme's own
+    fn authority(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == if self.https { 443 } else { 80 } {
+            host
+        } else {
+            format!("{}:{}", host, self.port)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+fn parse(url: &str) -> Result<Url> {
+    let (https, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else {
+        bail!("only http:// and https:// URLs are supported");
+    };
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(index) if rest[index..].starts_with('?') => {
+            (&rest[..index], format!("/{}", &rest[index..]))
+        }
+        Some(index) => (&rest[..index], rest[index..].to_string()),
+        None => (rest, "/".to_string()),
+    };
+    let default_port = if https { 443 } else { 80 };
+    let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+        let (host, after) = bracketed
+            .split_once(']')
+            .context("missing ] after the IPv6 address")?;
+        match after.strip_prefix(':') {
+            Some(port) => (host, port.parse().context("invalid port")?),
+            None if after.is_empty() => (host, default_port),
+            None => bail!("unexpected {:?} after the IPv6 address", after),
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().context("invalid port")?),
+            None => (authority, default_port),
+        }
+    };
+    if host.is_empty() {
+        bail!("missing host");
+    }
+    Ok(Url {
+        https,
+        host: host.to_string(),
+        port,
+        path,
+    })
+}
+
+#[cfg(feature = "webhook")]
+mod enabled {
+    use anyhow::{Context, Result, bail};
+    use log::{debug, error};
+    use std::io::{Read, Write};
+    use std::net::{TcpStream, ToSocketAddrs};
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc::{Sender, channel};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{Url, WebhookEvent, parse, payload};
+
+    static TIMEOUT: Duration = Duration::from_secs(5);
+
+    // Sends from a thread of its own, so a slow server never holds up a frame
+    pub struct Poster {
+        sender: Sender<String>,
+    }
+
+    impl Poster {
+        pub fn send(&self, event: &WebhookEvent) {
+            // The thread only stops when the poster is dropped
+            let _ = self.sender.send(payload(event));
+        }
+    }
+
+    pub fn open(url: &str) -> Result<Poster> {
+        let url = parse(url)?;
+        let (sender, receiver) = channel::<String>();
+        thread::Builder::new()
+            .name("webhook".to_string())
+            .spawn(move || {
+                for body in receiver {
+                    let result = if url.https {
+                        post_tls(&url, &body)
+                    } else {
+                        post(&url, &body)
+                    };
+                    match result {
+                        Ok(status) => debug!("Webhook answered {}", status),
+                        Err(err) => error!("Cannot post to the webhook: {:#}", err),
+                    }
+                }
+            })
+            .context("cannot start the webhook thread")?;
+        Ok(Poster { sender })
+    }
+
+    // Returns the status line of the response
+    fn post(url: &Url, body: &str) -> Result<String> {
+        let addr = (url.host.as_str(), url.port)
+            .to_socket_addrs()
+            .with_context(|| format!("cannot resolve {}", url.host))?
+            .next()
+            .with_context(|| format!("no address for {}", url.host))?;
+        let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)
+            .with_context(|| format!("cannot connect to {}", addr))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            url.path,
+            url.authority(),
+            body.len(),
+            body
+        )?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        let response = String::from_utf8_lossy(&response);
+        let status = response.lines().next().unwrap_or_default();
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(status.to_string()),
+            _ => bail!("unexpected response {:?}", status),
+        }
+    }
+
+    // https goes through the system's curl, which brings the TLS stack and
+    // the certificates of the platform. Returns the status code.
+    fn post_tls(url: &Url, body: &str) -> Result<String> {
+        let mut curl = Command::new("curl")
+            .args(["--silent", "--show-error", "--max-time"])
+            .arg(TIMEOUT.as_secs().to_string())
+            .args(["--output", if cfg!(windows) { "NUL" } else { "/dev/null" }])
+            .args(["--write-out", "%{http_code}", "--request", "POST"])
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data-binary", "@-"])
+            .arg(format!("https://{}{}", url.authority(), url.path))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("cannot run curl for https")?;
+        if let Some(mut stdin) = curl.stdin.take() {
+            stdin.write_all(body.as_bytes())?;
+        }
+        let output = curl.wait_with_output()?;
+        let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !output.status.success() {
+            bail!(
+                "curl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        if !status.starts_with('2') {
+            bail!("unexpected response {}", status);
+        }
+        Ok(status)
+    }
+}
+
+#[cfg(not(feature = "webhook"))]
+mod disabled {
+    use anyhow::{Result, bail};
+
+    use super::WebhookEvent;
+
+    // Can never be created, so nothing is posted.
+    pub enum Poster {}
+
+    impl Poster {
+        pub fn send(&self, _event: &WebhookEvent) {
+            match *self {}
+        }
+    }
+
+    pub fn open(_url: &str) -> Result<Poster> {
+        bail!("built without the webhook feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(https: bool, host: &str, port: u16, path: &str) -> Url {
+        Url {
+            https,
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_http_and_https() {
+        assert_eq!(
+            parse("http://localhost:8080/pong").ok(),
+            Some(url(false, "localhost", 8080, "/pong"))
+        );
+        assert_eq!(
+            parse("https://discord.com/api/webhooks/1/abc?wait=true").ok(),
+            Some(url(
+                true,
+                "discord.com",
+                443,
+                "/api/webhooks/1/abc?wait=true"
+            ))
+        );
+        assert_eq!(
+            parse("http://example.com").ok(),
+            Some(url(false, "example.com", 80, "/"))
+        );
+        assert_eq!(
+            parse("http://example.com?key=1").ok(),
+            Some(url(false, "example.com", 80, "/?key=1"))
+        );
+        assert!(parse("ftp://example.com").is_err());
+        assert!(parse("http://:80/").is_err());
+        assert!(parse("http://example.com:http/").is_err());
+    }
+
+    #[test]
+    fn parses_ipv6_hosts() {
+        assert_eq!(
+            parse("http://[::1]:8080/pong").ok(),
+            Some(url(false, "::1", 8080, "/pong"))
+        );
+        assert_eq!(
+            parse("https://[2001:db8::1]/").ok(),
+            Some(url(true, "2001:db8::1", 443, "/"))
+        );
+        assert!(parse("http://[::1/").is_err());
+        assert!(parse("http://[::1]8080/").is_err());
+    }
+
+    #[test]
+    fn host_header_keeps_other_ports() {
+        let authority = |text: &str| parse(text).map(|url| url.authority()).ok();
+        assert_eq!(authority("http://example.com/"), Some("example.com".into()));
+        assert_eq!(
+            authority("http://example.com:80/"),
+            Some("example.com".into())
+        );
+        assert_eq!(
+            authority("http://example.com:8080/"),
+            Some("example.com:8080".into())
+        );
+        assert_eq!(
+            authority("https://example.com:80/"),
+            Some("example.com:80".into())
+        );
+        assert_eq!(authority("http://[::1]:8080/"), Some("[::1]:8080".into()));
+        assert_eq!(authority("https://[::1]/"), Some("[::1]".into()));
+    }
+}